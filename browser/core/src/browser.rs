@@ -1,3 +1,6 @@
+use crate::constants::MIN_REFRESH_DELAY;
+use crate::renderer::form::submission::FormSender;
+use crate::renderer::loader::Fetcher;
 use crate::renderer::page::Page;
 use alloc::rc::Rc;
 use alloc::vec::Vec;
@@ -7,6 +10,11 @@ use core::cell::RefCell;
 pub struct Browser {
     active_page_index: usize,
     pages: Vec<Rc<RefCell<Page>>>,
+    fetcher: Option<Fetcher>,
+    // the network layer which sends POST form submissions
+    form_sender: Option<FormSender>,
+    // refresh navigations are delayed at least this many seconds
+    min_refresh_delay: u64,
 }
 
 impl Browser {
@@ -16,6 +24,9 @@ impl Browser {
         let browser = Rc::new(RefCell::new(Self {
             active_page_index: 0,
             pages: Vec::new(),
+            fetcher: None,
+            form_sender: None,
+            min_refresh_delay: MIN_REFRESH_DELAY,
         }));
 
         page.set_browser(Rc::downgrade(&browser));
@@ -27,4 +38,29 @@ impl Browser {
     pub fn current_page(&self) -> Rc<RefCell<Page>> {
         self.pages[self.active_page_index].clone()
     }
+
+    // sets the network layer, which pages use to fetch subresources
+    pub fn set_fetcher(&mut self, fetcher: Fetcher) {
+        self.fetcher = Some(fetcher);
+    }
+
+    pub fn fetcher(&self) -> Option<Fetcher> {
+        self.fetcher
+    }
+
+    pub fn set_form_sender(&mut self, form_sender: FormSender) {
+        self.form_sender = Some(form_sender);
+    }
+
+    pub fn form_sender(&self) -> Option<FormSender> {
+        self.form_sender
+    }
+
+    pub fn set_min_refresh_delay(&mut self, seconds: u64) {
+        self.min_refresh_delay = seconds;
+    }
+
+    pub fn min_refresh_delay(&self) -> u64 {
+        self.min_refresh_delay
+    }
 }
@@ -12,3 +12,28 @@ pub static CONTENT_AREA_HEIGHT: i64 =
 pub static CHAR_WIDTH: i64 = 8;
 pub static CHAR_HEIGHT: i64 = 16;
 pub static CHAR_HEIGHT_WITH_PADDING: i64 = CHAR_HEIGHT + 4;
+
+// the default minimum delay of refresh navigations in seconds, which prevents refresh loops
+pub static MIN_REFRESH_DELAY: u64 = 1;
+// the maximum delay of refresh navigations in seconds, which is a day. longer delays are
+// clamped, so that the deadline can't overflow
+pub static MAX_REFRESH_DELAY: u64 = 24 * 60 * 60;
+
+// the maximum width and height of images and frames in pixels, which keeps the layout and
+// the rendering of the page in range
+pub static MAX_DIMENSION: i64 = 10_000;
+
+// the maximum depth of nested <iframe>, which stops pages that nest each other
+pub static MAX_FRAME_DEPTH: usize = 4;
+// the maximum number of <iframe> loaded by a page and its child pages, which stops pages
+// that have many frames in each frame
+pub static MAX_FRAMES: usize = 32;
+
+// the maximum number of instructions of a compiled pattern attribute, which limits the
+// expansion of counted repetitions such as "(a{1000}){1000}"
+pub static MAX_PATTERN_SIZE: usize = 10_000;
+// the maximum nesting of the groups of a pattern attribute
+pub static MAX_PATTERN_DEPTH: usize = 100;
+
+// https://html.spec.whatwg.org/multipage/system-state.html#dom-navigator-useragent
+pub static USER_AGENT: &str = "Mozilla/5.0 (compatible; SimpleBrowser/0.1)";
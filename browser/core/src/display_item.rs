@@ -1,6 +1,10 @@
+use crate::renderer::form::control::ControlType;
+use crate::renderer::html::dom::NodeId;
+use crate::renderer::image::bitmap::Image;
 use crate::renderer::layout::computed_style::ComputedStyle;
 use crate::renderer::layout::layout_object::LayoutPoint;
 use crate::renderer::layout::layout_object::LayoutSize;
+use alloc::rc::Rc;
 use alloc::string::String;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -14,5 +18,35 @@ pub enum DisplayItem {
         text: String,
         style: ComputedStyle,
         layout_point: LayoutPoint,
+        // the destination of the link which contains the text, which is resolved against the
+        // base URL of the page
+        href: Option<String>,
+        // the text node, which the UI dispatches click events to. it's None for the text of
+        // child pages and generated text
+        node_id: Option<NodeId>,
+    },
+    // a form control which is rendered as text, e.g. "[x]" for a checked checkbox
+    FormControl {
+        node_id: NodeId,
+        control_type: ControlType,
+        text: String,
+        style: ComputedStyle,
+        layout_point: LayoutPoint,
+    },
+    // an image which is scaled to the rect, or the alt text when the image is not available
+    Image {
+        image: Option<Rc<Image>>,
+        alt: String,
+        style: ComputedStyle,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
+    },
+    // the box of <iframe> in the page, which is followed by the display items of the nested
+    // document. the frames of the nested document are painted as Rect
+    Frame {
+        node_id: NodeId,
+        style: ComputedStyle,
+        layout_point: LayoutPoint,
+        layout_size: LayoutSize,
     },
 }
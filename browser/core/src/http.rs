@@ -11,6 +11,7 @@ pub struct HttpResponse {
     reason: String,
     headers: Vec<Header>,
     body: String,
+    body_bytes: Vec<u8>,
 }
 
 impl HttpResponse {
@@ -50,9 +51,35 @@ impl HttpResponse {
             reason: statuses[2].to_string(),
             headers,
             body: body.to_string(),
+            body_bytes: body.as_bytes().to_vec(),
         })
     }
 
+    // parses a response whose body may be binary, e.g. an image
+    pub fn from_bytes(raw_response: Vec<u8>) -> Result<Self, Error> {
+        let separator = (0..raw_response.len()).find_map(|i| {
+            if raw_response[i..].starts_with(b"\r\n\r\n") {
+                Some((i, 4))
+            } else if raw_response[i..].starts_with(b"\n\n") {
+                Some((i, 2))
+            } else {
+                None
+            }
+        });
+
+        let (head, body) = match separator {
+            Some((i, len)) => (&raw_response[..i], &raw_response[i + len..]),
+            None => return Self::new(String::from_utf8_lossy(&raw_response).to_string()),
+        };
+
+        let mut head = String::from_utf8_lossy(head).replace("\r\n", "\n");
+        head.push_str("\n\n");
+        let mut response = Self::new(head)?;
+        response.body = String::from_utf8_lossy(body).to_string();
+        response.body_bytes = body.to_vec();
+        Ok(response)
+    }
+
     pub fn version(&self) -> String {
         self.version.clone()
     }
@@ -73,9 +100,14 @@ impl HttpResponse {
         self.body.clone()
     }
 
+    pub fn body_bytes(&self) -> Vec<u8> {
+        self.body_bytes.clone()
+    }
+
     pub fn header_value(&self, name: &str) -> Result<String, String> {
         for h in &self.headers {
-            if h.name == name {
+            // header names are case-insensitive
+            if h.name.eq_ignore_ascii_case(name) {
                 return Ok(h.value.clone());
             }
         }
@@ -99,6 +131,7 @@ impl Header {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::vec;
 
     #[test]
     fn test_invalid() {
@@ -136,6 +169,7 @@ mod tests {
 
         assert_eq!(res.header_value("Date"), Ok("xx xx xx".to_string()));
         assert_eq!(res.header_value("Content-Length"), Ok("42".to_string()));
+        assert_eq!(res.header_value("content-length"), Ok("42".to_string()));
     }
 
     #[test]
@@ -150,4 +184,17 @@ mod tests {
 
         assert_eq!(res.body(), "body message".to_string());
     }
+
+    #[test]
+    fn test_binary_body() {
+        let mut raw = b"HTTP/1.1 200 OK\r\nContent-Type: image/png\r\n\r\n".to_vec();
+        raw.extend_from_slice(&[0x89, b'P', b'N', b'G', 0x00, 0xff]);
+        let res = HttpResponse::from_bytes(raw).expect("failed to parse http response");
+        assert_eq!(res.status_code(), 200);
+        assert_eq!(
+            res.header_value("Content-Type"),
+            Ok("image/png".to_string())
+        );
+        assert_eq!(res.body_bytes(), vec![0x89, b'P', b'N', b'G', 0x00, 0xff]);
+    }
 }
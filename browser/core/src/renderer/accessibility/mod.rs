@@ -0,0 +1,3 @@
+pub mod name;
+pub mod role;
+pub mod tree;
@@ -0,0 +1,326 @@
+use crate::renderer::accessibility::role::role;
+use crate::renderer::accessibility::role::Role;
+use crate::renderer::form::control::option_label;
+use crate::renderer::form::control::selected_option;
+use crate::renderer::form::control::value;
+use crate::renderer::html::dom::descendants;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeId;
+use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::helper::api::attribute;
+use crate::renderer::html::helper::api::get_element_by_id;
+use crate::renderer::html::helper::api::strip_and_collapse_whitespace;
+use alloc::collections::BTreeSet;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// https://w3c.github.io/accname/#mapping_additional_nd_name
+pub fn accessible_name(node: &Rc<RefCell<Node>>) -> String {
+    let mut visited = BTreeSet::new();
+    strip_and_collapse_whitespace(&text_alternative(node, &mut visited, false, false))
+}
+
+// https://w3c.github.io/accname/#mapping_additional_nd_description
+// aria-describedby, aria-description, or the title which is not used as the name
+pub fn accessible_description(node: &Rc<RefCell<Node>>) -> String {
+    if let Some(description) = referenced_text(node, "aria-describedby", &mut BTreeSet::new()) {
+        return strip_and_collapse_whitespace(&description);
+    }
+    if let Some(description) = attribute(node, "aria-description") {
+        return strip_and_collapse_whitespace(&description);
+    }
+    match attribute(node, "title").map(|t| strip_and_collapse_whitespace(&t)) {
+        Some(title) if title != accessible_name(node) => title,
+        _ => String::new(),
+    }
+}
+
+// https://w3c.github.io/accname/#comp_embedded_control
+// the value of text fields, selects and ranges, which screen readers announce with the name
+pub fn accessible_value(node: &Rc<RefCell<Node>>) -> Option<String> {
+    let role = role(node);
+    if !role.is_embedded_control() {
+        return None;
+    }
+    if let Some(text) = attribute(node, "aria-valuetext") {
+        return Some(text);
+    }
+    match role {
+        Role::Combobox | Role::Listbox
+            if node
+                .borrow()
+                .get_element()
+                .is_some_and(|e| e.local_name() == "select") =>
+        {
+            Some(
+                selected_option(node)
+                    .map(|o| option_label(&o))
+                    .unwrap_or_default(),
+            )
+        }
+        Role::Slider | Role::Spinbutton => {
+            attribute(node, "aria-valuenow").or_else(|| Some(value(node)))
+        }
+        _ => Some(value(node)),
+    }
+}
+
+// https://w3c.github.io/accname/#computation-steps
+// `referenced` is true in the traversal of aria-labelledby and aria-describedby, which includes
+// hidden nodes, and `recursive` is true for the contents of the node whose name is computed
+fn text_alternative(
+    node: &Rc<RefCell<Node>>,
+    visited: &mut BTreeSet<NodeId>,
+    referenced: bool,
+    recursive: bool,
+) -> String {
+    // each node is visited once, which stops the loops of the references
+    if let Some(id) = node.borrow().id() {
+        if !visited.insert(id) {
+            return String::new();
+        }
+    }
+
+    // step 2A
+    if !referenced && is_hidden(node) {
+        return String::new();
+    }
+
+    // step 2G
+    if let NodeKind::Text(text) = node.borrow().kind() {
+        return text;
+    }
+    if node.borrow().get_element().is_none() {
+        return String::new();
+    }
+    let role = role(node);
+
+    // step 2B
+    if !referenced {
+        if let Some(text) = referenced_text(node, "aria-labelledby", visited) {
+            return text;
+        }
+    }
+
+    // step 2C
+    if recursive && role.is_embedded_control() {
+        return accessible_value(node).unwrap_or_default();
+    }
+
+    // step 2D
+    if let Some(label) = attribute(node, "aria-label") {
+        if !label.trim().is_empty() {
+            return label;
+        }
+    }
+
+    // step 2E
+    if role != Role::None {
+        let text = native_text_alternative(node, visited);
+        if !text.trim().is_empty() {
+            return text;
+        }
+    }
+
+    // step 2F
+    if recursive || role.is_named_from_content() {
+        let mut text = String::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            let child_text = text_alternative(&c, visited, referenced, true);
+            // the texts of block elements are separated by spaces
+            if c.borrow()
+                .get_element()
+                .is_some_and(|e| e.is_block_element())
+            {
+                text.push(' ');
+                text.push_str(&child_text);
+                text.push(' ');
+            } else {
+                text.push_str(&child_text);
+            }
+            child = c.borrow().next_sibling();
+        }
+        if !text.trim().is_empty() {
+            return text;
+        }
+    }
+
+    // step 2I
+    if let Some(title) = attribute(node, "title") {
+        return title;
+    }
+    // https://www.w3.org/TR/html-aam-1.0/#input-type-text-input-type-password-input-type-number-input-type-search-input-type-tel-input-type-email-input-type-url-and-textarea-element-accessible-name-computation
+    attribute(node, "placeholder").unwrap_or_default()
+}
+
+// the texts of the elements whose ids are listed in the attribute, joined by spaces. the node
+// may refer to itself, and its text is computed without its references then
+fn referenced_text(
+    node: &Rc<RefCell<Node>>,
+    name: &str,
+    visited: &mut BTreeSet<NodeId>,
+) -> Option<String> {
+    let ids = attribute(node, name)?;
+    let document = node.borrow().owner_document();
+    let texts = ids
+        .split_ascii_whitespace()
+        .filter_map(|id| get_element_by_id(document.clone(), &id.to_string()))
+        .map(|n| {
+            if Rc::ptr_eq(&n, node) {
+                if let Some(id) = n.borrow().id() {
+                    visited.remove(&id);
+                }
+            }
+            text_alternative(&n, visited, true, true)
+        })
+        .filter(|text| !text.trim().is_empty())
+        .collect::<Vec<String>>();
+    if texts.is_empty() {
+        return None;
+    }
+    Some(texts.join(" "))
+}
+
+// https://www.w3.org/TR/html-aam-1.0/#accessible-name-computations-by-html-element
+fn native_text_alternative(node: &Rc<RefCell<Node>>, visited: &mut BTreeSet<NodeId>) -> String {
+    let local_name = match node.borrow().get_element() {
+        Some(e) => e.local_name(),
+        None => return String::new(),
+    };
+    let input_type = attribute(node, "type")
+        .map(|t| t.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match local_name.as_str() {
+        // https://html.spec.whatwg.org/multipage/input.html#the-input-element:attr-input-value
+        "input" if input_type == "submit" => {
+            attribute(node, "value").unwrap_or("Submit".to_string())
+        }
+        "input" if input_type == "reset" => attribute(node, "value").unwrap_or("Reset".to_string()),
+        "input" if input_type == "button" => attribute(node, "value").unwrap_or_default(),
+        "input" if input_type == "image" => attribute(node, "alt")
+            .or_else(|| attribute(node, "value"))
+            .unwrap_or("Submit".to_string()),
+        "input" | "select" | "textarea" => labels(node)
+            .iter()
+            .map(|label| text_alternative(label, visited, false, true))
+            .collect::<Vec<String>>()
+            .join(" "),
+        "img" | "area" => attribute(node, "alt").unwrap_or_default(),
+        "option" => attribute(node, "label").unwrap_or_default(),
+        "table" => first_child_text(node, "caption", visited),
+        "fieldset" => first_child_text(node, "legend", visited),
+        "figure" => first_child_text(node, "figcaption", visited),
+        _ => String::new(),
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/forms.html#the-label-element
+// the <label> elements whose for attribute is the id of the control, and the nearest ancestor
+// <label> without the for attribute
+fn labels(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut labels = Vec::new();
+    if let (Some(id), Some(document)) = (attribute(node, "id"), node.borrow().owner_document()) {
+        labels.extend(descendants(&document).filter(|n| {
+            n.borrow().get_element().is_some_and(|e| {
+                e.local_name() == "label" && e.get_attribute("for") == Some(id.clone())
+            })
+        }));
+    }
+
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        let is_label = n
+            .borrow()
+            .get_element()
+            .is_some_and(|e| e.local_name() == "label" && e.get_attribute("for").is_none());
+        if is_label {
+            labels.push(n);
+            break;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    labels
+}
+
+// the text of the first child element of the local name, e.g. <caption> of <table>
+fn first_child_text(
+    node: &Rc<RefCell<Node>>,
+    local_name: &str,
+    visited: &mut BTreeSet<NodeId>,
+) -> String {
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        if c.borrow()
+            .get_element()
+            .is_some_and(|e| e.local_name() == local_name)
+        {
+            return text_alternative(&c, visited, false, true);
+        }
+        child = c.borrow().next_sibling();
+    }
+    String::new()
+}
+
+// https://w3c.github.io/accname/#dfn-hidden
+// the elements which are not rendered or are hidden from assistive technologies
+pub fn is_hidden(node: &Rc<RefCell<Node>>) -> bool {
+    let element = match node.borrow().get_element() {
+        Some(e) => e,
+        None => return false,
+    };
+    matches!(
+        element.local_name().as_str(),
+        "head" | "script" | "style" | "template" | "title"
+    ) || element.get_attribute("hidden").is_some()
+        || element
+            .get_attribute("aria-hidden")
+            .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::helper::test_util::by_id;
+    use crate::renderer::html::helper::test_util::parse;
+
+    #[test]
+    fn test_accessible_name() {
+        let document = parse(
+            "<html><body><a id=link href=x>Read <b>more</b><span hidden>hidden</span></a><button id=labelled aria-labelledby=\"l1 l2\" aria-label=ignored>x</button><span id=l1>Send</span><span id=l2 hidden>now</span><input id=field><label for=field>Email</label><label>Count <input id=count type=number value=3> times</label><input id=submit type=submit><img id=img alt=Logo><input id=placeholder placeholder=Search title=Find><div id=div>generic</div><button id=del aria-labelledby=\"del file\">Delete</button><span id=file>a.txt</span></body></html>",
+        );
+        assert_eq!("Read more", accessible_name(&by_id(&document, "link")));
+        // referenced nodes are named even when they are hidden
+        assert_eq!("Send now", accessible_name(&by_id(&document, "labelled")));
+        assert_eq!("Email", accessible_name(&by_id(&document, "field")));
+        // the control itself is not a part of its own label
+        assert_eq!("Count times", accessible_name(&by_id(&document, "count")));
+        assert_eq!("Submit", accessible_name(&by_id(&document, "submit")));
+        assert_eq!("Logo", accessible_name(&by_id(&document, "img")));
+        assert_eq!("Find", accessible_name(&by_id(&document, "placeholder")));
+        assert_eq!("", accessible_name(&by_id(&document, "div")));
+        // the node refers to itself
+        assert_eq!("Delete a.txt", accessible_name(&by_id(&document, "del")));
+    }
+
+    #[test]
+    fn test_accessible_description() {
+        let document = parse(
+            "<html><body><input id=a aria-describedby=hint title=Name><p id=hint>At least 8 <b>characters</b></p><button id=b title=\"Sends the form\">Send</button><button id=c title=Send></button></body></html>",
+        );
+        assert_eq!(
+            "At least 8 characters",
+            accessible_description(&by_id(&document, "a"))
+        );
+        assert_eq!(
+            "Sends the form",
+            accessible_description(&by_id(&document, "b"))
+        );
+        // the title is already the name
+        assert_eq!("", accessible_description(&by_id(&document, "c")));
+    }
+}
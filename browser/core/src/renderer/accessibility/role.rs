@@ -0,0 +1,357 @@
+use crate::renderer::form::control::has_attribute;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::helper::api::attribute;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::cell::RefCell;
+use core::fmt::Display;
+use core::fmt::Formatter;
+use core::str::FromStr;
+
+// https://w3c.github.io/aria/#role_definitions
+// the non-abstract roles which elements of HTML map to, and the text of the text nodes
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    Article,
+    Banner,
+    Button,
+    Caption,
+    Cell,
+    Checkbox,
+    Columnheader,
+    Combobox,
+    Complementary,
+    Contentinfo,
+    Dialog,
+    Document,
+    Form,
+    Generic,
+    Group,
+    Heading,
+    Img,
+    Link,
+    List,
+    Listbox,
+    Listitem,
+    Main,
+    Navigation,
+    // https://w3c.github.io/aria/#none
+    // "presentation" is the synonym of "none"
+    None,
+    Option,
+    Paragraph,
+    Radio,
+    Region,
+    Row,
+    Rowgroup,
+    Rowheader,
+    Searchbox,
+    Separator,
+    Slider,
+    Spinbutton,
+    Table,
+    // the text of a text node, which is called StaticText by some browsers
+    Text,
+    Textbox,
+}
+
+impl Role {
+    // https://w3c.github.io/aria/#namefromcontent
+    pub fn is_named_from_content(&self) -> bool {
+        matches!(
+            self,
+            Role::Button
+                | Role::Caption
+                | Role::Cell
+                | Role::Checkbox
+                | Role::Columnheader
+                | Role::Heading
+                | Role::Link
+                | Role::Option
+                | Role::Radio
+                | Role::Row
+                | Role::Rowheader
+                | Role::Text
+        )
+    }
+
+    // https://w3c.github.io/aria/#namefromprohibited
+    pub fn is_name_prohibited(&self) -> bool {
+        matches!(
+            self,
+            Role::Caption | Role::Generic | Role::None | Role::Paragraph | Role::Text
+        )
+    }
+
+    // https://w3c.github.io/accname/#comp_embedded_control
+    // the controls whose value is their name, when they are embedded in a label
+    pub fn is_embedded_control(&self) -> bool {
+        matches!(
+            self,
+            Role::Combobox
+                | Role::Listbox
+                | Role::Searchbox
+                | Role::Slider
+                | Role::Spinbutton
+                | Role::Textbox
+        )
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "article" => Ok(Role::Article),
+            "banner" => Ok(Role::Banner),
+            "button" => Ok(Role::Button),
+            "caption" => Ok(Role::Caption),
+            "cell" | "gridcell" => Ok(Role::Cell),
+            "checkbox" | "switch" => Ok(Role::Checkbox),
+            "columnheader" => Ok(Role::Columnheader),
+            "combobox" => Ok(Role::Combobox),
+            "complementary" => Ok(Role::Complementary),
+            "contentinfo" => Ok(Role::Contentinfo),
+            "dialog" | "alertdialog" => Ok(Role::Dialog),
+            "document" => Ok(Role::Document),
+            "form" => Ok(Role::Form),
+            "generic" => Ok(Role::Generic),
+            "group" => Ok(Role::Group),
+            "heading" => Ok(Role::Heading),
+            "img" | "image" => Ok(Role::Img),
+            "link" => Ok(Role::Link),
+            "list" => Ok(Role::List),
+            "listbox" => Ok(Role::Listbox),
+            "listitem" => Ok(Role::Listitem),
+            "main" => Ok(Role::Main),
+            "navigation" => Ok(Role::Navigation),
+            "none" | "presentation" => Ok(Role::None),
+            "option" => Ok(Role::Option),
+            "paragraph" => Ok(Role::Paragraph),
+            "radio" => Ok(Role::Radio),
+            "region" => Ok(Role::Region),
+            "row" => Ok(Role::Row),
+            "rowgroup" => Ok(Role::Rowgroup),
+            "rowheader" => Ok(Role::Rowheader),
+            "searchbox" => Ok(Role::Searchbox),
+            "separator" => Ok(Role::Separator),
+            "slider" => Ok(Role::Slider),
+            "spinbutton" => Ok(Role::Spinbutton),
+            "table" => Ok(Role::Table),
+            "textbox" => Ok(Role::Textbox),
+            _ => Err(format!("unsupported role {:?}", s)),
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            Role::Article => "article",
+            Role::Banner => "banner",
+            Role::Button => "button",
+            Role::Caption => "caption",
+            Role::Cell => "cell",
+            Role::Checkbox => "checkbox",
+            Role::Columnheader => "columnheader",
+            Role::Combobox => "combobox",
+            Role::Complementary => "complementary",
+            Role::Contentinfo => "contentinfo",
+            Role::Dialog => "dialog",
+            Role::Document => "document",
+            Role::Form => "form",
+            Role::Generic => "generic",
+            Role::Group => "group",
+            Role::Heading => "heading",
+            Role::Img => "img",
+            Role::Link => "link",
+            Role::List => "list",
+            Role::Listbox => "listbox",
+            Role::Listitem => "listitem",
+            Role::Main => "main",
+            Role::Navigation => "navigation",
+            Role::None => "none",
+            Role::Option => "option",
+            Role::Paragraph => "paragraph",
+            Role::Radio => "radio",
+            Role::Region => "region",
+            Role::Row => "row",
+            Role::Rowgroup => "rowgroup",
+            Role::Rowheader => "rowheader",
+            Role::Searchbox => "searchbox",
+            Role::Separator => "separator",
+            Role::Slider => "slider",
+            Role::Spinbutton => "spinbutton",
+            Role::Table => "table",
+            Role::Text => "text",
+            Role::Textbox => "textbox",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// https://w3c.github.io/aria/#document-handling_author-errors_roles
+// the first supported token of the role attribute, or the implicit role of the element
+pub fn role(node: &Rc<RefCell<Node>>) -> Role {
+    let explicit = attribute(node, "role").and_then(|roles| {
+        roles
+            .split_ascii_whitespace()
+            .find_map(|r| r.to_ascii_lowercase().parse::<Role>().ok())
+    });
+    match explicit {
+        Some(role) => role,
+        None => implicit_role(node),
+    }
+}
+
+// https://www.w3.org/TR/html-aria/#docconformance
+pub fn implicit_role(node: &Rc<RefCell<Node>>) -> Role {
+    let element = match node.borrow().kind() {
+        NodeKind::Document => return Role::Document,
+        NodeKind::Text(_) => return Role::Text,
+        NodeKind::Element(e) => e,
+        _ => return Role::None,
+    };
+
+    match element.local_name().as_str() {
+        "a" | "area" if has_attribute(node, "href") => Role::Link,
+        "article" => Role::Article,
+        "aside" => Role::Complementary,
+        "button" | "summary" => Role::Button,
+        "caption" => Role::Caption,
+        "details" | "fieldset" | "optgroup" => Role::Group,
+        "dialog" => Role::Dialog,
+        // header and footer are landmarks unless they are in sectioning content
+        "footer" if !in_sectioning_content(node) => Role::Contentinfo,
+        "header" if !in_sectioning_content(node) => Role::Banner,
+        "form" => Role::Form,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Role::Heading,
+        "hr" => Role::Separator,
+        // https://www.w3.org/TR/html-aria/#el-img-empty-alt
+        "img" if attribute(node, "alt").is_some_and(|alt| alt.is_empty()) => Role::None,
+        "img" => Role::Img,
+        "input" => input_role(node),
+        "li" => Role::Listitem,
+        "main" => Role::Main,
+        "menu" | "ol" | "ul" => Role::List,
+        "nav" => Role::Navigation,
+        "option" => Role::Option,
+        "p" => Role::Paragraph,
+        // a section is a landmark only when it is named
+        "section"
+            if has_attribute(node, "aria-label") || has_attribute(node, "aria-labelledby") =>
+        {
+            Role::Region
+        }
+        "select" => {
+            let size = attribute(node, "size")
+                .and_then(|s| s.trim().parse::<usize>().ok())
+                .unwrap_or(0);
+            if has_attribute(node, "multiple") || size > 1 {
+                Role::Listbox
+            } else {
+                Role::Combobox
+            }
+        }
+        "table" => Role::Table,
+        "tbody" | "tfoot" | "thead" => Role::Rowgroup,
+        "td" => Role::Cell,
+        "textarea" => Role::Textbox,
+        "th" if attribute(node, "scope").is_some_and(|s| s.eq_ignore_ascii_case("row")) => {
+            Role::Rowheader
+        }
+        "th" => Role::Columnheader,
+        "tr" => Role::Row,
+        _ => Role::Generic,
+    }
+}
+
+// https://www.w3.org/TR/html-aria/#el-input-text
+fn input_role(node: &Rc<RefCell<Node>>) -> Role {
+    let input_type = attribute(node, "type")
+        .map(|t| t.to_ascii_lowercase())
+        .unwrap_or("text".to_string());
+    match input_type.as_str() {
+        "button" | "image" | "reset" | "submit" => Role::Button,
+        "checkbox" => Role::Checkbox,
+        "number" => Role::Spinbutton,
+        "radio" => Role::Radio,
+        "range" => Role::Slider,
+        "search" if !has_attribute(node, "list") => Role::Searchbox,
+        _ if has_attribute(node, "list") => Role::Combobox,
+        "hidden" => Role::None,
+        _ => Role::Textbox,
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/dom.html#sectioning-content-2
+fn in_sectioning_content(node: &Rc<RefCell<Node>>) -> bool {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        let is_sectioning = n.borrow().get_element().is_some_and(|e| {
+            matches!(
+                e.local_name().as_str(),
+                "article" | "aside" | "main" | "nav" | "section"
+            )
+        });
+        if is_sectioning {
+            return true;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::dom::descendants;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    fn roles(html: &str) -> Vec<String> {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        let roles = descendants(&document)
+            .filter(|n| {
+                n.borrow()
+                    .get_element()
+                    .is_some_and(|e| e.get_attribute("id").is_some())
+            })
+            .map(|n| role(&n).to_string())
+            .collect();
+        roles
+    }
+
+    #[test]
+    fn test_role() {
+        assert_eq!(
+            vec![
+                "link",
+                "generic",
+                "heading",
+                "none",
+                "img",
+                "checkbox",
+                "searchbox",
+                "textbox",
+                "listbox",
+                "banner",
+                "generic",
+                "region",
+                "generic",
+                "navigation",
+                "button",
+            ],
+            roles(
+                "<html><body><a id=1 href=x>a</a><a id=2>b</a><h2 id=3>c</h2><img id=4 alt=\"\"><img id=5 src=y><input id=6 type=checkbox><input id=7 type=search><input id=8><select id=9 multiple></select><header id=10></header><article><header id=11></header></article><section id=12 aria-label=s></section><section id=13></section><div id=14 role=\"foo navigation\"></div><span id=15 role=button></span></body></html>"
+            )
+        );
+    }
+}
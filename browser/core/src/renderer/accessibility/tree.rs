@@ -0,0 +1,525 @@
+use crate::renderer::accessibility::name::accessible_description;
+use crate::renderer::accessibility::name::accessible_name;
+use crate::renderer::accessibility::name::accessible_value;
+use crate::renderer::accessibility::name::is_hidden;
+use crate::renderer::accessibility::role::role;
+use crate::renderer::accessibility::role::Role;
+use crate::renderer::form::control::checkedness;
+use crate::renderer::form::control::control_type;
+use crate::renderer::form::control::has_attribute;
+use crate::renderer::form::control::is_disabled;
+use crate::renderer::form::control::selected_option;
+use crate::renderer::form::control::ControlType;
+use crate::renderer::html::dom::ElementKind;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeId;
+use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::helper::api::attribute;
+use crate::renderer::html::helper::api::get_elements_by_tag_name;
+use crate::renderer::html::helper::api::get_target_element_node;
+use crate::renderer::html::helper::api::get_text_content;
+use crate::renderer::html::helper::api::heading_level;
+use crate::renderer::layout::layout_object::LayoutObject;
+use crate::renderer::layout::layout_view::LayoutView;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Display;
+use core::fmt::Formatter;
+
+// https://w3c.github.io/aria/#state_prop_def
+// the states which are exposed with the role and the name
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum State {
+    Focusable,
+    Focused,
+    Disabled,
+    Required,
+    Checked,
+    Unchecked,
+    Selected,
+    Expanded,
+    Collapsed,
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        let s = match self {
+            State::Focusable => "focusable",
+            State::Focused => "focused",
+            State::Disabled => "disabled",
+            State::Required => "required",
+            State::Checked => "checked",
+            State::Unchecked => "unchecked",
+            State::Selected => "selected",
+            State::Expanded => "expanded",
+            State::Collapsed => "collapsed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// https://w3c.github.io/aria/#dfn-accessible-object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessibilityNode {
+    node_id: Option<NodeId>,
+    role: Role,
+    name: String,
+    description: String,
+    value: Option<String>,
+    // the level of a heading, which is given by <h1> to <h6> or aria-level
+    level: Option<usize>,
+    states: Vec<State>,
+    children: Vec<AccessibilityNode>,
+}
+
+impl AccessibilityNode {
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.node_id
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn description(&self) -> String {
+        self.description.clone()
+    }
+
+    pub fn value(&self) -> Option<String> {
+        self.value.clone()
+    }
+
+    pub fn level(&self) -> Option<usize> {
+        self.level
+    }
+
+    pub fn states(&self) -> Vec<State> {
+        self.states.clone()
+    }
+
+    pub fn children(&self) -> Vec<AccessibilityNode> {
+        self.children.clone()
+    }
+
+    pub fn has_state(&self, state: State) -> bool {
+        self.states.contains(&state)
+    }
+
+    // the line of the node in the dump, e.g. `heading "Intro" level=1`
+    pub fn describe(&self) -> String {
+        let mut line = self.role.to_string();
+        if !self.name.is_empty() {
+            line.push_str(&format!(" {:?}", self.name));
+        }
+        if let Some(value) = &self.value {
+            line.push_str(&format!(" value={:?}", value));
+        }
+        if let Some(level) = self.level {
+            line.push_str(&format!(" level={}", level));
+        }
+        for state in &self.states {
+            line.push_str(&format!(" {}", state));
+        }
+        if !self.description.is_empty() {
+            line.push_str(&format!(" description={:?}", self.description));
+        }
+        line
+    }
+
+    // the node and its descendants in tree order, with the depth from the node
+    pub fn walk(&self) -> Vec<(usize, &AccessibilityNode)> {
+        let mut nodes = vec![(0, self)];
+        for child in &self.children {
+            nodes.extend(child.walk().into_iter().map(|(depth, n)| (depth + 1, n)));
+        }
+        nodes
+    }
+}
+
+// https://w3c.github.io/aria/#accessibility_tree
+// the tree which assistive technologies read, which is built from the DOM tree and the nodes
+// which are rendered by the layout tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessibilityTree {
+    root: AccessibilityNode,
+}
+
+impl AccessibilityTree {
+    pub fn new(
+        document: Rc<RefCell<Node>>,
+        layout_view: &LayoutView,
+        focused: Option<NodeId>,
+    ) -> Self {
+        let mut rendered = BTreeSet::new();
+        collect_rendered_nodes(&layout_view.root(), &mut rendered);
+        let builder = TreeBuilder { rendered, focused };
+
+        let title = get_elements_by_tag_name(&document, "title")
+            .first()
+            .map(get_text_content)
+            .unwrap_or_default();
+        let children = match get_target_element_node(Some(document.clone()), ElementKind::Body) {
+            Some(body) => builder.build_children(&body),
+            None => Vec::new(),
+        };
+        let root = AccessibilityNode {
+            node_id: document.borrow().id(),
+            role: Role::Document,
+            name: title
+                .split_ascii_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" "),
+            description: String::new(),
+            value: None,
+            level: None,
+            states: Vec::new(),
+            children,
+        };
+
+        Self { root }
+    }
+
+    pub fn root(&self) -> AccessibilityNode {
+        self.root.clone()
+    }
+
+    // the tree as text, where each node is a line indented by its depth
+    pub fn dump(&self) -> String {
+        self.root
+            .walk()
+            .iter()
+            .map(|(depth, node)| format!("{}{}", "  ".repeat(*depth), node.describe()))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    // the lines of the dump which are split before each heading, so that the tree is read
+    // heading by heading. the content before the first heading is the first section
+    pub fn sections(&self) -> Vec<Vec<String>> {
+        let mut sections: Vec<Vec<String>> = vec![Vec::new()];
+        for (depth, node) in self.root.walk() {
+            if node.role == Role::Heading {
+                sections.push(Vec::new());
+            }
+            if let Some(section) = sections.last_mut() {
+                section.push(format!("{}{}", "  ".repeat(depth), node.describe()));
+            }
+        }
+        // the document line alone is not a section
+        if sections.len() > 1 && sections[0].len() <= 1 {
+            sections.remove(0);
+        }
+        sections
+    }
+}
+
+fn collect_rendered_nodes(
+    layout_object: &Option<Rc<RefCell<LayoutObject>>>,
+    rendered: &mut BTreeSet<NodeId>,
+) {
+    let mut current = layout_object.clone();
+    while let Some(object) = current {
+        if let Some(id) = object.borrow().node_id() {
+            rendered.insert(id);
+        }
+        collect_rendered_nodes(&object.borrow().first_child(), rendered);
+        current = object.borrow().next_sibling();
+    }
+}
+
+struct TreeBuilder {
+    rendered: BTreeSet<NodeId>,
+    focused: Option<NodeId>,
+}
+
+impl TreeBuilder {
+    fn build_children(&self, node: &Rc<RefCell<Node>>) -> Vec<AccessibilityNode> {
+        let mut children = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            children.extend(self.build(&c));
+            child = c.borrow().next_sibling();
+        }
+        children
+    }
+
+    // the nodes of the DOM node, which is empty for the nodes which are not rendered. generic
+    // elements without names are ignored, and their children take their place
+    fn build(&self, node: &Rc<RefCell<Node>>) -> Vec<AccessibilityNode> {
+        if !self.is_rendered(node) {
+            return Vec::new();
+        }
+
+        if let NodeKind::Text(text) = node.borrow().kind() {
+            let text = text
+                .split_ascii_whitespace()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            if text.is_empty() {
+                return Vec::new();
+            }
+            return vec![AccessibilityNode {
+                node_id: node.borrow().id(),
+                role: Role::Text,
+                name: text,
+                description: String::new(),
+                value: None,
+                level: None,
+                states: Vec::new(),
+                children: Vec::new(),
+            }];
+        }
+
+        // https://w3c.github.io/aria/#aria-hidden
+        // the subtree of aria-hidden="true" is rendered, but excluded from the tree
+        if is_hidden(node) {
+            return Vec::new();
+        }
+
+        let role = role(node);
+        let name = if role.is_name_prohibited() {
+            String::new()
+        } else {
+            accessible_name(node)
+        };
+        let states = self.states(node, role);
+        let children = self.build_children(node);
+
+        if matches!(role, Role::Generic | Role::None) && states.is_empty() {
+            return children;
+        }
+
+        vec![AccessibilityNode {
+            node_id: node.borrow().id(),
+            role,
+            name,
+            description: accessible_description(node),
+            value: accessible_value(node),
+            level: aria_level(node, role),
+            states,
+            children,
+        }]
+    }
+
+    // the options of <select> are rendered as the label of the control
+    fn is_rendered(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let mut current = Some(node.clone());
+        while let Some(n) = current {
+            let is_rendered = n
+                .borrow()
+                .id()
+                .is_some_and(|id| self.rendered.contains(&id));
+            if is_rendered {
+                return Rc::ptr_eq(&n, node)
+                    || n.borrow().element_kind() == Some(ElementKind::Select);
+            }
+            let in_select = n
+                .borrow()
+                .get_element()
+                .is_some_and(|e| matches!(e.local_name().as_str(), "option" | "optgroup"));
+            if !in_select {
+                return false;
+            }
+            current = n.borrow().parent().upgrade();
+        }
+        false
+    }
+
+    fn states(&self, node: &Rc<RefCell<Node>>, role: Role) -> Vec<State> {
+        let mut states = Vec::new();
+        let control_type = control_type(node);
+        let aria_state = |name: &str| attribute(node, name).map(|v| v.to_ascii_lowercase());
+
+        if is_focusable(node, role) {
+            states.push(State::Focusable);
+        }
+        if self.focused.is_some() && node.borrow().id() == self.focused {
+            states.push(State::Focused);
+        }
+        if (control_type.is_some() && is_disabled(node))
+            || aria_state("aria-disabled").as_deref() == Some("true")
+        {
+            states.push(State::Disabled);
+        }
+        if (control_type.is_some() && has_attribute(node, "required"))
+            || aria_state("aria-required").as_deref() == Some("true")
+        {
+            states.push(State::Required);
+        }
+
+        if matches!(role, Role::Checkbox | Role::Radio) {
+            let checked = match aria_state("aria-checked") {
+                Some(checked) => checked == "true",
+                None => {
+                    matches!(
+                        control_type,
+                        Some(ControlType::Checkbox | ControlType::Radio)
+                    ) && checkedness(node)
+                }
+            };
+            states.push(if checked {
+                State::Checked
+            } else {
+                State::Unchecked
+            });
+        }
+
+        if role == Role::Option && is_selected(node) {
+            states.push(State::Selected);
+        }
+
+        // https://html.spec.whatwg.org/multipage/interactive-elements.html#the-summary-element
+        let expanded = match aria_state("aria-expanded") {
+            Some(expanded) => Some(expanded == "true"),
+            None if is_details_summary(node) => node
+                .borrow()
+                .parent()
+                .upgrade()
+                .map(|details| has_attribute(&details, "open")),
+            None => None,
+        };
+        match expanded {
+            Some(true) => states.push(State::Expanded),
+            Some(false) => states.push(State::Collapsed),
+            None => {}
+        }
+        states
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/interaction.html#focusable-area
+fn is_focusable(node: &Rc<RefCell<Node>>, role: Role) -> bool {
+    if has_attribute(node, "tabindex") {
+        return true;
+    }
+    if let Some(control_type) = control_type(node) {
+        return control_type != ControlType::Hidden && !is_disabled(node);
+    }
+    role == Role::Link
+        || is_details_summary(node)
+        || node.borrow().element_kind() == Some(ElementKind::Iframe)
+}
+
+// https://html.spec.whatwg.org/multipage/interactive-elements.html#summary-for-its-parent-details
+fn is_details_summary(node: &Rc<RefCell<Node>>) -> bool {
+    let is_summary = node
+        .borrow()
+        .get_element()
+        .is_some_and(|e| e.local_name() == "summary");
+    let in_details = node
+        .borrow()
+        .parent()
+        .upgrade()
+        .and_then(|p| p.borrow().get_element())
+        .is_some_and(|e| e.local_name() == "details");
+    is_summary && in_details
+}
+
+// the option which aria-selected or the <select> selects
+fn is_selected(option: &Rc<RefCell<Node>>) -> bool {
+    if let Some(selected) = attribute(option, "aria-selected") {
+        return selected.eq_ignore_ascii_case("true");
+    }
+    let mut current = option.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::Select) {
+            return selected_option(&n).is_some_and(|selected| Rc::ptr_eq(&selected, option));
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+// https://w3c.github.io/aria/#aria-level
+fn aria_level(node: &Rc<RefCell<Node>>, role: Role) -> Option<usize> {
+    if role != Role::Heading {
+        return None;
+    }
+    if let Some(level) = attribute(node, "aria-level").and_then(|l| l.trim().parse().ok()) {
+        return Some(level);
+    }
+    // https://w3c.github.io/aria/#heading
+    Some(heading_level(node).unwrap_or(2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::css::cssom::StyleSheet;
+    use crate::renderer::css::parser::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::html::helper::api::get_element_by_id;
+    use crate::renderer::html::helper::api::get_style_contents;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn tree(html: &str, focused: Option<&str>) -> AccessibilityTree {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        let css = get_style_contents(document.clone()).join("\n");
+        let cssom: StyleSheet = CssParser::new(CssTokenizer::new(css)).parse_stylesheet();
+        let layout_view = LayoutView::new(document.clone(), &cssom);
+        let focused = focused.and_then(|id| {
+            get_element_by_id(Some(document.clone()), &id.to_string()).and_then(|n| n.borrow().id())
+        });
+        AccessibilityTree::new(document, &layout_view, focused)
+    }
+
+    #[test]
+    fn test_dump() {
+        let tree = tree(
+            "<html><head><title>Shop</title><style>.gone { display: none; }</style></head><body><nav aria-label=Main><ul><li><a href=/>Home</a></li></ul></nav><h1>Cart</h1><div><p>Two <b>items</b></p><p class=gone>removed</p></div><div aria-hidden=true><p>icon</p></div><form><label>Gift <input id=gift type=checkbox checked></label><select id=size><option>S</option><option selected>M</option></select><button aria-expanded=false disabled>More</button></form></body></html>",
+            Some("gift"),
+        );
+        assert_eq!(
+            "document \"Shop\"
+  navigation \"Main\"
+    list
+      listitem
+        link \"Home\" focusable
+          text \"Home\"
+  heading \"Cart\" level=1
+    text \"Cart\"
+  paragraph
+    text \"Two\"
+    text \"items\"
+  form
+    text \"Gift\"
+    checkbox \"Gift\" focusable focused checked
+    combobox value=\"M\" focusable
+      option \"S\"
+      option \"M\" selected
+    button \"More\" disabled collapsed",
+            tree.dump()
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let tree = tree(
+            "<html><body><p>intro</p><h1>One</h1><p>a</p><h2 aria-hidden=true>Hidden</h2><h2>Two</h2><p>b</p></body></html>",
+            None,
+        );
+        let sections = tree.sections();
+        assert_eq!(3, sections.len());
+        assert_eq!(
+            vec![
+                "  heading \"One\" level=1".to_string(),
+                "    text \"One\"".to_string(),
+                "  paragraph".to_string(),
+                "    text \"a\"".to_string(),
+            ],
+            sections[1]
+        );
+    }
+}
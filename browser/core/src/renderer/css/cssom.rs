@@ -1,6 +1,6 @@
+use crate::renderer::css::selector::SelectorList;
 use crate::renderer::css::token::CssToken;
 use alloc::string::String;
-use alloc::string::ToString;
 use alloc::vec::Vec;
 
 // https://www.w3.org/TR/cssom-1/#cssstylesheet
@@ -17,25 +17,30 @@ impl StyleSheet {
     pub fn set_rules(&mut self, rules: Vec<QualifiedRule>) {
         self.rules = rules;
     }
+
+    // appends the rules after the existing ones, so that they win in the cascade
+    pub fn append_rules(&mut self, rules: Vec<QualifiedRule>) {
+        self.rules.extend(rules);
+    }
 }
 
 // https://www.w3.org/TR/css-syntax-3/#qualified-rule
 #[derive(Debug, Clone, PartialEq)]
 pub struct QualifiedRule {
-    pub selector: Selector,
+    pub selectors: SelectorList,
     pub declarations: Vec<Declaration>,
 }
 
 impl QualifiedRule {
     pub fn new() -> Self {
         Self {
-            selector: Selector::TypeSelector("".to_string()),
+            selectors: SelectorList::new(),
             declarations: Vec::new(),
         }
     }
 
-    pub fn set_selector(&mut self, selector: Selector) {
-        self.selector = selector;
+    pub fn set_selectors(&mut self, selectors: SelectorList) {
+        self.selectors = selectors;
     }
 
     pub fn set_declarations(&mut self, declarations: Vec<Declaration>) {
@@ -43,19 +48,6 @@ impl QualifiedRule {
     }
 }
 
-// https://www.w3.org/TR/selectors-4/
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum Selector {
-    // https://www.w3.org/TR/selectors-4/#type-selectors
-    TypeSelector(String),
-    // https://www.w3.org/TR/selectors-4/#class-html
-    ClassSelector(String),
-    // https://www.w3.org/TR/selectors-4/#id-selectors
-    IdSelector(String),
-    // dummy selector for unparsables
-    UnknownSelector,
-}
-
 // https://www.w3.org/TR/css-syntax-3/#declaration
 #[derive(Debug, Clone, PartialEq)]
 pub struct Declaration {
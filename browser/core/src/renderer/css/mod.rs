@@ -1,3 +1,4 @@
 pub mod cssom;
 pub mod parser;
+pub mod selector;
 pub mod token;
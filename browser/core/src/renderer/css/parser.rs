@@ -2,22 +2,34 @@ use crate::alloc::string::ToString;
 use crate::renderer::css::cssom::ComponentValue;
 use crate::renderer::css::cssom::Declaration;
 use crate::renderer::css::cssom::QualifiedRule;
-use crate::renderer::css::cssom::Selector;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::css::selector::parse_selector_list;
 use crate::renderer::css::token::CssToken;
 use crate::renderer::css::token::CssTokenizer;
 use alloc::string::String;
 use alloc::vec::Vec;
-use core::iter::Peekable;
 
 #[derive(Debug, Clone)]
 pub struct CssParser {
-    t: Peekable<CssTokenizer>,
+    t: CssTokenizer,
+    // the token which is looked ahead in declarations
+    peeked: Option<CssToken>,
 }
 
 impl CssParser {
     pub fn new(t: CssTokenizer) -> Self {
-        Self { t: t.peekable() }
+        Self { t, peeked: None }
+    }
+
+    fn peek_token(&mut self) -> Option<&CssToken> {
+        if self.peeked.is_none() {
+            self.peeked = self.t.next();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn next_token(&mut self) -> Option<CssToken> {
+        self.peeked.take().or_else(|| self.t.next())
     }
 
     pub fn parse_stylesheet(&mut self) -> StyleSheet {
@@ -31,87 +43,32 @@ impl CssParser {
         let mut rules = Vec::new();
 
         loop {
-            let token = match self.t.peek() {
-                Some(t) => t,
+            // the prelude is consumed as is, since selectors aren't tokenized
+            let prelude = match self.t.consume_prelude() {
+                Some(prelude) => prelude,
                 None => return rules,
             };
-            match token {
-                CssToken::AtKeyword(_keyword) => {
-                    // does not support eg. @import, @media, so ignores
-                    let _rule = self.consume_qualified_rule();
-                }
-                _ => {
-                    let rule = self.consume_qualified_rule();
-                    match rule {
-                        Some(r) => rules.push(r),
-                        None => return rules,
-                    }
-                }
+            match self.next_token() {
+                Some(CssToken::OpenCurly) => {}
+                // the at-rule without a block, eg. @import
+                Some(CssToken::SemiColon) => continue,
+                _ => return rules,
             }
-        }
-    }
-
-    // consumes one qualified rule
-    fn consume_qualified_rule(&mut self) -> Option<QualifiedRule> {
-        let mut rule = QualifiedRule::new();
-
-        loop {
-            let token = match self.t.peek() {
-                Some(t) => t,
-                None => return None,
-            };
 
-            match token {
-                // if {, declarations inside will be seen
-                CssToken::OpenCurly => {
-                    assert_eq!(self.t.next(), Some(CssToken::OpenCurly));
-                    rule.set_declarations(self.consume_list_of_declarations());
-                    return Some(rule);
-                }
-                // else, is a selector
-                _ => {
-                    rule.set_selector(self.consume_selector());
-                }
+            // does not support eg. @media, so ignores the whole block
+            if prelude.starts_with('@') {
+                self.t.skip_block();
+                continue;
             }
-        }
-    }
-
-    // consumes selector
-    fn consume_selector(&mut self) -> Selector {
-        let token = match self.t.next() {
-            Some(t) => t,
-            None => panic!("should have a token but got None"),
-        };
 
-        match token {
-            // if #, for id selector
-            CssToken::HashToken(value) => Selector::IdSelector(value[1..].to_string()),
-            CssToken::Delim(delim) => {
-                // if ., for class selector
-                if delim == '.' {
-                    return Selector::ClassSelector(self.consume_ident());
-                }
-                panic!("{:?} is an unexpected token", token);
-            }
-            CssToken::Ident(ident) => {
-                // does not support eg. a:hover, so ignores
-                if self.t.peek() == Some(&CssToken::Colon) {
-                    while self.t.peek() != Some(&CssToken::OpenCurly) {
-                        self.t.next();
-                    }
-                }
-                Selector::TypeSelector(ident.to_string())
-            }
-            CssToken::AtKeyword(_keyword) => {
-                // does not support eg. @import, @media, so ignores
-                while self.t.peek() != Some(&CssToken::OpenCurly) {
-                    self.t.next();
-                }
-                Selector::UnknownSelector
-            }
-            _ => {
-                self.t.next();
-                Selector::UnknownSelector
+            let declarations = self.consume_list_of_declarations();
+            // https://www.w3.org/TR/selectors-4/#invalid
+            // the rule whose selector is invalid is ignored as a whole
+            if let Ok(selectors) = parse_selector_list(&prelude) {
+                let mut rule = QualifiedRule::new();
+                rule.set_selectors(selectors);
+                rule.set_declarations(declarations);
+                rules.push(rule);
             }
         }
     }
@@ -121,18 +78,18 @@ impl CssParser {
         let mut declarations = Vec::new();
 
         loop {
-            let token = match self.t.peek() {
+            let token = match self.peek_token() {
                 Some(t) => t,
                 None => return declarations,
             };
 
             match token {
                 CssToken::CloseCurly => {
-                    assert_eq!(self.t.next(), Some(CssToken::CloseCurly));
+                    assert_eq!(self.next_token(), Some(CssToken::CloseCurly));
                     return declarations;
                 }
                 CssToken::SemiColon => {
-                    assert_eq!(self.t.next(), Some(CssToken::SemiColon));
+                    assert_eq!(self.next_token(), Some(CssToken::SemiColon));
                 }
                 CssToken::Ident(ref _ident) => {
                     if let Some(declaration) = self.consume_declaration() {
@@ -140,7 +97,7 @@ impl CssParser {
                     }
                 }
                 _ => {
-                    self.t.next();
+                    self.next_token();
                 }
             }
         }
@@ -148,26 +105,27 @@ impl CssParser {
 
     // consumes declaration
     fn consume_declaration(&mut self) -> Option<Declaration> {
-        if self.t.peek().is_none() {
+        if self.peek_token().is_none() {
             return None;
         }
 
         let mut declaration = Declaration::new();
         declaration.set_property(self.consume_ident());
 
-        match self.t.next() {
+        match self.next_token() {
             Some(CssToken::Colon) => {}
             _ => return None,
         }
 
-        declaration.set_value(self.consume_component_value());
+        // the declaration without a value at the end of the input is dropped
+        declaration.set_value(self.consume_component_value()?);
 
         Some(declaration)
     }
 
     // consumes identifier
     fn consume_ident(&mut self) -> String {
-        let token = match self.t.next() {
+        let token = match self.next_token() {
             Some(t) => t,
             None => panic!("should have a token but got None"),
         };
@@ -181,18 +139,21 @@ impl CssParser {
     }
 
     // consumes component value
-    fn consume_component_value(&mut self) -> ComponentValue {
-        self.t
-            .next()
-            .expect("should have a token in consume_component_value")
+    fn consume_component_value(&mut self) -> Option<ComponentValue> {
+        self.next_token()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::renderer::css::selector::SelectorList;
     use alloc::vec;
 
+    fn selectors(selectors: &str) -> SelectorList {
+        parse_selector_list(selectors).expect("failed to parse the selectors")
+    }
+
     #[test]
     fn test_empty() {
         let style = "".to_string();
@@ -209,7 +170,7 @@ mod tests {
         let cssom = CssParser::new(t).parse_stylesheet();
 
         let mut rule = QualifiedRule::new();
-        rule.set_selector(Selector::TypeSelector("p".to_string()));
+        rule.set_selectors(selectors("p"));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
         declaration.set_value(ComponentValue::Ident("red".to_string()));
@@ -232,7 +193,7 @@ mod tests {
         let cssom = CssParser::new(t).parse_stylesheet();
 
         let mut rule = QualifiedRule::new();
-        rule.set_selector(Selector::IdSelector("id".to_string()));
+        rule.set_selectors(selectors("#id"));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
         declaration.set_value(ComponentValue::Ident("red".to_string()));
@@ -255,7 +216,7 @@ mod tests {
         let cssom = CssParser::new(t).parse_stylesheet();
 
         let mut rule = QualifiedRule::new();
-        rule.set_selector(Selector::ClassSelector("class".to_string()));
+        rule.set_selectors(selectors(".class"));
         let mut declaration = Declaration::new();
         declaration.set_property("color".to_string());
         declaration.set_value(ComponentValue::Ident("red".to_string()));
@@ -278,14 +239,14 @@ mod tests {
         let cssom = CssParser::new(t).parse_stylesheet();
 
         let mut rule1 = QualifiedRule::new();
-        rule1.set_selector(Selector::TypeSelector("p".to_string()));
+        rule1.set_selectors(selectors("p"));
         let mut declaration1 = Declaration::new();
         declaration1.set_property("content".to_string());
         declaration1.set_value(ComponentValue::StringToken("Hey".to_string()));
         rule1.set_declarations(vec![declaration1]);
 
         let mut rule2 = QualifiedRule::new();
-        rule2.set_selector(Selector::TypeSelector("h1".to_string()));
+        rule2.set_selectors(selectors("h1"));
         let mut declaration2 = Declaration::new();
         declaration2.set_property("font-size".to_string());
         declaration2.set_value(ComponentValue::Number(40.0));
@@ -303,4 +264,20 @@ mod tests {
             i += 1;
         }
     }
+
+    #[test]
+    fn test_complex_selectors() {
+        let style = "@import \"a.css\"; div > p, .a[title~='{x}'] { color: red; } @media screen { p > a { color: blue; } } p:: { color: green; } h1 { color: black; }".to_string();
+        let t = CssTokenizer::new(style);
+        let cssom = CssParser::new(t).parse_stylesheet();
+
+        // the at-rules and the rule of the invalid selector are ignored
+        assert_eq!(2, cssom.rules.len());
+        assert_eq!(
+            selectors("div > p, .a[title~='{x}']"),
+            cssom.rules[0].selectors
+        );
+        assert_eq!(1, cssom.rules[0].declarations.len());
+        assert_eq!(selectors("h1"), cssom.rules[1].selectors);
+    }
 }
@@ -0,0 +1,837 @@
+use crate::error::Error;
+use crate::renderer::html::dom::Namespace;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeKind;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// https://www.w3.org/TR/selectors-4/#selector-list
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SelectorList {
+    selectors: Vec<ComplexSelector>,
+}
+
+impl SelectorList {
+    // the empty list, which matches no element
+    pub fn new() -> Self {
+        Self {
+            selectors: Vec::new(),
+        }
+    }
+
+    pub fn selectors(&self) -> Vec<ComplexSelector> {
+        self.selectors.clone()
+    }
+
+    // https://www.w3.org/TR/selectors-4/#match-a-selector-against-an-element
+    pub fn matches(&self, element: &Rc<RefCell<Node>>) -> bool {
+        self.selectors.iter().any(|s| s.matches(element))
+    }
+
+    // https://www.w3.org/TR/selectors-4/#specificity-rules
+    // the specificity of the most specific selector which matches the element, which is used
+    // by the cascade
+    pub fn matching_specificity(&self, element: &Rc<RefCell<Node>>) -> Option<Specificity> {
+        self.selectors
+            .iter()
+            .filter(|s| s.matches(element))
+            .map(|s| s.specificity())
+            .max()
+    }
+
+    // the specificity of the most specific selector, which is used for :not()
+    fn max_specificity(&self) -> Specificity {
+        self.selectors
+            .iter()
+            .map(|s| s.specificity())
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#specificity
+// the numbers of ID selectors, of class, attribute and pseudo-class selectors, and of type
+// selectors, which are compared in this order
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Specificity(usize, usize, usize);
+
+impl Specificity {
+    pub fn new(ids: usize, classes: usize, types: usize) -> Self {
+        Self(ids, classes, types)
+    }
+
+    fn add(self, other: Specificity) -> Self {
+        Self(self.0 + other.0, self.1 + other.1, self.2 + other.2)
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#complex
+// compound selectors separated by combinators, e.g. "div > p.note"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComplexSelector {
+    compounds: Vec<CompoundSelector>,
+    // the combinator between compounds[i] and compounds[i + 1]
+    combinators: Vec<Combinator>,
+}
+
+impl ComplexSelector {
+    pub fn compounds(&self) -> Vec<CompoundSelector> {
+        self.compounds.clone()
+    }
+
+    pub fn combinators(&self) -> Vec<Combinator> {
+        self.combinators.clone()
+    }
+
+    pub fn matches(&self, element: &Rc<RefCell<Node>>) -> bool {
+        self.matches_from(self.compounds.len() - 1, element)
+    }
+
+    // https://www.w3.org/TR/selectors-4/#specificity-rules
+    pub fn specificity(&self) -> Specificity {
+        self.compounds
+            .iter()
+            .flat_map(|c| c.simple_selectors.iter())
+            .fold(Specificity::default(), |total, s| {
+                total.add(s.specificity())
+            })
+    }
+
+    // matches the compounds from right to left
+    fn matches_from(&self, index: usize, element: &Rc<RefCell<Node>>) -> bool {
+        if !self.compounds[index].matches(element) {
+            return false;
+        }
+        if index == 0 {
+            return true;
+        }
+
+        match self.combinators[index - 1] {
+            Combinator::Descendant => {
+                let mut ancestor = parent_element(element);
+                while let Some(a) = ancestor {
+                    if self.matches_from(index - 1, &a) {
+                        return true;
+                    }
+                    ancestor = parent_element(&a);
+                }
+                false
+            }
+            Combinator::Child => match parent_element(element) {
+                Some(p) => self.matches_from(index - 1, &p),
+                None => false,
+            },
+            Combinator::NextSibling => match previous_element_sibling(element) {
+                Some(s) => self.matches_from(index - 1, &s),
+                None => false,
+            },
+            Combinator::SubsequentSibling => {
+                let mut sibling = previous_element_sibling(element);
+                while let Some(s) = sibling {
+                    if self.matches_from(index - 1, &s) {
+                        return true;
+                    }
+                    sibling = previous_element_sibling(&s);
+                }
+                false
+            }
+        }
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#selector-combinator
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Combinator {
+    // https://www.w3.org/TR/selectors-4/#descendant-combinators
+    Descendant,
+    // https://www.w3.org/TR/selectors-4/#child-combinators
+    Child,
+    // https://www.w3.org/TR/selectors-4/#adjacent-sibling-combinators
+    NextSibling,
+    // https://www.w3.org/TR/selectors-4/#general-sibling-combinators
+    SubsequentSibling,
+}
+
+// https://www.w3.org/TR/selectors-4/#compound
+// simple selectors which all match the same element, e.g. "p.note[title]"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompoundSelector {
+    simple_selectors: Vec<SimpleSelector>,
+}
+
+impl CompoundSelector {
+    pub fn simple_selectors(&self) -> Vec<SimpleSelector> {
+        self.simple_selectors.clone()
+    }
+
+    pub fn matches(&self, element: &Rc<RefCell<Node>>) -> bool {
+        self.simple_selectors.iter().all(|s| s.matches(element))
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#simple
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimpleSelector {
+    // https://www.w3.org/TR/selectors-4/#type-selectors
+    Type(String),
+    // https://www.w3.org/TR/selectors-4/#the-universal-selector
+    Universal,
+    // https://www.w3.org/TR/selectors-4/#id-selectors
+    Id(String),
+    // https://www.w3.org/TR/selectors-4/#class-html
+    Class(String),
+    // https://www.w3.org/TR/selectors-4/#attribute-selectors
+    Attribute {
+        name: String,
+        matcher: Option<(AttributeOperator, String)>,
+        case_insensitive: bool,
+    },
+    // https://www.w3.org/TR/selectors-4/#pseudo-classes
+    PseudoClass(PseudoClass),
+}
+
+impl SimpleSelector {
+    fn specificity(&self) -> Specificity {
+        match self {
+            SimpleSelector::Id(_) => Specificity(1, 0, 0),
+            // :not() has the specificity of its most specific argument
+            SimpleSelector::PseudoClass(PseudoClass::Not(list)) => list.max_specificity(),
+            SimpleSelector::Class(_)
+            | SimpleSelector::Attribute { .. }
+            | SimpleSelector::PseudoClass(_) => Specificity(0, 1, 0),
+            SimpleSelector::Type(_) => Specificity(0, 0, 1),
+            SimpleSelector::Universal => Specificity::default(),
+        }
+    }
+
+    pub fn matches(&self, element: &Rc<RefCell<Node>>) -> bool {
+        let e = match element.borrow().get_element() {
+            Some(e) => e,
+            None => return false,
+        };
+        let is_html = e.namespace() == Namespace::Html;
+
+        match self {
+            // HTML elements are matched case-insensitively
+            SimpleSelector::Type(name) => {
+                if is_html {
+                    e.local_name() == name.to_ascii_lowercase()
+                } else {
+                    e.local_name() == *name
+                }
+            }
+            SimpleSelector::Universal => true,
+            SimpleSelector::Id(id) => e.get_attribute("id").as_ref() == Some(id),
+            SimpleSelector::Class(class) => e.has_class(class),
+            SimpleSelector::Attribute {
+                name,
+                matcher,
+                case_insensitive,
+            } => {
+                let name = if is_html {
+                    name.to_ascii_lowercase()
+                } else {
+                    name.clone()
+                };
+                let value = match e.get_attribute(&name) {
+                    Some(v) => v,
+                    None => return false,
+                };
+                match matcher {
+                    Some((operator, expected)) => {
+                        if *case_insensitive {
+                            operator.matches(
+                                &value.to_ascii_lowercase(),
+                                &expected.to_ascii_lowercase(),
+                            )
+                        } else {
+                            operator.matches(&value, expected)
+                        }
+                    }
+                    None => true,
+                }
+            }
+            SimpleSelector::PseudoClass(pseudo_class) => pseudo_class.matches(element),
+        }
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#attribute-representation
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AttributeOperator {
+    // [attr=value]
+    Equals,
+    // [attr~=value]
+    Includes,
+    // [attr|=value]
+    DashMatch,
+    // [attr^=value]
+    Prefix,
+    // [attr$=value]
+    Suffix,
+    // [attr*=value]
+    Substring,
+}
+
+impl AttributeOperator {
+    fn matches(&self, value: &str, expected: &str) -> bool {
+        match self {
+            AttributeOperator::Equals => value == expected,
+            AttributeOperator::Includes => {
+                !expected.is_empty() && value.split_ascii_whitespace().any(|v| v == expected)
+            }
+            AttributeOperator::DashMatch => {
+                value == expected || value.starts_with(&format!("{}-", expected))
+            }
+            AttributeOperator::Prefix => !expected.is_empty() && value.starts_with(expected),
+            AttributeOperator::Suffix => !expected.is_empty() && value.ends_with(expected),
+            AttributeOperator::Substring => !expected.is_empty() && value.contains(expected),
+        }
+    }
+}
+
+// https://www.w3.org/TR/selectors-4/#structural-pseudos
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PseudoClass {
+    Root,
+    Empty,
+    FirstChild,
+    LastChild,
+    OnlyChild,
+    // https://www.w3.org/TR/selectors-4/#child-index
+    // matches the element of the index a*n+b for any n >= 0, which is 1-based
+    NthChild(i64, i64),
+    // https://www.w3.org/TR/selectors-4/#negation
+    Not(SelectorList),
+}
+
+impl PseudoClass {
+    fn matches(&self, element: &Rc<RefCell<Node>>) -> bool {
+        match self {
+            PseudoClass::Root => {
+                let parent = element.borrow().parent().upgrade();
+                match parent {
+                    Some(p) => p.borrow().kind() == NodeKind::Document,
+                    None => false,
+                }
+            }
+            PseudoClass::Empty => {
+                let mut child = element.borrow().first_child();
+                while let Some(c) = child {
+                    match c.borrow().kind() {
+                        NodeKind::Comment(_) => {}
+                        NodeKind::Text(ref t) if t.is_empty() => {}
+                        _ => return false,
+                    }
+                    child = c.borrow().next_sibling();
+                }
+                true
+            }
+            PseudoClass::FirstChild => previous_element_sibling(element).is_none(),
+            PseudoClass::LastChild => next_element_sibling(element).is_none(),
+            PseudoClass::OnlyChild => {
+                previous_element_sibling(element).is_none()
+                    && next_element_sibling(element).is_none()
+            }
+            PseudoClass::NthChild(a, b) => {
+                let mut index = 1;
+                let mut sibling = previous_element_sibling(element);
+                while let Some(s) = sibling {
+                    index += 1;
+                    sibling = previous_element_sibling(&s);
+                }
+
+                // finds n >= 0 where a*n+b == index
+                let diff = index - b;
+                match *a {
+                    0 => diff == 0,
+                    a => diff % a == 0 && diff / a >= 0,
+                }
+            }
+            PseudoClass::Not(list) => !list.matches(element),
+        }
+    }
+}
+
+fn parent_element(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let parent = node.borrow().parent().upgrade()?;
+    let is_element = parent.borrow().get_element().is_some();
+    if is_element {
+        Some(parent)
+    } else {
+        None
+    }
+}
+
+fn previous_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut sibling = node.borrow().previous_sibling().upgrade();
+    while let Some(s) = sibling {
+        if s.borrow().get_element().is_some() {
+            return Some(s);
+        }
+        sibling = s.borrow().previous_sibling().upgrade();
+    }
+    None
+}
+
+fn next_element_sibling(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let mut sibling = node.borrow().next_sibling();
+    while let Some(s) = sibling {
+        if s.borrow().get_element().is_some() {
+            return Some(s);
+        }
+        sibling = s.borrow().next_sibling();
+    }
+    None
+}
+
+fn syntax_error(message: &str) -> Error {
+    Error::UnexpectedInput(format!("SyntaxError: {}", message))
+}
+
+// https://www.w3.org/TR/selectors-4/#parse-a-selector
+pub fn parse_selector_list(selectors: &str) -> Result<SelectorList, Error> {
+    let mut parser = SelectorParser::new(selectors);
+    let list = parser.consume_selector_list()?;
+    parser.skip_whitespace();
+    match parser.peek() {
+        None => Ok(list),
+        Some(c) => Err(syntax_error(&format!(
+            "unexpected {:?} in selector {:?}",
+            c, selectors
+        ))),
+    }
+}
+
+// parses selectors char by char, as the tokenizer of stylesheets skips whitespace, which is
+// the descendant combinator in selectors
+#[derive(Debug, Clone)]
+struct SelectorParser {
+    input: Vec<char>,
+    pos: usize,
+}
+
+impl SelectorParser {
+    fn new(input: &str) -> Self {
+        Self {
+            input: input.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    // skips whitespace and returns true if there was any
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r' | '\x0c')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn consume_selector_list(&mut self) -> Result<SelectorList, Error> {
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.consume_complex_selector()?);
+            self.skip_whitespace();
+            if self.peek() != Some(',') {
+                return Ok(SelectorList { selectors });
+            }
+            self.next();
+        }
+    }
+
+    fn consume_complex_selector(&mut self) -> Result<ComplexSelector, Error> {
+        let mut compounds = vec![self.consume_compound_selector()?];
+        let mut combinators = Vec::new();
+
+        loop {
+            let whitespace = self.skip_whitespace();
+            let combinator = match self.peek() {
+                Some('>') => Combinator::Child,
+                Some('+') => Combinator::NextSibling,
+                Some('~') => Combinator::SubsequentSibling,
+                Some(',') | Some(')') | None => break,
+                Some(_) if whitespace => Combinator::Descendant,
+                Some(c) => return Err(syntax_error(&format!("unexpected {:?}", c))),
+            };
+            if combinator != Combinator::Descendant {
+                self.next();
+                self.skip_whitespace();
+            }
+            combinators.push(combinator);
+            compounds.push(self.consume_compound_selector()?);
+        }
+
+        Ok(ComplexSelector {
+            compounds,
+            combinators,
+        })
+    }
+
+    fn consume_compound_selector(&mut self) -> Result<CompoundSelector, Error> {
+        let mut simple_selectors = Vec::new();
+
+        match self.peek() {
+            Some('*') => {
+                self.next();
+                simple_selectors.push(SimpleSelector::Universal);
+            }
+            Some(c) if is_name_start(c) => {
+                simple_selectors.push(SimpleSelector::Type(self.consume_name()?));
+            }
+            _ => {}
+        }
+
+        loop {
+            let selector = match self.peek() {
+                Some('#') => {
+                    self.next();
+                    SimpleSelector::Id(self.consume_name()?)
+                }
+                Some('.') => {
+                    self.next();
+                    SimpleSelector::Class(self.consume_name()?)
+                }
+                Some('[') => {
+                    self.next();
+                    self.consume_attribute_selector()?
+                }
+                Some(':') => {
+                    self.next();
+                    SimpleSelector::PseudoClass(self.consume_pseudo_class()?)
+                }
+                _ => break,
+            };
+            simple_selectors.push(selector);
+        }
+
+        if simple_selectors.is_empty() {
+            return Err(syntax_error("expected a selector"));
+        }
+        Ok(CompoundSelector { simple_selectors })
+    }
+
+    // consumes the rest of [name op value i]
+    fn consume_attribute_selector(&mut self) -> Result<SimpleSelector, Error> {
+        self.skip_whitespace();
+        let name = self.consume_name()?;
+        self.skip_whitespace();
+
+        let operator = match self.next() {
+            Some(']') => {
+                return Ok(SimpleSelector::Attribute {
+                    name,
+                    matcher: None,
+                    case_insensitive: false,
+                })
+            }
+            Some('=') => AttributeOperator::Equals,
+            Some(c) => {
+                let operator = match c {
+                    '~' => AttributeOperator::Includes,
+                    '|' => AttributeOperator::DashMatch,
+                    '^' => AttributeOperator::Prefix,
+                    '$' => AttributeOperator::Suffix,
+                    '*' => AttributeOperator::Substring,
+                    _ => return Err(syntax_error(&format!("unexpected {:?}", c))),
+                };
+                if self.next() != Some('=') {
+                    return Err(syntax_error("expected '='"));
+                }
+                operator
+            }
+            None => return Err(syntax_error("unterminated attribute selector")),
+        };
+
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some(quote @ ('"' | '\'')) => {
+                self.next();
+                let mut value = String::new();
+                loop {
+                    match self.next() {
+                        Some(c) if c == quote => break,
+                        Some(c) => value.push(c),
+                        None => return Err(syntax_error("unterminated string")),
+                    }
+                }
+                value
+            }
+            _ => self.consume_name()?,
+        };
+        self.skip_whitespace();
+
+        let case_insensitive = matches!(self.peek(), Some('i' | 'I'));
+        if case_insensitive {
+            self.next();
+            self.skip_whitespace();
+        }
+        if self.next() != Some(']') {
+            return Err(syntax_error("unterminated attribute selector"));
+        }
+
+        Ok(SimpleSelector::Attribute {
+            name,
+            matcher: Some((operator, value)),
+            case_insensitive,
+        })
+    }
+
+    // consumes the rest of :name or :name(arguments)
+    fn consume_pseudo_class(&mut self) -> Result<PseudoClass, Error> {
+        let name = self.consume_name()?.to_ascii_lowercase();
+        let pseudo_class = match name.as_str() {
+            "root" => PseudoClass::Root,
+            "empty" => PseudoClass::Empty,
+            "first-child" => PseudoClass::FirstChild,
+            "last-child" => PseudoClass::LastChild,
+            "only-child" => PseudoClass::OnlyChild,
+            "nth-child" | "not" => {
+                if self.next() != Some('(') {
+                    return Err(syntax_error(&format!("expected arguments of :{}", name)));
+                }
+                self.skip_whitespace();
+                let pseudo_class = if name == "not" {
+                    PseudoClass::Not(self.consume_selector_list()?)
+                } else {
+                    let (a, b) = self.consume_an_plus_b()?;
+                    PseudoClass::NthChild(a, b)
+                };
+                self.skip_whitespace();
+                if self.next() != Some(')') {
+                    return Err(syntax_error(&format!("unterminated :{}", name)));
+                }
+                pseudo_class
+            }
+            _ => {
+                return Err(syntax_error(&format!(
+                    "pseudo-class :{} is not supported yet",
+                    name
+                )))
+            }
+        };
+        Ok(pseudo_class)
+    }
+
+    // https://www.w3.org/TR/css-syntax-3/#anb-microsyntax
+    fn consume_an_plus_b(&mut self) -> Result<(i64, i64), Error> {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if c == ')' {
+                break;
+            }
+            if !c.is_ascii_whitespace() {
+                s.push(c.to_ascii_lowercase());
+            }
+            self.next();
+        }
+
+        let parse_int = |s: &str| -> Result<i64, Error> {
+            s.parse::<i64>()
+                .map_err(|_| syntax_error(&format!("invalid An+B {:?}", s)))
+        };
+
+        match s.as_str() {
+            "odd" => return Ok((2, 1)),
+            "even" => return Ok((2, 0)),
+            _ => {}
+        }
+        match s.find('n') {
+            Some(i) => {
+                let a = match &s[..i] {
+                    "" | "+" => 1,
+                    "-" => -1,
+                    a => parse_int(a)?,
+                };
+                let b = match &s[i + 1..] {
+                    "" => 0,
+                    b => parse_int(b.trim_start_matches('+'))?,
+                };
+                Ok((a, b))
+            }
+            None => Ok((0, parse_int(&s)?)),
+        }
+    }
+
+    // https://www.w3.org/TR/css-syntax-3/#consume-name
+    fn consume_name(&mut self) -> Result<String, Error> {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '\\' => {
+                    self.next();
+                    match self.next() {
+                        Some(escaped) => name.push(escaped),
+                        None => return Err(syntax_error("invalid escape")),
+                    }
+                }
+                c if is_name_start(c) || c.is_ascii_digit() || c == '-' => {
+                    name.push(c);
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+
+        if name.is_empty() {
+            return Err(match self.peek() {
+                Some(c) => syntax_error(&format!("unexpected {:?}", c)),
+                None => syntax_error("unexpected end of selector"),
+            });
+        }
+        Ok(name)
+    }
+}
+
+// https://www.w3.org/TR/css-syntax-3/#ident-start-code-point
+fn is_name_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || c == '-' || !c.is_ascii()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::helper::api::get_element_by_id;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use alloc::string::ToString;
+
+    fn parse_html(html: &str) -> Rc<RefCell<Node>> {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        document
+    }
+
+    #[test]
+    fn test_parse_selector_list() {
+        let list = parse_selector_list("div > p.note[title], #main a:not(.x)").expect("failed");
+        let selectors = list.selectors();
+        assert_eq!(2, selectors.len());
+
+        assert_eq!(vec![Combinator::Child], selectors[0].combinators());
+        assert_eq!(
+            vec![
+                SimpleSelector::Type("p".to_string()),
+                SimpleSelector::Class("note".to_string()),
+                SimpleSelector::Attribute {
+                    name: "title".to_string(),
+                    matcher: None,
+                    case_insensitive: false,
+                },
+            ],
+            selectors[0].compounds()[1].simple_selectors()
+        );
+
+        assert_eq!(vec![Combinator::Descendant], selectors[1].combinators());
+        assert_eq!(
+            vec![SimpleSelector::Id("main".to_string())],
+            selectors[1].compounds()[0].simple_selectors()
+        );
+    }
+
+    #[test]
+    fn test_invalid_selectors() {
+        for selector in [
+            "",
+            "div,",
+            "> p",
+            "p >",
+            "[title",
+            "a[title~]",
+            ":hover",
+            ":not(p",
+            "p!",
+            "#",
+        ] {
+            assert!(
+                parse_selector_list(selector).is_err(),
+                "{:?} should be invalid",
+                selector
+            );
+        }
+    }
+
+    #[test]
+    fn test_an_plus_b() {
+        for (input, expected) in [
+            ("odd", (2, 1)),
+            ("even", (2, 0)),
+            ("3", (0, 3)),
+            ("n", (1, 0)),
+            ("-n+3", (-1, 3)),
+            ("2n + 1", (2, 1)),
+            ("+3n-2", (3, -2)),
+        ] {
+            let list = parse_selector_list(&format!(":nth-child({})", input)).expect("failed");
+            assert_eq!(
+                vec![SimpleSelector::PseudoClass(PseudoClass::NthChild(
+                    expected.0, expected.1
+                ))],
+                list.selectors()[0].compounds()[0].simple_selectors()
+            );
+        }
+    }
+
+    #[test]
+    fn test_specificity() {
+        for (input, expected) in [
+            ("*", Specificity::new(0, 0, 0)),
+            ("li", Specificity::new(0, 0, 1)),
+            ("ul li", Specificity::new(0, 0, 2)),
+            ("ul > li.red", Specificity::new(0, 1, 2)),
+            ("a[href]:first-child", Specificity::new(0, 2, 1)),
+            ("#x", Specificity::new(1, 0, 0)),
+            (":not(#x, .a)", Specificity::new(1, 0, 0)),
+        ] {
+            let list = parse_selector_list(input).expect("failed");
+            assert_eq!(expected, list.selectors()[0].specificity());
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let document = parse_html(
+            "<div id=main class=\"a b\"><p id=p1 lang=en-US>1</p><span id=s1></span><p id=p2 title=\"Hello World\">2</p></div>",
+        );
+        let get = |id: &str| get_element_by_id(Some(document.clone()), &id.to_string()).unwrap();
+        let matches = |selector: &str, id: &str| {
+            parse_selector_list(selector)
+                .expect("failed to parse")
+                .matches(&get(id))
+        };
+
+        assert!(matches("DIV.b.a", "main"));
+        assert!(matches("body > div#main", "main"));
+        assert!(matches("html p", "p1"));
+        assert!(!matches("body > p", "p1"));
+        assert!(matches("p + span", "s1"));
+        assert!(matches("p ~ p", "p2"));
+        assert!(!matches("span + span", "s1"));
+        assert!(matches("[lang|=en]", "p1"));
+        assert!(matches("[title~=World]", "p2"));
+        assert!(matches("[title^=\"hello\" i]", "p2"));
+        assert!(!matches("[title^=hello]", "p2"));
+        assert!(matches("p:first-child", "p1"));
+        assert!(matches("p:last-child:nth-child(3)", "p2"));
+        assert!(matches("*:nth-child(odd)", "p2"));
+        assert!(matches(":not(p, div)", "s1"));
+        assert!(matches("span:empty", "s1"));
+        assert!(!matches(":root", "main"));
+        assert!(matches("#p1, #p2", "p2"));
+    }
+}
@@ -1,8 +1,9 @@
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 
 // There are 24 tokens defined in `CSS Syntax Module Level 3`,
-// but only 12 will be implemented
+// but only 13 will be implemented
 #[derive(Debug, Clone, PartialEq)]
 pub enum CssToken {
     /// https://www.w3.org/TR/css-syntax-3/#typedef-hash-token
@@ -27,6 +28,8 @@ pub enum CssToken {
     Ident(String),
     /// https://www.w3.org/TR/css-syntax-3/#typedef-string-token
     StringToken(String),
+    /// https://www.w3.org/TR/css-syntax-3/#typedef-bad-string-token
+    BadString,
     /// https://www.w3.org/TR/css-syntax-3/#typedef-at-keyword-token
     AtKeyword(String),
 }
@@ -52,6 +55,9 @@ impl CssTokenizer {
 
         loop {
             self.pos += 1;
+            if self.pos >= self.input.len() {
+                break;
+            }
             let c = self.input[self.pos];
             match c {
                 'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '_' => {
@@ -64,24 +70,56 @@ impl CssTokenizer {
         s
     }
 
-    // gives back string until the next " or '
-    fn consume_string_token(&mut self) -> String {
+    // https://www.w3.org/TR/css-syntax-3/#consume-string-token
+    // gives back string until the quote which it starts with. the string is cut at the end of
+    // the input, and is bad at a newline, which is reconsumed
+    fn consume_string_token(&mut self) -> CssToken {
+        let quote = self.input[self.pos];
         let mut s = String::new();
 
         loop {
+            self.pos += 1;
             if self.pos >= self.input.len() {
-                return s;
+                return CssToken::StringToken(s);
             }
 
-            self.pos += 1;
             let c = self.input[self.pos];
             match c {
-                '"' | '\'' => break,
+                '\n' => {
+                    self.pos -= 1;
+                    return CssToken::BadString;
+                }
+                // the escaped character is taken as it is
+                '\\' => {
+                    if let Some(escaped) = self.input.get(self.pos + 1) {
+                        if *escaped != '\n' {
+                            s.push(*escaped);
+                        }
+                        self.pos += 1;
+                    }
+                }
+                _ if c == quote => return CssToken::StringToken(s),
                 _ => s.push(c),
             }
         }
+    }
 
-        s
+    // https://www.w3.org/TR/css-syntax-3/#consume-comment
+    // skips the comment which starts at the current position, up to the end of the input
+    // if it isn't closed
+    fn skip_comment(&mut self) -> bool {
+        if self.input.get(self.pos) != Some(&'/') || self.input.get(self.pos + 1) != Some(&'*') {
+            return false;
+        }
+        self.pos += 2;
+        while self.pos < self.input.len() {
+            if self.input[self.pos] == '*' && self.input.get(self.pos + 1) == Some(&'/') {
+                self.pos += 2;
+                return true;
+            }
+            self.pos += 1;
+        }
+        true
     }
 
     // gives back number until continuous numeric symbols or period ends
@@ -117,6 +155,64 @@ impl CssTokenizer {
 
         num
     }
+
+    // https://www.w3.org/TR/css-syntax-3/#consume-a-qualified-rule
+    // gives back the prelude of the rule as is until { or the ; of an at-rule, as selectors need
+    // the whitespace and the characters which are not tokenized. None at the end of the input
+    pub fn consume_prelude(&mut self) -> Option<String> {
+        while self.pos < self.input.len() {
+            if self.input[self.pos].is_ascii_whitespace() {
+                self.pos += 1;
+            } else if !self.skip_comment() {
+                break;
+            }
+        }
+        if self.pos >= self.input.len() {
+            return None;
+        }
+
+        let mut s = String::new();
+        let mut quote = None;
+        while self.pos < self.input.len() {
+            if quote.is_none() && self.skip_comment() {
+                continue;
+            }
+            let c = self.input[self.pos];
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c == '{' || (c == ';' && s.starts_with('@')) => break,
+                None => {}
+            }
+            s.push(c);
+            self.pos += 1;
+        }
+        Some(s.trim_end().to_string())
+    }
+
+    // skips the rest of the block whose { is consumed, including the nested blocks
+    pub fn skip_block(&mut self) {
+        let mut depth = 1;
+        let mut quote = None;
+        while self.pos < self.input.len() {
+            let c = self.input[self.pos];
+            self.pos += 1;
+            match quote {
+                Some(q) if c == q => quote = None,
+                Some(_) => {}
+                None if c == '"' || c == '\'' => quote = Some(c),
+                None if c == '{' => depth += 1,
+                None if c == '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                None => {}
+            }
+        }
+    }
 }
 
 impl Iterator for CssTokenizer {
@@ -128,6 +224,10 @@ impl Iterator for CssTokenizer {
                 return None;
             }
 
+            if self.skip_comment() {
+                continue;
+            }
+
             let c = self.input[self.pos];
 
             let token = match c {
@@ -139,14 +239,11 @@ impl Iterator for CssTokenizer {
                 ';' => CssToken::SemiColon,
                 '{' => CssToken::OpenCurly,
                 '}' => CssToken::CloseCurly,
-                ' ' | '\n' => {
+                c if c.is_ascii_whitespace() => {
                     self.pos += 1;
                     continue;
                 }
-                '"' | '\'' => {
-                    let value = self.consume_string_token();
-                    CssToken::StringToken(value)
-                }
+                '"' | '\'' => self.consume_string_token(),
                 '0'..='9' => {
                     let t = CssToken::Number(self.consume_numeric_token());
                     self.pos -= 1;
@@ -166,9 +263,11 @@ impl Iterator for CssTokenizer {
                 }
                 '@' => {
                     // if the next 3 char represent an identifier
-                    if self.input[self.pos + 1].is_ascii_alphabetic()
-                        && self.input[self.pos + 2].is_alphanumeric()
-                        && self.input[self.pos + 3].is_alphanumeric()
+                    // the characters after the end of the input are none of them
+                    let next = |i: usize| self.input.get(self.pos + i).copied().unwrap_or(' ');
+                    if next(1).is_ascii_alphabetic()
+                        && next(2).is_alphanumeric()
+                        && next(3).is_alphanumeric()
                     {
                         self.pos += 1; // skip '@'
                         let t = CssToken::AtKeyword(self.consume_ident_token());
@@ -183,9 +282,9 @@ impl Iterator for CssTokenizer {
                     self.pos -= 1;
                     t
                 }
-                _ => {
-                    unimplemented!("char {} is not supported yet", c);
-                }
+                // https://www.w3.org/TR/css-syntax-3/#consume-token
+                // anything else, eg. '%' of a percentage or '!' of !important
+                _ => CssToken::Delim(c),
             };
 
             self.pos += 1;
@@ -198,6 +297,7 @@ impl Iterator for CssTokenizer {
 mod tests {
     use super::*;
     use alloc::string::ToString;
+    use alloc::vec;
 
     #[test]
     fn test_empty() {
@@ -293,4 +393,40 @@ mod tests {
         }
         assert!(t.next().is_none());
     }
+
+    #[test]
+    fn test_delim_and_comments() {
+        let style = "/* a */ p { width: 50% !important; }".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::Ident("p".to_string()),
+            CssToken::OpenCurly,
+            CssToken::Ident("width".to_string()),
+            CssToken::Colon,
+            CssToken::Number(50.0),
+            CssToken::Delim('%'),
+            CssToken::Delim('!'),
+            CssToken::Ident("important".to_string()),
+            CssToken::SemiColon,
+            CssToken::CloseCurly,
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
+
+    #[test]
+    fn test_bad_and_unterminated_strings() {
+        let style = "a: 'x\ny; b: \"it's".to_string();
+        let t = CssTokenizer::new(style);
+        let expected = vec![
+            CssToken::Ident("a".to_string()),
+            CssToken::Colon,
+            CssToken::BadString,
+            CssToken::Ident("y".to_string()),
+            CssToken::SemiColon,
+            CssToken::Ident("b".to_string()),
+            CssToken::Colon,
+            CssToken::StringToken("it's".to_string()),
+        ];
+        assert_eq!(expected, t.collect::<Vec<CssToken>>());
+    }
 }
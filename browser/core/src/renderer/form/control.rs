@@ -0,0 +1,481 @@
+use crate::renderer::html::dom::descendants;
+use crate::renderer::html::dom::set_dirty_checkedness;
+use crate::renderer::html::dom::set_dirty_value;
+use crate::renderer::html::dom::ElementKind;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::helper::api::attribute;
+use crate::renderer::html::helper::api::get_element_by_id;
+use crate::renderer::html::helper::api::get_text_content;
+use crate::renderer::html::helper::api::strip_and_collapse_whitespace;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// https://html.spec.whatwg.org/multipage/input.html#attr-input-type
+// the kinds of form controls, which decide how they are rendered, edited and submitted
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ControlType {
+    // text, search and tel, and unknown types of <input>
+    Text,
+    Password,
+    Email,
+    Url,
+    Number,
+    Hidden,
+    Checkbox,
+    Radio,
+    File,
+    Submit,
+    Image,
+    Reset,
+    Button,
+    Textarea,
+    Select,
+}
+
+impl ControlType {
+    // the value is edited as a text by the user
+    pub fn is_text_like(&self) -> bool {
+        matches!(
+            self,
+            ControlType::Text
+                | ControlType::Password
+                | ControlType::Email
+                | ControlType::Url
+                | ControlType::Number
+                | ControlType::Textarea
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/forms.html#concept-submit-button
+    pub fn is_submit_button(&self) -> bool {
+        matches!(self, ControlType::Submit | ControlType::Image)
+    }
+
+    pub fn is_button(&self) -> bool {
+        matches!(
+            self,
+            ControlType::Submit | ControlType::Image | ControlType::Reset | ControlType::Button
+        )
+    }
+}
+
+// gets the type of <input>, <button>, <select> and <textarea>, which is None for other nodes
+pub fn control_type(node: &Rc<RefCell<Node>>) -> Option<ControlType> {
+    let kind = node.borrow().element_kind()?;
+    let type_attribute = attribute(node, "type").map(|t| t.to_ascii_lowercase());
+    let control_type = match kind {
+        ElementKind::Input => match type_attribute.as_deref() {
+            Some("password") => ControlType::Password,
+            Some("email") => ControlType::Email,
+            Some("url") => ControlType::Url,
+            Some("number") => ControlType::Number,
+            Some("hidden") => ControlType::Hidden,
+            Some("checkbox") => ControlType::Checkbox,
+            Some("radio") => ControlType::Radio,
+            Some("file") => ControlType::File,
+            Some("submit") => ControlType::Submit,
+            Some("image") => ControlType::Image,
+            Some("reset") => ControlType::Reset,
+            Some("button") => ControlType::Button,
+            _ => ControlType::Text,
+        },
+        // https://html.spec.whatwg.org/multipage/form-elements.html#attr-button-type
+        ElementKind::Button => match type_attribute.as_deref() {
+            Some("reset") => ControlType::Reset,
+            Some("button") => ControlType::Button,
+            _ => ControlType::Submit,
+        },
+        ElementKind::Select => ControlType::Select,
+        ElementKind::Textarea => ControlType::Textarea,
+        _ => return None,
+    };
+    Some(control_type)
+}
+
+pub fn has_attribute(node: &Rc<RefCell<Node>>, name: &str) -> bool {
+    attribute(node, name).is_some()
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-fe-disabled
+pub fn is_disabled(node: &Rc<RefCell<Node>>) -> bool {
+    if has_attribute(node, "disabled") {
+        return true;
+    }
+
+    // a control in a disabled <fieldset> is disabled too
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        let is_disabled_fieldset = n
+            .borrow()
+            .get_element()
+            .is_some_and(|e| e.local_name() == "fieldset" && e.get_attribute("disabled").is_some());
+        if is_disabled_fieldset {
+            return true;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-owner
+// the form of the form attribute, or the nearest ancestor <form>
+pub fn form_owner(node: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    if let Some(id) = attribute(node, "form") {
+        let document = node.borrow().owner_document();
+        return get_element_by_id(document, &id)
+            .filter(|form| form.borrow().element_kind() == Some(ElementKind::Form));
+    }
+
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::Form) {
+            return Some(n);
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+// https://html.spec.whatwg.org/multipage/forms.html#category-listed
+// the controls whose form owner is the form, in tree order
+pub fn listed_elements(form: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    // controls outside of the form can be associated by the form attribute
+    let root = form.borrow().owner_document().unwrap_or(form.clone());
+    descendants(&root)
+        .filter(|n| control_type(n).is_some())
+        .filter(|n| form_owner(n).is_some_and(|owner| Rc::ptr_eq(&owner, form)))
+        .collect()
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-fe-value
+pub fn value(node: &Rc<RefCell<Node>>) -> String {
+    let control_type = match control_type(node) {
+        Some(t) => t,
+        None => return String::new(),
+    };
+    let element = match node.borrow().get_element() {
+        Some(e) => e,
+        None => return String::new(),
+    };
+
+    match control_type {
+        // https://html.spec.whatwg.org/multipage/form-elements.html#concept-textarea-raw-value
+        ControlType::Textarea => element
+            .dirty_value()
+            .unwrap_or_else(|| get_text_content(node)),
+        ControlType::Select => match selected_option(node) {
+            Some(option) => option_value(&option),
+            None => String::new(),
+        },
+        // https://html.spec.whatwg.org/multipage/input.html#dom-input-value-default-on
+        ControlType::Checkbox | ControlType::Radio => element
+            .get_attribute("value")
+            .unwrap_or_else(|| "on".to_string()),
+        // https://html.spec.whatwg.org/multipage/input.html#dom-input-value-default
+        t if t.is_button() => element.get_attribute("value").unwrap_or_default(),
+        t => {
+            let value = element
+                .dirty_value()
+                .or_else(|| element.get_attribute("value"))
+                .unwrap_or_default();
+            sanitize_value(t, &value)
+        }
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/input.html#value-sanitization-algorithm
+fn sanitize_value(control_type: ControlType, value: &str) -> String {
+    let value: String = value.chars().filter(|c| *c != '\n' && *c != '\r').collect();
+    match control_type {
+        ControlType::Email | ControlType::Url => value.trim().to_string(),
+        // an invalid floating-point number is not a value
+        ControlType::Number => match value.parse::<f64>() {
+            Ok(n) if n.is_finite() => value,
+            _ => String::new(),
+        },
+        _ => value,
+    }
+}
+
+// sets the value which the user edited, which is ignored by buttons, checkboxes and selects
+pub fn set_value(node: &Rc<RefCell<Node>>, value: &str) {
+    if control_type(node).is_some_and(|t| t.is_text_like() || t == ControlType::Hidden) {
+        set_dirty_value(node, Some(value.to_string()));
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/input.html#concept-fe-checked
+pub fn checkedness(node: &Rc<RefCell<Node>>) -> bool {
+    match node.borrow().get_element() {
+        Some(e) => e
+            .dirty_checkedness()
+            .unwrap_or(e.get_attribute("checked").is_some()),
+        None => false,
+    }
+}
+
+// checks or unchecks a checkbox or a radio button, and unchecks the others of the radio group
+pub fn set_checkedness(node: &Rc<RefCell<Node>>, checked: bool) {
+    match control_type(node) {
+        Some(ControlType::Checkbox) => set_dirty_checkedness(node, Some(checked)),
+        Some(ControlType::Radio) => {
+            if checked {
+                for other in radio_group(node) {
+                    set_dirty_checkedness(&other, Some(false));
+                }
+            }
+            set_dirty_checkedness(node, Some(checked));
+        }
+        _ => {}
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/input.html#radio-button-group
+// the radio buttons which have the same name and the same form owner, including the node
+pub fn radio_group(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let name = match attribute(node, "name") {
+        Some(name) if !name.is_empty() => name,
+        _ => return vec![node.clone()],
+    };
+    let owner = form_owner(node);
+    let root = match &owner {
+        Some(form) => form.borrow().owner_document().unwrap_or(form.clone()),
+        None => match node.borrow().owner_document() {
+            Some(document) => document,
+            None => return vec![node.clone()],
+        },
+    };
+
+    descendants(&root)
+        .filter(|n| control_type(n) == Some(ControlType::Radio))
+        .filter(|n| attribute(n, "name").as_ref() == Some(&name))
+        .filter(|n| match (&owner, form_owner(n)) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, &b),
+            (None, None) => true,
+            _ => false,
+        })
+        .collect()
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#concept-select-option-list
+pub fn options(select: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    descendants(select)
+        .filter(|n| n.borrow().element_kind() == Some(ElementKind::Option))
+        .collect()
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-value
+pub fn option_value(option: &Rc<RefCell<Node>>) -> String {
+    attribute(option, "value").unwrap_or_else(|| option_text(option))
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-label
+pub fn option_label(option: &Rc<RefCell<Node>>) -> String {
+    match attribute(option, "label") {
+        Some(label) if !label.is_empty() => label,
+        _ => option_text(option),
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#dom-option-text
+fn option_text(option: &Rc<RefCell<Node>>) -> String {
+    strip_and_collapse_whitespace(&descendant_text(option))
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#concept-option-selectedness
+fn selectedness(option: &Rc<RefCell<Node>>) -> bool {
+    match option.borrow().get_element() {
+        Some(e) => e
+            .dirty_checkedness()
+            .unwrap_or(e.get_attribute("selected").is_some()),
+        None => false,
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-elements.html#selectedness-setting-algorithm
+// the last selected option, or the first enabled option when none is selected.
+// multiple selections are not supported
+pub fn selected_option(select: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    let options = options(select);
+    if let Some(option) = options.iter().rev().find(|o| selectedness(o)) {
+        return Some(option.clone());
+    }
+    options.into_iter().find(|o| !has_attribute(o, "disabled"))
+}
+
+// selects the option of the index, and deselects the others
+pub fn select_option(select: &Rc<RefCell<Node>>, index: usize) {
+    for (i, option) in options(select).iter().enumerate() {
+        set_dirty_checkedness(option, Some(i == index));
+    }
+}
+
+// selects the next enabled option, which wraps around to the first one
+pub fn select_next_option(select: &Rc<RefCell<Node>>) {
+    let options = options(select);
+    let current = selected_option(select)
+        .and_then(|selected| options.iter().position(|o| Rc::ptr_eq(o, &selected)));
+    let start = current.map_or(0, |i| i + 1);
+    let next = (0..options.len())
+        .map(|offset| (start + offset) % options.len())
+        .find(|i| !has_attribute(&options[*i], "disabled"));
+    if let Some(index) = next {
+        select_option(select, index);
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-form-reset
+// restores the default values of the controls, which the attributes and the contents define
+pub fn reset_form(form: &Rc<RefCell<Node>>) {
+    for control in listed_elements(form) {
+        set_dirty_value(&control, None);
+        set_dirty_checkedness(&control, None);
+        for option in options(&control) {
+            set_dirty_checkedness(&option, None);
+        }
+    }
+}
+
+// the text which the control is rendered as, e.g. "[value   ]" for a text field and "[x]" for a
+// checked checkbox. hidden controls are not rendered
+pub fn control_label(node: &Rc<RefCell<Node>>) -> Option<String> {
+    let control_type = control_type(node)?;
+    let label = match control_type {
+        ControlType::Hidden => return None,
+        ControlType::Checkbox => {
+            if checkedness(node) {
+                "[x]".to_string()
+            } else {
+                "[ ]".to_string()
+            }
+        }
+        ControlType::Radio => {
+            if checkedness(node) {
+                "(*)".to_string()
+            } else {
+                "( )".to_string()
+            }
+        }
+        ControlType::Select => {
+            let selected = selected_option(node)
+                .map(|o| option_label(&o))
+                .unwrap_or_default();
+            format!("[{} v]", selected)
+        }
+        ControlType::File => "[no file selected]".to_string(),
+        t if t.is_button() => format!("[ {} ]", button_label(node, t)),
+        t => {
+            let value = value(node);
+            let text = if value.is_empty() {
+                attribute(node, "placeholder").unwrap_or_default()
+            } else if t == ControlType::Password {
+                "*".repeat(value.chars().count())
+            } else {
+                value.replace('\n', "\u{21b5}")
+            };
+            // https://html.spec.whatwg.org/multipage/input.html#attr-input-size
+            let width = attribute(node, "size")
+                .or_else(|| attribute(node, "cols"))
+                .and_then(|size| size.parse::<usize>().ok())
+                .filter(|size| *size > 0)
+                .unwrap_or(20);
+            format!("[{:<width$}]", text, width = width)
+        }
+    };
+    Some(label)
+}
+
+fn button_label(node: &Rc<RefCell<Node>>, control_type: ControlType) -> String {
+    if node.borrow().element_kind() == Some(ElementKind::Button) {
+        return strip_and_collapse_whitespace(&descendant_text(node));
+    }
+
+    // https://html.spec.whatwg.org/multipage/input.html#the-input-element:attr-input-value
+    let default = match control_type {
+        ControlType::Submit => "Submit",
+        ControlType::Reset => "Reset",
+        ControlType::Image => return attribute(node, "alt").unwrap_or("Submit".to_string()),
+        _ => "",
+    };
+    attribute(node, "value").unwrap_or(default.to_string())
+}
+
+// https://dom.spec.whatwg.org/#concept-descendant-text-content
+fn descendant_text(node: &Rc<RefCell<Node>>) -> String {
+    descendants(node)
+        .filter_map(|n| match n.borrow().kind() {
+            NodeKind::Text(t) => Some(t),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::helper::test_util::by_id;
+    use crate::renderer::html::helper::test_util::parse;
+
+    #[test]
+    fn test_values() {
+        let document = parse(
+            "<form id=f><input id=t value=\"a\nb\"><input id=n type=number value=x><input id=c type=checkbox checked><select id=s><option>one<option value=2 selected>two</select><textarea id=a>text</textarea></form><input id=o form=f name=outside>",
+        );
+        let text = by_id(&document, "t");
+        assert_eq!(Some(ControlType::Text), control_type(&text));
+        assert_eq!("ab", value(&text));
+        set_value(&text, "edited");
+        assert_eq!("edited", value(&text));
+        assert_eq!("", value(&by_id(&document, "n")));
+        assert_eq!("on", value(&by_id(&document, "c")));
+        assert_eq!("2", value(&by_id(&document, "s")));
+        assert_eq!("text", value(&by_id(&document, "a")));
+
+        let form = by_id(&document, "f");
+        let listed: Vec<String> = listed_elements(&form)
+            .iter()
+            .filter_map(|n| attribute(n, "id"))
+            .collect();
+        assert_eq!(vec!["t", "n", "c", "s", "a", "o"], listed);
+
+        reset_form(&form);
+        assert_eq!("ab", value(&text));
+    }
+
+    #[test]
+    fn test_checkedness_and_selection() {
+        let document = parse(
+            "<form><input type=radio name=r id=a checked><input type=radio name=r id=b><input type=checkbox id=c><select id=s><option>1<option disabled>2<option>3</select></form><input type=radio name=r id=d checked>",
+        );
+        let (a, b, d) = (
+            by_id(&document, "a"),
+            by_id(&document, "b"),
+            by_id(&document, "d"),
+        );
+        set_checkedness(&b, true);
+        assert!(!checkedness(&a));
+        assert!(checkedness(&b));
+        // a radio button outside of the form is in another group
+        assert!(checkedness(&d));
+
+        let checkbox = by_id(&document, "c");
+        assert_eq!(Some("[ ]".to_string()), control_label(&checkbox));
+        set_checkedness(&checkbox, true);
+        assert_eq!(Some("[x]".to_string()), control_label(&checkbox));
+
+        let select = by_id(&document, "s");
+        assert_eq!("1", value(&select));
+        // the disabled option is skipped
+        select_next_option(&select);
+        assert_eq!("3", value(&select));
+        select_next_option(&select);
+        assert_eq!("1", value(&select));
+        assert_eq!(Some("[1 v]".to_string()), control_label(&select));
+    }
+}
@@ -0,0 +1,4 @@
+pub mod control;
+pub mod pattern;
+pub mod submission;
+pub mod validation;
@@ -0,0 +1,546 @@
+use crate::constants::MAX_PATTERN_DEPTH;
+use crate::constants::MAX_PATTERN_SIZE;
+use crate::error::Error;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// https://html.spec.whatwg.org/multipage/input.html#the-pattern-attribute
+// a regular expression of the pattern attribute, which supports a subset of the syntax:
+// literals, ".", character classes, "\d\w\s", groups, "|", "^$" and quantifiers. the pattern
+// is compiled into a Thompson NFA, so that matching takes linear time in the input even for
+// patterns such as "(a*)*b", as the pattern is controlled by the page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    program: Vec<Instruction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternNode {
+    Literal(char),
+    // "." matches any character except line terminators
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Group(Vec<Vec<PatternNode>>),
+    // lazy quantifiers are parsed as greedy ones, since the laziness doesn't change whether the
+    // entire value matches
+    Repeat {
+        node: Box<PatternNode>,
+        min: usize,
+        max: Option<usize>,
+    },
+    Start,
+    End,
+}
+
+impl PatternNode {
+    // whether the node which consumes a character matches the character
+    fn matches(&self, c: char) -> bool {
+        match self {
+            PatternNode::Literal(l) => *l == c,
+            PatternNode::Any => c != '\n' && c != '\r',
+            PatternNode::Class { items, negated } => {
+                items.iter().any(|item| item.matches(c)) != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ClassItem::Range(from, to) => *from <= c && c <= *to,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != *negated,
+            ClassItem::Word(negated) => (c.is_ascii_alphanumeric() || c == '_') != *negated,
+            ClassItem::Space(negated) => c.is_whitespace() != *negated,
+        }
+    }
+}
+
+// https://swtch.com/~rsc/regexp/regexp1.html
+// an instruction of the NFA, where the other instructions follow the next one
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Instruction {
+    // consumes a character which the literal, "." or the class matches
+    Char(PatternNode),
+    // continues at both of the instructions
+    Split(usize, usize),
+    Jump(usize),
+    Start,
+    End,
+    Match,
+}
+
+impl Pattern {
+    // https://html.spec.whatwg.org/multipage/input.html#compiled-pattern-regular-expression
+    pub fn compile(source: &str) -> Result<Self, Error> {
+        let mut parser = PatternParser {
+            chars: source.chars().collect(),
+            pos: 0,
+            depth: 0,
+        };
+        let alternatives = parser.parse_alternatives()?;
+        if parser.pos < parser.chars.len() {
+            return Err(parser.error("unmatched )"));
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.emit_alternatives(&alternatives)?;
+        compiler.push(Instruction::Match)?;
+        Ok(Self {
+            program: compiler.program,
+        })
+    }
+
+    // the pattern must match the entire value, as if it was wrapped by "^(?:" and ")$". all
+    // the states of the NFA are followed at once, so each character is visited once
+    pub fn is_full_match(&self, input: &str) -> bool {
+        let chars: Vec<char> = input.chars().collect();
+        // the position where each instruction was added last, which adds it once per position
+        let mut added = vec![usize::MAX; self.program.len()];
+        let mut states = Vec::new();
+        self.add_state(&mut states, &mut added, 0, 0, chars.len());
+
+        for (pos, c) in chars.iter().enumerate() {
+            let mut next = Vec::new();
+            for pc in states {
+                if let Instruction::Char(node) = &self.program[pc] {
+                    if node.matches(*c) {
+                        self.add_state(&mut next, &mut added, pc + 1, pos + 1, chars.len());
+                    }
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            states = next;
+        }
+
+        states
+            .iter()
+            .any(|pc| self.program[*pc] == Instruction::Match)
+    }
+
+    // adds the instruction and the ones which are reached without consuming a character
+    fn add_state(
+        &self,
+        states: &mut Vec<usize>,
+        added: &mut [usize],
+        pc: usize,
+        pos: usize,
+        len: usize,
+    ) {
+        let mut stack = vec![pc];
+        while let Some(pc) = stack.pop() {
+            if added[pc] == pos {
+                continue;
+            }
+            added[pc] = pos;
+            match self.program[pc] {
+                Instruction::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Instruction::Jump(target) => stack.push(target),
+                Instruction::Start if pos == 0 => stack.push(pc + 1),
+                Instruction::End if pos == len => stack.push(pc + 1),
+                Instruction::Start | Instruction::End => {}
+                Instruction::Char(_) | Instruction::Match => states.push(pc),
+            }
+        }
+    }
+}
+
+struct Compiler {
+    program: Vec<Instruction>,
+}
+
+impl Compiler {
+    // counted repetitions are expanded, so the size of the program is limited
+    fn push(&mut self, instruction: Instruction) -> Result<usize, Error> {
+        if self.program.len() >= MAX_PATTERN_SIZE {
+            return Err(Error::UnexpectedInput(
+                "SyntaxError: invalid pattern: the pattern is too large".to_string(),
+            ));
+        }
+        self.program.push(instruction);
+        Ok(self.program.len() - 1)
+    }
+
+    fn patch(&mut self, pc: usize, target: usize) {
+        match &mut self.program[pc] {
+            Instruction::Split(_, second) => *second = target,
+            Instruction::Jump(next) => *next = target,
+            _ => {}
+        }
+    }
+
+    // each alternative but the last one is tried by a split, and jumps to the end after it
+    fn emit_alternatives(&mut self, alternatives: &[Vec<PatternNode>]) -> Result<(), Error> {
+        let mut jumps = Vec::new();
+        for (i, sequence) in alternatives.iter().enumerate() {
+            if i + 1 == alternatives.len() {
+                self.emit_sequence(sequence)?;
+                break;
+            }
+            let split = self.push(Instruction::Split(self.program.len() + 1, 0))?;
+            self.emit_sequence(sequence)?;
+            jumps.push(self.push(Instruction::Jump(0))?);
+            let next = self.program.len();
+            self.patch(split, next);
+        }
+        let end = self.program.len();
+        for jump in jumps {
+            self.patch(jump, end);
+        }
+        Ok(())
+    }
+
+    fn emit_sequence(&mut self, sequence: &[PatternNode]) -> Result<(), Error> {
+        for node in sequence {
+            self.emit_node(node)?;
+        }
+        Ok(())
+    }
+
+    fn emit_node(&mut self, node: &PatternNode) -> Result<(), Error> {
+        match node {
+            PatternNode::Group(alternatives) => self.emit_alternatives(alternatives),
+            PatternNode::Repeat { node, min, max } => {
+                for _ in 0..*min {
+                    self.emit_node(node)?;
+                }
+                match max {
+                    // the optional copies skip to the end
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Instruction::Split(self.program.len() + 1, 0))?);
+                            self.emit_node(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.patch(split, end);
+                        }
+                    }
+                    None => {
+                        let split = self.push(Instruction::Split(self.program.len() + 1, 0))?;
+                        self.emit_node(node)?;
+                        self.push(Instruction::Jump(split))?;
+                        let end = self.program.len();
+                        self.patch(split, end);
+                    }
+                }
+                Ok(())
+            }
+            PatternNode::Start => self.push(Instruction::Start).map(|_| ()),
+            PatternNode::End => self.push(Instruction::End).map(|_| ()),
+            node => self.push(Instruction::Char(node.clone())).map(|_| ()),
+        }
+    }
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+    // the nesting of the groups, which is limited as the groups are parsed recursively
+    depth: usize,
+}
+
+impl PatternParser {
+    fn error(&self, reason: &str) -> Error {
+        Error::UnexpectedInput(format!(
+            "SyntaxError: invalid pattern at {}: {}",
+            self.pos, reason
+        ))
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.pos += 1;
+        c
+    }
+
+    // Disjunction ::= Alternative ( "|" Alternative )*
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<PatternNode>>, Error> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    // Alternative ::= ( Term Quantifier? )*
+    fn parse_sequence(&mut self) -> Result<Vec<PatternNode>, Error> {
+        let mut sequence = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let term = self.parse_term()?;
+            let term = self.parse_quantifier(term)?;
+            sequence.push(term);
+        }
+        Ok(sequence)
+    }
+
+    fn parse_term(&mut self) -> Result<PatternNode, Error> {
+        match self.next() {
+            Some('^') => Ok(PatternNode::Start),
+            Some('$') => Ok(PatternNode::End),
+            Some('.') => Ok(PatternNode::Any),
+            Some('(') => {
+                // only non-capturing groups are distinguished, as captures are not used
+                if self.peek() == Some('?') {
+                    self.pos += 1;
+                    if self.next() != Some(':') {
+                        return Err(self.error("lookarounds are not supported"));
+                    }
+                }
+                self.depth += 1;
+                if self.depth > MAX_PATTERN_DEPTH {
+                    return Err(self.error("too many nested groups"));
+                }
+                let alternatives = self.parse_alternatives()?;
+                self.depth -= 1;
+                if self.next() != Some(')') {
+                    return Err(self.error("missing )"));
+                }
+                Ok(PatternNode::Group(alternatives))
+            }
+            Some('[') => self.parse_class(),
+            Some('\\') => self.parse_escape(),
+            Some(c @ ('*' | '+' | '?' | '{' | '}' | ']')) => {
+                Err(self.error(&format!("unexpected {}", c)))
+            }
+            Some(c) => Ok(PatternNode::Literal(c)),
+            None => Err(self.error("unexpected end")),
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<PatternNode, Error> {
+        let item = match self.next() {
+            Some('d') => ClassItem::Digit(false),
+            Some('D') => ClassItem::Digit(true),
+            Some('w') => ClassItem::Word(false),
+            Some('W') => ClassItem::Word(true),
+            Some('s') => ClassItem::Space(false),
+            Some('S') => ClassItem::Space(true),
+            Some(c) => return Ok(PatternNode::Literal(escaped_char(c))),
+            None => return Err(self.error("\\ at end of pattern")),
+        };
+        Ok(PatternNode::Class {
+            items: vec![item],
+            negated: false,
+        })
+    }
+
+    // CharacterClass ::= "[" "^"? ClassRanges "]"
+    fn parse_class(&mut self) -> Result<PatternNode, Error> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut items = Vec::new();
+        loop {
+            let from = match self.next() {
+                Some(']') => break,
+                Some('\\') => match self.next() {
+                    Some('d') => {
+                        items.push(ClassItem::Digit(false));
+                        continue;
+                    }
+                    Some('D') => {
+                        items.push(ClassItem::Digit(true));
+                        continue;
+                    }
+                    Some('w') => {
+                        items.push(ClassItem::Word(false));
+                        continue;
+                    }
+                    Some('W') => {
+                        items.push(ClassItem::Word(true));
+                        continue;
+                    }
+                    Some('s') => {
+                        items.push(ClassItem::Space(false));
+                        continue;
+                    }
+                    Some('S') => {
+                        items.push(ClassItem::Space(true));
+                        continue;
+                    }
+                    Some(c) => escaped_char(c),
+                    None => return Err(self.error("missing ]")),
+                },
+                Some(c) => c,
+                None => return Err(self.error("missing ]")),
+            };
+
+            // "-" at the end of the class is a literal
+            let is_range =
+                self.peek() == Some('-') && !matches!(self.chars.get(self.pos + 1), Some(']'));
+            if !is_range {
+                items.push(ClassItem::Range(from, from));
+                continue;
+            }
+            self.pos += 1;
+            let to = match self.next() {
+                Some('\\') => match self.next() {
+                    Some(c) => escaped_char(c),
+                    None => return Err(self.error("missing ]")),
+                },
+                Some(c) => c,
+                None => return Err(self.error("missing ]")),
+            };
+            if to < from {
+                return Err(self.error("range out of order"));
+            }
+            items.push(ClassItem::Range(from, to));
+        }
+
+        Ok(PatternNode::Class { items, negated })
+    }
+
+    // Quantifier ::= ( "*" | "+" | "?" | "{" n ( "," m? )? "}" ) "?"?
+    fn parse_quantifier(&mut self, node: PatternNode) -> Result<PatternNode, Error> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self
+                    .parse_number()
+                    .ok_or_else(|| self.error("missing number"))?;
+                let max = if self.peek() == Some(',') {
+                    self.pos += 1;
+                    self.parse_number()
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') {
+                    return Err(self.error("missing }"));
+                }
+                if max.is_some_and(|max| max < min) {
+                    return Err(self.error("numbers out of order"));
+                }
+                (min, max)
+            }
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+
+        if matches!(node, PatternNode::Start | PatternNode::End) {
+            return Err(self.error("nothing to repeat"));
+        }
+
+        if self.peek() == Some('?') {
+            self.pos += 1;
+        }
+
+        Ok(PatternNode::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+        })
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+}
+
+fn escaped_char(c: char) -> char {
+    match c {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        c => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, input: &str) -> bool {
+        Pattern::compile(pattern)
+            .expect("failed to compile")
+            .is_full_match(input)
+    }
+
+    #[test]
+    fn test_full_match() {
+        assert!(is_match("[0-9]{3}-[0-9]{4}", "123-4567"));
+        assert!(!is_match("[0-9]{3}-[0-9]{4}", "123-45678"));
+        assert!(is_match("\\d+", "42"));
+        assert!(!is_match("\\d+", ""));
+        assert!(is_match("[A-Za-z_]\\w*", "_ok1"));
+        assert!(!is_match("[^a-z]+", "ABc"));
+        assert!(is_match("a|bc", "bc"));
+        // the pattern is wrapped in a group, so that "|" does not escape the anchors
+        assert!(!is_match("a|bc", "abc"));
+        assert!(is_match("(?:ab)*c?", "ababc"));
+        assert!(is_match("a.c", "a-c"));
+        assert!(is_match("x\\.y", "x.y"));
+        assert!(!is_match("x\\.y", "x-y"));
+        assert!(is_match("(a*)*b", "aaab"));
+        assert!(is_match(".*?end", "the end"));
+        assert!(is_match("[a-]+", "a-a"));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Pattern::compile("(a").is_err());
+        assert!(Pattern::compile("a)").is_err());
+        assert!(Pattern::compile("[z-a]").is_err());
+        assert!(Pattern::compile("*a").is_err());
+        assert!(Pattern::compile("(?=a)").is_err());
+        assert!(Pattern::compile("a{3,1}").is_err());
+        assert!(Pattern::compile("(a{1000}){1000}").is_err());
+        assert!(Pattern::compile(&"(".repeat(100_000)).is_err());
+    }
+
+    #[test]
+    fn test_pathological_input() {
+        // the backtracking takes exponential time for the nested repetition
+        assert!(!is_match("(a*)*b", &"a".repeat(26)));
+        assert!(!is_match("(a|a)*(a|a)*c", &"a".repeat(1000)));
+        // the recursion overflows the stack for the long value
+        let long = "x".repeat(100_000);
+        assert!(is_match(".*", &long));
+        assert!(is_match("x+?", &long));
+    }
+}
@@ -0,0 +1,449 @@
+use crate::error::Error;
+use crate::http::HttpResponse;
+use crate::renderer::form::control::checkedness;
+use crate::renderer::form::control::control_type;
+use crate::renderer::form::control::is_disabled;
+use crate::renderer::form::control::listed_elements;
+use crate::renderer::form::control::option_value;
+use crate::renderer::form::control::selected_option;
+use crate::renderer::form::control::value;
+use crate::renderer::form::control::ControlType;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::helper::api::attribute;
+use crate::url::resolve_url;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// sends a form submission with the POST method, and returns the response
+pub type FormSender = fn(FormSubmission) -> Result<HttpResponse, Error>;
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#attr-fs-method
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#attr-fs-enctype
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FormEnctype {
+    UrlEncoded,
+    Multipart,
+    TextPlain,
+}
+
+impl FormEnctype {
+    // an invalid value is the default, application/x-www-form-urlencoded
+    fn from_attribute(value: &str) -> Self {
+        if value.eq_ignore_ascii_case("multipart/form-data") {
+            FormEnctype::Multipart
+        } else if value.eq_ignore_ascii_case("text/plain") {
+            FormEnctype::TextPlain
+        } else {
+            FormEnctype::UrlEncoded
+        }
+    }
+}
+
+// https://xhr.spec.whatwg.org/#concept-formdata-entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormEntry {
+    name: String,
+    value: String,
+    // the file name of a file entry, whose contents are always empty
+    filename: Option<String>,
+}
+
+impl FormEntry {
+    pub fn new(name: String, value: String) -> Self {
+        Self {
+            name,
+            value,
+            filename: None,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn value(&self) -> String {
+        self.value.clone()
+    }
+
+    pub fn filename(&self) -> Option<String> {
+        self.filename.clone()
+    }
+}
+
+// a request which submits a form, where GET submissions carry the entries in the URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormSubmission {
+    method: FormMethod,
+    url: String,
+    // the Content-Type header of a POST submission
+    content_type: Option<String>,
+    body: Option<String>,
+}
+
+impl FormSubmission {
+    pub fn method(&self) -> FormMethod {
+        self.method
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn content_type(&self) -> Option<String> {
+        self.content_type.clone()
+    }
+
+    pub fn body(&self) -> Option<String> {
+        self.body.clone()
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constructing-the-form-data-set
+// collects the names and the values of the controls, where only the submitter of the buttons is
+// included
+pub fn construct_entry_list(
+    form: &Rc<RefCell<Node>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+) -> Vec<FormEntry> {
+    let mut entries = Vec::new();
+
+    for control in listed_elements(form) {
+        let control_type = match control_type(&control) {
+            Some(t) => t,
+            None => continue,
+        };
+        if is_disabled(&control) {
+            continue;
+        }
+        let is_submitter = submitter.is_some_and(|s| Rc::ptr_eq(s, &control));
+        if control_type.is_button() && !is_submitter {
+            continue;
+        }
+        if matches!(control_type, ControlType::Checkbox | ControlType::Radio)
+            && !checkedness(&control)
+        {
+            continue;
+        }
+
+        let name = match attribute(&control, "name") {
+            Some(name) if !name.is_empty() => name,
+            _ => continue,
+        };
+
+        match control_type {
+            // the coordinate where the image is clicked, which is always the origin
+            ControlType::Image => {
+                entries.push(FormEntry::new(format!("{}.x", name), "0".to_string()));
+                entries.push(FormEntry::new(format!("{}.y", name), "0".to_string()));
+            }
+            ControlType::Select => {
+                if let Some(option) = selected_option(&control) {
+                    if !is_disabled(&option) {
+                        entries.push(FormEntry::new(name, option_value(&option)));
+                    }
+                }
+            }
+            // selecting files is not supported, so that a file entry is always empty
+            ControlType::File => entries.push(FormEntry {
+                name,
+                value: String::new(),
+                filename: Some(String::new()),
+            }),
+            ControlType::Hidden if name.eq_ignore_ascii_case("_charset_") => {
+                entries.push(FormEntry::new(name, "UTF-8".to_string()));
+            }
+            _ => entries.push(FormEntry::new(name, value(&control))),
+        }
+    }
+
+    entries
+}
+
+// https://url.spec.whatwg.org/#concept-urlencoded-serializer
+pub fn encode_urlencoded(entries: &[FormEntry]) -> String {
+    entries
+        .iter()
+        .map(|entry| {
+            let value = entry.filename.as_ref().unwrap_or(&entry.value);
+            format!(
+                "{}={}",
+                percent_encode(&normalize_newlines(&entry.name)),
+                percent_encode(&normalize_newlines(value))
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+// https://url.spec.whatwg.org/#application-x-www-form-urlencoded-percent-encode-set
+fn percent_encode(s: &str) -> String {
+    let mut encoded = String::new();
+    for byte in s.bytes() {
+        match byte {
+            b'*' | b'-' | b'.' | b'_' => encoded.push(byte as char),
+            b if b.is_ascii_alphanumeric() => encoded.push(b as char),
+            b' ' => encoded.push('+'),
+            b => encoded.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    encoded
+}
+
+// https://infra.spec.whatwg.org/#normalize-newlines
+// converts every line break to CRLF
+fn normalize_newlines(s: &str) -> String {
+    s.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\r\n")
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart/form-data-encoding-algorithm
+pub fn encode_multipart(entries: &[FormEntry], boundary: &str) -> String {
+    // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart-form-data
+    // quotes and line breaks in names are escaped
+    let escape = |s: &str| {
+        s.replace('\n', "%0A")
+            .replace('\r', "%0D")
+            .replace('"', "%22")
+    };
+
+    let mut body = String::new();
+    for entry in entries {
+        body.push_str(&format!("--{}\r\n", boundary));
+        body.push_str(&format!(
+            "Content-Disposition: form-data; name=\"{}\"",
+            escape(&normalize_newlines(&entry.name))
+        ));
+        match &entry.filename {
+            Some(filename) => {
+                body.push_str(&format!("; filename=\"{}\"\r\n", escape(filename)));
+                body.push_str("Content-Type: application/octet-stream\r\n\r\n");
+            }
+            None => {
+                body.push_str("\r\n\r\n");
+                body.push_str(&normalize_newlines(&entry.value));
+            }
+        }
+        body.push_str("\r\n");
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#text/plain-encoding-algorithm
+pub fn encode_text_plain(entries: &[FormEntry]) -> String {
+    let mut body = String::new();
+    for entry in entries {
+        let value = entry.filename.as_ref().unwrap_or(&entry.value);
+        body.push_str(&normalize_newlines(&entry.name));
+        body.push('=');
+        body.push_str(&normalize_newlines(value));
+        body.push_str("\r\n");
+    }
+    body
+}
+
+// a boundary of multipart/form-data, which doesn't appear in any entry
+fn choose_boundary(entries: &[FormEntry]) -> String {
+    let mut n = 0;
+    loop {
+        let boundary = format!("----SimpleBrowserFormBoundary{}", n);
+        let is_used = entries
+            .iter()
+            .any(|e| e.name.contains(&boundary) || e.value.contains(&boundary));
+        if !is_used {
+            return boundary;
+        }
+        n += 1;
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-submission-algorithm
+// decides the request of the submission, where the formaction, formmethod and formenctype
+// attributes of the submitter override the attributes of the form
+pub fn plan_submission(
+    form: &Rc<RefCell<Node>>,
+    submitter: Option<&Rc<RefCell<Node>>>,
+    document_url: &str,
+    base_url: &str,
+) -> FormSubmission {
+    let form_attribute = |name: &str| {
+        submitter
+            .and_then(|s| attribute(s, &format!("form{}", name)))
+            .or_else(|| attribute(form, name))
+    };
+
+    // an empty action submits the form to the document itself
+    let url = match form_attribute("action") {
+        Some(action) if !action.trim().is_empty() => resolve_url(base_url, &action),
+        _ => document_url.to_string(),
+    };
+    let method = match form_attribute("method") {
+        Some(method) if method.eq_ignore_ascii_case("post") => FormMethod::Post,
+        _ => FormMethod::Get,
+    };
+    let enctype = FormEnctype::from_attribute(&form_attribute("enctype").unwrap_or_default());
+    let entries = construct_entry_list(form, submitter);
+
+    match method {
+        // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#submit-mutate-action
+        // the query of the action is replaced, and the fragment is kept
+        FormMethod::Get => {
+            let (without_fragment, fragment) = match url.split_once('#') {
+                Some((url, fragment)) => (url.to_string(), Some(fragment.to_string())),
+                None => (url.clone(), None),
+            };
+            let without_query = without_fragment
+                .split_once('?')
+                .map_or(without_fragment.clone(), |(url, _)| url.to_string());
+            let mut url = format!("{}?{}", without_query, encode_urlencoded(&entries));
+            if let Some(fragment) = fragment {
+                url.push('#');
+                url.push_str(&fragment);
+            }
+            FormSubmission {
+                method,
+                url,
+                content_type: None,
+                body: None,
+            }
+        }
+        // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#submit-body
+        FormMethod::Post => {
+            let (content_type, body) = match enctype {
+                FormEnctype::UrlEncoded => (
+                    "application/x-www-form-urlencoded".to_string(),
+                    encode_urlencoded(&entries),
+                ),
+                FormEnctype::Multipart => {
+                    let boundary = choose_boundary(&entries);
+                    (
+                        format!("multipart/form-data; boundary={}", boundary),
+                        encode_multipart(&entries, &boundary),
+                    )
+                }
+                FormEnctype::TextPlain => ("text/plain".to_string(), encode_text_plain(&entries)),
+            };
+            FormSubmission {
+                method,
+                url,
+                content_type: Some(content_type),
+                body: Some(body),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::helper::test_util::by_id;
+    use crate::renderer::html::helper::test_util::parse;
+    use alloc::vec;
+
+    #[test]
+    fn test_entry_list() {
+        let document = parse(
+            "<form id=f><input name=q value=\"a b&c\"><input name=no disabled value=x><input type=checkbox name=c><input type=checkbox name=d checked><input type=radio name=r value=1><input type=radio name=r value=2 checked><select name=s><option>x<option selected value=y>Y</select><textarea name=t>l1\nl2</textarea><input type=submit name=go value=Go id=go><input type=submit name=other value=Other><input value=unnamed></form>",
+        );
+        let form = by_id(&document, "f");
+        let submitter = by_id(&document, "go");
+
+        let entries = construct_entry_list(&form, Some(&submitter));
+        assert_eq!(
+            vec![
+                FormEntry::new("q".to_string(), "a b&c".to_string()),
+                FormEntry::new("d".to_string(), "on".to_string()),
+                FormEntry::new("r".to_string(), "2".to_string()),
+                FormEntry::new("s".to_string(), "y".to_string()),
+                FormEntry::new("t".to_string(), "l1\nl2".to_string()),
+                FormEntry::new("go".to_string(), "Go".to_string()),
+            ],
+            entries
+        );
+        assert_eq!(
+            "q=a+b%26c&d=on&r=2&s=y&t=l1%0D%0Al2&go=Go",
+            encode_urlencoded(&entries)
+        );
+        assert_eq!(
+            "q=a b&c\r\nd=on\r\nr=2\r\ns=y\r\nt=l1\r\nl2\r\ngo=Go\r\n",
+            encode_text_plain(&entries)
+        );
+    }
+
+    #[test]
+    fn test_multipart() {
+        let entries = vec![
+            FormEntry::new("a\"b".to_string(), "x\ny".to_string()),
+            FormEntry {
+                name: "f".to_string(),
+                value: String::new(),
+                filename: Some(String::new()),
+            },
+        ];
+        assert_eq!(
+            "--B\r\nContent-Disposition: form-data; name=\"a%22b\"\r\n\r\nx\r\ny\r\n--B\r\nContent-Disposition: form-data; name=\"f\"; filename=\"\"\r\nContent-Type: application/octet-stream\r\n\r\n\r\n--B--\r\n",
+            encode_multipart(&entries, "B")
+        );
+    }
+
+    #[test]
+    fn test_plan_submission() {
+        let document = parse(
+            "<form id=f action=\"search?old=1#top\"><input name=q value=rust><button id=post formmethod=post formenctype=multipart/form-data formaction=/upload>Send</button></form><form id=empty method=POST></form>",
+        );
+        let form = by_id(&document, "f");
+
+        let submission = plan_submission(
+            &form,
+            None,
+            "http://example.com/dir/page.html",
+            "http://example.com/dir/page.html",
+        );
+        assert_eq!(FormMethod::Get, submission.method());
+        assert_eq!("http://example.com/dir/search?q=rust#top", submission.url());
+        assert_eq!(None, submission.body());
+
+        let submitter = by_id(&document, "post");
+        let submission = plan_submission(
+            &form,
+            Some(&submitter),
+            "http://example.com/dir/page.html",
+            "http://example.com/base/",
+        );
+        assert_eq!(FormMethod::Post, submission.method());
+        assert_eq!("http://example.com/upload", submission.url());
+        assert_eq!(
+            Some("multipart/form-data; boundary=----SimpleBrowserFormBoundary0".to_string()),
+            submission.content_type()
+        );
+        assert_eq!(
+            Some("------SimpleBrowserFormBoundary0\r\nContent-Disposition: form-data; name=\"q\"\r\n\r\nrust\r\n------SimpleBrowserFormBoundary0--\r\n".to_string()),
+            submission.body()
+        );
+
+        // an empty action submits to the document URL, not the base URL
+        let submission = plan_submission(
+            &by_id(&document, "empty"),
+            None,
+            "http://example.com/dir/page.html",
+            "http://example.com/base/",
+        );
+        assert_eq!("http://example.com/dir/page.html", submission.url());
+        assert_eq!(
+            Some("application/x-www-form-urlencoded".to_string()),
+            submission.content_type()
+        );
+        assert_eq!(Some(String::new()), submission.body());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::renderer::form::control::checkedness;
+use crate::renderer::form::control::control_type;
+use crate::renderer::form::control::has_attribute;
+use crate::renderer::form::control::is_disabled;
+use crate::renderer::form::control::radio_group;
+use crate::renderer::form::control::value;
+use crate::renderer::form::control::ControlType;
+use crate::renderer::form::pattern::Pattern;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::helper::api::attribute;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::cell::RefCell;
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#the-constraint-validation-api
+// the flags of the constraints which the value of a control doesn't satisfy
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidityState {
+    value_missing: bool,
+    type_mismatch: bool,
+    pattern_mismatch: bool,
+    too_long: bool,
+    too_short: bool,
+}
+
+impl ValidityState {
+    pub fn value_missing(&self) -> bool {
+        self.value_missing
+    }
+
+    pub fn type_mismatch(&self) -> bool {
+        self.type_mismatch
+    }
+
+    pub fn pattern_mismatch(&self) -> bool {
+        self.pattern_mismatch
+    }
+
+    pub fn too_long(&self) -> bool {
+        self.too_long
+    }
+
+    pub fn too_short(&self) -> bool {
+        self.too_short
+    }
+
+    pub fn is_valid(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // a message which is shown to the user for the first failing constraint
+    pub fn message(&self) -> Option<String> {
+        let message = if self.value_missing {
+            "Please fill out this field."
+        } else if self.type_mismatch {
+            "Please enter a valid value."
+        } else if self.pattern_mismatch {
+            "Please match the requested format."
+        } else if self.too_long {
+            "Please shorten this text."
+        } else if self.too_short {
+            "Please lengthen this text."
+        } else {
+            return None;
+        };
+        Some(message.to_string())
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#barred-from-constraint-validation
+pub fn is_barred_from_validation(node: &Rc<RefCell<Node>>) -> bool {
+    match control_type(node) {
+        Some(ControlType::Hidden) | None => true,
+        Some(t) if t.is_button() => true,
+        Some(t) => {
+            is_disabled(node) || (has_attribute(node, "readonly") && t != ControlType::Select)
+        }
+    }
+}
+
+// checks the constraints of the required, pattern, minlength and maxlength attributes and the
+// type of the control
+pub fn validity(node: &Rc<RefCell<Node>>) -> ValidityState {
+    let mut state = ValidityState::default();
+    if is_barred_from_validation(node) {
+        return state;
+    }
+    let control_type = match control_type(node) {
+        Some(t) => t,
+        None => return state,
+    };
+    let value = value(node);
+
+    // https://html.spec.whatwg.org/multipage/input.html#the-required-attribute
+    if has_attribute(node, "required") {
+        state.value_missing = match control_type {
+            ControlType::Checkbox => !checkedness(node),
+            ControlType::Radio => !radio_group(node).iter().any(checkedness),
+            ControlType::File => true,
+            _ => value.is_empty(),
+        };
+    }
+
+    if !control_type.is_text_like() || value.is_empty() {
+        return state;
+    }
+
+    // https://html.spec.whatwg.org/multipage/input.html#email-state-(type=email)
+    // https://html.spec.whatwg.org/multipage/input.html#url-state-(type=url)
+    state.type_mismatch = match control_type {
+        ControlType::Email => !is_valid_email(&value),
+        ControlType::Url => !is_absolute_url(&value),
+        _ => false,
+    };
+
+    // https://html.spec.whatwg.org/multipage/input.html#attr-input-pattern
+    // an invalid pattern is ignored
+    if control_type != ControlType::Textarea {
+        if let Some(pattern) = attribute(node, "pattern").and_then(|p| Pattern::compile(&p).ok()) {
+            state.pattern_mismatch = !pattern.is_full_match(&value);
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#limiting-user-input-length:-the-maxlength-attribute
+    // lengths are checked only for values which the user edited
+    let is_dirty = node
+        .borrow()
+        .get_element()
+        .is_some_and(|e| e.dirty_value().is_some());
+    if is_dirty {
+        let length = value.encode_utf16().count();
+        let limit = |name: &str| attribute(node, name).and_then(|v| v.parse::<usize>().ok());
+        state.too_long = limit("maxlength").is_some_and(|max| length > max);
+        state.too_short = limit("minlength").is_some_and(|min| length < min);
+    }
+
+    state
+}
+
+// https://html.spec.whatwg.org/multipage/input.html#valid-e-mail-address
+fn is_valid_email(value: &str) -> bool {
+    let (local, domain) = match value.split_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let is_local_char = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+/=?^_`{|}~.-".contains(c);
+    !local.is_empty()
+        && local.chars().all(is_local_char)
+        && !domain.is_empty()
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+// https://url.spec.whatwg.org/#absolute-url-string
+fn is_absolute_url(value: &str) -> bool {
+    match value.split_once(':') {
+        Some((scheme, rest)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                && !rest.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+// the messages of the controls which are invalid, e.g. "name: Please fill out this field."
+pub fn validation_message(node: &Rc<RefCell<Node>>) -> Option<String> {
+    let message = validity(node).message()?;
+    let name = attribute(node, "name")
+        .or_else(|| attribute(node, "id"))
+        .unwrap_or_else(|| "field".to_string());
+    Some(format!("{}: {}", name, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::form::control::set_value;
+    use crate::renderer::html::helper::api::get_element_by_id;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    #[test]
+    fn test_validity() {
+        let window = HtmlParser::new(HtmlTokenizer::new(
+            "<form><input id=r required><input id=p pattern=\"[a-z]+\" value=\"abc1\"><input id=e type=email value=\"a@b.c\"><input id=u type=url value=\"no scheme\"><input id=l maxlength=3><input type=radio name=g id=g required><input type=radio name=g><input id=d required disabled><input type=submit required></form>"
+                .to_string(),
+        ))
+        .construct_tree();
+        let document = window.borrow().document();
+        let by_id = |id: &str| {
+            get_element_by_id(Some(document.clone()), &id.to_string()).expect("no element")
+        };
+
+        assert!(validity(&by_id("r")).value_missing());
+        assert_eq!(
+            Some("r: Please fill out this field.".to_string()),
+            validation_message(&by_id("r"))
+        );
+        assert!(validity(&by_id("p")).pattern_mismatch());
+        set_value(&by_id("p"), "abc");
+        assert!(validity(&by_id("p")).is_valid());
+        assert!(validity(&by_id("e")).is_valid());
+        assert!(validity(&by_id("u")).type_mismatch());
+        set_value(&by_id("l"), "long");
+        assert!(validity(&by_id("l")).too_long());
+        assert!(validity(&by_id("g")).value_missing());
+        // disabled controls and buttons are barred from validation
+        assert!(validity(&by_id("d")).is_valid());
+        assert_eq!(None, validation_message(&by_id("d")));
+    }
+}
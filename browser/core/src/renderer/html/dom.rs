@@ -1,34 +1,239 @@
+use crate::constants::USER_AGENT;
+use crate::constants::WINDOW_HEIGHT;
+use crate::constants::WINDOW_WIDTH;
+use crate::error::Error;
+use crate::renderer::html::event::EventListener;
 use crate::renderer::html::helper::attribute::Attribute;
+use crate::renderer::image::bitmap::Image;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
 use alloc::format;
 use alloc::rc::{Rc, Weak};
 use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::cmp::Ordering;
+use core::fmt::Debug;
 use core::fmt::Display;
 use core::fmt::Formatter;
 use core::str::FromStr;
 
+// a stable handle of a node, which is the index in the arena of its document
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
+
+// owns all nodes of a document, and nodes refer to each other by NodeId
+#[derive(Debug)]
+pub struct NodeArena {
+    // a node moved to another arena leaves an empty slot, so that ids are never reused
+    nodes: Vec<Option<Rc<RefCell<Node>>>>,
+    // the document owns the arena, so it is referred weakly to avoid a cycle
+    document: Option<(NodeId, Weak<RefCell<Node>>)>,
+    // live indexes of the elements connected to the document
+    ids: ElementIndex,
+    classes: ElementIndex,
+    tags: ElementIndex,
+    // https://html.spec.whatwg.org/multipage/scripting.html#associated-inert-template-document
+    // the document which owns the contents of <template>, where scripts never run
+    inert_document: Option<Rc<RefCell<Node>>>,
+    // the contents of each <template> in the arena
+    template_contents: BTreeMap<NodeId, Rc<RefCell<Node>>>,
+    // the number of removals since the detached nodes were collected last
+    removals: usize,
+}
+
+impl NodeArena {
+    fn new() -> Rc<RefCell<Self>> {
+        Rc::new(RefCell::new(Self {
+            nodes: Vec::new(),
+            document: None,
+            ids: ElementIndex::new(),
+            classes: ElementIndex::new(),
+            tags: ElementIndex::new(),
+            inert_document: None,
+            template_contents: BTreeMap::new(),
+            removals: 0,
+        }))
+    }
+
+    fn index(&mut self, kind: IndexKind) -> &mut ElementIndex {
+        match kind {
+            IndexKind::Id => &mut self.ids,
+            IndexKind::Class => &mut self.classes,
+            IndexKind::Tag => &mut self.tags,
+        }
+    }
+
+    // the document which owns the arena
+    pub fn document(&self) -> Option<Rc<RefCell<Node>>> {
+        self.document
+            .as_ref()
+            .and_then(|(_, document)| document.upgrade())
+    }
+
+    pub fn get(&self, id: NodeId) -> Option<Rc<RefCell<Node>>> {
+        if let Some((document_id, ref document)) = self.document {
+            if document_id == id {
+                return document.upgrade();
+            }
+        }
+
+        match self.nodes.get(id.0) {
+            Some(Some(node)) => Some(node.clone()),
+            _ => None,
+        }
+    }
+
+    // the number of ids which have been assigned
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    fn push(&mut self, node: Rc<RefCell<Node>>) -> NodeId {
+        self.nodes.push(Some(node));
+        NodeId(self.nodes.len() - 1)
+    }
+}
+
+// empties the slots of the detached subtrees which nothing but the arena refers to, so that
+// removed nodes are freed. it runs once the removals reach half of the slots, which keeps the
+// cost amortized
+fn collect_garbage(arena: &Rc<RefCell<NodeArena>>) {
+    {
+        let mut arena = arena.borrow_mut();
+        if arena.removals < (arena.nodes.len() / 2).max(64) {
+            return;
+        }
+        arena.removals = 0;
+    }
+
+    let nodes: Vec<Rc<RefCell<Node>>> = arena.borrow().nodes.iter().flatten().cloned().collect();
+    for root in nodes.iter().filter(|n| n.borrow().parent.is_none()) {
+        let mut subtree = vec![root.clone()];
+        subtree.extend(descendants(root));
+        // the arena, `nodes` and `subtree` refer to each node
+        if subtree.iter().any(|n| Rc::strong_count(n) > 3) {
+            continue;
+        }
+        let mut arena = arena.borrow_mut();
+        for n in &subtree {
+            if let Some(id) = n.borrow().id {
+                arena.nodes[id.0] = None;
+                arena.template_contents.remove(&id);
+            }
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-document
+// creates a document node, which owns a new arena for the nodes of the document
+pub fn create_document() -> Rc<RefCell<Node>> {
+    let arena = NodeArena::new();
+    let document = Rc::new(RefCell::new(Node::new(NodeKind::Document)));
+
+    let id = {
+        let mut arena = arena.borrow_mut();
+        arena.nodes.push(None);
+        let id = NodeId(arena.nodes.len() - 1);
+        arena.document = Some((id, Rc::downgrade(&document)));
+        id
+    };
+
+    {
+        let mut d = document.borrow_mut();
+        d.id = Some(id);
+        d.arena = Rc::downgrade(&arena);
+        d.owned_arena = Some(arena);
+    }
+    document
+}
+
+// https://html.spec.whatwg.org/multipage/scripting.html#template-contents
+// the fragment of the contents of <template>, which is created in the inert document the first
+// time it is needed, or None for the other nodes and a template without an arena
+pub fn template_contents(template: &Rc<RefCell<Node>>) -> Option<Rc<RefCell<Node>>> {
+    if template.borrow().element_kind() != Some(ElementKind::Template) {
+        return None;
+    }
+    let id = template.borrow().id()?;
+    let arena = template.borrow().arena()?;
+
+    if let Some(contents) = arena.borrow().template_contents.get(&id) {
+        return Some(contents.clone());
+    }
+
+    let inert_document = arena
+        .borrow_mut()
+        .inert_document
+        .get_or_insert_with(create_document)
+        .clone();
+    let inert_arena = inert_document.borrow().arena()?;
+    let contents = create_node(&inert_arena, NodeKind::DocumentFragment);
+    arena
+        .borrow_mut()
+        .template_contents
+        .insert(id, contents.clone());
+    Some(contents)
+}
+
+// creates a node owned by the arena
+pub fn create_node(arena: &Rc<RefCell<NodeArena>>, kind: NodeKind) -> Rc<RefCell<Node>> {
+    let node = Rc::new(RefCell::new(Node::new(kind)));
+    let id = arena.borrow_mut().push(node.clone());
+    node.borrow_mut().id = Some(id);
+    node.borrow_mut().arena = Rc::downgrade(arena);
+    node
+}
+
 #[derive(Debug, Clone)]
 pub struct Node {
     pub kind: NodeKind,
+    // None until the node is owned by an arena
+    id: Option<NodeId>,
+    arena: Weak<RefCell<NodeArena>>,
+    // only a document owns its arena
+    owned_arena: Option<Rc<RefCell<NodeArena>>>,
+    // only a document has a window, which owns the document
     window: Weak<RefCell<Window>>,
-    parent: Weak<RefCell<Node>>,
-    first_child: Option<Rc<RefCell<Node>>>,
-    last_child: Weak<RefCell<Node>>,
-    previous_sibling: Weak<RefCell<Node>>,
-    next_sibling: Option<Rc<RefCell<Node>>>,
+    parent: Option<NodeId>,
+    first_child: Option<NodeId>,
+    last_child: Option<NodeId>,
+    previous_sibling: Option<NodeId>,
+    next_sibling: Option<NodeId>,
+    registered_observers: Vec<RegisteredObserver>,
+    // https://dom.spec.whatwg.org/#eventtarget-event-listener-list
+    event_listeners: Vec<EventListener>,
 }
 
 impl Node {
+    // creates a node which doesn't belong to any arena yet, and is adopted into the arena of
+    // the parent when it is inserted
     pub fn new(kind: NodeKind) -> Self {
         Self {
             kind: kind.clone(),
+            id: None,
+            arena: Weak::new(),
+            owned_arena: None,
             window: Weak::new(),
-            parent: Weak::new(),
+            parent: None,
             first_child: None,
-            last_child: Weak::new(),
-            previous_sibling: Weak::new(),
+            last_child: None,
+            previous_sibling: None,
             next_sibling: None,
+            registered_observers: Vec::new(),
+            event_listeners: Vec::new(),
         }
     }
 
@@ -36,62 +241,91 @@ impl Node {
         self.kind.clone()
     }
 
+    pub fn id(&self) -> Option<NodeId> {
+        self.id
+    }
+
+    pub fn arena(&self) -> Option<Rc<RefCell<NodeArena>>> {
+        self.arena.upgrade()
+    }
+
     pub fn get_element(&self) -> Option<Element> {
         match self.kind {
-            NodeKind::Document | NodeKind::Text(_) => None,
             NodeKind::Element(ref e) => Some(e.clone()),
+            _ => None,
         }
     }
 
     pub fn element_kind(&self) -> Option<ElementKind> {
         match self.kind {
-            NodeKind::Document | NodeKind::Text(_) => None,
-            NodeKind::Element(ref e) => Some(e.kind()),
+            NodeKind::Element(ref e) => e.kind(),
+            _ => None,
         }
     }
 
-    pub fn set_window(&mut self, window: Weak<RefCell<Window>>) {
-        self.window = window;
+    pub fn event_listeners(&self) -> Vec<EventListener> {
+        self.event_listeners.clone()
     }
 
-    pub fn set_parent(&mut self, parent: Weak<RefCell<Node>>) {
-        self.parent = parent;
+    pub(crate) fn event_listeners_mut(&mut self) -> &mut Vec<EventListener> {
+        &mut self.event_listeners
     }
 
-    pub fn parent(&self) -> Weak<RefCell<Node>> {
-        self.parent.clone()
+    fn set_window(&mut self, window: Weak<RefCell<Window>>) {
+        self.window = window;
     }
 
-    pub fn set_first_child(&mut self, first_child: Option<Rc<RefCell<Node>>>) {
-        self.first_child = first_child;
+    // https://dom.spec.whatwg.org/#dom-node-ownerdocument
+    // the document of the arena, which is None for a document and a node without an arena
+    pub fn owner_document(&self) -> Option<Rc<RefCell<Node>>> {
+        if self.kind == NodeKind::Document {
+            return None;
+        }
+        self.arena.upgrade()?.borrow().document()
     }
 
-    pub fn first_child(&self) -> Option<Rc<RefCell<Node>>> {
-        self.first_child.as_ref().cloned()
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-document-defaultview
+    // the window of a document, which is None for other nodes and a document without a window
+    pub fn default_view(&self) -> Option<Rc<RefCell<Window>>> {
+        match self.kind {
+            NodeKind::Document => self.window.upgrade(),
+            _ => None,
+        }
     }
 
-    pub fn set_last_child(&mut self, last_child: Weak<RefCell<Node>>) {
-        self.last_child = last_child;
+    // looks up the node of the id in the same arena
+    fn resolve(&self, id: Option<NodeId>) -> Option<Rc<RefCell<Node>>> {
+        let id = id?;
+        self.arena.upgrade()?.borrow().get(id)
     }
 
-    pub fn last_child(&self) -> Weak<RefCell<Node>> {
-        self.last_child.clone()
+    pub fn parent(&self) -> Weak<RefCell<Node>> {
+        match self.resolve(self.parent) {
+            Some(n) => Rc::downgrade(&n),
+            None => Weak::new(),
+        }
     }
 
-    pub fn set_previous_sibling(&mut self, previous_sibling: Weak<RefCell<Node>>) {
-        self.previous_sibling = previous_sibling;
+    pub fn first_child(&self) -> Option<Rc<RefCell<Node>>> {
+        self.resolve(self.first_child)
     }
 
-    pub fn previous_sibling(&self) -> Weak<RefCell<Node>> {
-        self.previous_sibling.clone()
+    pub fn last_child(&self) -> Weak<RefCell<Node>> {
+        match self.resolve(self.last_child) {
+            Some(n) => Rc::downgrade(&n),
+            None => Weak::new(),
+        }
     }
 
-    pub fn set_next_sibling(&mut self, next_sibling: Option<Rc<RefCell<Node>>>) {
-        self.next_sibling = next_sibling;
+    pub fn previous_sibling(&self) -> Weak<RefCell<Node>> {
+        match self.resolve(self.previous_sibling) {
+            Some(n) => Rc::downgrade(&n),
+            None => Weak::new(),
+        }
     }
 
     pub fn next_sibling(&self) -> Option<Rc<RefCell<Node>>> {
-        self.next_sibling.as_ref().cloned()
+        self.resolve(self.next_sibling)
     }
 }
 
@@ -101,86 +335,1313 @@ impl PartialEq for Node {
     }
 }
 
+// https://dom.spec.whatwg.org/#concept-tree-order
+// iterates over the descendants of the root in tree order, without recursion
+#[derive(Debug, Clone)]
+pub struct Descendants {
+    root: Rc<RefCell<Node>>,
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+impl Iterator for Descendants {
+    type Item = Rc<RefCell<Node>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+
+        // the first child, or the next sibling of the nearest inclusive ancestor under the root
+        let mut next = current.borrow().first_child();
+        let mut node = current.clone();
+        while next.is_none() && !Rc::ptr_eq(&node, &self.root) {
+            next = node.borrow().next_sibling();
+            let parent = node.borrow().parent().upgrade();
+            match parent {
+                Some(p) => node = p,
+                None => break,
+            }
+        }
+        self.next = next;
+
+        Some(current)
+    }
+}
+
+pub fn descendants(root: &Rc<RefCell<Node>>) -> Descendants {
+    Descendants {
+        root: root.clone(),
+        next: root.borrow().first_child(),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IndexKind {
+    // the id attribute
+    Id,
+    // each class of the class attribute
+    Class,
+    // the local name
+    Tag,
+}
+
+// maps keys to the elements, which are put in tree order lazily when they are looked up
+#[derive(Debug, Clone)]
+struct ElementIndex {
+    entries: BTreeMap<String, BTreeSet<NodeId>>,
+    // the elements of the keys in tree order, which is dropped when the elements change
+    ordered: BTreeMap<String, Vec<NodeId>>,
+}
+
+impl ElementIndex {
+    fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            ordered: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, key: String, id: NodeId) {
+        self.ordered.remove(&key);
+        self.entries.entry(key).or_default().insert(id);
+    }
+
+    fn remove(&mut self, key: &str, id: NodeId) {
+        self.ordered.remove(key);
+        if let Some(ids) = self.entries.get_mut(key) {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.entries.remove(key);
+            }
+        }
+    }
+}
+
+// the keys of the element in each index
+fn index_keys(element: &Element) -> Vec<(IndexKind, String)> {
+    let mut keys = vec![(IndexKind::Tag, element.local_name())];
+    if let Some(id) = element.get_attribute("id") {
+        if !id.is_empty() {
+            keys.push((IndexKind::Id, id));
+        }
+    }
+    for class in element.class_list() {
+        keys.push((IndexKind::Class, class));
+    }
+    keys
+}
+
+// adds or removes the node and its descendants to the indexes of the document, when the node
+// is connected to it
+fn update_indexes(node: &Rc<RefCell<Node>>, add: bool) {
+    if !is_connected(node) {
+        return;
+    }
+    let arena = match node.borrow().arena() {
+        Some(arena) => arena,
+        None => return,
+    };
+
+    let mut nodes = vec![node.clone()];
+    nodes.extend(descendants(node));
+    for n in nodes {
+        let (id, element) = {
+            let n = n.borrow();
+            match (n.id, n.get_element()) {
+                (Some(id), Some(element)) => (id, element),
+                _ => continue,
+            }
+        };
+        let mut arena = arena.borrow_mut();
+        for (kind, key) in index_keys(&element) {
+            if add {
+                arena.index(kind).add(key, id);
+            } else {
+                arena.index(kind).remove(&key, id);
+            }
+        }
+    }
+}
+
+// gets the elements connected to the document of the arena by the key, in tree order
+pub fn indexed_elements(
+    arena: &Rc<RefCell<NodeArena>>,
+    kind: IndexKind,
+    key: &str,
+) -> Vec<Rc<RefCell<Node>>> {
+    let (ordered, entries) = {
+        let mut arena = arena.borrow_mut();
+        let index = arena.index(kind);
+        (
+            index.ordered.get(key).cloned(),
+            index.entries.get(key).cloned().unwrap_or_default(),
+        )
+    };
+
+    // the elements are put in tree order by one traversal of the document, as comparing
+    // each pair of them walks the lists of siblings
+    let ids = match ordered {
+        Some(ids) => ids,
+        None => {
+            let document = arena.borrow().document();
+            let ids: Vec<NodeId> = match document {
+                Some(document) if entries.len() > 1 => descendants(&document)
+                    .filter_map(|n| n.borrow().id)
+                    .filter(|id| entries.contains(id))
+                    .take(entries.len())
+                    .collect(),
+                _ => entries.into_iter().collect(),
+            };
+            arena
+                .borrow_mut()
+                .index(kind)
+                .ordered
+                .insert(key.to_string(), ids.clone());
+            ids
+        }
+    };
+
+    ids.into_iter()
+        .filter_map(|id| arena.borrow().get(id))
+        .collect()
+}
+
+// https://dom.spec.whatwg.org/#connected
+pub fn is_connected(node: &Rc<RefCell<Node>>) -> bool {
+    let mut current = node.clone();
+    loop {
+        let parent = current.borrow().parent().upgrade();
+        match parent {
+            Some(p) => current = p,
+            None => break,
+        }
+    }
+    let is_document = current.borrow().kind == NodeKind::Document;
+    is_document
+}
+
+// the node and its ancestors, from the root
+fn inclusive_ancestors(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut ancestors = vec![node.clone()];
+    loop {
+        let parent = ancestors[ancestors.len() - 1].borrow().parent().upgrade();
+        match parent {
+            Some(p) => ancestors.push(p),
+            None => break,
+        }
+    }
+    ancestors.reverse();
+    ancestors
+}
+
+// https://dom.spec.whatwg.org/#concept-tree-preceding
+// compares the positions of two nodes of the same tree
+pub fn compare_tree_order(a: &Rc<RefCell<Node>>, b: &Rc<RefCell<Node>>) -> Ordering {
+    let a_ancestors = inclusive_ancestors(a);
+    let b_ancestors = inclusive_ancestors(b);
+
+    for (x, y) in a_ancestors.iter().zip(b_ancestors.iter()) {
+        if Rc::ptr_eq(x, y) {
+            continue;
+        }
+        // the first different ancestors are siblings
+        let mut sibling = x.borrow().next_sibling();
+        while let Some(s) = sibling {
+            if Rc::ptr_eq(&s, y) {
+                return Ordering::Less;
+            }
+            sibling = s.borrow().next_sibling();
+        }
+        return Ordering::Greater;
+    }
+
+    // an ancestor precedes its descendants
+    a_ancestors.len().cmp(&b_ancestors.len())
+}
+
+// sets the value of a form control which the user edited, or resets it to the default with None
+pub fn set_dirty_value(node: &Rc<RefCell<Node>>, value: Option<String>) {
+    if let NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+        e.dirty_value = value;
+    }
+}
+
+// sets the checkedness or the selectedness which the user changed, or resets it with None
+pub fn set_dirty_checkedness(node: &Rc<RefCell<Node>>, checkedness: Option<bool>) {
+    if let NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+        e.dirty_checkedness = checkedness;
+    }
+}
+
+// sets the decoded image of <img>, or discards it with None
+pub fn set_image(node: &Rc<RefCell<Node>>, image: Option<Rc<Image>>) {
+    if let NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+        e.image = image;
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-element-attributes-set-value
+// sets the attribute of the element, and updates the indexes of the document
+pub fn set_attribute(node: &Rc<RefCell<Node>>, name: &str, value: &str) {
+    if node.borrow().get_element().is_none() {
+        return;
+    }
+
+    // the node itself is reindexed, as its descendants are not changed
+    let connected = is_connected(node);
+    let arena = node.borrow().arena();
+    let id = node.borrow().id;
+    let reindex = |node: &Rc<RefCell<Node>>, add: bool| {
+        if let (true, Some(arena), Some(id), Some(element)) =
+            (connected, &arena, id, node.borrow().get_element())
+        {
+            let mut arena = arena.borrow_mut();
+            for (kind, key) in index_keys(&element) {
+                if add {
+                    arena.index(kind).add(key, id);
+                } else {
+                    arena.index(kind).remove(&key, id);
+                }
+            }
+        }
+    };
+
+    reindex(node, false);
+    if let NodeKind::Element(ref mut e) = node.borrow_mut().kind {
+        e.set_attribute(name, value);
+    }
+    reindex(node, true);
+}
+
+// https://dom.spec.whatwg.org/#concept-node-adopt
+// moves the node and its descendants into the arena, which assigns new ids to them
+pub(crate) fn adopt(node: &Rc<RefCell<Node>>, arena: &Rc<RefCell<NodeArena>>) {
+    let old_arena = node.borrow().arena();
+    if let Some(ref old) = old_arena {
+        if Rc::ptr_eq(old, arena) {
+            return;
+        }
+    }
+
+    let mut nodes = vec![node.clone()];
+    nodes.extend(descendants(node));
+
+    let mut ids = BTreeMap::new();
+    for n in &nodes {
+        let new_id = arena.borrow_mut().push(n.clone());
+        if let Some(old_id) = n.borrow().id {
+            ids.insert(old_id, new_id);
+        }
+        n.borrow_mut().id = Some(new_id);
+    }
+
+    let map = |id: Option<NodeId>| id.and_then(|id| ids.get(&id).copied());
+    for n in &nodes {
+        let mut n = n.borrow_mut();
+        n.arena = Rc::downgrade(arena);
+        n.parent = map(n.parent);
+        n.first_child = map(n.first_child);
+        n.last_child = map(n.last_child);
+        n.previous_sibling = map(n.previous_sibling);
+        n.next_sibling = map(n.next_sibling);
+    }
+
+    if let Some(old) = old_arena {
+        for old_id in ids.keys() {
+            old.borrow_mut().nodes[old_id.0] = None;
+        }
+
+        // https://html.spec.whatwg.org/multipage/scripting.html#template-adopting-steps
+        // the contents of <template> are moved into the inert document of the new arena
+        for (old_id, new_id) in &ids {
+            let contents = old.borrow_mut().template_contents.remove(old_id);
+            if let Some(contents) = contents {
+                let inert_document = arena
+                    .borrow_mut()
+                    .inert_document
+                    .get_or_insert_with(create_document)
+                    .clone();
+                if let Some(inert_arena) = inert_document.borrow().arena() {
+                    adopt(&contents, &inert_arena);
+                }
+                arena
+                    .borrow_mut()
+                    .template_contents
+                    .insert(*new_id, contents);
+            }
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-mutationrecord
+// a record of a childList mutation
+#[derive(Debug, Clone)]
+pub struct MutationRecord {
+    target: Rc<RefCell<Node>>,
+    added_nodes: Vec<Rc<RefCell<Node>>>,
+    removed_nodes: Vec<Rc<RefCell<Node>>>,
+    previous_sibling: Option<Rc<RefCell<Node>>>,
+    next_sibling: Option<Rc<RefCell<Node>>>,
+}
+
+impl MutationRecord {
+    pub fn target(&self) -> Rc<RefCell<Node>> {
+        self.target.clone()
+    }
+
+    pub fn added_nodes(&self) -> Vec<Rc<RefCell<Node>>> {
+        self.added_nodes.clone()
+    }
+
+    pub fn removed_nodes(&self) -> Vec<Rc<RefCell<Node>>> {
+        self.removed_nodes.clone()
+    }
+
+    pub fn previous_sibling(&self) -> Option<Rc<RefCell<Node>>> {
+        self.previous_sibling.clone()
+    }
+
+    pub fn next_sibling(&self) -> Option<Rc<RefCell<Node>>> {
+        self.next_sibling.clone()
+    }
+}
+
+// https://dom.spec.whatwg.org/#interface-mutationobserver
+// records are delivered synchronously, as there is no microtask queue yet
+#[derive(Clone)]
+pub struct MutationObserver {
+    callback: Rc<dyn Fn(&MutationRecord)>,
+}
+
+impl MutationObserver {
+    pub fn new(callback: Rc<dyn Fn(&MutationRecord)>) -> Self {
+        Self { callback }
+    }
+
+    fn notify(&self, record: &MutationRecord) {
+        (self.callback)(record)
+    }
+}
+
+impl Debug for MutationObserver {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        write!(f, "MutationObserver")
+    }
+}
+
+// https://dom.spec.whatwg.org/#registered-observer
+#[derive(Debug, Clone)]
+struct RegisteredObserver {
+    observer: MutationObserver,
+    subtree: bool,
+}
+
+// https://dom.spec.whatwg.org/#dom-mutationobserver-observe
+// observes the mutations of the children of the node, and of its descendants if subtree
+// is true
+pub fn observe(node: &Rc<RefCell<Node>>, observer: MutationObserver, subtree: bool) {
+    node.borrow_mut()
+        .registered_observers
+        .push(RegisteredObserver { observer, subtree });
+}
+
+// https://dom.spec.whatwg.org/#queue-a-tree-mutation-record
+fn queue_tree_mutation_record(record: MutationRecord) {
+    let mut observers = Vec::new();
+    let mut node = Some(record.target.clone());
+    let mut is_target = true;
+    while let Some(n) = node {
+        for registered in &n.borrow().registered_observers {
+            if is_target || registered.subtree {
+                observers.push(registered.observer.clone());
+            }
+        }
+        node = n.borrow().parent().upgrade();
+        is_target = false;
+    }
+
+    // callbacks are called after all borrows are released, as they may access the tree
+    for observer in observers {
+        observer.notify(&record);
+    }
+}
+
+// returns the children of the node in tree order
+pub fn child_nodes(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut children = Vec::new();
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        child = c.borrow().next_sibling();
+        children.push(c);
+    }
+    children
+}
+
+fn hierarchy_request_error(message: &str) -> Error {
+    Error::UnexpectedInput(format!("HierarchyRequestError: {}", message))
+}
+
+fn not_found_error(message: &str) -> Error {
+    Error::UnexpectedInput(format!("NotFoundError: {}", message))
+}
+
+fn is_parent_of(parent: &Rc<RefCell<Node>>, child: &Rc<RefCell<Node>>) -> bool {
+    match child.borrow().parent().upgrade() {
+        Some(p) => Rc::ptr_eq(&p, parent),
+        None => false,
+    }
+}
+
+// checks the validity which doesn't depend on the other children of the parent
+fn ensure_insertable(parent: &Rc<RefCell<Node>>, node: &Rc<RefCell<Node>>) -> Result<(), Error> {
+    let parent_kind = parent.borrow().kind();
+    if !matches!(
+        parent_kind,
+        NodeKind::Document | NodeKind::DocumentFragment | NodeKind::Element(_)
+    ) {
+        return Err(hierarchy_request_error("the parent cannot have children"));
+    }
+
+    // the nodes are linked by the ids of an arena, so they can't be linked without one
+    if parent.borrow().arena().is_none() && node.borrow().arena().is_none() {
+        return Err(Error::UnexpectedInput(
+            "NotSupportedError: neither node belongs to a document".to_string(),
+        ));
+    }
+
+    // the node must not be an inclusive ancestor of the parent
+    let mut ancestor = Some(parent.clone());
+    while let Some(a) = ancestor {
+        if Rc::ptr_eq(&a, node) {
+            return Err(hierarchy_request_error(
+                "the node is an inclusive ancestor of the parent",
+            ));
+        }
+        ancestor = a.borrow().parent().upgrade();
+    }
+
+    let is_document = parent_kind == NodeKind::Document;
+    match node.borrow().kind() {
+        NodeKind::Document => return Err(hierarchy_request_error("a document cannot be inserted")),
+        // the children of a fragment would be inserted instead, which is not supported yet
+        NodeKind::DocumentFragment => {
+            return Err(hierarchy_request_error(
+                "a document fragment cannot be inserted",
+            ))
+        }
+        NodeKind::Text(_) if is_document => {
+            return Err(hierarchy_request_error(
+                "a text cannot be a child of a document",
+            ))
+        }
+        NodeKind::DocumentType(_) if !is_document => {
+            return Err(hierarchy_request_error(
+                "a doctype can only be a child of a document",
+            ))
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+// https://dom.spec.whatwg.org/#concept-node-ensure-pre-insertion-validity
+// https://dom.spec.whatwg.org/#concept-node-replace
+// the child is the reference child when inserting, or the replaced child when replacing
+fn ensure_validity(
+    parent: &Rc<RefCell<Node>>,
+    node: &Rc<RefCell<Node>>,
+    child: Option<&Rc<RefCell<Node>>>,
+    replacing: bool,
+) -> Result<(), Error> {
+    ensure_insertable(parent, node)?;
+
+    if let Some(child) = child {
+        if !is_parent_of(parent, child) {
+            return Err(not_found_error("the child is not a child of the parent"));
+        }
+    }
+
+    if parent.borrow().kind() != NodeKind::Document {
+        return Ok(());
+    }
+
+    // a document has at most one doctype, followed by at most one element
+    let children = child_nodes(parent);
+    let other_children = children
+        .iter()
+        .filter(|c| !(replacing && child.map_or(false, |child| Rc::ptr_eq(c, child))));
+    let child_index = child.and_then(|child| children.iter().position(|c| Rc::ptr_eq(c, child)));
+    let is_element = |n: &Rc<RefCell<Node>>| matches!(n.borrow().kind(), NodeKind::Element(_));
+    let is_doctype = |n: &Rc<RefCell<Node>>| matches!(n.borrow().kind(), NodeKind::DocumentType(_));
+
+    match node.borrow().kind() {
+        NodeKind::Element(_) => {
+            let doctype_after = match child_index {
+                Some(i) => {
+                    let start = if replacing { i + 1 } else { i };
+                    children[start..].iter().any(is_doctype)
+                }
+                None => false,
+            };
+            if other_children.clone().any(is_element) || doctype_after {
+                return Err(hierarchy_request_error(
+                    "a document can only have one element after its doctype",
+                ));
+            }
+        }
+        NodeKind::DocumentType(_) => {
+            let element_before = match child_index {
+                Some(i) => children[..i].iter().any(is_element),
+                None => children.iter().any(is_element),
+            };
+            if other_children.clone().any(is_doctype) || element_before {
+                return Err(hierarchy_request_error(
+                    "a document can only have one doctype before its element",
+                ));
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+// https://dom.spec.whatwg.org/#concept-node-insert
+// inserts the node before the child, or as the last child of the parent if the child is
+// None, without checking the validity
+pub(crate) fn insert(
+    parent: &Rc<RefCell<Node>>,
+    node: Rc<RefCell<Node>>,
+    child: Option<&Rc<RefCell<Node>>>,
+) {
+    // the node is moved if it is already in a tree
+    if node.borrow().parent().upgrade().is_some() {
+        remove(&node);
+    }
+
+    let parent_arena = parent.borrow().arena();
+    let node_arena = node.borrow().arena();
+    let arena = match (parent_arena, node_arena) {
+        (Some(arena), _) => arena,
+        (None, Some(arena)) => {
+            adopt(parent, &arena);
+            arena
+        }
+        // the callers ensure that one of them belongs to an arena
+        (None, None) => panic!("failed to insert a node: neither node belongs to an arena"),
+    };
+    adopt(&node, &arena);
+
+    let previous = match child {
+        Some(c) => c.borrow().previous_sibling().upgrade(),
+        None => parent.borrow().last_child().upgrade(),
+    };
+    let parent_id = parent.borrow().id;
+    let node_id = node.borrow().id;
+
+    match previous {
+        Some(ref p) => {
+            let previous_id = p.borrow().id;
+            p.borrow_mut().next_sibling = node_id;
+            node.borrow_mut().previous_sibling = previous_id;
+        }
+        None => parent.borrow_mut().first_child = node_id,
+    }
+
+    match child {
+        Some(c) => {
+            let child_id = c.borrow().id;
+            node.borrow_mut().next_sibling = child_id;
+            c.borrow_mut().previous_sibling = node_id;
+        }
+        None => parent.borrow_mut().last_child = node_id,
+    }
+    node.borrow_mut().parent = parent_id;
+    update_indexes(&node, true);
+
+    queue_tree_mutation_record(MutationRecord {
+        target: parent.clone(),
+        added_nodes: vec![node],
+        removed_nodes: Vec::new(),
+        previous_sibling: previous,
+        next_sibling: child.cloned(),
+    });
+}
+
+// https://dom.spec.whatwg.org/#concept-node-remove
+// removes the node from its parent
+pub(crate) fn remove(node: &Rc<RefCell<Node>>) {
+    update_indexes(node, false);
+
+    let parent = node.borrow().parent().upgrade();
+    let previous = node.borrow().previous_sibling().upgrade();
+    let next = node.borrow().next_sibling();
+    let previous_id = previous.as_ref().and_then(|p| p.borrow().id);
+    let next_id = next.as_ref().and_then(|n| n.borrow().id);
+
+    if let Some(ref parent) = parent {
+        if previous.is_none() {
+            parent.borrow_mut().first_child = next_id;
+        }
+        if next.is_none() {
+            parent.borrow_mut().last_child = previous_id;
+        }
+    }
+
+    if let Some(ref p) = previous {
+        p.borrow_mut().next_sibling = next_id;
+    }
+    if let Some(ref n) = next {
+        n.borrow_mut().previous_sibling = previous_id;
+    }
+
+    {
+        let mut node = node.borrow_mut();
+        node.parent = None;
+        node.previous_sibling = None;
+        node.next_sibling = None;
+    }
+
+    if let Some(parent) = parent {
+        queue_tree_mutation_record(MutationRecord {
+            target: parent,
+            added_nodes: Vec::new(),
+            removed_nodes: vec![node.clone()],
+            previous_sibling: previous,
+            next_sibling: next,
+        });
+    }
+
+    if let Some(arena) = node.borrow().arena() {
+        arena.borrow_mut().removals += 1;
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-node-replace-all
+// replaces all children of the parent with the nodes, without checking the validity
+pub(crate) fn replace_all(parent: &Rc<RefCell<Node>>, nodes: Vec<Rc<RefCell<Node>>>) {
+    for child in child_nodes(parent) {
+        remove(&child);
+    }
+    for node in nodes {
+        insert(parent, node, None);
+    }
+    if let Some(arena) = parent.borrow().arena() {
+        collect_garbage(&arena);
+    }
+}
+
+// https://dom.spec.whatwg.org/#dom-node-appendchild
+pub fn append_child(
+    parent: &Rc<RefCell<Node>>,
+    node: Rc<RefCell<Node>>,
+) -> Result<Rc<RefCell<Node>>, Error> {
+    insert_before(parent, node, None)
+}
+
+// https://dom.spec.whatwg.org/#dom-node-insertbefore
+// inserts the node before the child, or as the last child if the child is None
+pub fn insert_before(
+    parent: &Rc<RefCell<Node>>,
+    node: Rc<RefCell<Node>>,
+    child: Option<&Rc<RefCell<Node>>>,
+) -> Result<Rc<RefCell<Node>>, Error> {
+    ensure_validity(parent, &node, child, false)?;
+
+    // the reference child is the next sibling, if the node itself is the reference child
+    let child = match child {
+        Some(c) if Rc::ptr_eq(c, &node) => node.borrow().next_sibling(),
+        Some(c) => Some(c.clone()),
+        None => None,
+    };
+
+    insert(parent, node.clone(), child.as_ref());
+    Ok(node)
+}
+
+// https://dom.spec.whatwg.org/#dom-node-removechild
+pub fn remove_child(
+    parent: &Rc<RefCell<Node>>,
+    child: &Rc<RefCell<Node>>,
+) -> Result<Rc<RefCell<Node>>, Error> {
+    if !is_parent_of(parent, child) {
+        return Err(not_found_error("the child is not a child of the parent"));
+    }
+
+    remove(child);
+    if let Some(arena) = parent.borrow().arena() {
+        collect_garbage(&arena);
+    }
+    Ok(child.clone())
+}
+
+// https://dom.spec.whatwg.org/#dom-node-replacechild
+// replaces the child with the node, and returns the child
+pub fn replace_child(
+    parent: &Rc<RefCell<Node>>,
+    node: Rc<RefCell<Node>>,
+    child: &Rc<RefCell<Node>>,
+) -> Result<Rc<RefCell<Node>>, Error> {
+    ensure_validity(parent, &node, Some(child), true)?;
+
+    if Rc::ptr_eq(&node, child) {
+        return Ok(node);
+    }
+
+    let mut reference_child = child.borrow().next_sibling();
+    if let Some(ref r) = reference_child {
+        if Rc::ptr_eq(r, &node) {
+            reference_child = node.borrow().next_sibling();
+        }
+    }
+
+    remove(child);
+    insert(parent, node, reference_child.as_ref());
+    if let Some(arena) = parent.borrow().arena() {
+        collect_garbage(&arena);
+    }
+    Ok(child.clone())
+}
+
+// https://dom.spec.whatwg.org/#dom-parentnode-replacechildren
+pub fn replace_children(
+    parent: &Rc<RefCell<Node>>,
+    nodes: Vec<Rc<RefCell<Node>>>,
+) -> Result<(), Error> {
+    // a document is validated as if it had no children
+    if parent.borrow().kind() == NodeKind::Document {
+        let doctype_after_element = nodes
+            .iter()
+            .skip_while(|n| !matches!(n.borrow().kind(), NodeKind::Element(_)))
+            .any(|n| matches!(n.borrow().kind(), NodeKind::DocumentType(_)));
+        let elements = nodes
+            .iter()
+            .filter(|n| matches!(n.borrow().kind(), NodeKind::Element(_)))
+            .count();
+        let doctypes = nodes
+            .iter()
+            .filter(|n| matches!(n.borrow().kind(), NodeKind::DocumentType(_)))
+            .count();
+        if elements > 1 || doctypes > 1 || doctype_after_element {
+            return Err(hierarchy_request_error(
+                "a document can only have one doctype and one element",
+            ));
+        }
+    }
+
+    for node in &nodes {
+        ensure_insertable(parent, node)?;
+    }
+
+    replace_all(parent, nodes);
+    Ok(())
+}
+
+// https://dom.spec.whatwg.org/#concept-node-clone
+// clones the node without its parent, and its descendants too if deep is true
+pub fn clone_node(node: &Rc<RefCell<Node>>, deep: bool) -> Rc<RefCell<Node>> {
+    // the copy belongs to the same arena as the node
+    let kind = node.borrow().kind();
+    let copy = match node.borrow().arena() {
+        Some(arena) => create_node(&arena, kind),
+        None => Rc::new(RefCell::new(Node::new(kind))),
+    };
+
+    if deep {
+        for child in child_nodes(node) {
+            insert(&copy, clone_node(&child, true), None);
+        }
+
+        // https://html.spec.whatwg.org/multipage/scripting.html#template-clone-steps
+        // the contents of a template are cloned into the contents of the copy, which belong to
+        // the inert document
+        if let (Some(contents), Some(copy_contents)) =
+            (template_contents(node), template_contents(&copy))
+        {
+            for child in child_nodes(&contents) {
+                insert(&copy_contents, clone_node(&child, true), None);
+            }
+        }
+    }
+
+    copy
+}
+
 #[derive(Debug, Clone, Eq)]
 pub enum NodeKind {
     // https://dom.spec.whatwg.org/#interface-document
     Document,
+    // https://dom.spec.whatwg.org/#interface-documentfragment
+    DocumentFragment,
+    // https://dom.spec.whatwg.org/#interface-documenttype
+    DocumentType(DocumentType),
     // https://dom.spec.whatwg.org/#interface-element
     Element(Element),
     // https://dom.spec.whatwg.org/#interface-text
     Text(String),
+    // https://dom.spec.whatwg.org/#interface-comment
+    Comment(String),
 }
 
 impl PartialEq for NodeKind {
     fn eq(&self, other: &Self) -> bool {
         match &self {
             NodeKind::Document => matches!(other, NodeKind::Document),
+            NodeKind::DocumentFragment => matches!(other, NodeKind::DocumentFragment),
+            NodeKind::DocumentType(_) => matches!(other, NodeKind::DocumentType(_)),
             NodeKind::Element(e1) => match &other {
-                NodeKind::Element(e2) => e1.kind == e2.kind,
+                NodeKind::Element(e2) => {
+                    e1.namespace == e2.namespace && e1.local_name == e2.local_name
+                }
                 _ => false,
             },
             NodeKind::Text(_) => matches!(other, NodeKind::Text(_)),
+            NodeKind::Comment(_) => matches!(other, NodeKind::Comment(_)),
         }
     }
 }
 
+// https://dom.spec.whatwg.org/#interface-documenttype
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentType {
+    name: String,
+    public_id: String,
+    system_id: String,
+}
+
+impl DocumentType {
+    pub fn new(name: String, public_id: String, system_id: String) -> Self {
+        Self {
+            name,
+            public_id,
+            system_id,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    pub fn public_id(&self) -> String {
+        self.public_id.clone()
+    }
+
+    pub fn system_id(&self) -> String {
+        self.system_id.clone()
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-document-mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DocumentMode {
+    NoQuirks,
+    Quirks,
+    LimitedQuirks,
+}
+
 // https://html.spec.whatwg.org/multipage/nav-history-apis.html#window
 #[derive(Debug, Clone)]
 pub struct Window {
+    // the window owns the document, and the document refers to the window weakly
     document: Rc<RefCell<Node>>,
+    document_mode: DocumentMode,
+    location: Location,
+    navigator: Navigator,
+    screen: Screen,
+}
+
+// creates a window with a new document, which can reach the window by defaultView
+pub fn create_window() -> Rc<RefCell<Window>> {
+    let window = Rc::new(RefCell::new(Window::new()));
+    window
+        .borrow()
+        .document
+        .borrow_mut()
+        .set_window(Rc::downgrade(&window));
+    window
 }
 
 impl Window {
-    pub fn new() -> Self {
-        let window = Self {
-            document: Rc::new(RefCell::new(Node::new(NodeKind::Document))),
+    fn new() -> Self {
+        Self {
+            document: create_document(),
+            document_mode: DocumentMode::NoQuirks,
+            location: Location::new("about:blank".to_string()),
+            navigator: Navigator::new(),
+            screen: Screen::new(WINDOW_WIDTH, WINDOW_HEIGHT),
+        }
+    }
+
+    pub fn document(&self) -> Rc<RefCell<Node>> {
+        self.document.clone()
+    }
+
+    // the arena which owns all nodes of the document
+    pub fn arena(&self) -> Rc<RefCell<NodeArena>> {
+        self.document
+            .borrow()
+            .arena()
+            .expect("the document always owns its arena")
+    }
+
+    pub fn create_node(&self, kind: NodeKind) -> Rc<RefCell<Node>> {
+        create_node(&self.arena(), kind)
+    }
+
+    pub fn set_document_mode(&mut self, document_mode: DocumentMode) {
+        self.document_mode = document_mode;
+    }
+
+    pub fn document_mode(&self) -> DocumentMode {
+        self.document_mode
+    }
+
+    pub fn location(&self) -> Location {
+        self.location.clone()
+    }
+
+    pub fn set_location(&mut self, location: Location) {
+        self.location = location;
+    }
+
+    pub fn navigator(&self) -> Navigator {
+        self.navigator.clone()
+    }
+
+    pub fn screen(&self) -> Screen {
+        self.screen.clone()
+    }
+
+    pub fn set_screen(&mut self, screen: Screen) {
+        self.screen = screen;
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/nav-history-apis.html#the-location-interface
+// the URL of the document, which is split into components by the getters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    href: String,
+}
+
+impl Location {
+    pub fn new(href: String) -> Self {
+        Self { href }
+    }
+
+    pub fn href(&self) -> String {
+        self.href.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-protocol
+    pub fn protocol(&self) -> String {
+        match self.href.find(':') {
+            Some(i) => self.href[..=i].to_string(),
+            None => String::new(),
+        }
+    }
+
+    // splits the URL after the scheme into the authority and the rest
+    fn authority_and_rest(&self) -> (&str, &str) {
+        let after_scheme = match self.href.find(':') {
+            Some(i) => &self.href[i + 1..],
+            None => return ("", ""),
         };
+        match after_scheme.strip_prefix("//") {
+            Some(after) => {
+                let end = after.find(['/', '?', '#']).unwrap_or(after.len());
+                after.split_at(end)
+            }
+            None => ("", after_scheme),
+        }
+    }
 
-        window
-            .document
-            .borrow_mut()
-            .set_window(Rc::downgrade(&Rc::new(RefCell::new(window.clone()))));
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-host
+    pub fn host(&self) -> String {
+        let authority = self.authority_and_rest().0;
+        // credentials are not exposed
+        match authority.rfind('@') {
+            Some(i) => authority[i + 1..].to_string(),
+            None => authority.to_string(),
+        }
+    }
 
-        window
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-hostname
+    pub fn hostname(&self) -> String {
+        let host = self.host();
+        match host.find(':') {
+            Some(i) => host[..i].to_string(),
+            None => host,
+        }
     }
 
-    pub fn document(&self) -> Rc<RefCell<Node>> {
-        self.document.clone()
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-port
+    pub fn port(&self) -> String {
+        let host = self.host();
+        match host.find(':') {
+            Some(i) => host[i + 1..].to_string(),
+            None => String::new(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-pathname
+    pub fn pathname(&self) -> String {
+        let (authority, rest) = self.authority_and_rest();
+        let end = rest.find(['?', '#']).unwrap_or(rest.len());
+        // a URL with an authority always has a path
+        if rest[..end].is_empty() && !authority.is_empty() {
+            return "/".to_string();
+        }
+        rest[..end].to_string()
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-search
+    // the query with "?", which is empty for an empty query
+    pub fn search(&self) -> String {
+        let rest = self.authority_and_rest().1;
+        let rest = &rest[..rest.find('#').unwrap_or(rest.len())];
+        match rest.find('?') {
+            Some(i) if i + 1 < rest.len() => rest[i..].to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-hash
+    // the fragment with "#", which is empty for an empty fragment
+    pub fn hash(&self) -> String {
+        match self.href.find('#') {
+            Some(i) if i + 1 < self.href.len() => self.href[i..].to_string(),
+            _ => String::new(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-location-origin
+    pub fn origin(&self) -> String {
+        let host = self.host();
+        if host.is_empty() {
+            return "null".to_string();
+        }
+        format!("{}//{}", self.protocol(), host)
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/system-state.html#the-navigator-object
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Navigator {
+    user_agent: String,
+    language: String,
+}
+
+impl Navigator {
+    fn new() -> Self {
+        Self {
+            user_agent: USER_AGENT.to_string(),
+            language: "en-US".to_string(),
+        }
+    }
+
+    pub fn user_agent(&self) -> String {
+        self.user_agent.clone()
+    }
+
+    pub fn language(&self) -> String {
+        self.language.clone()
+    }
+
+    // https://html.spec.whatwg.org/multipage/system-state.html#dom-navigator-appname
+    // always "Netscape" for compatibility
+    pub fn app_name(&self) -> String {
+        "Netscape".to_string()
+    }
+}
+
+// https://drafts.csswg.org/cssom-view/#the-screen-interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    width: i64,
+    height: i64,
+}
+
+impl Screen {
+    pub fn new(width: i64, height: i64) -> Self {
+        Self { width, height }
+    }
+
+    pub fn width(&self) -> i64 {
+        self.width
+    }
+
+    pub fn height(&self) -> i64 {
+        self.height
+    }
+
+    // https://drafts.csswg.org/cssom-view/#dom-screen-colordepth
+    pub fn color_depth(&self) -> i64 {
+        24
     }
 }
 
 // https://dom.spec.whatwg.org/#interface-element
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Element {
-    kind: ElementKind,
+    // https://dom.spec.whatwg.org/#concept-element-local-name
+    local_name: String,
+    // https://dom.spec.whatwg.org/#concept-element-namespace
+    namespace: Namespace,
+    // known HTML elements, to avoid comparing names everywhere
+    kind: Option<ElementKind>,
     attributes: Vec<Attribute>,
+    // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#concept-fe-dirty
+    // the value which the user edited, which overrides the default value of a form control
+    dirty_value: Option<String>,
+    // https://html.spec.whatwg.org/multipage/input.html#concept-input-checked-dirty-flag
+    // the checkedness of <input> or the selectedness of <option>, which the user changed
+    dirty_checkedness: Option<bool>,
+    // https://html.spec.whatwg.org/multipage/images.html#img-all
+    // the decoded pixels of <img>, which is None until the image is available
+    image: Option<Rc<Image>>,
 }
 
 impl Element {
+    // creates an element in the HTML namespace
     pub fn new(element_name: &str, attributes: Vec<Attribute>) -> Self {
+        Self::new_with_namespace(element_name, Namespace::Html, attributes)
+    }
+
+    pub fn new_with_namespace(
+        element_name: &str,
+        namespace: Namespace,
+        attributes: Vec<Attribute>,
+    ) -> Self {
+        let kind = match namespace {
+            Namespace::Html => ElementKind::from_str(element_name).ok(),
+            _ => None,
+        };
+
         Self {
-            kind: ElementKind::from_str(element_name)
-                .expect("failed to convert string to ElementKind"),
+            local_name: element_name.to_string(),
+            namespace,
+            kind,
             attributes,
+            dirty_value: None,
+            dirty_checkedness: None,
+            image: None,
         }
     }
 
-    pub fn kind(&self) -> ElementKind {
+    pub fn kind(&self) -> Option<ElementKind> {
         self.kind
     }
 
+    pub fn local_name(&self) -> String {
+        self.local_name.clone()
+    }
+
+    pub fn namespace(&self) -> Namespace {
+        self.namespace
+    }
+
     pub fn attributes(&self) -> Vec<Attribute> {
         self.attributes.clone()
     }
 
+    // https://dom.spec.whatwg.org/#dom-element-getattribute
+    pub fn get_attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|attr| attr.name() == name)
+            .map(|attr| attr.value())
+    }
+
+    // https://dom.spec.whatwg.org/#dom-element-classlist
+    pub fn class_list(&self) -> Vec<String> {
+        let mut classes: Vec<String> = Vec::new();
+        if let Some(class) = self.get_attribute("class") {
+            for c in class.split_ascii_whitespace() {
+                if !classes.iter().any(|existing| existing == c) {
+                    classes.push(c.to_string());
+                }
+            }
+        }
+        classes
+    }
+
+    pub fn has_class(&self, class_name: &str) -> bool {
+        match self.get_attribute("class") {
+            Some(class) => class.split_ascii_whitespace().any(|c| c == class_name),
+            None => false,
+        }
+    }
+
+    // use dom::set_attribute to keep the indexes of the document up to date
+    fn set_attribute(&mut self, name: &str, value: &str) {
+        match self.attributes.iter_mut().find(|attr| attr.name() == name) {
+            Some(attr) => *attr = Attribute::new_with_value(name, value),
+            None => self.attributes.push(Attribute::new_with_value(name, value)),
+        }
+    }
+
+    pub fn dirty_value(&self) -> Option<String> {
+        self.dirty_value.clone()
+    }
+
+    pub fn dirty_checkedness(&self) -> Option<bool> {
+        self.dirty_checkedness
+    }
+
+    pub fn image(&self) -> Option<Rc<Image>> {
+        self.image.clone()
+    }
+
     pub fn is_block_element(&self) -> bool {
         match self.kind {
-            ElementKind::Body | ElementKind::H1 | ElementKind::H2 | ElementKind::P => true,
+            Some(ElementKind::Body)
+            | Some(ElementKind::Form)
+            | Some(ElementKind::Div)
+            | Some(ElementKind::Section)
+            | Some(ElementKind::H1)
+            | Some(ElementKind::H2)
+            | Some(ElementKind::H3)
+            | Some(ElementKind::H4)
+            | Some(ElementKind::H5)
+            | Some(ElementKind::H6)
+            | Some(ElementKind::P)
+            | Some(ElementKind::Pre)
+            | Some(ElementKind::Hr)
+            | Some(ElementKind::Ul)
+            | Some(ElementKind::Ol)
+            | Some(ElementKind::Li)
+            | Some(ElementKind::Table)
+            | Some(ElementKind::Caption)
+            | Some(ElementKind::Tbody)
+            | Some(ElementKind::Thead)
+            | Some(ElementKind::Tfoot)
+            | Some(ElementKind::Tr) => true,
             _ => false,
         }
     }
 }
 
+// https://infra.spec.whatwg.org/#namespaces
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Namespace {
+    // https://infra.spec.whatwg.org/#html-namespace
+    Html,
+    // https://infra.spec.whatwg.org/#svg-namespace
+    Svg,
+    // https://infra.spec.whatwg.org/#mathml-namespace
+    MathMl,
+}
+
+impl Namespace {
+    pub fn url(&self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+        }
+    }
+}
+
 // https://dom.spec.whatwg.org/#interface-element
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ElementKind {
@@ -197,10 +1658,78 @@ pub enum ElementKind {
     // https://html.spec.whatwg.org/multipage/sections.html#the-h1,-h2,-h3,-h4,-h5,-and-h6-elements
     H1,
     H2,
+    H3,
+    H4,
+    H5,
+    H6,
+    // https://html.spec.whatwg.org/multipage/sections.html#the-section-element
+    Section,
     // https://html.spec.whatwg.org/multipage/grouping-content.html#the-p-element
     P,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-pre-element
+    Pre,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-hr-element
+    Hr,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-ul-element
+    Ul,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-ol-element
+    Ol,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-li-element
+    Li,
     // https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-a-element
     A,
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-div-element
+    Div,
+    // https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-span-element
+    Span,
+    // https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-br-element
+    Br,
+    // https://html.spec.whatwg.org/multipage/embedded-content.html#the-img-element
+    Img,
+    // https://html.spec.whatwg.org/multipage/iframe-embed-object.html#the-iframe-element
+    Iframe,
+    // https://html.spec.whatwg.org/multipage/scripting.html#the-template-element
+    Template,
+    // https://html.spec.whatwg.org/multipage/forms.html#the-form-element
+    Form,
+    // https://html.spec.whatwg.org/multipage/forms.html#the-label-element
+    Label,
+    // https://html.spec.whatwg.org/multipage/input.html#the-input-element
+    Input,
+    // https://html.spec.whatwg.org/multipage/form-elements.html#the-button-element
+    Button,
+    // https://html.spec.whatwg.org/multipage/form-elements.html#the-select-element
+    Select,
+    // https://html.spec.whatwg.org/multipage/form-elements.html#the-optgroup-element
+    Optgroup,
+    // https://html.spec.whatwg.org/multipage/form-elements.html#the-option-element
+    Option,
+    // https://html.spec.whatwg.org/multipage/form-elements.html#the-textarea-element
+    Textarea,
+    // https://html.spec.whatwg.org/multipage/semantics.html#the-meta-element
+    Meta,
+    // https://html.spec.whatwg.org/multipage/semantics.html#the-link-element
+    Link,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-table-element
+    Table,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-caption-element
+    Caption,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-colgroup-element
+    Colgroup,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-col-element
+    Col,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-tbody-element
+    Tbody,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-thead-element
+    Thead,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-tfoot-element
+    Tfoot,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-tr-element
+    Tr,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-td-element
+    Td,
+    // https://html.spec.whatwg.org/multipage/tables.html#the-th-element
+    Th,
 }
 
 impl FromStr for ElementKind {
@@ -215,8 +1744,44 @@ impl FromStr for ElementKind {
             "body" => Ok(ElementKind::Body),
             "h1" => Ok(ElementKind::H1),
             "h2" => Ok(ElementKind::H2),
+            "h3" => Ok(ElementKind::H3),
+            "h4" => Ok(ElementKind::H4),
+            "h5" => Ok(ElementKind::H5),
+            "h6" => Ok(ElementKind::H6),
+            "section" => Ok(ElementKind::Section),
             "p" => Ok(ElementKind::P),
+            "pre" => Ok(ElementKind::Pre),
+            "hr" => Ok(ElementKind::Hr),
+            "ul" => Ok(ElementKind::Ul),
+            "ol" => Ok(ElementKind::Ol),
+            "li" => Ok(ElementKind::Li),
             "a" => Ok(ElementKind::A),
+            "div" => Ok(ElementKind::Div),
+            "span" => Ok(ElementKind::Span),
+            "br" => Ok(ElementKind::Br),
+            "img" => Ok(ElementKind::Img),
+            "iframe" => Ok(ElementKind::Iframe),
+            "template" => Ok(ElementKind::Template),
+            "form" => Ok(ElementKind::Form),
+            "label" => Ok(ElementKind::Label),
+            "input" => Ok(ElementKind::Input),
+            "button" => Ok(ElementKind::Button),
+            "select" => Ok(ElementKind::Select),
+            "optgroup" => Ok(ElementKind::Optgroup),
+            "option" => Ok(ElementKind::Option),
+            "textarea" => Ok(ElementKind::Textarea),
+            "meta" => Ok(ElementKind::Meta),
+            "link" => Ok(ElementKind::Link),
+            "table" => Ok(ElementKind::Table),
+            "caption" => Ok(ElementKind::Caption),
+            "colgroup" => Ok(ElementKind::Colgroup),
+            "col" => Ok(ElementKind::Col),
+            "tbody" => Ok(ElementKind::Tbody),
+            "thead" => Ok(ElementKind::Thead),
+            "tfoot" => Ok(ElementKind::Tfoot),
+            "tr" => Ok(ElementKind::Tr),
+            "td" => Ok(ElementKind::Td),
+            "th" => Ok(ElementKind::Th),
             _ => Err(format!("unimplemented element name: {:?}", s)),
         }
     }
@@ -232,9 +1797,420 @@ impl Display for ElementKind {
             ElementKind::Body => "body",
             ElementKind::H1 => "h1",
             ElementKind::H2 => "h2",
+            ElementKind::H3 => "h3",
+            ElementKind::H4 => "h4",
+            ElementKind::H5 => "h5",
+            ElementKind::H6 => "h6",
+            ElementKind::Section => "section",
             ElementKind::P => "p",
+            ElementKind::Pre => "pre",
+            ElementKind::Hr => "hr",
+            ElementKind::Ul => "ul",
+            ElementKind::Ol => "ol",
+            ElementKind::Li => "li",
             ElementKind::A => "a",
+            ElementKind::Div => "div",
+            ElementKind::Span => "span",
+            ElementKind::Br => "br",
+            ElementKind::Img => "img",
+            ElementKind::Iframe => "iframe",
+            ElementKind::Template => "template",
+            ElementKind::Form => "form",
+            ElementKind::Label => "label",
+            ElementKind::Input => "input",
+            ElementKind::Button => "button",
+            ElementKind::Select => "select",
+            ElementKind::Optgroup => "optgroup",
+            ElementKind::Option => "option",
+            ElementKind::Textarea => "textarea",
+            ElementKind::Meta => "meta",
+            ElementKind::Link => "link",
+            ElementKind::Table => "table",
+            ElementKind::Caption => "caption",
+            ElementKind::Colgroup => "colgroup",
+            ElementKind::Col => "col",
+            ElementKind::Tbody => "tbody",
+            ElementKind::Thead => "thead",
+            ElementKind::Tfoot => "tfoot",
+            ElementKind::Tr => "tr",
+            ElementKind::Td => "td",
+            ElementKind::Th => "th",
         };
         write!(f, "{}", s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+
+    fn element(document: &Rc<RefCell<Node>>, name: &str) -> Rc<RefCell<Node>> {
+        let arena = document.borrow().arena().expect("no arena");
+        create_node(&arena, NodeKind::Element(Element::new(name, Vec::new())))
+    }
+
+    fn text(document: &Rc<RefCell<Node>>, data: &str) -> Rc<RefCell<Node>> {
+        let arena = document.borrow().arena().expect("no arena");
+        create_node(&arena, NodeKind::Text(data.to_string()))
+    }
+
+    fn names(parent: &Rc<RefCell<Node>>) -> Vec<String> {
+        child_nodes(parent)
+            .iter()
+            .map(|n| match n.borrow().kind() {
+                NodeKind::Element(e) => e.local_name(),
+                NodeKind::Text(t) => t,
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    // checks every link of the children, in both directions
+    fn assert_consistent(parent: &Rc<RefCell<Node>>) {
+        let children = child_nodes(parent);
+        let mut previous: Option<Rc<RefCell<Node>>> = None;
+        for child in &children {
+            assert!(is_parent_of(parent, child));
+            match (&previous, child.borrow().previous_sibling().upgrade()) {
+                (Some(p), Some(q)) => assert!(Rc::ptr_eq(p, &q)),
+                (None, None) => {}
+                _ => panic!("inconsistent previous sibling"),
+            }
+            previous = Some(child.clone());
+        }
+        match (previous, parent.borrow().last_child().upgrade()) {
+            (Some(p), Some(q)) => assert!(Rc::ptr_eq(&p, &q)),
+            (None, None) => {}
+            _ => panic!("inconsistent last child"),
+        }
+    }
+
+    #[test]
+    fn test_append_and_insert() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let a = element(&document, "a");
+        let b = element(&document, "b");
+        let c = element(&document, "c");
+        assert!(append_child(&div, a.clone()).is_ok());
+        assert!(append_child(&div, c.clone()).is_ok());
+        assert!(insert_before(&div, b.clone(), Some(&c)).is_ok());
+        assert_eq!(vec!["a", "b", "c"], names(&div));
+        assert_consistent(&div);
+
+        // an inserted node is moved from its old position
+        assert!(append_child(&div, a.clone()).is_ok());
+        assert_eq!(vec!["b", "c", "a"], names(&div));
+        assert_consistent(&div);
+
+        assert!(insert_before(&div, c.clone(), Some(&c)).is_ok());
+        assert_eq!(vec!["b", "c", "a"], names(&div));
+        assert_consistent(&div);
+    }
+
+    #[test]
+    fn test_remove_and_replace() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let a = element(&document, "a");
+        let b = element(&document, "b");
+        let c = element(&document, "c");
+        assert!(replace_children(&div, vec![a.clone(), b.clone()]).is_ok());
+
+        assert!(replace_child(&div, c.clone(), &a).is_ok());
+        assert_eq!(vec!["c", "b"], names(&div));
+        assert!(a.borrow().parent().upgrade().is_none());
+        assert_consistent(&div);
+
+        assert!(remove_child(&div, &b).is_ok());
+        assert_eq!(vec!["c"], names(&div));
+        assert_consistent(&div);
+        assert!(remove_child(&div, &b).is_err());
+
+        assert!(replace_children(&div, vec![text(&document, "x"), b.clone()]).is_ok());
+        assert_eq!(vec!["x", "b"], names(&div));
+        assert!(c.borrow().parent().upgrade().is_none());
+        assert_consistent(&div);
+    }
+
+    #[test]
+    fn test_invalid_mutations() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let span = element(&document, "span");
+        assert!(append_child(&div, span.clone()).is_ok());
+
+        // hierarchy errors
+        assert!(append_child(&span, div.clone()).is_err());
+        assert!(append_child(&span, span.clone()).is_err());
+        assert!(append_child(&text(&document, "x"), element(&document, "p")).is_err());
+        assert!(append_child(&div, document.clone()).is_err());
+        assert!(append_child(&document, text(&document, "x")).is_err());
+        let doctype = create_node(
+            &document.borrow().arena().expect("no arena"),
+            NodeKind::DocumentType(DocumentType::new(
+                "html".to_string(),
+                String::new(),
+                String::new(),
+            )),
+        );
+        assert!(append_child(&div, doctype.clone()).is_err());
+
+        // a document has at most one doctype followed by one element
+        let html = element(&document, "html");
+        assert!(append_child(&document, html.clone()).is_ok());
+        assert!(append_child(&document, element(&document, "html")).is_err());
+        assert!(append_child(&document, doctype.clone()).is_err());
+        assert!(insert_before(&document, doctype.clone(), Some(&html)).is_ok());
+        assert!(replace_child(&document, element(&document, "body"), &html).is_ok());
+        assert!(replace_children(
+            &document,
+            vec![element(&document, "a"), element(&document, "b")]
+        )
+        .is_err());
+
+        // the reference child must be a child of the parent
+        assert!(insert_before(&div, element(&document, "p"), Some(&html)).is_err());
+        assert!(replace_child(&div, element(&document, "p"), &html).is_err());
+        assert_eq!(vec!["span"], names(&div));
+    }
+
+    #[test]
+    fn test_clone_node() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let span = element(&document, "span");
+        assert!(append_child(&div, span.clone()).is_ok());
+        assert!(append_child(&span, text(&document, "x")).is_ok());
+
+        let shallow = clone_node(&div, false);
+        assert_eq!(div.borrow().kind(), shallow.borrow().kind());
+        assert!(shallow.borrow().first_child().is_none());
+
+        let deep = clone_node(&span, true);
+        assert!(deep.borrow().parent().upgrade().is_none());
+        assert_eq!(vec!["x"], names(&deep));
+        assert_consistent(&deep);
+        assert!(!Rc::ptr_eq(
+            &deep.borrow().first_child().expect("no child"),
+            &span.borrow().first_child().expect("no child")
+        ));
+
+        // the contents of a template are cloned only by a deep clone
+        let template = element(&document, "template");
+        let contents = template_contents(&template).expect("no contents");
+        assert!(append_child(&contents, text(&document, "y")).is_ok());
+        let deep = clone_node(&template, true);
+        let copy_contents = template_contents(&deep).expect("no contents");
+        assert!(!Rc::ptr_eq(&contents, &copy_contents));
+        assert_eq!(vec!["y"], names(&copy_contents));
+        assert_consistent(&copy_contents);
+        let shallow = clone_node(&template, false);
+        assert!(names(&template_contents(&shallow).expect("no contents")).is_empty());
+    }
+
+    #[test]
+    fn test_mutation_observer() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let span = element(&document, "span");
+        assert!(append_child(&div, span.clone()).is_ok());
+
+        let records: Rc<RefCell<Vec<MutationRecord>>> = Rc::new(RefCell::new(Vec::new()));
+        let subtree_records = records.clone();
+        observe(
+            &div,
+            MutationObserver::new(Rc::new(move |r: &MutationRecord| {
+                subtree_records.borrow_mut().push(r.clone())
+            })),
+            true,
+        );
+        let child_count = Rc::new(RefCell::new(0));
+        let counter = child_count.clone();
+        observe(
+            &div,
+            MutationObserver::new(Rc::new(move |_: &MutationRecord| {
+                *counter.borrow_mut() += 1
+            })),
+            false,
+        );
+
+        let x = text(&document, "x");
+        assert!(append_child(&span, x.clone()).is_ok());
+        assert!(remove_child(&div, &span).is_ok());
+
+        let records = records.borrow();
+        assert_eq!(2, records.len());
+        assert!(Rc::ptr_eq(&span, &records[0].target()));
+        assert!(Rc::ptr_eq(&x, &records[0].added_nodes()[0]));
+        assert!(Rc::ptr_eq(&div, &records[1].target()));
+        assert!(Rc::ptr_eq(&span, &records[1].removed_nodes()[0]));
+        // the observer without subtree is notified only of the mutations of its children
+        assert_eq!(1, *child_count.borrow());
+    }
+
+    #[test]
+    fn test_node_ids() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let span = element(&document, "span");
+        assert!(append_child(&document, div.clone()).is_ok());
+        assert!(append_child(&div, span.clone()).is_ok());
+
+        let arena = document.borrow().arena().expect("no arena");
+        for node in [&document, &div, &span] {
+            let id = node.borrow().id().expect("no id");
+            assert!(Rc::ptr_eq(node, &arena.borrow().get(id).expect("no node")));
+        }
+
+        // ids are stable across mutations
+        let id = span.borrow().id();
+        assert!(remove_child(&div, &span).is_ok());
+        assert!(append_child(&div, span.clone()).is_ok());
+        assert_eq!(id, span.borrow().id());
+    }
+
+    #[test]
+    fn test_nodes_without_arena() {
+        let parent = Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+            "div",
+            Vec::new(),
+        )))));
+        let child = Rc::new(RefCell::new(Node::new(NodeKind::Text("x".to_string()))));
+        assert!(append_child(&parent, child.clone()).is_err());
+        assert!(insert_before(&parent, child, None).is_err());
+    }
+
+    #[test]
+    fn test_collect_removed_nodes() {
+        let document = create_document();
+        let div = element(&document, "div");
+        assert!(append_child(&document, div.clone()).is_ok());
+        let arena = document.borrow().arena().expect("no arena");
+
+        let kept = element(&document, "kept");
+        assert!(append_child(&div, kept.clone()).is_ok());
+        let kept_id = kept.borrow().id().expect("no id");
+        let mut dropped_ids = Vec::new();
+        for _ in 0..100 {
+            let children: Vec<Rc<RefCell<Node>>> =
+                (0..10).map(|_| element(&document, "p")).collect();
+            dropped_ids.extend(children.iter().filter_map(|c| c.borrow().id()));
+            assert!(replace_children(&div, children).is_ok());
+        }
+
+        // the removed nodes are freed, except the one which is still referred to
+        assert!(dropped_ids[..10]
+            .iter()
+            .all(|id| arena.borrow().get(*id).is_none()));
+        assert!(arena.borrow().get(kept_id).is_some());
+        assert!(kept.borrow().parent().upgrade().is_none());
+        // the children of the tree are kept
+        assert_eq!(10, child_nodes(&div).len());
+        assert!(child_nodes(&div).iter().all(|c| c
+            .borrow()
+            .id()
+            .is_some_and(|id| arena.borrow().get(id).is_some())));
+    }
+
+    #[test]
+    fn test_descendants() {
+        let document = create_document();
+        let div = element(&document, "div");
+        let a = element(&document, "a");
+        let b = element(&document, "b");
+        assert!(append_child(&div, a.clone()).is_ok());
+        assert!(append_child(&a, text(&document, "x")).is_ok());
+        assert!(append_child(&div, b.clone()).is_ok());
+        assert!(append_child(&b, element(&document, "c")).is_ok());
+
+        let names: Vec<String> = descendants(&div)
+            .map(|n| match n.borrow().kind() {
+                NodeKind::Element(e) => e.local_name(),
+                NodeKind::Text(t) => t,
+                _ => String::new(),
+            })
+            .collect();
+        assert_eq!(vec!["a", "x", "b", "c"], names);
+        // the siblings of the root are not its descendants
+        assert_eq!(1, descendants(&a).count());
+    }
+
+    #[test]
+    fn test_adopt() {
+        let document = create_document();
+        let other = create_document();
+        let div = element(&other, "div");
+        assert!(append_child(&div, element(&other, "span")).is_ok());
+        let old_id = div.borrow().id().expect("no id");
+
+        // the subtree is moved into the arena of the new parent
+        assert!(append_child(&document, div.clone()).is_ok());
+        let arena = document.borrow().arena().expect("no arena");
+        assert!(Rc::ptr_eq(&arena, &div.borrow().arena().expect("no arena")));
+        let span = div.borrow().first_child().expect("no span");
+        assert!(Rc::ptr_eq(
+            &arena,
+            &span.borrow().arena().expect("no arena")
+        ));
+        assert_eq!(vec!["span"], names(&div));
+        assert_consistent(&div);
+
+        // the old slot is left empty
+        let other_arena = other.borrow().arena().expect("no arena");
+        assert!(other_arena.borrow().get(old_id).is_none());
+    }
+
+    #[test]
+    fn test_window_back_references() {
+        let window = create_window();
+        let document = window.borrow().document();
+        let default_view = document.borrow().default_view().expect("no window");
+        assert!(Rc::ptr_eq(&window, &default_view));
+        assert!(document.borrow().owner_document().is_none());
+
+        let div = element(&document, "div");
+        let child = text(&document, "a");
+        append_child(&div, child.clone()).expect("failed to append");
+        // a node knows its document even before it is connected
+        let owner = child.borrow().owner_document().expect("no owner document");
+        assert!(Rc::ptr_eq(&document, &owner));
+        assert!(div.borrow().default_view().is_none());
+
+        // a node moved to another document follows the new document
+        let other = create_document();
+        append_child(&other, div.clone()).expect("failed to append");
+        let owner = child.borrow().owner_document().expect("no owner document");
+        assert!(Rc::ptr_eq(&other, &owner));
+        assert!(other.borrow().default_view().is_none());
+
+        drop(default_view);
+        drop(window);
+        assert!(document.borrow().default_view().is_none());
+    }
+
+    #[test]
+    fn test_location() {
+        let location = Location::new("http://user@example.com:8080/dir/a.html?q=1#top".to_string());
+        assert_eq!("http:", location.protocol());
+        assert_eq!("example.com:8080", location.host());
+        assert_eq!("example.com", location.hostname());
+        assert_eq!("8080", location.port());
+        assert_eq!("/dir/a.html", location.pathname());
+        assert_eq!("?q=1", location.search());
+        assert_eq!("#top", location.hash());
+        assert_eq!("http://example.com:8080", location.origin());
+
+        let location = Location::new("http://example.com?#".to_string());
+        assert_eq!("", location.port());
+        assert_eq!("/", location.pathname());
+        assert_eq!("", location.search());
+        assert_eq!("", location.hash());
+
+        let location = Location::new("about:blank".to_string());
+        assert_eq!("about:", location.protocol());
+        assert_eq!("blank", location.pathname());
+        assert_eq!("null", location.origin());
+    }
+}
@@ -0,0 +1,427 @@
+use crate::renderer::html::dom::Node;
+use crate::renderer::js::runtime::JsRuntime;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use core::cell::Cell;
+use core::cell::RefCell;
+use core::fmt::Debug;
+use core::fmt::Formatter;
+
+// https://dom.spec.whatwg.org/#dom-event-eventphase
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventPhase {
+    None = 0,
+    Capturing = 1,
+    AtTarget = 2,
+    Bubbling = 3,
+}
+
+// https://dom.spec.whatwg.org/#interface-event
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    event_type: String,
+    bubbles: bool,
+    cancelable: bool,
+    target: Option<Rc<RefCell<Node>>>,
+    current_target: Option<Rc<RefCell<Node>>>,
+    phase: EventPhase,
+    // https://dom.spec.whatwg.org/#stop-propagation-flag
+    stop_propagation: bool,
+    // https://dom.spec.whatwg.org/#stop-immediate-propagation-flag
+    stop_immediate_propagation: bool,
+    // https://dom.spec.whatwg.org/#canceled-flag
+    canceled: bool,
+}
+
+impl Event {
+    pub fn new(event_type: &str, bubbles: bool, cancelable: bool) -> Self {
+        Self {
+            event_type: event_type.to_string(),
+            bubbles,
+            cancelable,
+            target: None,
+            current_target: None,
+            phase: EventPhase::None,
+            stop_propagation: false,
+            stop_immediate_propagation: false,
+            canceled: false,
+        }
+    }
+
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    pub fn bubbles(&self) -> bool {
+        self.bubbles
+    }
+
+    pub fn cancelable(&self) -> bool {
+        self.cancelable
+    }
+
+    pub fn target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.target.clone()
+    }
+
+    // the node whose listeners are being called, which is None outside of the dispatch
+    pub fn current_target(&self) -> Option<Rc<RefCell<Node>>> {
+        self.current_target.clone()
+    }
+
+    pub fn event_phase(&self) -> EventPhase {
+        self.phase
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-defaultprevented
+    pub fn default_prevented(&self) -> bool {
+        self.canceled
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-preventdefault
+    pub fn prevent_default(&mut self) {
+        if self.cancelable {
+            self.canceled = true;
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-stoppropagation
+    pub fn stop_propagation(&mut self) {
+        self.stop_propagation = true;
+    }
+
+    // https://dom.spec.whatwg.org/#dom-event-stopimmediatepropagation
+    pub fn stop_immediate_propagation(&mut self) {
+        self.stop_propagation = true;
+        self.stop_immediate_propagation = true;
+    }
+}
+
+// https://dom.spec.whatwg.org/#callbackdef-eventlistener
+#[derive(Clone)]
+pub enum EventCallback {
+    // a callback of the browser, e.g. the UI
+    Native(Rc<dyn Fn(&mut Event)>),
+    // the name of a function which is declared by a script, which the runtime calls with the
+    // event as the argument
+    Script(String),
+}
+
+impl PartialEq for EventCallback {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (EventCallback::Native(a), EventCallback::Native(b)) => Rc::ptr_eq(a, b),
+            (EventCallback::Script(a), EventCallback::Script(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl Debug for EventCallback {
+    fn fmt(&self, f: &mut Formatter) -> core::fmt::Result {
+        match self {
+            EventCallback::Native(_) => write!(f, "Native"),
+            EventCallback::Script(name) => write!(f, "Script({})", name),
+        }
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-event-listener
+#[derive(Debug, Clone)]
+pub struct EventListener {
+    event_type: String,
+    callback: EventCallback,
+    capture: bool,
+    once: bool,
+    // https://dom.spec.whatwg.org/#event-listener-removed
+    // shared with the copies of the listener list which are being dispatched
+    removed: Rc<Cell<bool>>,
+}
+
+impl EventListener {
+    pub fn event_type(&self) -> String {
+        self.event_type.clone()
+    }
+
+    pub fn capture(&self) -> bool {
+        self.capture
+    }
+
+    pub fn once(&self) -> bool {
+        self.once
+    }
+}
+
+// https://dom.spec.whatwg.org/#add-an-event-listener
+// the listener is not added when the same callback is already registered for the type and
+// the phase
+pub fn add_event_listener(
+    node: &Rc<RefCell<Node>>,
+    event_type: &str,
+    callback: EventCallback,
+    capture: bool,
+    once: bool,
+) {
+    let mut node = node.borrow_mut();
+    let listeners = node.event_listeners_mut();
+    if listeners
+        .iter()
+        .any(|l| l.event_type == event_type && l.callback == callback && l.capture == capture)
+    {
+        return;
+    }
+    listeners.push(EventListener {
+        event_type: event_type.to_string(),
+        callback,
+        capture,
+        once,
+        removed: Rc::new(Cell::new(false)),
+    });
+}
+
+// https://dom.spec.whatwg.org/#remove-an-event-listener
+pub fn remove_event_listener(
+    node: &Rc<RefCell<Node>>,
+    event_type: &str,
+    callback: &EventCallback,
+    capture: bool,
+) {
+    node.borrow_mut().event_listeners_mut().retain(|l| {
+        let matched = l.event_type == event_type && &l.callback == callback && l.capture == capture;
+        if matched {
+            l.removed.set(true);
+        }
+        !matched
+    });
+}
+
+// https://dom.spec.whatwg.org/#concept-event-dispatch
+// calls the capture listeners from the root down to the target, and then the other listeners
+// from the target up to the root if the event bubbles. listeners of scripts and event handler
+// attributes are called by the runtime. returns false if a listener cancels the event
+pub fn dispatch_event(
+    target: &Rc<RefCell<Node>>,
+    event: &mut Event,
+    mut runtime: Option<&mut JsRuntime>,
+) -> bool {
+    event.target = Some(target.clone());
+
+    // https://dom.spec.whatwg.org/#get-the-parent
+    // there are no shadow trees, so the composed path is the target and its ancestors.
+    // the window isn't a node and can't have listeners, so the path ends at the document
+    // instead of the window
+    let mut path = vec![target.clone()];
+    let mut parent = target.borrow().parent().upgrade();
+    while let Some(p) = parent {
+        parent = p.borrow().parent().upgrade();
+        path.push(p);
+    }
+
+    for (i, node) in path.iter().enumerate().rev() {
+        event.phase = if i == 0 {
+            EventPhase::AtTarget
+        } else {
+            EventPhase::Capturing
+        };
+        invoke(node, event, true, &mut runtime);
+    }
+    for (i, node) in path.iter().enumerate() {
+        if i == 0 {
+            event.phase = EventPhase::AtTarget;
+        } else if event.bubbles {
+            event.phase = EventPhase::Bubbling;
+        } else {
+            continue;
+        }
+        invoke(node, event, false, &mut runtime);
+    }
+
+    event.phase = EventPhase::None;
+    event.current_target = None;
+    event.stop_propagation = false;
+    event.stop_immediate_propagation = false;
+    !event.canceled
+}
+
+// https://dom.spec.whatwg.org/#concept-event-listener-invoke
+fn invoke(
+    node: &Rc<RefCell<Node>>,
+    event: &mut Event,
+    capture: bool,
+    runtime: &mut Option<&mut JsRuntime>,
+) {
+    if event.stop_propagation {
+        return;
+    }
+    event.current_target = Some(node.clone());
+
+    // https://html.spec.whatwg.org/multipage/webappapis.html#event-handler-attributes
+    // the handler of the attribute is registered by the parser, before any listener of scripts
+    if !capture {
+        let handler = node
+            .borrow()
+            .get_element()
+            .and_then(|e| e.get_attribute(&format!("on{}", event.event_type)));
+        if let (Some(handler), Some(runtime)) = (handler, runtime.as_deref_mut()) {
+            runtime.run_event_handler(&handler, event);
+            if event.stop_immediate_propagation {
+                return;
+            }
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-event-listener-inner-invoke
+    // the listeners which are added during the dispatch are not called, and the removed ones
+    // are skipped
+    let listeners = node.borrow().event_listeners();
+    for listener in listeners {
+        if listener.removed.get()
+            || listener.event_type != event.event_type
+            || listener.capture != capture
+        {
+            continue;
+        }
+        if listener.once {
+            remove_event_listener(node, &listener.event_type, &listener.callback, capture);
+        }
+
+        // the borrows of the node are released, as the callback may change the tree
+        match &listener.callback {
+            EventCallback::Native(callback) => callback(event),
+            EventCallback::Script(name) => {
+                if let Some(runtime) = runtime.as_deref_mut() {
+                    runtime.call_event_listener(name, event);
+                }
+            }
+        }
+        if event.stop_immediate_propagation {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::helper::test_util::by_id;
+    use crate::renderer::html::helper::test_util::parse;
+    use alloc::vec::Vec;
+
+    // a listener which records the id of the current target and the phase
+    fn recorder(log: &Rc<RefCell<Vec<String>>>, name: &str) -> EventCallback {
+        let log = log.clone();
+        let name = name.to_string();
+        EventCallback::Native(Rc::new(move |event: &mut Event| {
+            let id = event
+                .current_target()
+                .and_then(|n| n.borrow().get_element())
+                .and_then(|e| e.get_attribute("id"))
+                .unwrap_or("document".to_string());
+            log.borrow_mut()
+                .push(format!("{} {} {:?}", name, id, event.event_phase()));
+        }))
+    }
+
+    #[test]
+    fn test_dispatch_phases() {
+        let document =
+            parse("<html><body><div id=outer><a id=link href=x>link</a></div></body></html>");
+        let outer = by_id(&document, "outer");
+        let link = by_id(&document, "link");
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        add_event_listener(&outer, "click", recorder(&log, "bubble"), false, false);
+        add_event_listener(&outer, "click", recorder(&log, "capture"), true, false);
+        add_event_listener(&link, "click", recorder(&log, "bubble"), false, false);
+        add_event_listener(&link, "click", recorder(&log, "capture"), true, false);
+        add_event_listener(&document, "click", recorder(&log, "capture"), true, false);
+        // the same listener is registered once
+        let callback = recorder(&log, "once");
+        add_event_listener(&document, "click", callback.clone(), false, true);
+        add_event_listener(&document, "click", callback, false, true);
+        add_event_listener(&link, "focus", recorder(&log, "focus"), false, false);
+
+        let mut event = Event::new("click", true, true);
+        assert!(dispatch_event(&link, &mut event, None));
+        assert_eq!(
+            vec![
+                "capture document Capturing",
+                "capture outer Capturing",
+                "capture link AtTarget",
+                "bubble link AtTarget",
+                "bubble outer Bubbling",
+                "once document Bubbling",
+            ],
+            *log.borrow()
+        );
+        assert_eq!(Some(link.clone()), event.target());
+        assert_eq!(None, event.current_target());
+        assert_eq!(EventPhase::None, event.event_phase());
+
+        // the once listener has been removed, and an event which doesn't bubble stops at the
+        // target
+        log.borrow_mut().clear();
+        dispatch_event(&link, &mut Event::new("click", false, true), None);
+        assert_eq!(
+            vec![
+                "capture document Capturing",
+                "capture outer Capturing",
+                "capture link AtTarget",
+                "bubble link AtTarget",
+            ],
+            *log.borrow()
+        );
+    }
+
+    #[test]
+    fn test_cancel_and_stop_propagation() {
+        let document =
+            parse("<html><body><div id=outer><a id=link href=x>link</a></div></body></html>");
+        let outer = by_id(&document, "outer");
+        let link = by_id(&document, "link");
+        let log = Rc::new(RefCell::new(Vec::new()));
+
+        let stop = EventCallback::Native(Rc::new(|event: &mut Event| {
+            event.prevent_default();
+            event.stop_propagation();
+        }));
+        add_event_listener(&link, "click", stop.clone(), false, false);
+        add_event_listener(&link, "click", recorder(&log, "bubble"), false, false);
+        add_event_listener(&outer, "click", recorder(&log, "bubble"), false, false);
+
+        // the listeners of the current target are still called after stopPropagation
+        let mut event = Event::new("click", true, true);
+        assert!(!dispatch_event(&link, &mut event, None));
+        assert!(event.default_prevented());
+        assert_eq!(vec!["bubble link AtTarget"], *log.borrow());
+
+        // an event which is not cancelable can't be canceled
+        log.borrow_mut().clear();
+        remove_event_listener(&link, "click", &stop, false);
+        let immediate = EventCallback::Native(Rc::new(|event: &mut Event| {
+            event.prevent_default();
+            event.stop_immediate_propagation();
+        }));
+        add_event_listener(&outer, "click", immediate, true, false);
+        let mut event = Event::new("click", true, false);
+        assert!(dispatch_event(&link, &mut event, None));
+        assert!(!event.default_prevented());
+        assert!(log.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_event_handler_attribute() {
+        let document = parse(
+            "<html><body><a id=link href=x onclick=\"event.foo.bar; event.preventDefault()\">link</a></body></html>",
+        );
+        let link = by_id(&document, "link");
+        let mut runtime = JsRuntime::new(document.clone());
+
+        // the unknown properties of the event are undefined
+        let mut event = Event::new("click", true, true);
+        assert!(!dispatch_event(&link, &mut event, Some(&mut runtime)));
+    }
+}
@@ -1,38 +1,280 @@
+use crate::error::Error;
+use crate::renderer::css::selector::parse_selector_list;
+use crate::renderer::html::dom::append_child;
+use crate::renderer::html::dom::compare_tree_order;
+use crate::renderer::html::dom::create_node;
+use crate::renderer::html::dom::descendants;
+use crate::renderer::html::dom::indexed_elements;
+use crate::renderer::html::dom::insert_before;
+use crate::renderer::html::dom::is_connected;
+use crate::renderer::html::dom::replace_all;
+use crate::renderer::html::dom::template_contents;
 use crate::renderer::html::dom::Element;
 use crate::renderer::html::dom::ElementKind;
+use crate::renderer::html::dom::IndexKind;
+use crate::renderer::html::dom::Namespace;
 use crate::renderer::html::dom::Node;
 use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::parser::HtmlParser;
+use crate::renderer::html::token::HtmlTokenizer;
+use crate::url::resolve_url;
+use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
 
+// gets the node, its following siblings and all of their descendants in tree order
+fn tree_order(node: Option<Rc<RefCell<Node>>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = Vec::new();
+    let mut current = node;
+
+    while let Some(n) = current {
+        nodes.push(n.clone());
+        nodes.extend(descendants(&n));
+        current = n.borrow().next_sibling();
+    }
+
+    nodes
+}
+
 // gets the first node of the matching id, similar to getElementById
 pub fn get_element_by_id(
     node: Option<Rc<RefCell<Node>>>,
     id_name: &String,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if let NodeKind::Element(e) = n.borrow().kind() {
-                for attr in &e.attributes() {
-                    if attr.name() == "id" && attr.value() == *id_name {
-                        return Some(n.clone());
-                    }
-                }
+    // the whole document is looked up by its index
+    if let Some(ref n) = node {
+        let arena = n.borrow().arena();
+        if let (NodeKind::Document, Some(arena)) = (n.borrow().kind(), arena) {
+            return indexed_elements(&arena, IndexKind::Id, id_name)
+                .into_iter()
+                .next();
+        }
+    }
+
+    tree_order(node)
+        .into_iter()
+        .find(|n| match n.borrow().get_element() {
+            Some(e) => e.get_attribute("id").as_ref() == Some(id_name),
+            None => false,
+        })
+}
+
+fn is_descendant_of(node: &Rc<RefCell<Node>>, root: &Rc<RefCell<Node>>) -> bool {
+    let mut parent = node.borrow().parent().upgrade();
+    while let Some(p) = parent {
+        if Rc::ptr_eq(&p, root) {
+            return true;
+        }
+        parent = p.borrow().parent().upgrade();
+    }
+    false
+}
+
+// gets the descendants of the root which have the key, from the indexes of the document if the
+// root is connected to it
+fn indexed_descendants(
+    root: &Rc<RefCell<Node>>,
+    kind: IndexKind,
+    key: &str,
+) -> Vec<Rc<RefCell<Node>>> {
+    let arena = root.borrow().arena();
+    match arena {
+        Some(arena) if is_connected(root) => {
+            let is_document = root.borrow().kind() == NodeKind::Document;
+            indexed_elements(&arena, kind, key)
+                .into_iter()
+                .filter(|n| is_document || is_descendant_of(n, root))
+                .collect()
+        }
+        _ => descendants(root)
+            .filter(|n| match n.borrow().get_element() {
+                Some(e) => match kind {
+                    IndexKind::Id => e.get_attribute("id").as_deref() == Some(key),
+                    IndexKind::Class => e.has_class(key),
+                    IndexKind::Tag => e.local_name() == key,
+                },
+                None => false,
+            })
+            .collect(),
+    }
+}
+
+// https://dom.spec.whatwg.org/#concept-getelementsbyclassname
+// gets the descendants which have all of the classes separated by whitespace
+pub fn get_elements_by_class_name(
+    root: &Rc<RefCell<Node>>,
+    class_names: &str,
+) -> Vec<Rc<RefCell<Node>>> {
+    let classes: Vec<&str> = class_names.split_ascii_whitespace().collect();
+    if classes.is_empty() {
+        return Vec::new();
+    }
+
+    indexed_descendants(root, IndexKind::Class, classes[0])
+        .into_iter()
+        .filter(|n| match n.borrow().get_element() {
+            Some(e) => classes[1..].iter().all(|c| e.has_class(c)),
+            None => false,
+        })
+        .collect()
+}
+
+// https://dom.spec.whatwg.org/#concept-getelementsbytagname
+// gets the descendants of the tag name, or all descendant elements for "*"
+pub fn get_elements_by_tag_name(
+    root: &Rc<RefCell<Node>>,
+    qualified_name: &str,
+) -> Vec<Rc<RefCell<Node>>> {
+    if qualified_name == "*" {
+        return descendants(root)
+            .filter(|n| n.borrow().get_element().is_some())
+            .collect();
+    }
+
+    // HTML elements are matched in lowercase, and the others as it is
+    let lowercase = qualified_name.to_ascii_lowercase();
+    if lowercase == qualified_name {
+        return indexed_descendants(root, IndexKind::Tag, qualified_name);
+    }
+
+    let is_html = |n: &Rc<RefCell<Node>>| match n.borrow().get_element() {
+        Some(e) => e.namespace() == Namespace::Html,
+        None => false,
+    };
+    let mut nodes: Vec<Rc<RefCell<Node>>> = indexed_descendants(root, IndexKind::Tag, &lowercase)
+        .into_iter()
+        .filter(is_html)
+        .collect();
+    nodes.extend(
+        indexed_descendants(root, IndexKind::Tag, qualified_name)
+            .into_iter()
+            .filter(|n| !is_html(n)),
+    );
+    nodes.sort_by(compare_tree_order);
+    nodes
+}
+
+// https://dom.spec.whatwg.org/#dom-parentnode-queryselector
+// gets the first descendant of the root which matches the selectors
+pub fn query_selector(
+    root: &Rc<RefCell<Node>>,
+    selectors: &str,
+) -> Result<Option<Rc<RefCell<Node>>>, Error> {
+    let list = parse_selector_list(selectors)?;
+    Ok(descendants(root).find(|n| list.matches(n)))
+}
+
+// https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall
+// gets all descendants of the root which match the selectors in tree order
+pub fn query_selector_all(
+    root: &Rc<RefCell<Node>>,
+    selectors: &str,
+) -> Result<Vec<Rc<RefCell<Node>>>, Error> {
+    let list = parse_selector_list(selectors)?;
+    Ok(descendants(root).filter(|n| list.matches(n)).collect())
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+// gets the URL which relative URLs of the document are resolved against, which is the href of
+// the first <base> element if any, or the URL of the document
+pub fn get_document_base_url(document: &Rc<RefCell<Node>>, document_url: &str) -> String {
+    let href = get_elements_by_tag_name(document, "base")
+        .into_iter()
+        .find_map(|base| base.borrow().get_element()?.get_attribute("href"));
+
+    // https://html.spec.whatwg.org/multipage/semantics.html#set-the-frozen-base-url
+    match href {
+        Some(href) => resolve_url(document_url, &href),
+        None => document_url.to_string(),
+    }
+}
+
+// parses the HTML string as children of the context node, and returns the nodes
+// which are not inserted into any tree yet. the nodes belong to the arena of the context,
+// so the context must be a node of a document
+pub fn parse_fragment(context: &Rc<RefCell<Node>>, html: &str) -> Vec<Rc<RefCell<Node>>> {
+    HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_fragment(context.clone())
+}
+
+// https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-innerhtml
+pub fn set_inner_html(node: &Rc<RefCell<Node>>, html: &str) {
+    let fragment = parse_fragment(node, html);
+    // the children of <template> are its contents
+    let parent = template_contents(node).unwrap_or_else(|| node.clone());
+    replace_all(&parent, fragment);
+}
+
+// https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-insertadjacenthtml
+pub fn insert_adjacent_html(
+    node: &Rc<RefCell<Node>>,
+    position: &str,
+    html: &str,
+) -> Result<(), Error> {
+    let position = position.to_ascii_lowercase();
+    let context = match position.as_str() {
+        "beforebegin" | "afterend" => match node.borrow().parent().upgrade() {
+            Some(parent) if parent.borrow().kind() != NodeKind::Document => parent,
+            _ => {
+                return Err(Error::UnexpectedInput(format!(
+                "NoModificationAllowedError: cannot insert HTML {} a node without a parent element",
+                position
+            )))
             }
+        },
+        "afterbegin" | "beforeend" => node.clone(),
+        _ => {
+            return Err(Error::UnexpectedInput(format!(
+                "SyntaxError: invalid position {:?}",
+                position
+            )))
+        }
+    };
 
-            let result1 = get_element_by_id(n.borrow().first_child(), id_name);
-            let result2 = get_element_by_id(n.borrow().next_sibling(), id_name);
-            if result1.is_none() {
-                return result2;
+    // <html> and non-element contexts are replaced with <body>
+    let context = match context.borrow().get_element() {
+        Some(e) if !(e.namespace() == Namespace::Html && e.local_name() == "html") => {
+            context.clone()
+        }
+        _ => {
+            let body = NodeKind::Element(Element::new("body", Vec::new()));
+            match node.borrow().arena() {
+                Some(arena) => create_node(&arena, body),
+                None => {
+                    return Err(Error::UnexpectedInput(
+                        "NotSupportedError: the node doesn't belong to any document".to_string(),
+                    ))
+                }
             }
+        }
+    };
+
+    let fragment = parse_fragment(&context, html);
+    let first_child = node.borrow().first_child();
+    let next_sibling = node.borrow().next_sibling();
 
-            result1
+    for n in fragment {
+        match position.as_str() {
+            "beforebegin" => {
+                let parent = node.borrow().parent().upgrade().expect("no parent");
+                insert_before(&parent, n, Some(node))?;
+            }
+            "afterbegin" => {
+                insert_before(node, n, first_child.as_ref())?;
+            }
+            "beforeend" => {
+                append_child(node, n)?;
+            }
+            _ => {
+                let parent = node.borrow().parent().upgrade().expect("no parent");
+                insert_before(&parent, n, next_sibling.as_ref())?;
+            }
         }
-        None => None,
     }
+
+    Ok(())
 }
 
 // gets the first node of the matching element_kind
@@ -41,54 +283,354 @@ pub fn get_target_element_node(
     node: Option<Rc<RefCell<Node>>>,
     element_kind: ElementKind,
 ) -> Option<Rc<RefCell<Node>>> {
-    match node {
-        Some(n) => {
-            if n.borrow().kind()
-                == NodeKind::Element(Element::new(&element_kind.to_string(), Vec::new()))
-            {
-                return Some(n.clone());
-            }
-            let result1 = get_target_element_node(n.borrow().first_child(), element_kind);
-            let result2 = get_target_element_node(n.borrow().next_sibling(), element_kind);
-            if result1.is_none() {
-                return result2;
-            }
-            result1
+    tree_order(node)
+        .into_iter()
+        .find(|n| n.borrow().element_kind() == Some(element_kind))
+}
+
+// gets all nodes of the matching element kinds in document order
+pub fn get_element_nodes(
+    node: Option<Rc<RefCell<Node>>>,
+    element_kinds: &[ElementKind],
+) -> Vec<Rc<RefCell<Node>>> {
+    tree_order(node)
+        .into_iter()
+        .filter(|n| match n.borrow().element_kind() {
+            Some(kind) => element_kinds.contains(&kind),
+            None => false,
+        })
+        .collect()
+}
+
+// gets the concatenation of the text children, similar to child text content
+pub fn get_text_content(node: &Rc<RefCell<Node>>) -> String {
+    let mut content = String::new();
+    let mut child = node.borrow().first_child();
+
+    while let Some(c) = child {
+        if let NodeKind::Text(ref s) = c.borrow().kind {
+            content.push_str(s);
         }
-        None => None,
+        child = c.borrow().next_sibling();
     }
-}
 
-// gets the style tag contents
-pub fn get_style_content(root: Rc<RefCell<Node>>) -> String {
-    let style_node = match get_target_element_node(Some(root), ElementKind::Style) {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let text_node = match style_node.borrow().first_child() {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let content = match &text_node.borrow().kind() {
-        NodeKind::Text(ref s) => s.clone(),
-        _ => "".to_string(),
-    };
     content
 }
 
-// gets the script tag contents
-pub fn get_js_content(root: Rc<RefCell<Node>>) -> String {
-    let js_node = match get_target_element_node(Some(root), ElementKind::Script) {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let text_node = match js_node.borrow().first_child() {
-        Some(node) => node,
-        None => return "".to_string(),
-    };
-    let content = match &text_node.borrow().kind() {
-        NodeKind::Text(ref s) => s.clone(),
-        _ => "".to_string(),
+// gets the contents of all style tags in document order
+pub fn get_style_contents(root: Rc<RefCell<Node>>) -> Vec<String> {
+    get_element_nodes(Some(root), &[ElementKind::Style])
+        .iter()
+        .map(get_text_content)
+        .collect()
+}
+
+// https://mimesniff.spec.whatwg.org/#javascript-mime-type
+fn is_javascript_mime_type(mime_type: &str) -> bool {
+    matches!(
+        mime_type.trim().to_ascii_lowercase().as_str(),
+        "application/ecmascript"
+            | "application/javascript"
+            | "application/x-ecmascript"
+            | "application/x-javascript"
+            | "text/ecmascript"
+            | "text/javascript"
+            | "text/javascript1.0"
+            | "text/javascript1.1"
+            | "text/javascript1.2"
+            | "text/javascript1.3"
+            | "text/javascript1.4"
+            | "text/javascript1.5"
+            | "text/jscript"
+            | "text/livescript"
+            | "text/x-ecmascript"
+            | "text/x-javascript"
+    )
+}
+
+// https://html.spec.whatwg.org/multipage/scripting.html#prepare-the-script-element
+// returns true if the script is a classic script, which the runtime can execute
+pub fn is_classic_script(script: &Rc<RefCell<Node>>) -> bool {
+    let element = match script.borrow().get_element() {
+        Some(e) => e,
+        None => return false,
     };
-    content
+
+    match element.get_attribute("type") {
+        // an empty type is the same as no type
+        Some(t) => t.is_empty() || is_javascript_mime_type(&t),
+        None => true,
+    }
+}
+
+// gets the contents of all script tags in document order,
+// skipping scripts that are not JavaScript
+pub fn get_js_contents(root: Rc<RefCell<Node>>) -> Vec<String> {
+    get_element_nodes(Some(root), &[ElementKind::Script])
+        .iter()
+        .filter(|script| is_classic_script(script))
+        .map(get_text_content)
+        .collect()
+}
+
+// gets the attribute of the element, which is None for the other nodes
+pub fn attribute(node: &Rc<RefCell<Node>>, name: &str) -> Option<String> {
+    node.borrow()
+        .get_element()
+        .and_then(|e| e.get_attribute(name))
+}
+
+// the level of <h1> to <h6>, which is None for the other nodes
+pub fn heading_level(node: &Rc<RefCell<Node>>) -> Option<usize> {
+    match node.borrow().element_kind()? {
+        ElementKind::H1 => Some(1),
+        ElementKind::H2 => Some(2),
+        ElementKind::H3 => Some(3),
+        ElementKind::H4 => Some(4),
+        ElementKind::H5 => Some(5),
+        ElementKind::H6 => Some(6),
+        _ => None,
+    }
+}
+
+// https://infra.spec.whatwg.org/#strip-and-collapse-ascii-whitespace
+pub fn strip_and_collapse_whitespace(s: &str) -> String {
+    s.split_ascii_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::renderer::html::dom::create_window;
+    use crate::renderer::html::dom::remove_child;
+    use crate::renderer::html::dom::set_attribute;
+    use crate::renderer::html::helper::test_util::parse;
+    use crate::renderer::html::serializer::serialize_children;
+    use crate::renderer::html::serializer::serialize_node;
+    use alloc::vec;
+
+    #[test]
+    fn test_get_style_contents() {
+        let html = "<html><head><style>p{color:red;}</style></head><body><style>a{color:blue;}</style></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        assert_eq!(
+            vec!["p{color:red;}".to_string(), "a{color:blue;}".to_string()],
+            get_style_contents(document)
+        );
+    }
+
+    #[test]
+    fn test_get_js_contents() {
+        let html = r#"<html><head><script>var a=1;</script><script type="text/template">x</script></head><body><script type="TEXT/JavaScript ">var b=2;</script><script type=module>var c=3;</script><script type="">var d=4;</script></body></html>"#.to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let document = window.borrow().document();
+
+        assert_eq!(
+            vec![
+                "var a=1;".to_string(),
+                "var b=2;".to_string(),
+                "var d=4;".to_string()
+            ],
+            get_js_contents(document)
+        );
+    }
+
+    #[test]
+    fn test_parse_fragment() {
+        let window = create_window();
+        let div = window
+            .borrow()
+            .create_node(NodeKind::Element(Element::new("div", Vec::new())));
+        let nodes = parse_fragment(&div, "<p>a<p>b</body>c");
+        assert_eq!(
+            vec!["<p>a</p>".to_string(), "<p>bc</p>".to_string()],
+            nodes.iter().map(serialize_node).collect::<Vec<String>>()
+        );
+        assert!(nodes[0].borrow().parent().upgrade().is_none());
+
+        let tr = window
+            .borrow()
+            .create_node(NodeKind::Element(Element::new("tr", Vec::new())));
+        let nodes = parse_fragment(&tr, "<td>1<td>2");
+        assert_eq!(
+            vec!["<td>1</td>".to_string(), "<td>2</td>".to_string()],
+            nodes.iter().map(serialize_node).collect::<Vec<String>>()
+        );
+    }
+
+    #[test]
+    fn test_set_inner_html() {
+        let document = parse("<html><body><div id=target>old<span>x</span></div></body></html>");
+        let target =
+            get_element_by_id(Some(document.clone()), &"target".to_string()).expect("no target");
+
+        set_inner_html(&target, "<b>new</b>, text");
+        assert_eq!("<b>new</b>, text", serialize_children(&target));
+
+        let last = target
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("no last child");
+        assert_eq!(NodeKind::Text(", text".to_string()), last.borrow().kind());
+        assert!(Rc::ptr_eq(
+            &target,
+            &last.borrow().parent().upgrade().expect("no parent")
+        ));
+    }
+
+    #[test]
+    fn test_insert_adjacent_html() {
+        let document = parse("<html><body><div id=target><p>x</p></div></body></html>");
+        let target =
+            get_element_by_id(Some(document.clone()), &"target".to_string()).expect("no target");
+
+        assert!(insert_adjacent_html(&target, "beforebegin", "<i>1</i>").is_ok());
+        assert!(insert_adjacent_html(&target, "afterbegin", "<i>2</i>").is_ok());
+        assert!(insert_adjacent_html(&target, "BeforeEnd", "<i>3</i>").is_ok());
+        assert!(insert_adjacent_html(&target, "afterend", "<i>4</i>").is_ok());
+        assert!(insert_adjacent_html(&target, "middle", "<i>5</i>").is_err());
+
+        let html = document.borrow().first_child().expect("no html");
+        assert!(insert_adjacent_html(&html, "afterend", "<i>6</i>").is_err());
+
+        assert_eq!(
+            "<html><head></head><body><i>1</i><div id=\"target\"><i>2</i><p>x</p><i>3</i></div><i>4</i></body></html>",
+            serialize_children(&document)
+        );
+    }
+
+    fn ids(nodes: &[Rc<RefCell<Node>>]) -> Vec<String> {
+        nodes
+            .iter()
+            .map(|n| match n.borrow().get_element() {
+                Some(e) => e.get_attribute("id").unwrap_or_default(),
+                None => String::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_get_elements() {
+        let document = parse(
+            "<div id=a class=\"x y\"><p id=b class=x></p></div><span id=c class=\"y x\"></span>",
+        );
+        assert_eq!(
+            vec!["a", "b", "c"],
+            ids(&get_elements_by_class_name(&document, "x"))
+        );
+        assert_eq!(
+            vec!["a", "c"],
+            ids(&get_elements_by_class_name(&document, " y  x "))
+        );
+        assert_eq!(vec!["b"], ids(&get_elements_by_tag_name(&document, "P")));
+
+        // only the descendants of the root are matched
+        let a = get_element_by_id(Some(document.clone()), &"a".to_string()).expect("no a");
+        assert_eq!(vec!["b"], ids(&get_elements_by_class_name(&a, "x")));
+    }
+
+    #[test]
+    fn test_indexes_on_mutation() {
+        let document = parse("<div id=a></div><p id=b class=x></p>");
+        let a = get_element_by_id(Some(document.clone()), &"a".to_string()).expect("no a");
+        let b = get_element_by_id(Some(document.clone()), &"b".to_string()).expect("no b");
+
+        // moved elements are found in the new tree order
+        let body = a.borrow().parent().upgrade().expect("no body");
+        assert!(insert_before(&body, b.clone(), Some(&a)).is_ok());
+        let c = parse_fragment(&a, "<i id=c class=x></i>");
+        assert!(append_child(&a, c[0].clone()).is_ok());
+        assert_eq!(
+            vec!["b", "c"],
+            ids(&get_elements_by_class_name(&document, "x"))
+        );
+
+        // removed elements are not found anymore
+        assert!(remove_child(&body, &b).is_ok());
+        assert!(get_element_by_id(Some(document.clone()), &"b".to_string()).is_none());
+        assert_eq!(vec!["c"], ids(&get_elements_by_class_name(&document, "x")));
+
+        // attribute changes are reflected
+        set_attribute(&a, "id", "d");
+        set_attribute(&a, "class", "x");
+        assert!(get_element_by_id(Some(document.clone()), &"a".to_string()).is_none());
+        assert!(get_element_by_id(Some(document.clone()), &"d".to_string()).is_some());
+        assert_eq!(
+            vec!["d", "c"],
+            ids(&get_elements_by_class_name(&document, "x"))
+        );
+    }
+
+    #[test]
+    fn test_indexes_on_large_document() {
+        // a flat list of siblings is put in tree order without comparing each pair of them
+        let n = 10000;
+        let html: String = (0..n)
+            .map(|i| format!("<p id={} class=x></p>", i))
+            .collect();
+        let document = parse(&html);
+        let found = get_elements_by_class_name(&document, "x");
+        assert_eq!(n, found.len());
+        assert_eq!(
+            vec!["0", "9999"],
+            ids(&[found[0].clone(), found[n - 1].clone()])
+        );
+
+        let body = found[0].borrow().parent().upgrade().expect("no body");
+        let first = parse_fragment(&body, "<p id=first class=x></p>");
+        assert!(insert_before(&body, first[0].clone(), Some(&found[0])).is_ok());
+        assert!(remove_child(&body, &found[n - 1]).is_ok());
+        let found = get_elements_by_class_name(&document, "x");
+        assert_eq!(n, found.len());
+        assert_eq!(
+            vec!["first", "9998"],
+            ids(&[found[0].clone(), found[n - 1].clone()])
+        );
+    }
+
+    #[test]
+    fn test_query_selector() {
+        let document = parse(
+            "<div id=a><p id=b class=x></p><section id=c><p id=d class=x></p></section></div><p id=e></p>",
+        );
+        assert_eq!(
+            vec!["b", "d"],
+            ids(&query_selector_all(&document, "div p.x").expect("failed"))
+        );
+        assert_eq!(
+            vec!["a", "d", "e"],
+            ids(&query_selector_all(&document, "section > p, body > *").expect("failed"))
+        );
+        let first = query_selector(&document, "p:not(.x)").expect("failed");
+        assert_eq!(vec!["e"], ids(&first.into_iter().collect::<Vec<_>>()));
+
+        // the selectors match against the whole tree, but only descendants of the root
+        let c = get_element_by_id(Some(document.clone()), &"c".to_string()).expect("no c");
+        assert_eq!(
+            vec!["d"],
+            ids(&query_selector_all(&c, "div p").expect("failed"))
+        );
+        assert!(query_selector(&c, "section").expect("failed").is_none());
+
+        assert!(query_selector(&document, "p >").is_err());
+    }
+
+    #[test]
+    fn test_get_document_base_url() {
+        let url = "http://example.com/a/b.html";
+        let document = parse("<p>no base</p>");
+        assert_eq!(url, get_document_base_url(&document, url));
+
+        // the first base with href wins, and is resolved against the document URL
+        let document =
+            parse("<head><base target=_blank><base href=\"../c/\"><base href=\"/d/\"></head>");
+        assert_eq!(
+            "http://example.com/c/",
+            get_document_base_url(&document, url)
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use alloc::string::String;
+use alloc::string::ToString;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attribute {
@@ -14,6 +15,13 @@ impl Attribute {
         }
     }
 
+    pub fn new_with_value(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: value.to_string(),
+        }
+    }
+
     pub fn add_char(&mut self, c: char, is_name: bool) {
         if is_name {
             self.name.push(c);
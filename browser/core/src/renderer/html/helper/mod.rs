@@ -1,2 +1,5 @@
 pub mod api;
 pub mod attribute;
+pub mod entity;
+#[cfg(test)]
+pub mod test_util;
@@ -0,0 +1,19 @@
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::helper::api::get_element_by_id;
+use crate::renderer::html::parser::HtmlParser;
+use crate::renderer::html::token::HtmlTokenizer;
+use alloc::rc::Rc;
+use alloc::string::ToString;
+use core::cell::RefCell;
+
+// the document of the HTML
+pub fn parse(html: &str) -> Rc<RefCell<Node>> {
+    let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+    let document = window.borrow().document();
+    document
+}
+
+// the element with the ID, which must exist
+pub fn by_id(document: &Rc<RefCell<Node>>, id: &str) -> Rc<RefCell<Node>> {
+    get_element_by_id(Some(document.clone()), &id.to_string()).expect("no element")
+}
@@ -1,4 +1,6 @@
 pub mod dom;
+pub mod event;
 pub mod helper;
 pub mod parser;
+pub mod serializer;
 pub mod token;
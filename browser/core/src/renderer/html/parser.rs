@@ -1,3 +1,10 @@
+use crate::renderer::html::dom::adopt;
+use crate::renderer::html::dom::create_window;
+use crate::renderer::html::dom::insert;
+use crate::renderer::html::dom::remove;
+use crate::renderer::html::dom::template_contents;
+use crate::renderer::html::dom::DocumentMode;
+use crate::renderer::html::dom::DocumentType;
 use crate::renderer::html::dom::Element;
 use crate::renderer::html::dom::ElementKind;
 use crate::renderer::html::dom::Node;
@@ -6,11 +13,12 @@ use crate::renderer::html::dom::Window;
 use crate::renderer::html::helper::attribute::Attribute;
 use crate::renderer::html::token::HtmlToken;
 use crate::renderer::html::token::HtmlTokenizer;
+use crate::renderer::html::token::State;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 use core::cell::RefCell;
-use core::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct HtmlParser {
@@ -20,137 +28,259 @@ pub struct HtmlParser {
     original_insertion_mode: InsertionMode,
     // https://html.spec.whatwg.org/multipage/parsing.html#the-stack-of-open-elements
     stack_of_open_elements: Vec<Rc<RefCell<Node>>>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#list-of-active-formatting-elements
+    active_formatting_elements: Vec<ActiveFormattingElement>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#foster-parent
+    foster_parenting: bool,
+    // https://html.spec.whatwg.org/multipage/parsing.html#using-the-rules-for
+    using_rules_for: Option<InsertionMode>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#concept-pending-table-char-tokens
+    pending_table_characters: String,
+    // https://html.spec.whatwg.org/multipage/parsing.html#concept-frag-parse-context
+    context_element: Option<Rc<RefCell<Node>>>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#stack-of-template-insertion-modes
+    template_insertion_modes: Vec<InsertionMode>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#form-element-pointer
+    // the last <form> which is opened and not closed, which prevents nested forms
+    form_element: Option<Rc<RefCell<Node>>>,
     t: HtmlTokenizer,
 }
 
 impl HtmlParser {
     pub fn new(t: HtmlTokenizer) -> Self {
         Self {
-            window: Rc::new(RefCell::new(Window::new())),
+            window: create_window(),
             mode: InsertionMode::Initial,
             original_insertion_mode: InsertionMode::Initial,
             stack_of_open_elements: Vec::new(),
+            active_formatting_elements: Vec::new(),
+            foster_parenting: false,
+            using_rules_for: None,
+            pending_table_characters: String::new(),
+            context_element: None,
+            template_insertion_modes: Vec::new(),
+            form_element: None,
             t,
         }
     }
 
-    // creates new element node
-    fn create_element(&self, tag: &str, attributes: Vec<Attribute>) -> Node {
-        Node::new(NodeKind::Element(Element::new(tag, attributes)))
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+    // parses the input as children of the context element, and returns the parsed nodes
+    // which are detached from any tree
+    pub fn construct_fragment(&mut self, context: Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+        // the tokenizer state depends on the context element, and the PLAINTEXT state
+        // is not supported yet
+        let context_name = context.borrow().get_element().map(|e| e.local_name());
+        match context_name.as_deref() {
+            Some("title" | "textarea") => self.t.switch_to(State::Rcdata),
+            Some("style" | "xmp" | "iframe" | "noembed" | "noframes") => {
+                self.t.switch_to(State::Rawtext)
+            }
+            Some("script") => self.t.switch_to(State::ScriptData),
+            _ => {}
+        }
+
+        let root = self.create_element("html", Vec::new());
+        let document = self.window.borrow().document();
+        insert(&document, root.clone(), None);
+        self.stack_of_open_elements.push(root.clone());
+
+        // the nearest <form> of the context is the form element pointer
+        let mut node = Some(context.clone());
+        while let Some(n) = node {
+            if n.borrow().element_kind() == Some(ElementKind::Form) {
+                self.form_element = Some(n);
+                break;
+            }
+            node = n.borrow().parent().upgrade();
+        }
+
+        let context_arena = context.borrow().arena();
+        self.context_element = Some(context);
+        self.reset_insertion_mode_appropriately();
+
+        self.construct_tree();
+
+        // the nodes are moved into the arena of the context, as the arena of this parser is
+        // dropped with it
+        let arena = context_arena.expect("the context element must belong to an arena");
+        let mut nodes = Vec::new();
+        let mut child = root.borrow().first_child();
+        while let Some(c) = child {
+            child = c.borrow().next_sibling();
+            remove(&c);
+            adopt(&c, &arena);
+            nodes.push(c);
+        }
+        nodes
     }
 
-    // inserts the node in the appropriate place
-    fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
-        let window = self.window.borrow();
-        // if there is nothing on stack, current node is the root element
-        let current = match self.stack_of_open_elements.last() {
+    // creates new element node in the arena of the document
+    fn create_element(&self, tag: &str, attributes: Vec<Attribute>) -> Rc<RefCell<Node>> {
+        self.window
+            .borrow()
+            .create_node(NodeKind::Element(Element::new(tag, attributes)))
+    }
+
+    // gets the current node, which is the root element if there is nothing on stack
+    fn current_node(&self) -> Rc<RefCell<Node>> {
+        match self.stack_of_open_elements.last() {
             Some(n) => n.clone(),
-            None => window.document(),
+            None => self.window.borrow().document(),
+        }
+    }
+
+    // inserts the node before the child if any, or as the last child of the parent
+    fn insert_node(
+        parent: &Rc<RefCell<Node>>,
+        child: Option<Rc<RefCell<Node>>>,
+        node: Rc<RefCell<Node>>,
+    ) {
+        insert(parent, node, child.as_ref());
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#appropriate-place-for-inserting-a-node
+    // returns the parent, and the child which the node should be inserted before
+    fn appropriate_place_for_inserting_node(
+        &self,
+        override_target: Option<Rc<RefCell<Node>>>,
+    ) -> (Rc<RefCell<Node>>, Option<Rc<RefCell<Node>>>) {
+        let target = match override_target {
+            Some(target) => target,
+            None => self.current_node(),
         };
 
-        let node = Rc::new(RefCell::new(self.create_element(tag, attributes)));
+        let is_table_related = matches!(
+            local_name_of(&target).as_deref(),
+            Some("table" | "tbody" | "tfoot" | "thead" | "tr")
+        );
+        if !self.foster_parenting || !is_table_related {
+            // the children of <template> are inserted into its contents instead
+            let target = template_contents(&target).unwrap_or(target);
+            return (target, None);
+        }
 
-        // if the node has a child, the node is appended to an existing sibling
-        if current.borrow().first_child().is_some() {
-            let mut last_sibiling = current.borrow().first_child();
-            loop {
-                last_sibiling = match last_sibiling {
-                    Some(ref node) => {
-                        if node.borrow().next_sibling().is_some() {
-                            node.borrow().next_sibling()
-                        } else {
-                            break;
-                        }
-                    }
-                    None => unimplemented!("last_sibiling should be Some"),
-                };
+        // the node is inserted right before the last table
+        let last_table_index = self
+            .stack_of_open_elements
+            .iter()
+            .rposition(|n| local_name_of(n).as_deref() == Some("table"));
+        match last_table_index {
+            Some(i) => {
+                let last_table = self.stack_of_open_elements[i].clone();
+                let parent = last_table.borrow().parent().upgrade();
+                match parent {
+                    Some(parent) => (parent, Some(last_table)),
+                    None => (
+                        self.stack_of_open_elements[i.saturating_sub(1)].clone(),
+                        None,
+                    ),
+                }
             }
-
-            last_sibiling
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ))
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
+            None => (self.stack_of_open_elements[0].clone(), None),
         }
+    }
+
+    // inserts the node in the appropriate place
+    fn insert_element(&mut self, tag: &str, attributes: Vec<Attribute>) -> Rc<RefCell<Node>> {
+        let (parent, child) = self.appropriate_place_for_inserting_node(None);
+        let node = self.create_element(tag, attributes);
+
+        Self::insert_node(&parent, child, node.clone());
 
-        // creates a weak reference with the appended node
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
+        self.stack_of_open_elements.push(node.clone());
+        node
+    }
 
-        self.stack_of_open_elements.push(node);
+    // https://html.spec.whatwg.org/multipage/parsing.html#generic-raw-text-element-parsing-algorithm
+    // https://html.spec.whatwg.org/multipage/parsing.html#generic-rcdata-element-parsing-algorithm
+    // the text of the element is tokenized as text until its end tag
+    fn insert_text_element(&mut self, tag: &str, attributes: Vec<Attribute>) {
+        self.insert_element(tag, attributes);
+        self.t.switch_to(match tag {
+            "title" | "textarea" => State::Rcdata,
+            "script" => State::ScriptData,
+            _ => State::Rawtext,
+        });
+        self.original_insertion_mode = self.mode;
+        self.mode = InsertionMode::Text;
     }
 
     // creates new text node
-    fn create_char(&self, c: char) -> Node {
+    fn create_char(&self, c: char) -> Rc<RefCell<Node>> {
         let mut s = String::new();
         s.push(c);
-        Node::new(NodeKind::Text(s))
+        self.window.borrow().create_node(NodeKind::Text(s))
     }
 
     // inserts a character in the text node
     fn insert_char(&mut self, c: char) {
-        let current = match self.stack_of_open_elements.last() {
-            Some(n) => n.clone(),
-            None => return,
-        };
-
-        // if current is a text node, append the character
-        if let NodeKind::Text(ref mut s) = current.borrow_mut().kind {
-            s.push(c);
+        if self.stack_of_open_elements.is_empty() {
             return;
         }
+        let (parent, child) = self.appropriate_place_for_inserting_node(None);
 
-        // do not append the character, if it is '\n' or ' '
-        if c == '\n' || c == ' ' {
+        // if the node right before the insertion point is a text node, append the character
+        let previous = match child {
+            Some(ref child) => child.borrow().previous_sibling().upgrade(),
+            None => parent.borrow().last_child().upgrade(),
+        };
+        if let Some(previous) = previous {
+            if let NodeKind::Text(ref mut s) = previous.borrow_mut().kind {
+                s.push(c);
+                return;
+            }
+        }
+
+        // do not append the character, if it is '\n' or ' ', except in the preformatted text
+        // whose white space is rendered as it is
+        if (c == '\n' || c == ' ') && !self.is_in_preformatted_text() {
             return;
         }
 
-        let node = Rc::new(RefCell::new(self.create_char(c)));
+        let node = self.create_char(c);
+        Self::insert_node(&parent, child, node);
+    }
 
-        if current.borrow().first_child().is_some() {
-            current
-                .borrow()
-                .first_child()
-                .unwrap()
-                .borrow_mut()
-                .set_next_sibling(Some(node.clone()));
-            node.borrow_mut().set_previous_sibling(Rc::downgrade(
-                &current
-                    .borrow()
-                    .first_child()
-                    .expect("failed to get a first child"),
-            ));
-        } else {
-            current.borrow_mut().set_first_child(Some(node.clone()));
-        }
-
-        current.borrow_mut().set_last_child(Rc::downgrade(&node));
-        node.borrow_mut().set_parent(Rc::downgrade(&current));
-
-        self.stack_of_open_elements.push(node);
-    }
-
-    // looks if the last element on stack is of the same kind
-    // TODO: rename to is_last_element_in_stack
-    fn pop_current_node(&mut self, element_kind: ElementKind) -> bool {
-        let current = match self.stack_of_open_elements.last() {
-            Some(n) => n,
-            None => return false,
+    // inserts a comment node as the last child of the given parent,
+    // or of the current node if no parent is given
+    fn insert_comment(&mut self, data: &str, parent: Option<Rc<RefCell<Node>>>) {
+        let parent = match parent {
+            Some(p) => p,
+            None => self.current_node(),
         };
+        let node = self
+            .window
+            .borrow()
+            .create_node(NodeKind::Comment(data.to_string()));
 
-        if current.borrow().element_kind() == Some(element_kind) {
-            self.stack_of_open_elements.pop();
-            return true;
-        }
+        insert(&parent, node, None);
+    }
 
-        false
+    // inserts a doctype node as the last child of the document,
+    // and sets the document mode from it
+    fn insert_doctype(
+        &mut self,
+        name: &Option<String>,
+        public_id: &Option<String>,
+        system_id: &Option<String>,
+        force_quirks: bool,
+    ) {
+        let doctype = DocumentType::new(
+            name.clone().unwrap_or_default(),
+            public_id.clone().unwrap_or_default(),
+            system_id.clone().unwrap_or_default(),
+        );
+        let node = self
+            .window
+            .borrow()
+            .create_node(NodeKind::DocumentType(doctype));
+
+        let document = self.window.borrow().document();
+        insert(&document, node, None);
+
+        let mode = document_mode_from_doctype(name, public_id, system_id, force_quirks);
+        self.window.borrow_mut().set_document_mode(mode);
     }
 
     // looks if the any element on stack is of the same kind
@@ -185,318 +315,1874 @@ impl HtmlParser {
         }
     }
 
-    pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
-        let mut token = self.t.next();
+    // pops until the element with the given name, if any is on the stack
+    fn pop_until_local_name(&mut self, local_name: &str) {
+        self.pop_until_one_of(&[local_name]);
+    }
 
-        while token.is_some() {
-            match self.mode {
-                InsertionMode::Initial => {
-                    // as DOCTYPE is not supported,
-                    // <!doctype html> is handled as Char token
-                    if let Some(HtmlToken::Char(_)) = token {
-                        token = self.t.next();
+    // pops until one of the elements with the given names, if any is on the stack
+    fn pop_until_one_of(&mut self, local_names: &[&str]) {
+        let index = self
+            .stack_of_open_elements
+            .iter()
+            .rposition(|n| match local_name_of(n) {
+                Some(name) => local_names.contains(&name.as_str()),
+                None => false,
+            });
+
+        if let Some(i) = index {
+            self.stack_of_open_elements.truncate(i);
+        }
+    }
+
+    fn current_node_name(&self) -> Option<String> {
+        self.stack_of_open_elements.last().and_then(local_name_of)
+    }
+
+    // https://html.spec.whatwg.org/multipage/grouping-content.html#the-pre-element
+    fn is_in_preformatted_text(&self) -> bool {
+        self.stack_of_open_elements
+            .iter()
+            .any(|n| matches!(local_name_of(n).as_deref(), Some("pre") | Some("listing")))
+    }
+
+    fn has_template_on_stack(&self) -> bool {
+        self.stack_of_open_elements
+            .iter()
+            .any(|n| local_name_of(n).as_deref() == Some("template"))
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-scope
+    // the scope of the node itself rather than of the local name
+    fn has_node_in_scope(&self, node: &Rc<RefCell<Node>>) -> bool {
+        for n in self.stack_of_open_elements.iter().rev() {
+            if Rc::ptr_eq(n, node) {
+                return true;
+            }
+            if local_name_of(n).is_some_and(|name| is_scope_boundary(&name)) {
+                return false;
+            }
+        }
+        false
+    }
+
+    fn is_in_stack(&self, node: &Rc<RefCell<Node>>) -> bool {
+        self.stack_of_open_elements
+            .iter()
+            .any(|n| Rc::ptr_eq(n, node))
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-the-specific-scope
+    fn has_element_in_specific_scope(&self, local_names: &[&str], boundaries: &[&str]) -> bool {
+        for node in self.stack_of_open_elements.iter().rev() {
+            let name = match local_name_of(node) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if local_names.contains(&name.as_str()) {
+                return true;
+            }
+
+            if is_scope_boundary(&name) || boundaries.contains(&name.as_str()) {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-scope
+    fn has_element_in_scope(&self, local_name: &str) -> bool {
+        self.has_element_in_specific_scope(&[local_name], &[])
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-list-item-scope
+    fn has_element_in_list_item_scope(&self, local_name: &str) -> bool {
+        self.has_element_in_specific_scope(&[local_name], &["ol", "ul"])
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-button-scope
+    fn has_element_in_button_scope(&self, local_name: &str) -> bool {
+        self.has_element_in_specific_scope(&[local_name], &["button"])
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#generate-implied-end-tags
+    fn generate_implied_end_tags(&mut self, exception: Option<&str>) {
+        while let Some(name) = self.current_node_name() {
+            if Some(name.as_str()) == exception || !has_implied_end_tag(&name) {
+                return;
+            }
+            self.stack_of_open_elements.pop();
+        }
+    }
+
+    // the modes which create <html>, <head> and <body> when they are missing
+    fn is_before_body(&self) -> bool {
+        matches!(
+            self.mode,
+            InsertionMode::Initial
+                | InsertionMode::BeforeHtml
+                | InsertionMode::BeforeHead
+                | InsertionMode::InHead
+                | InsertionMode::AfterHead
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#close-a-p-element
+    fn close_p_element(&mut self) {
+        self.generate_implied_end_tags(Some("p"));
+        self.pop_until_local_name("p");
+    }
+
+    // closes the previous <li>, or <dd> and <dt>, before opening a new one
+    fn close_list_item(&mut self, local_names: &[&str]) {
+        for i in (0..self.stack_of_open_elements.len()).rev() {
+            let name = match local_name_of(&self.stack_of_open_elements[i]) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if local_names.contains(&name.as_str()) {
+                self.generate_implied_end_tags(Some(&name));
+                self.pop_until_local_name(&name);
+                return;
+            }
+
+            if is_special_element(&name) && !matches!(name.as_str(), "address" | "div" | "p") {
+                return;
+            }
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody
+    // handles "any other end tag"
+    fn close_element_by_end_tag(&mut self, tag: &str) {
+        for i in (0..self.stack_of_open_elements.len()).rev() {
+            let name = match local_name_of(&self.stack_of_open_elements[i]) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if name == tag {
+                self.generate_implied_end_tags(Some(tag));
+                self.stack_of_open_elements.truncate(i);
+                return;
+            }
+
+            // the end tag is ignored
+            if is_special_element(&name) {
+                return;
+            }
+        }
+    }
+
+    // finds the formatting element with the given name after the last marker
+    fn active_formatting_element(&self, local_name: &str) -> Option<Rc<RefCell<Node>>> {
+        for entry in self.active_formatting_elements.iter().rev() {
+            match entry {
+                ActiveFormattingElement::Marker => return None,
+                ActiveFormattingElement::Element(node) => {
+                    if local_name_of(node).as_deref() == Some(local_name) {
+                        return Some(node.clone());
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#push-onto-the-list-of-active-formatting-elements
+    fn push_active_formatting_element(&mut self, node: Rc<RefCell<Node>>) {
+        let element = node.borrow().get_element();
+
+        // at most three identical elements are kept after the last marker
+        let mut identical = Vec::new();
+        for (i, entry) in self.active_formatting_elements.iter().enumerate().rev() {
+            match entry {
+                ActiveFormattingElement::Marker => break,
+                ActiveFormattingElement::Element(n) => {
+                    if is_identical_element(&n.borrow().get_element(), &element) {
+                        identical.push(i);
+                    }
+                }
+            }
+        }
+        if identical.len() >= 3 {
+            let earliest = identical[identical.len() - 1];
+            self.active_formatting_elements.remove(earliest);
+        }
+
+        self.active_formatting_elements
+            .push(ActiveFormattingElement::Element(node));
+    }
+
+    fn active_formatting_element_index(&self, node: &Rc<RefCell<Node>>) -> Option<usize> {
+        self.active_formatting_elements
+            .iter()
+            .position(|entry| match entry {
+                ActiveFormattingElement::Marker => false,
+                ActiveFormattingElement::Element(n) => Rc::ptr_eq(n, node),
+            })
+    }
+
+    fn is_in_active_formatting_elements(&self, node: &Rc<RefCell<Node>>) -> bool {
+        self.active_formatting_element_index(node).is_some()
+    }
+
+    fn remove_active_formatting_element(&mut self, node: &Rc<RefCell<Node>>) {
+        self.active_formatting_elements.retain(|entry| match entry {
+            ActiveFormattingElement::Marker => true,
+            ActiveFormattingElement::Element(n) => !Rc::ptr_eq(n, node),
+        });
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#reconstruct-the-active-formatting-elements
+    fn reconstruct_active_formatting_elements(&mut self) {
+        let is_open_or_marker =
+            |parser: &Self, i: usize| match &parser.active_formatting_elements[i] {
+                ActiveFormattingElement::Marker => true,
+                ActiveFormattingElement::Element(n) => parser.is_in_stack(n),
+            };
+
+        let len = self.active_formatting_elements.len();
+        if len == 0 || is_open_or_marker(self, len - 1) {
+            return;
+        }
+
+        // rewinds to the entry right after the last open element or marker
+        let mut start = len - 1;
+        while start > 0 && !is_open_or_marker(self, start - 1) {
+            start -= 1;
+        }
+
+        // creates the elements again, and replaces the entries with them
+        for i in start..len {
+            let element = match &self.active_formatting_elements[i] {
+                ActiveFormattingElement::Element(n) => n.borrow().get_element(),
+                ActiveFormattingElement::Marker => None,
+            };
+            if let Some(element) = element {
+                let node = self.insert_element(&element.local_name(), element.attributes());
+                self.active_formatting_elements[i] = ActiveFormattingElement::Element(node);
+            }
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#clear-the-list-of-active-formatting-elements-up-to-the-last-marker
+    fn clear_active_formatting_elements_up_to_last_marker(&mut self) {
+        while let Some(entry) = self.active_formatting_elements.pop() {
+            if let ActiveFormattingElement::Marker = entry {
+                return;
+            }
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#adoption-agency-algorithm
+    fn run_adoption_agency_algorithm(&mut self, subject: &str) {
+        // the current node is closed as is, when it is not a formatting element
+        if let Some(current) = self.stack_of_open_elements.last().cloned() {
+            if local_name_of(&current).as_deref() == Some(subject)
+                && !self.is_in_active_formatting_elements(&current)
+            {
+                self.stack_of_open_elements.pop();
+                return;
+            }
+        }
+
+        for _ in 0..8 {
+            let formatting_element = match self.active_formatting_element(subject) {
+                Some(node) => node,
+                None => {
+                    self.close_element_by_end_tag(subject);
+                    return;
+                }
+            };
+
+            let formatting_index = match self
+                .stack_of_open_elements
+                .iter()
+                .rposition(|n| Rc::ptr_eq(n, &formatting_element))
+            {
+                Some(i) => i,
+                None => {
+                    // parse error
+                    self.remove_active_formatting_element(&formatting_element);
+                    return;
+                }
+            };
+
+            if !self.has_element_in_scope(subject) {
+                // parse error, and the token is ignored
+                return;
+            }
+
+            // the topmost special element below the formatting element
+            let furthest_block_index = match (formatting_index + 1
+                ..self.stack_of_open_elements.len())
+                .find(|&i| match local_name_of(&self.stack_of_open_elements[i]) {
+                    Some(name) => is_special_element(&name),
+                    None => false,
+                }) {
+                Some(i) => i,
+                None => {
+                    self.stack_of_open_elements.truncate(formatting_index);
+                    self.remove_active_formatting_element(&formatting_element);
+                    return;
+                }
+            };
+            let furthest_block = self.stack_of_open_elements[furthest_block_index].clone();
+            // the formatting element is never the bottom of the stack, since <html> stays there
+            let common_ancestor = match formatting_index.checked_sub(1) {
+                Some(i) => self.stack_of_open_elements[i].clone(),
+                None => {
+                    self.remove_active_formatting_element(&formatting_element);
+                    return;
+                }
+            };
+
+            let mut bookmark = match self.active_formatting_element_index(&formatting_element) {
+                Some(i) => i,
+                None => return,
+            };
+
+            let mut node_index = furthest_block_index;
+            let mut last_node = furthest_block.clone();
+            let mut inner_loop_counter = 0;
+            loop {
+                inner_loop_counter += 1;
+
+                node_index -= 1;
+                let node = self.stack_of_open_elements[node_index].clone();
+                if Rc::ptr_eq(&node, &formatting_element) {
+                    break;
+                }
+
+                let mut entry_index = self.active_formatting_element_index(&node);
+                if inner_loop_counter > 3 {
+                    if let Some(i) = entry_index {
+                        self.active_formatting_elements.remove(i);
+                        if i < bookmark {
+                            bookmark -= 1;
+                        }
+                        entry_index = None;
+                    }
+                }
+
+                let entry_index = match entry_index {
+                    Some(i) => i,
+                    None => {
+                        self.stack_of_open_elements.remove(node_index);
                         continue;
                     }
+                };
 
-                    self.mode = InsertionMode::BeforeHtml;
-                    continue;
+                // replaces the node with a new element for the same token
+                let element = match node.borrow().get_element() {
+                    Some(e) => e,
+                    None => return,
+                };
+                let new_node = self.create_element(&element.local_name(), element.attributes());
+                self.active_formatting_elements[entry_index] =
+                    ActiveFormattingElement::Element(new_node.clone());
+                self.stack_of_open_elements[node_index] = new_node.clone();
+
+                if Rc::ptr_eq(&last_node, &furthest_block) {
+                    bookmark = entry_index + 1;
                 }
 
-                InsertionMode::BeforeHtml => {
-                    match token {
-                        Some(HtmlToken::Char(c)) => {
-                            if c == ' ' || c == '\n' {
+                remove(&last_node);
+                insert(&new_node, last_node, None);
+                last_node = new_node;
+            }
+
+            remove(&last_node);
+            let (parent, child) = self.appropriate_place_for_inserting_node(Some(common_ancestor));
+            Self::insert_node(&parent, child, last_node);
+
+            // moves the children of the furthest block into a new formatting element
+            let element = match formatting_element.borrow().get_element() {
+                Some(e) => e,
+                None => return,
+            };
+            let new_node = self.create_element(&element.local_name(), element.attributes());
+            let mut child = furthest_block.borrow().first_child();
+            while let Some(c) = child {
+                remove(&c);
+                insert(&new_node, c, None);
+                child = furthest_block.borrow().first_child();
+            }
+            insert(&furthest_block, new_node.clone(), None);
+
+            if let Some(i) = self.active_formatting_element_index(&formatting_element) {
+                self.active_formatting_elements.remove(i);
+                if i < bookmark {
+                    bookmark -= 1;
+                }
+            }
+            self.active_formatting_elements
+                .insert(bookmark, ActiveFormattingElement::Element(new_node.clone()));
+
+            self.stack_of_open_elements
+                .retain(|n| !Rc::ptr_eq(n, &formatting_element));
+            if let Some(i) = self
+                .stack_of_open_elements
+                .iter()
+                .position(|n| Rc::ptr_eq(n, &furthest_block))
+            {
+                self.stack_of_open_elements.insert(i + 1, new_node);
+            }
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-table-scope
+    fn has_element_in_table_scope(&self, local_names: &[&str]) -> bool {
+        for node in self.stack_of_open_elements.iter().rev() {
+            let name = match local_name_of(node) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if local_names.contains(&name.as_str()) {
+                return true;
+            }
+
+            if matches!(name.as_str(), "html" | "table" | "template") {
+                return false;
+            }
+        }
+
+        false
+    }
+
+    // pops until one of the given elements becomes the current node
+    fn clear_stack_back_to(&mut self, local_names: &[&str]) {
+        while let Some(name) = self.current_node_name() {
+            if local_names.contains(&name.as_str()) {
+                return;
+            }
+            self.stack_of_open_elements.pop();
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#reset-the-insertion-mode-appropriately
+    fn reset_insertion_mode_appropriately(&mut self) {
+        for (i, node) in self.stack_of_open_elements.iter().enumerate().rev() {
+            let last = i == 0;
+            // the context element is used instead of the root in the fragment case
+            let node = match (last, &self.context_element) {
+                (true, Some(context)) => context,
+                _ => node,
+            };
+            let name = match local_name_of(node) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            let mode = match name.as_str() {
+                "template" => match self.template_insertion_modes.last() {
+                    Some(mode) => *mode,
+                    None => InsertionMode::InBody,
+                },
+                "td" | "th" if !last => InsertionMode::InCell,
+                "tr" => InsertionMode::InRow,
+                "tbody" | "thead" | "tfoot" => InsertionMode::InTableBody,
+                "caption" => InsertionMode::InCaption,
+                "colgroup" => InsertionMode::InColumnGroup,
+                "table" => InsertionMode::InTable,
+                "head" if !last => InsertionMode::InHead,
+                "body" => InsertionMode::InBody,
+                // there is no <head> in the fragment case
+                "html" if self.context_element.is_some() => InsertionMode::BeforeHead,
+                // <head> has already been inserted, if <html> has any child
+                "html" => match node.borrow().first_child() {
+                    Some(_) => InsertionMode::AfterHead,
+                    None => InsertionMode::BeforeHead,
+                },
+                _ if last => InsertionMode::InBody,
+                _ => continue,
+            };
+            self.mode = mode;
+            return;
+        }
+
+        self.mode = InsertionMode::InBody;
+    }
+
+    // closes <caption>, and returns true if it was open
+    fn close_caption(&mut self) -> bool {
+        if !self.has_element_in_table_scope(&["caption"]) {
+            return false;
+        }
+
+        self.generate_implied_end_tags(None);
+        self.pop_until_local_name("caption");
+        self.clear_active_formatting_elements_up_to_last_marker();
+        self.mode = InsertionMode::InTable;
+        true
+    }
+
+    // closes <tbody>, <thead> or <tfoot>, and returns true if it was open
+    fn close_table_body(&mut self) -> bool {
+        if !self.has_element_in_table_scope(&["tbody", "thead", "tfoot"]) {
+            return false;
+        }
+
+        self.clear_stack_back_to(&TABLE_BODY_CONTEXT);
+        self.stack_of_open_elements.pop();
+        self.mode = InsertionMode::InTable;
+        true
+    }
+
+    // closes <tr>, and returns true if it was open
+    fn close_row(&mut self) -> bool {
+        if !self.has_element_in_table_scope(&["tr"]) {
+            return false;
+        }
+
+        self.clear_stack_back_to(&TABLE_ROW_CONTEXT);
+        self.stack_of_open_elements.pop();
+        self.mode = InsertionMode::InTableBody;
+        true
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#close-the-cell
+    fn close_cell(&mut self) {
+        self.generate_implied_end_tags(None);
+        self.pop_until_one_of(&["td", "th"]);
+        self.clear_active_formatting_elements_up_to_last_marker();
+        self.mode = InsertionMode::InRow;
+    }
+
+    pub fn construct_tree(&mut self) -> Rc<RefCell<Window>> {
+        let mut token = self.t.next();
+
+        // the modes before "in body" insert the missing elements at the end of the input
+        while token.is_some() || self.is_before_body() {
+            // a token can be processed using the rules of another insertion mode,
+            // without switching the current one
+            let mode = match self.using_rules_for.take() {
+                Some(mode) => mode,
+                None => {
+                    self.foster_parenting = false;
+                    self.mode
+                }
+            };
+
+            match mode {
+                InsertionMode::Initial => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            let document = self.window.borrow().document();
+                            self.insert_comment(data, Some(document));
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype {
+                            ref name,
+                            ref public_id,
+                            ref system_id,
+                            force_quirks,
+                        }) => {
+                            self.insert_doctype(name, public_id, system_id, force_quirks);
+                            self.mode = InsertionMode::BeforeHtml;
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        _ => {}
+                    }
+
+                    // a document without doctype is rendered in quirks mode
+                    self.window
+                        .borrow_mut()
+                        .set_document_mode(DocumentMode::Quirks);
+                    self.mode = InsertionMode::BeforeHtml;
+                    continue;
+                }
+
+                InsertionMode::BeforeHtml => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            let document = self.window.borrow().document();
+                            self.insert_comment(data, Some(document));
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => {
+                            if tag == "html" {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::BeforeHead;
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if !matches!(tag.as_str(), "head" | "body" | "html" | "br") {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        _ => {}
+                    }
+
+                    self.insert_element("html", Vec::new());
+                    self.mode = InsertionMode::BeforeHead;
+                    continue;
+                }
+
+                InsertionMode::BeforeHead => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => {
+                            if tag == "head" {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InHead;
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if !matches!(tag.as_str(), "head" | "body" | "html" | "br") {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        _ => {}
+                    }
+
+                    self.insert_element("head", Vec::new());
+                    self.mode = InsertionMode::InHead;
+                    continue;
+                }
+
+                InsertionMode::InHead => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                token = self.t.next();
+                                continue;
+                            }
+                            // the text is a part of <body>
+                            self.pop_until(ElementKind::Head);
+                            self.mode = InsertionMode::AfterHead;
+                            continue;
+                        }
+
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => {
+                            if tag == "style" || tag == "script" || tag == "title" {
+                                self.insert_text_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                            if matches!(
+                                tag.as_str(),
+                                "base" | "basefont" | "bgsound" | "link" | "meta"
+                            ) {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "template" {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.active_formatting_elements
+                                    .push(ActiveFormattingElement::Marker);
+                                self.mode = InsertionMode::InTemplate;
+                                self.template_insertion_modes
+                                    .push(InsertionMode::InTemplate);
+                                token = self.t.next();
+                                continue;
+                            }
+                            // below is not included in spec,
+                            // but is necessary for avoiding infinite loop,
+                            // which occurs with HTML without <head> tag
+                            if tag == "body" {
+                                self.pop_until(ElementKind::Head);
+                                self.mode = InsertionMode::AfterHead;
+                                continue;
+                            }
+                            if !is_metadata_element(tag) {
+                                self.pop_until(ElementKind::Head);
+                                self.mode = InsertionMode::AfterHead;
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if tag == "head" {
+                                self.mode = InsertionMode::AfterHead;
+                                token = self.t.next();
+                                self.pop_until(ElementKind::Head);
+                                continue;
+                            }
+                            if tag == "template" {
+                                // </template> without <template> is ignored
+                                if self.has_template_on_stack() {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_local_name("template");
+                                    self.clear_active_formatting_elements_up_to_last_marker();
+                                    self.template_insertion_modes.pop();
+                                    self.reset_insertion_mode_appropriately();
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+                            if matches!(tag.as_str(), "body" | "html" | "br") {
+                                self.pop_until(ElementKind::Head);
+                                self.mode = InsertionMode::AfterHead;
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Eof) | None => {
+                            self.pop_until(ElementKind::Head);
+                            self.mode = InsertionMode::AfterHead;
+                            continue;
+                        }
+                    }
+
+                    // ignore unsupported tags like <meta> and <title>, and other end tags
+                    token = self.t.next();
+                    continue;
+                }
+
+                InsertionMode::AfterHead => {
+                    match token {
+                        Some(HtmlToken::Char(c)) => {
+                            if c == ' ' || c == '\n' {
+                                self.insert_char(c);
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => {
+                            if tag == "body" {
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                self.mode = InsertionMode::InBody;
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if !matches!(tag.as_str(), "body" | "html" | "br") {
+                                token = self.t.next();
+                                continue;
+                            }
+                        }
+
+                        _ => {}
+                    }
+
+                    self.insert_element("body", Vec::new());
+                    self.mode = InsertionMode::InBody;
+                    continue;
+                }
+
+                InsertionMode::InBody => {
+                    match token {
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "html" | "body" => {
+                                // parse error, and the attributes are not merged
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "base" | "basefont" | "bgsound" | "link" | "meta" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "style" | "script" => {
+                                self.insert_text_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
+                                continue;
+                            }
+
+                            "address" | "article" | "aside" | "blockquote" | "center"
+                            | "details" | "dialog" | "dir" | "div" | "dl" | "fieldset"
+                            | "figcaption" | "figure" | "footer" | "header" | "hgroup" | "main"
+                            | "menu" | "nav" | "ol" | "p" | "search" | "section" | "summary"
+                            | "ul" => {
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "form" => {
+                                // a form in a form is ignored, except in templates
+                                let has_template = self.has_template_on_stack();
+                                if self.form_element.is_some() && !has_template {
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                let form = self.insert_element(tag, attributes.to_vec());
+                                if !has_template {
+                                    self.form_element = Some(form);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                // headings cannot be nested
+                                if let Some(name) = self.current_node_name() {
+                                    if is_heading_element(&name) {
+                                        self.stack_of_open_elements.pop();
+                                    }
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "pre" | "listing" => {
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                // a newline right after the start tag is ignored
+                                if token == Some(HtmlToken::Char('\n')) {
+                                    token = self.t.next();
+                                }
+                                continue;
+                            }
+
+                            "li" => {
+                                self.close_list_item(&["li"]);
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "dd" | "dt" => {
+                                self.close_list_item(&["dd", "dt"]);
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "button" => {
+                                if self.has_element_in_scope("button") {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_local_name("button");
+                                }
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "a" => {
+                                // an <a> cannot be nested in another <a>
+                                if let Some(a) = self.active_formatting_element("a") {
+                                    self.run_adoption_agency_algorithm("a");
+                                    self.remove_active_formatting_element(&a);
+                                    self.stack_of_open_elements.retain(|n| !Rc::ptr_eq(n, &a));
+                                }
+                                self.reconstruct_active_formatting_elements();
+                                let node = self.insert_element(tag, attributes.to_vec());
+                                self.push_active_formatting_element(node);
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "b" | "big" | "code" | "em" | "font" | "i" | "s" | "small"
+                            | "strike" | "strong" | "tt" | "u" => {
+                                self.reconstruct_active_formatting_elements();
+                                let node = self.insert_element(tag, attributes.to_vec());
+                                self.push_active_formatting_element(node);
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "nobr" => {
+                                self.reconstruct_active_formatting_elements();
+                                if self.has_element_in_scope("nobr") {
+                                    self.run_adoption_agency_algorithm("nobr");
+                                    self.reconstruct_active_formatting_elements();
+                                }
+                                let node = self.insert_element(tag, attributes.to_vec());
+                                self.push_active_formatting_element(node);
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "applet" | "marquee" | "object" => {
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element(tag, attributes.to_vec());
+                                self.active_formatting_elements
+                                    .push(ActiveFormattingElement::Marker);
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "table" => {
+                                if self.window.borrow().document_mode() != DocumentMode::Quirks
+                                    && self.has_element_in_button_scope("p")
+                                {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InTable;
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "caption" | "col" | "colgroup" | "frame" | "head" | "tbody" | "td"
+                            | "tfoot" | "th" | "thead" | "tr" => {
+                                // parse error, and ignored outside of a table
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody:the-textarea-element
+                            "textarea" => {
+                                self.insert_text_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inbody:the-option-element
+                            "optgroup" | "option" => {
+                                if self.current_node_name().as_deref() == Some("option") {
+                                    self.stack_of_open_elements.pop();
+                                }
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "area" | "br" | "embed" | "img" | "input" | "keygen" | "wbr" => {
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "image" => {
+                                // parse error, treated as <img>
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element("img", attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "param" | "source" | "track" => {
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "hr" => {
+                                if self.has_element_in_button_scope("p") {
+                                    self.close_p_element();
+                                }
+                                self.insert_element(tag, attributes.to_vec());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element(tag, attributes.to_vec());
+                                token = self.t.next();
+                                continue;
+                            }
+                        },
+
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            // the elements are kept open, and only the insertion mode changes
+                            "body" => {
+                                token = self.t.next();
+                                // <body> is not open in the fragment case
+                                if self.has_element_in_scope("body") {
+                                    self.mode = InsertionMode::AfterBody;
+                                }
+                                continue;
+                            }
+
+                            "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
+                                continue;
+                            }
+
+                            "html" => {
+                                // parse error, and the token is ignored
+                                if !self.has_element_in_scope("body") {
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                // reprocessed in "after body"
+                                self.mode = InsertionMode::AfterBody;
+                                continue;
+                            }
+
+                            "address" | "article" | "aside" | "blockquote" | "button"
+                            | "center" | "details" | "dialog" | "dir" | "div" | "dl"
+                            | "fieldset" | "figcaption" | "figure" | "footer" | "header"
+                            | "hgroup" | "listing" | "main" | "menu" | "nav" | "ol" | "pre"
+                            | "search" | "section" | "summary" | "ul" => {
+                                if self.has_element_in_scope(tag) {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_local_name(tag);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "form" => {
+                                if self.has_template_on_stack() {
+                                    if self.has_element_in_scope("form") {
+                                        self.generate_implied_end_tags(None);
+                                        self.pop_until_local_name("form");
+                                    }
+                                    token = self.t.next();
+                                    continue;
+                                }
+
+                                // the form is removed from the stack even when it isn't the
+                                // current node, e.g. <form><div></form>
+                                if let Some(form) = self.form_element.take() {
+                                    if self.has_node_in_scope(&form) {
+                                        self.generate_implied_end_tags(None);
+                                        self.stack_of_open_elements
+                                            .retain(|n| !Rc::ptr_eq(n, &form));
+                                    }
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "p" => {
+                                // </p> without <p> creates an empty paragraph
+                                if !self.has_element_in_button_scope("p") {
+                                    self.insert_element("p", Vec::new());
+                                }
+                                self.close_p_element();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "li" => {
+                                if self.has_element_in_list_item_scope("li") {
+                                    self.generate_implied_end_tags(Some("li"));
+                                    self.pop_until_local_name("li");
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "dd" | "dt" => {
+                                if self.has_element_in_scope(tag) {
+                                    self.generate_implied_end_tags(Some(tag));
+                                    self.pop_until_local_name(tag);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                                let headings = ["h1", "h2", "h3", "h4", "h5", "h6"];
+                                if self.has_element_in_specific_scope(&headings, &[]) {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_one_of(&headings);
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "a" | "b" | "big" | "code" | "em" | "font" | "i" | "nobr" | "s"
+                            | "small" | "strike" | "strong" | "tt" | "u" => {
+                                self.run_adoption_agency_algorithm(tag);
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "applet" | "marquee" | "object" => {
+                                if self.has_element_in_scope(tag) {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_local_name(tag);
+                                    self.clear_active_formatting_elements_up_to_last_marker();
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "br" => {
+                                // parse error, treated as <br>
+                                self.reconstruct_active_formatting_elements();
+                                self.insert_element("br", Vec::new());
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {
+                                self.close_element_by_end_tag(tag);
+                                token = self.t.next();
+                                continue;
+                            }
+                        },
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        // any other character token, so _ => {} is unnecessary
+                        Some(HtmlToken::Char(c)) => {
+                            self.reconstruct_active_formatting_elements();
+                            self.insert_char(c);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+                    }
+                }
+
+                InsertionMode::Text => {
+                    match token {
+                        Some(HtmlToken::EndTag { ref tag }) => {
+                            if tag == "style" {
+                                self.pop_until(ElementKind::Style);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            if tag == "script" {
+                                self.pop_until(ElementKind::Script);
+                                self.mode = self.original_insertion_mode;
+                                token = self.t.next();
+                                continue;
+                            }
+                            // any other end tag, like </title>
+                            self.stack_of_open_elements.pop();
+                            self.mode = self.original_insertion_mode;
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Char(c)) => {
+                            self.insert_char(c);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Eof) | None => {
+                            return self.window.clone();
+                        }
+
+                        _ => {}
+                    }
+
+                    self.mode = self.original_insertion_mode;
+                }
+
+                InsertionMode::InTable => {
+                    match token {
+                        Some(HtmlToken::Char(_)) => {
+                            if matches!(
+                                self.current_node_name().as_deref(),
+                                Some("table" | "tbody" | "template" | "tfoot" | "thead" | "tr")
+                            ) {
+                                self.pending_table_characters = String::new();
+                                self.original_insertion_mode = self.mode;
+                                self.mode = InsertionMode::InTableText;
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::StartTag {
+                            ref tag,
+                            self_closing: _,
+                            ref attributes,
+                        }) => match tag.as_str() {
+                            "caption" => {
+                                self.clear_stack_back_to(&TABLE_CONTEXT);
+                                self.active_formatting_elements
+                                    .push(ActiveFormattingElement::Marker);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InCaption;
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "colgroup" => {
+                                self.clear_stack_back_to(&TABLE_CONTEXT);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InColumnGroup;
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "col" => {
+                                self.clear_stack_back_to(&TABLE_CONTEXT);
+                                self.insert_element("colgroup", Vec::new());
+                                self.mode = InsertionMode::InColumnGroup;
+                                continue;
+                            }
+
+                            "tbody" | "tfoot" | "thead" => {
+                                self.clear_stack_back_to(&TABLE_CONTEXT);
+                                self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InTableBody;
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "td" | "th" | "tr" => {
+                                self.clear_stack_back_to(&TABLE_CONTEXT);
+                                self.insert_element("tbody", Vec::new());
+                                self.mode = InsertionMode::InTableBody;
+                                continue;
+                            }
+
+                            "table" => {
+                                // parse error, and the current table is closed
+                                if !self.has_element_in_table_scope(&["table"]) {
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                self.pop_until_local_name("table");
+                                self.reset_insertion_mode_appropriately();
+                                continue;
+                            }
+
+                            "style" | "script" | "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
+                                continue;
+                            }
+
+                            "input" => {
+                                let is_hidden = attributes.iter().any(|attr| {
+                                    attr.name() == "type"
+                                        && attr.value().eq_ignore_ascii_case("hidden")
+                                });
+                                if is_hidden {
+                                    self.insert_element(tag, attributes.to_vec());
+                                    self.stack_of_open_elements.pop();
+                                    token = self.t.next();
+                                    continue;
+                                }
+                            }
+
+                            "form" => {
+                                if self.has_template_on_stack() || self.form_element.is_some() {
+                                    token = self.t.next();
+                                    continue;
+                                }
+                                let form = self.insert_element(tag, attributes.to_vec());
+                                self.form_element = Some(form);
+                                self.stack_of_open_elements.pop();
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {}
+                        },
+
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "table" => {
+                                if self.has_element_in_table_scope(&["table"]) {
+                                    self.pop_until_local_name("table");
+                                    self.reset_insertion_mode_appropriately();
+                                }
                                 token = self.t.next();
                                 continue;
                             }
-                        }
 
-                        Some(HtmlToken::StartTag {
-                            ref tag,
-                            self_closing: _,
-                            ref attributes,
-                        }) => {
-                            if tag == "html" {
-                                self.insert_element(tag, attributes.to_vec());
-                                self.mode = InsertionMode::BeforeHead;
+                            "body" | "caption" | "col" | "colgroup" | "html" | "tbody" | "td"
+                            | "tfoot" | "th" | "thead" | "tr" => {
                                 token = self.t.next();
                                 continue;
                             }
-                        }
+
+                            "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
+                                continue;
+                            }
+
+                            _ => {}
+                        },
 
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
                         }
+                    }
 
-                        _ => {}
+                    // anything else is inserted before the table
+                    self.foster_parenting = true;
+                    self.using_rules_for = Some(InsertionMode::InBody);
+                }
+
+                InsertionMode::InTableText => {
+                    if let Some(HtmlToken::Char(c)) = token {
+                        self.pending_table_characters.push(c);
+                        token = self.t.next();
+                        continue;
                     }
 
-                    self.insert_element("html", Vec::new());
-                    self.mode = InsertionMode::BeforeHead;
-                    continue;
+                    // non-whitespace characters are inserted before the table
+                    let chars = core::mem::take(&mut self.pending_table_characters);
+                    if chars
+                        .chars()
+                        .all(|c| matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' '))
+                    {
+                        for c in chars.chars() {
+                            self.insert_char(c);
+                        }
+                    } else {
+                        self.foster_parenting = true;
+                        for c in chars.chars() {
+                            self.reconstruct_active_formatting_elements();
+                            self.insert_char(c);
+                        }
+                        self.foster_parenting = false;
+                    }
+
+                    self.mode = self.original_insertion_mode;
                 }
 
-                InsertionMode::BeforeHead => {
+                InsertionMode::InCaption => {
                     match token {
-                        Some(HtmlToken::Char(c)) => {
-                            if c == ' ' || c == '\n' {
+                        Some(HtmlToken::StartTag { ref tag, .. }) => {
+                            if matches!(
+                                tag.as_str(),
+                                "caption"
+                                    | "col"
+                                    | "colgroup"
+                                    | "tbody"
+                                    | "td"
+                                    | "tfoot"
+                                    | "th"
+                                    | "thead"
+                                    | "tr"
+                            ) {
+                                if self.close_caption() {
+                                    continue;
+                                }
                                 token = self.t.next();
                                 continue;
                             }
                         }
 
-                        Some(HtmlToken::StartTag {
-                            ref tag,
-                            self_closing: _,
-                            ref attributes,
-                        }) => {
-                            if tag == "head" {
-                                self.insert_element(tag, attributes.to_vec());
-                                self.mode = InsertionMode::InHead;
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "caption" => {
+                                self.close_caption();
                                 token = self.t.next();
                                 continue;
                             }
-                        }
 
-                        Some(HtmlToken::Eof) | None => {
-                            return self.window.clone();
-                        }
+                            "table" => {
+                                if self.close_caption() {
+                                    continue;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "body" | "col" | "colgroup" | "html" | "tbody" | "td" | "tfoot"
+                            | "th" | "thead" | "tr" => {
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {}
+                        },
 
                         _ => {}
                     }
 
-                    self.insert_element("head", Vec::new());
-                    self.mode = InsertionMode::InHead;
-                    continue;
+                    self.using_rules_for = Some(InsertionMode::InBody);
                 }
 
-                InsertionMode::InHead => {
+                InsertionMode::InColumnGroup => {
                     match token {
                         Some(HtmlToken::Char(c)) => {
-                            if c == ' ' || c == '\n' {
+                            if matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ') {
+                                self.insert_char(c);
                                 token = self.t.next();
                                 continue;
                             }
                         }
 
+                        Some(HtmlToken::Comment(ref data)) => {
+                            self.insert_comment(data, None);
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
+                            token = self.t.next();
+                            continue;
+                        }
+
                         Some(HtmlToken::StartTag {
                             ref tag,
                             self_closing: _,
                             ref attributes,
-                        }) => {
-                            if tag == "style" || tag == "script" {
+                        }) => match tag.as_str() {
+                            "html" => {
+                                self.using_rules_for = Some(InsertionMode::InBody);
+                                continue;
+                            }
+
+                            "col" => {
                                 self.insert_element(tag, attributes.to_vec());
-                                self.original_insertion_mode = self.mode;
-                                self.mode = InsertionMode::Text;
+                                self.stack_of_open_elements.pop();
                                 token = self.t.next();
                                 continue;
                             }
-                            // below is not included in spec,
-                            // but is necessary for avoiding infinite loop,
-                            // which occurs with HTML without <head> tag
-                            if tag == "body" {
-                                self.pop_until(ElementKind::Head);
-                                self.mode = InsertionMode::AfterHead;
+
+                            "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
                                 continue;
                             }
-                            if let Ok(_element_kind) = ElementKind::from_str(tag) {
-                                self.pop_until(ElementKind::Head);
-                                self.mode = InsertionMode::AfterHead;
+
+                            _ => {}
+                        },
+
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "colgroup" => {
+                                if self.current_node_name().as_deref() == Some("colgroup") {
+                                    self.stack_of_open_elements.pop();
+                                    self.mode = InsertionMode::InTable;
+                                }
+                                token = self.t.next();
                                 continue;
                             }
-                        }
 
-                        Some(HtmlToken::EndTag { ref tag }) => {
-                            if tag == "head" {
-                                self.mode = InsertionMode::AfterHead;
+                            "col" => {
                                 token = self.t.next();
-                                self.pop_until(ElementKind::Head);
                                 continue;
                             }
-                        }
+
+                            "template" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
+                                continue;
+                            }
+
+                            _ => {}
+                        },
 
                         Some(HtmlToken::Eof) | None => {
                             return self.window.clone();
                         }
                     }
 
-                    // ignore unsupported tags like <meta> and <title>
-                    token = self.t.next();
-                    continue;
+                    // anything else closes the column group
+                    if self.current_node_name().as_deref() != Some("colgroup") {
+                        token = self.t.next();
+                        continue;
+                    }
+                    self.stack_of_open_elements.pop();
+                    self.mode = InsertionMode::InTable;
                 }
 
-                InsertionMode::AfterHead => {
+                InsertionMode::InTableBody => {
                     match token {
-                        Some(HtmlToken::Char(c)) => {
-                            if c == ' ' || c == '\n' {
-                                self.insert_char(c);
-                                token = self.t.next();
-                                continue;
-                            }
-                        }
-
                         Some(HtmlToken::StartTag {
                             ref tag,
                             self_closing: _,
                             ref attributes,
-                        }) => {
-                            if tag == "body" {
+                        }) => match tag.as_str() {
+                            "tr" => {
+                                self.clear_stack_back_to(&TABLE_BODY_CONTEXT);
                                 self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InRow;
                                 token = self.t.next();
-                                self.mode = InsertionMode::InBody;
                                 continue;
                             }
-                        }
 
-                        Some(HtmlToken::Eof) | None => {
-                            return self.window.clone();
-                        }
+                            "td" | "th" => {
+                                self.clear_stack_back_to(&TABLE_BODY_CONTEXT);
+                                self.insert_element("tr", Vec::new());
+                                self.mode = InsertionMode::InRow;
+                                continue;
+                            }
+
+                            "caption" | "col" | "colgroup" | "tbody" | "tfoot" | "thead" => {
+                                if self.close_table_body() {
+                                    continue;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {}
+                        },
+
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "tbody" | "tfoot" | "thead" => {
+                                if self.has_element_in_table_scope(&[tag]) {
+                                    self.clear_stack_back_to(&TABLE_BODY_CONTEXT);
+                                    self.stack_of_open_elements.pop();
+                                    self.mode = InsertionMode::InTable;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "table" => {
+                                if self.close_table_body() {
+                                    continue;
+                                }
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            "body" | "caption" | "col" | "colgroup" | "html" | "td" | "th"
+                            | "tr" => {
+                                token = self.t.next();
+                                continue;
+                            }
+
+                            _ => {}
+                        },
 
                         _ => {}
                     }
 
-                    self.insert_element("body", Vec::new());
-                    self.mode = InsertionMode::InBody;
-                    continue;
+                    self.using_rules_for = Some(InsertionMode::InTable);
                 }
 
-                InsertionMode::InBody => {
+                InsertionMode::InRow => {
                     match token {
                         Some(HtmlToken::StartTag {
                             ref tag,
                             self_closing: _,
                             ref attributes,
                         }) => match tag.as_str() {
-                            "h1" | "h2" => {
+                            "td" | "th" => {
+                                self.clear_stack_back_to(&TABLE_ROW_CONTEXT);
                                 self.insert_element(tag, attributes.to_vec());
+                                self.mode = InsertionMode::InCell;
+                                self.active_formatting_elements
+                                    .push(ActiveFormattingElement::Marker);
                                 token = self.t.next();
                                 continue;
                             }
 
-                            "p" => {
-                                self.insert_element(tag, attributes.to_vec());
+                            "caption" | "col" | "colgroup" | "tbody" | "tfoot" | "thead" | "tr" => {
+                                if self.close_row() {
+                                    continue;
+                                }
                                 token = self.t.next();
                                 continue;
                             }
 
-                            "a" => {
-                                self.insert_element(tag, attributes.to_vec());
+                            _ => {}
+                        },
+
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "tr" => {
+                                self.close_row();
                                 token = self.t.next();
                                 continue;
                             }
 
-                            _ => {
+                            "table" => {
+                                if self.close_row() {
+                                    continue;
+                                }
                                 token = self.t.next();
+                                continue;
                             }
-                        },
 
-                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
-                            "body" => {
-                                self.mode = InsertionMode::AfterBody;
-                                token = self.t.next();
-                                if !self.contain_in_stack(ElementKind::Body) {
+                            "tbody" | "tfoot" | "thead" => {
+                                if self.has_element_in_table_scope(&[tag]) && self.close_row() {
                                     continue;
                                 }
-                                self.pop_until(ElementKind::Body);
+                                token = self.t.next();
                                 continue;
                             }
 
-                            "html" => {
-                                if self.pop_current_node(ElementKind::Body) {
-                                    self.mode = InsertionMode::AfterBody;
-                                    assert!(self.pop_current_node(ElementKind::Html));
-                                } else {
-                                    token = self.t.next();
-                                }
+                            "body" | "caption" | "col" | "colgroup" | "html" | "td" | "th" => {
+                                token = self.t.next();
                                 continue;
                             }
 
-                            "h1" | "h2" => {
-                                let element_kind = ElementKind::from_str(tag)
-                                    .expect("failed to convert string to ElementKind");
+                            _ => {}
+                        },
+
+                        _ => {}
+                    }
+
+                    self.using_rules_for = Some(InsertionMode::InTable);
+                }
+
+                InsertionMode::InCell => {
+                    match token {
+                        Some(HtmlToken::StartTag { ref tag, .. }) => {
+                            if matches!(
+                                tag.as_str(),
+                                "caption"
+                                    | "col"
+                                    | "colgroup"
+                                    | "tbody"
+                                    | "td"
+                                    | "tfoot"
+                                    | "th"
+                                    | "thead"
+                                    | "tr"
+                            ) {
+                                if self.has_element_in_table_scope(&["td", "th"]) {
+                                    self.close_cell();
+                                    continue;
+                                }
                                 token = self.t.next();
-                                self.pop_until(element_kind);
                                 continue;
                             }
+                        }
 
-                            "p" => {
-                                let element_kind = ElementKind::from_str(tag)
-                                    .expect("failed to convert string to ElementKind");
+                        Some(HtmlToken::EndTag { ref tag }) => match tag.as_str() {
+                            "td" | "th" => {
+                                if self.has_element_in_table_scope(&[tag]) {
+                                    self.generate_implied_end_tags(None);
+                                    self.pop_until_local_name(tag);
+                                    self.clear_active_formatting_elements_up_to_last_marker();
+                                    self.mode = InsertionMode::InRow;
+                                }
                                 token = self.t.next();
-                                self.pop_until(element_kind);
                                 continue;
                             }
 
-                            "a" => {
-                                let element_kind = ElementKind::from_str(tag)
-                                    .expect("failed to convert string to ElementKind");
+                            "body" | "caption" | "col" | "colgroup" | "html" => {
                                 token = self.t.next();
-                                self.pop_until(element_kind);
                                 continue;
                             }
 
-                            _ => {
+                            "table" | "tbody" | "tfoot" | "thead" | "tr" => {
+                                if self.has_element_in_table_scope(&[tag]) {
+                                    self.close_cell();
+                                    continue;
+                                }
                                 token = self.t.next();
+                                continue;
                             }
-                        },
 
-                        // any other character token, so _ => {} is unnecessary
-                        Some(HtmlToken::Char(c)) => {
-                            self.insert_char(c);
-                            token = self.t.next();
-                            continue;
-                        }
+                            _ => {}
+                        },
 
-                        Some(HtmlToken::Eof) | None => {
-                            return self.window.clone();
-                        }
+                        _ => {}
                     }
+
+                    self.using_rules_for = Some(InsertionMode::InBody);
                 }
 
-                InsertionMode::Text => {
-                    match token {
-                        Some(HtmlToken::EndTag { ref tag }) => {
-                            if tag == "style" {
-                                self.pop_until(ElementKind::Style);
-                                self.mode = self.original_insertion_mode;
-                                token = self.t.next();
+                // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intemplate
+                InsertionMode::InTemplate => match token {
+                    Some(HtmlToken::Char(_))
+                    | Some(HtmlToken::Comment(_))
+                    | Some(HtmlToken::Doctype { .. }) => {
+                        self.using_rules_for = Some(InsertionMode::InBody);
+                        continue;
+                    }
+
+                    Some(HtmlToken::StartTag { ref tag, .. }) => {
+                        // the first start tag decides how the contents are parsed
+                        let mode = match tag.as_str() {
+                            "base" | "basefont" | "bgsound" | "link" | "meta" | "noframes"
+                            | "script" | "style" | "template" | "title" => {
+                                self.using_rules_for = Some(InsertionMode::InHead);
                                 continue;
                             }
-                            if tag == "script" {
-                                self.pop_until(ElementKind::Script);
-                                self.mode = self.original_insertion_mode;
-                                token = self.t.next();
-                                continue;
+                            "caption" | "colgroup" | "tbody" | "tfoot" | "thead" => {
+                                InsertionMode::InTable
                             }
-                        }
+                            "col" => InsertionMode::InColumnGroup,
+                            "tr" => InsertionMode::InTableBody,
+                            "td" | "th" => InsertionMode::InRow,
+                            _ => InsertionMode::InBody,
+                        };
+                        self.template_insertion_modes.pop();
+                        self.template_insertion_modes.push(mode);
+                        self.mode = mode;
+                        continue;
+                    }
 
-                        Some(HtmlToken::Char(c)) => {
-                            self.insert_char(c);
-                            token = self.t.next();
+                    Some(HtmlToken::EndTag { ref tag }) => {
+                        if tag == "template" {
+                            self.using_rules_for = Some(InsertionMode::InHead);
                             continue;
                         }
-
-                        Some(HtmlToken::Eof) | None => {
-                            return self.window.clone();
-                        }
-
-                        _ => {}
+                        token = self.t.next();
+                        continue;
                     }
 
-                    self.mode = self.original_insertion_mode;
-                }
+                    // the unclosed <template> is left as it is
+                    Some(HtmlToken::Eof) | None => {
+                        return self.window.clone();
+                    }
+                },
 
                 InsertionMode::AfterBody => {
                     match token {
-                        Some(HtmlToken::Char(_c)) => {
-                            // not aligned with spec
+                        Some(HtmlToken::Char(c)) => {
+                            if matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ') {
+                                self.using_rules_for = Some(InsertionMode::InBody);
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            // inserted as the last child of the html element
+                            let html = match self.stack_of_open_elements.first() {
+                                Some(n) => n.clone(),
+                                None => self.window.borrow().document(),
+                            };
+                            self.insert_comment(data, Some(html));
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
                             token = self.t.next();
                             continue;
                         }
 
                         Some(HtmlToken::EndTag { ref tag }) => {
                             if tag == "html" {
-                                self.mode = InsertionMode::AfterAfterBody;
+                                // ignored in the fragment case
+                                if self.context_element.is_none() {
+                                    self.mode = InsertionMode::AfterAfterBody;
+                                }
                                 token = self.t.next();
                                 continue;
                             }
@@ -514,8 +2200,21 @@ impl HtmlParser {
 
                 InsertionMode::AfterAfterBody => {
                     match token {
-                        Some(HtmlToken::Char(_c)) => {
-                            // not align with the spec
+                        Some(HtmlToken::Char(c)) => {
+                            if matches!(c, '\t' | '\n' | '\x0C' | '\r' | ' ') {
+                                self.using_rules_for = Some(InsertionMode::InBody);
+                                continue;
+                            }
+                        }
+
+                        Some(HtmlToken::Comment(ref data)) => {
+                            let document = self.window.borrow().document();
+                            self.insert_comment(data, Some(document));
+                            token = self.t.next();
+                            continue;
+                        }
+
+                        Some(HtmlToken::Doctype { .. }) => {
                             token = self.t.next();
                             continue;
                         }
@@ -536,6 +2235,116 @@ impl HtmlParser {
     }
 }
 
+// https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode
+static QUIRKS_PUBLIC_ID_PREFIXES: [&str; 55] = [
+    "+//silmaril//dtd html pro v0r11 19970101//",
+    "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+    "-//ietf//dtd html 2.0 level 1//",
+    "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//",
+    "-//ietf//dtd html 2.0 strict level 2//",
+    "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//",
+    "-//ietf//dtd html 2.1e//",
+    "-//ietf//dtd html 3.0//",
+    "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//",
+    "-//ietf//dtd html 3//",
+    "-//ietf//dtd html level 0//",
+    "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//",
+    "-//ietf//dtd html level 3//",
+    "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//",
+    "-//ietf//dtd html strict level 2//",
+    "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//",
+    "-//ietf//dtd html//",
+    "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//",
+    "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//",
+    "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//",
+    "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//",
+    "-//netscape comm. corp.//dtd strict html//",
+    "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//",
+    "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//",
+    "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+    "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//",
+    "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//",
+    "-//w3c//dtd html 3.2 draft//",
+    "-//w3c//dtd html 3.2 final//",
+    "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//",
+    "-//w3c//dtd html 4.0 frameset//",
+    "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//",
+    "-//w3c//dtd html experimental 970421//",
+    "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//",
+    "-//webtechs//dtd mozilla html 2.0//",
+    "-//webtechs//dtd mozilla html//",
+];
+
+// decides whether the document is rendered in quirks mode from the doctype,
+// all comparisons are ASCII case-insensitive
+fn document_mode_from_doctype(
+    name: &Option<String>,
+    public_id: &Option<String>,
+    system_id: &Option<String>,
+    force_quirks: bool,
+) -> DocumentMode {
+    let name = name.as_ref().map(|n| n.to_ascii_lowercase());
+    let public_id = public_id.as_ref().map(|p| p.to_ascii_lowercase());
+    let system_id = system_id.as_ref().map(|s| s.to_ascii_lowercase());
+
+    if force_quirks || name.as_deref() != Some("html") {
+        return DocumentMode::Quirks;
+    }
+
+    if let Some(ref p) = public_id {
+        if p == "-//w3o//dtd w3 html strict 3.0//en//"
+            || p == "-/w3c/dtd html 4.0 transitional/en"
+            || p == "html"
+            || QUIRKS_PUBLIC_ID_PREFIXES
+                .iter()
+                .any(|prefix| p.starts_with(prefix))
+        {
+            return DocumentMode::Quirks;
+        }
+    }
+
+    if system_id.as_deref() == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd") {
+        return DocumentMode::Quirks;
+    }
+
+    if let Some(ref p) = public_id {
+        let is_html401 = p.starts_with("-//w3c//dtd html 4.01 frameset//")
+            || p.starts_with("-//w3c//dtd html 4.01 transitional//");
+
+        if is_html401 && system_id.is_none() {
+            return DocumentMode::Quirks;
+        }
+
+        if p.starts_with("-//w3c//dtd xhtml 1.0 frameset//")
+            || p.starts_with("-//w3c//dtd xhtml 1.0 transitional//")
+            || is_html401
+        {
+            return DocumentMode::LimitedQuirks;
+        }
+    }
+
+    DocumentMode::NoQuirks
+}
+
 // There are 23 states defined, but only 9 will be implemented
 // https://html.spec.whatwg.org/multipage/parsing.html#original-insertion-mode
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -554,18 +2363,236 @@ pub enum InsertionMode {
     InBody,
     // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-incdata
     Text,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intable
+    InTable,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intabletext
+    InTableText,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-incaption
+    InCaption,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-incolgroup
+    InColumnGroup,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intbody
+    InTableBody,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intr
+    InRow,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intd
+    InCell,
+    // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-intemplate
+    InTemplate,
     // https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-afterbody
     AfterBody,
     // https://html.spec.whatwg.org/multipage/parsing.html#the-after-after-body-insertion-mode
     AfterAfterBody,
 }
 
+// https://html.spec.whatwg.org/multipage/parsing.html#clear-the-stack-back-to-a-table-context
+static TABLE_CONTEXT: [&str; 3] = ["table", "template", "html"];
+
+// https://html.spec.whatwg.org/multipage/parsing.html#clear-the-stack-back-to-a-table-body-context
+static TABLE_BODY_CONTEXT: [&str; 5] = ["tbody", "tfoot", "thead", "template", "html"];
+
+// https://html.spec.whatwg.org/multipage/parsing.html#clear-the-stack-back-to-a-table-row-context
+static TABLE_ROW_CONTEXT: [&str; 3] = ["tr", "template", "html"];
+
+// start tags ignored in <head>, since they are not supported yet
+fn is_metadata_element(tag: &str) -> bool {
+    matches!(tag, "head" | "noscript")
+}
+
+fn local_name_of(node: &Rc<RefCell<Node>>) -> Option<String> {
+    node.borrow().get_element().map(|e| e.local_name())
+}
+
+// https://dom.spec.whatwg.org/#concept-element-attributes
+fn is_identical_element(a: &Option<Element>, b: &Option<Element>) -> bool {
+    let (a, b) = match (a, b) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+
+    let (a_attributes, b_attributes) = (a.attributes(), b.attributes());
+    a.local_name() == b.local_name()
+        && a.namespace() == b.namespace()
+        && a_attributes.len() == b_attributes.len()
+        && a_attributes.iter().all(|attr| b_attributes.contains(attr))
+}
+
+fn is_heading_element(tag: &str) -> bool {
+    matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#generate-implied-end-tags
+fn has_implied_end_tag(tag: &str) -> bool {
+    matches!(
+        tag,
+        "dd" | "dt" | "li" | "optgroup" | "option" | "p" | "rb" | "rp" | "rt" | "rtc"
+    )
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#has-an-element-in-the-specific-scope
+fn is_scope_boundary(tag: &str) -> bool {
+    matches!(
+        tag,
+        "applet" | "caption" | "html" | "table" | "td" | "th" | "marquee" | "object" | "template"
+    )
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#special
+fn is_special_element(tag: &str) -> bool {
+    matches!(
+        tag,
+        "address"
+            | "applet"
+            | "area"
+            | "article"
+            | "aside"
+            | "base"
+            | "basefont"
+            | "bgsound"
+            | "blockquote"
+            | "body"
+            | "br"
+            | "button"
+            | "caption"
+            | "center"
+            | "col"
+            | "colgroup"
+            | "dd"
+            | "details"
+            | "dir"
+            | "div"
+            | "dl"
+            | "dt"
+            | "embed"
+            | "fieldset"
+            | "figcaption"
+            | "figure"
+            | "footer"
+            | "form"
+            | "frame"
+            | "frameset"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "head"
+            | "header"
+            | "hgroup"
+            | "hr"
+            | "html"
+            | "iframe"
+            | "img"
+            | "input"
+            | "keygen"
+            | "li"
+            | "link"
+            | "listing"
+            | "main"
+            | "marquee"
+            | "menu"
+            | "meta"
+            | "nav"
+            | "noembed"
+            | "noframes"
+            | "noscript"
+            | "object"
+            | "ol"
+            | "p"
+            | "param"
+            | "plaintext"
+            | "pre"
+            | "script"
+            | "search"
+            | "section"
+            | "select"
+            | "source"
+            | "style"
+            | "summary"
+            | "table"
+            | "tbody"
+            | "td"
+            | "template"
+            | "textarea"
+            | "tfoot"
+            | "th"
+            | "thead"
+            | "title"
+            | "tr"
+            | "track"
+            | "ul"
+            | "wbr"
+            | "xmp"
+    )
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#list-of-active-formatting-elements
+#[derive(Debug, Clone)]
+enum ActiveFormattingElement {
+    // https://html.spec.whatwg.org/multipage/parsing.html#concept-parser-marker
+    Marker,
+    Element(Rc<RefCell<Node>>),
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::renderer::html::dom::Namespace;
+    use alloc::format;
     use alloc::vec;
 
+    // dumps the tree in the format of html5lib-tests
+    fn dump(node: &Rc<RefCell<Node>>, depth: usize, result: &mut String) {
+        let indent = "  ".repeat(depth);
+        match node.borrow().kind() {
+            NodeKind::Document | NodeKind::DocumentFragment => {}
+            NodeKind::DocumentType(d) => {
+                result.push_str(&format!("| {}<!DOCTYPE {}>\n", indent, d.name()));
+            }
+            NodeKind::Element(e) => {
+                result.push_str(&format!("| {}<{}>\n", indent, e.local_name()));
+                let mut attributes = e.attributes();
+                attributes.sort_by_key(|a| a.name());
+                for attr in attributes {
+                    result.push_str(&format!(
+                        "| {}  {}=\"{}\"\n",
+                        indent,
+                        attr.name(),
+                        attr.value()
+                    ));
+                }
+            }
+            NodeKind::Text(t) => result.push_str(&format!("| {}\"{}\"\n", indent, t)),
+            NodeKind::Comment(c) => result.push_str(&format!("| {}<!-- {} -->\n", indent, c)),
+        }
+
+        // the contents of <template> are dumped under "content"
+        if let Some(contents) = template_contents(node) {
+            result.push_str(&format!("| {}  content\n", indent));
+            dump(&contents, depth + 2, result);
+        }
+
+        let depth = match node.borrow().kind() {
+            NodeKind::Document | NodeKind::DocumentFragment => depth,
+            _ => depth + 1,
+        };
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            dump(&c, depth, result);
+            child = c.borrow().next_sibling();
+        }
+    }
+
+    fn parse_and_dump(html: &str) -> String {
+        let t = HtmlTokenizer::new(html.to_string());
+        let window = HtmlParser::new(t).construct_tree();
+        let mut result = String::new();
+        dump(&window.borrow().document(), 0, &mut result);
+        result
+    }
+
     #[test]
     fn test_empty() {
         let html = "".to_string();
@@ -739,4 +2766,700 @@ mod tests {
             text
         );
     }
+
+    #[test]
+    fn test_doctype() {
+        let html = "<!DOCTYPE html><html></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let doctype = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        assert_eq!(
+            NodeKind::DocumentType(DocumentType::new(
+                "html".to_string(),
+                "".to_string(),
+                "".to_string()
+            )),
+            doctype.borrow().kind()
+        );
+        assert_eq!(DocumentMode::NoQuirks, window.borrow().document_mode());
+
+        let html = doctype
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of doctype");
+        assert_eq!(
+            Rc::new(RefCell::new(Node::new(NodeKind::Element(Element::new(
+                "html",
+                Vec::new()
+            ))))),
+            html
+        );
+    }
+
+    #[test]
+    fn test_document_mode() {
+        let cases = [
+            ("<html></html>", DocumentMode::Quirks),
+            ("<!DOCTYPE html>", DocumentMode::NoQuirks),
+            ("<!DOCTYPE svg>", DocumentMode::Quirks),
+            (
+                r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 3.2 Final//EN">"#,
+                DocumentMode::Quirks,
+            ),
+            (
+                r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN">"#,
+                DocumentMode::Quirks,
+            ),
+            (
+                r#"<!DOCTYPE HTML PUBLIC "-//W3C//DTD HTML 4.01 Transitional//EN" "http://www.w3.org/TR/html4/loose.dtd">"#,
+                DocumentMode::LimitedQuirks,
+            ),
+            (
+                r#"<!DOCTYPE html PUBLIC "-//W3C//DTD XHTML 1.0 Strict//EN" "http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd">"#,
+                DocumentMode::NoQuirks,
+            ),
+        ];
+
+        for (html, expected) in cases {
+            let t = HtmlTokenizer::new(html.to_string());
+            let window = HtmlParser::new(t).construct_tree();
+            assert_eq!(expected, window.borrow().document_mode(), "{}", html);
+        }
+    }
+
+    #[test]
+    fn test_comment() {
+        let html = "<!-- a --><html><body><p>x<!-- b -->y</p></body></html><!-- c -->".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let comment_a = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document");
+        assert_eq!(
+            NodeKind::Comment(" a ".to_string()),
+            comment_a.borrow().kind()
+        );
+
+        let html = comment_a
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of comment");
+        let p = html
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of html")
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of head")
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+
+        let x = p.borrow().first_child().expect("failed to get text x");
+        assert_eq!(NodeKind::Text("x".to_string()), x.borrow().kind());
+        let comment_b = x
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of text x");
+        assert_eq!(
+            NodeKind::Comment(" b ".to_string()),
+            comment_b.borrow().kind()
+        );
+        let y = comment_b
+            .borrow()
+            .next_sibling()
+            .expect("failed to get text y");
+        assert_eq!(NodeKind::Text("y".to_string()), y.borrow().kind());
+
+        let comment_c = document
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("failed to get a last child of document");
+        assert_eq!(
+            NodeKind::Comment(" c ".to_string()),
+            comment_c.borrow().kind()
+        );
+    }
+
+    #[test]
+    fn test_unknown_elements() {
+        let html = "<html><head><meta charset=utf-8></head><body><div><custom-tag>x</custom-tag><br>y</div></body></html>".to_string();
+        let t = HtmlTokenizer::new(html);
+        let window = HtmlParser::new(t).construct_tree();
+        let document = window.borrow().document();
+
+        let body = document
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of document")
+            .borrow()
+            .last_child()
+            .upgrade()
+            .expect("failed to get a last child of html");
+
+        let div = body
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of body");
+        let element = div
+            .borrow()
+            .get_element()
+            .expect("div should be an element");
+        assert_eq!(Some(ElementKind::Div), element.kind());
+        assert!(element.is_block_element());
+
+        let custom = div
+            .borrow()
+            .first_child()
+            .expect("failed to get a first child of div");
+        let element = custom
+            .borrow()
+            .get_element()
+            .expect("custom-tag should be an element");
+        assert_eq!(None, element.kind());
+        assert_eq!("custom-tag", element.local_name());
+        assert_eq!(Namespace::Html, element.namespace());
+        assert_eq!(
+            NodeKind::Text("x".to_string()),
+            custom
+                .borrow()
+                .first_child()
+                .expect("failed to get a first child of custom-tag")
+                .borrow()
+                .kind()
+        );
+
+        // br is a void element, so the following text is its sibling
+        let br = custom
+            .borrow()
+            .next_sibling()
+            .expect("failed to get a next sibling of custom-tag");
+        assert_eq!(
+            NodeKind::Element(Element::new("br", Vec::new())),
+            br.borrow().kind()
+        );
+        assert!(br.borrow().first_child().is_none());
+        assert_eq!(
+            NodeKind::Text("y".to_string()),
+            br.borrow()
+                .next_sibling()
+                .expect("failed to get a next sibling of br")
+                .borrow()
+                .kind()
+        );
+    }
+
+    #[test]
+    fn test_implied_end_tags() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <p>
+|       "a"
+|     <div>
+|       "b"
+|     <p>
+|     <ul>
+|       <li>
+|         "c"
+|       <li>
+|         "d"
+|     <dl>
+|       <dt>
+|         "e"
+|       <dd>
+|         "f"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<p>a<div>b</div></p><ul><li>c<li>d</ul><dl><dt>e<dd>f</dl>")
+        );
+    }
+
+    #[test]
+    fn test_headings() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <h1>
+|       "a"
+|     <h3>
+|       "b"
+|     <section>
+|       <h6>
+|         "c"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<h1>a<h3>b</h1><section><h6>c</h5></section>")
+        );
+    }
+
+    #[test]
+    fn test_void_elements() {
+        let expected = r#"| <html>
+|   <head>
+|     <meta>
+|       charset="utf-8"
+|   <body>
+|     <p>
+|       "a"
+|       <br>
+|       "b"
+|       <img>
+|         alt="x"
+|         src="y.png"
+|       <input>
+|     <hr>
+|     <link>
+|       rel="stylesheet"
+|     "c"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump(
+                "<head><meta charset=utf-8></head><p>a<br>b<img src=y.png alt=x><input><hr><link rel=stylesheet>c"
+            )
+        );
+    }
+
+    #[test]
+    fn test_reconstruct_active_formatting_elements() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <p>
+|       <b>
+|         <i>
+|           "a"
+|     <p>
+|       <b>
+|         <i>
+|           "b"
+|         "c"
+|       "d"
+"#;
+        assert_eq!(expected, parse_and_dump("<p><b><i>a<p>b</i>c</b>d"));
+    }
+
+    // https://github.com/html5lib/html5lib-tests/blob/master/tree-construction/adoption01.dat
+    #[test]
+    fn test_adoption_agency_with_paragraph() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|     <p>
+|       <a>
+"#;
+        assert_eq!(expected, parse_and_dump("<a><p></a></p>"));
+
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       "1"
+|     <p>
+|       <a>
+|         "2"
+|       "3"
+"#;
+        assert_eq!(expected, parse_and_dump("<a>1<p>2</a>3</p>"));
+    }
+
+    #[test]
+    fn test_adoption_agency_with_button() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       "1"
+|     <button>
+|       <a>
+|         "2"
+|       "3"
+"#;
+        assert_eq!(expected, parse_and_dump("<a>1<button>2</a>3</button>"));
+    }
+
+    #[test]
+    fn test_adoption_agency_without_furthest_block() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       "1"
+|       <b>
+|         "2"
+|     <b>
+|       "3"
+"#;
+        assert_eq!(expected, parse_and_dump("<a>1<b>2</a>3</b>"));
+
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <b>
+|       <i>
+|         "x"
+|     <i>
+|       "y"
+"#;
+        assert_eq!(expected, parse_and_dump("<b><i>x</b>y</i>"));
+    }
+
+    #[test]
+    fn test_adoption_agency_with_nested_blocks() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       "1"
+|     <div>
+|       <a>
+|         "2"
+|       <div>
+|         <a>
+|           "3"
+|         "4"
+|       "5"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<a>1<div>2<div>3</a>4</div>5</div>")
+        );
+    }
+
+    #[test]
+    fn test_nested_anchor() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       href="x"
+|       "1"
+|     <a>
+|       href="y"
+|       "2"
+"#;
+        assert_eq!(expected, parse_and_dump("<a href=x>1<a href=y>2</a>"));
+    }
+
+    #[test]
+    fn test_adoption_agency_with_formatting_element_inside() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       "1"
+|       <b>
+|         "2"
+|     <b>
+|       <div>
+|         <a>
+|           "3"
+|         "4"
+"#;
+        assert_eq!(expected, parse_and_dump("<a>1<b>2<div>3</a>4</div>"));
+    }
+
+    #[test]
+    fn test_template() {
+        let expected = r#"| <html>
+|   <head>
+|     <template>
+|       content
+|         <p>
+|           "a"
+|   <body>
+|     <table>
+|       <template>
+|         content
+|           <tr>
+|             <td>
+|               "x"
+|     <template>
+|       content
+|         "b"
+|         <template>
+|           content
+|             "c"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump(
+                "<head><template><p>a</template></head><table><template><tr><td>x</td></tr></template></table><template>b<template>c</template></template>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <table>
+|       <caption>
+|         "c"
+|       <colgroup>
+|         <col>
+|       <thead>
+|         <tr>
+|           <th>
+|             "h"
+|       <tbody>
+|         <tr>
+|           <td>
+|             "a"
+|           <td>
+|             "b"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump(
+                "<table><caption>c</caption><colgroup><col></colgroup><thead><tr><th>h</thead>\n<tbody><tr><td>a</td><td>b</td></tr></tbody></table>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_table_implied_elements() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <table>
+|       <colgroup>
+|         <col>
+|       <tbody>
+|         <tr>
+|           <td>
+|             "a"
+|         <tr>
+|           <td>
+|             "b"
+|     <p>
+|       "c"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<table><col><td>a<tr><td>b</table><p>c")
+        );
+    }
+
+    #[test]
+    fn test_foster_parenting() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     "a"
+|     <div>
+|       "b"
+|     <table>
+|       <tbody>
+|         <tr>
+|           <td>
+|             "c"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<table>a<div>b</div><tr><td>c</table>")
+        );
+    }
+
+    #[test]
+    fn test_nested_table() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <table>
+|       <tbody>
+|         <tr>
+|           <td>
+|             <b>
+|               "x"
+|             <table>
+|               <tbody>
+|                 <tr>
+|                   <td>
+|                     "y"
+|             "z"
+|           <td>
+|             "w"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<table><tr><td><b>x</b><table><tr><td>y</table>z<td>w</table>")
+        );
+    }
+
+    #[test]
+    fn test_form_controls() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <form>
+|       <select>
+|         <option>
+|           "a"
+|         <option>
+|           "b"
+|       <textarea>
+|         "x"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump(
+                "<form><select><option>a<option>b</select><textarea>x</textarea></form>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_form_element_pointer() {
+        // a form in a form is ignored
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <form>
+|       "a"
+|       <input>
+|     "b"
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<form>a<form><input></form>b</form>")
+        );
+
+        // </form> closes the form which isn't the current node, and another form can follow
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <form>
+|       <div>
+|         "a"
+|     <div>
+|       "b"
+|     <form>
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<form><div>a</form></div><div>b</div><form>")
+        );
+
+        // forms can be nested in templates
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <form>
+|       <template>
+|         content
+|           <form>
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("<form><template><form></form></template></form>")
+        );
+    }
+
+    #[test]
+    fn test_end_tag_p_without_p() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     "a"
+|     <p>
+|     "b"
+"#;
+        assert_eq!(expected, parse_and_dump("a</p>b"));
+
+        // the end tags before <body> are ignored, and the missing elements are inserted
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+"#;
+        assert_eq!(expected, parse_and_dump("</p>"));
+        assert_eq!(expected, parse_and_dump(""));
+    }
+
+    #[test]
+    fn test_content_after_body_and_html() {
+        // </html> only changes the insertion mode, and the elements stay open
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <p>
+|       "x"
+"#;
+        assert_eq!(expected, parse_and_dump("</html><p>x"));
+
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     "xy"
+"#;
+        assert_eq!(expected, parse_and_dump("x</html>y"));
+        assert_eq!(expected, parse_and_dump("x</body>y"));
+
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <button>
+|       <table>
+|         <tbody>
+|           <tr>
+|             <td>
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump("</body></html><button><table><td></table></html>")
+        );
+    }
+
+    #[test]
+    fn test_adoption_agency_after_html_end_tag() {
+        let expected = r#"| <html>
+|   <head>
+|   <body>
+|     <a>
+|       href="x"
+|     <dd>
+|       <a>
+|         href="x"
+|       <a>
+|         href="x"
+"#;
+        assert_eq!(expected, parse_and_dump("</html><a href=x><dd><a href=x>"));
+    }
+
+    #[test]
+    fn test_text_elements() {
+        let expected = r#"| <html>
+|   <head>
+|     <title>
+|       "a<b>"
+|     <style>
+|       "p<q>{}"
+|   <body>
+|     <script>
+|       "if (a<b) {}"
+|     <textarea>
+|       "<p>&"
+|     <p>
+"#;
+        assert_eq!(
+            expected,
+            parse_and_dump(
+                "<title>a<b></title><style>p<q>{}</style><body><script>if (a<b) {}</script><textarea><p>&amp;</textarea><p>"
+            )
+        );
+    }
 }
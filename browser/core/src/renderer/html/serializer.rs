@@ -0,0 +1,208 @@
+use crate::renderer::html::dom::template_contents;
+use crate::renderer::html::dom::Namespace;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeKind;
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+
+// https://html.spec.whatwg.org/multipage/parsing.html#serialising-html-fragments
+// serializes the children of the node, which is the value of innerHTML
+pub fn serialize_children(node: &Rc<RefCell<Node>>) -> String {
+    let mut result = String::new();
+
+    // void elements never have children to serialize
+    if let NodeKind::Element(e) = node.borrow().kind() {
+        if e.namespace() == Namespace::Html && is_void_element(&e.local_name()) {
+            return result;
+        }
+    }
+
+    // the children of <template> are its contents
+    let parent = template_contents(node).unwrap_or_else(|| node.clone());
+    let mut child = parent.borrow().first_child();
+    while let Some(c) = child {
+        serialize_node_internal(&c, &parent_local_name(node), &mut result);
+        child = c.borrow().next_sibling();
+    }
+
+    result
+}
+
+// serializes the node itself, which is the value of outerHTML
+pub fn serialize_node(node: &Rc<RefCell<Node>>) -> String {
+    let parent_name = match node.borrow().parent().upgrade() {
+        Some(parent) => parent_local_name(&parent),
+        None => String::new(),
+    };
+
+    let mut result = String::new();
+    serialize_node_internal(node, &parent_name, &mut result);
+    result
+}
+
+fn serialize_node_internal(node: &Rc<RefCell<Node>>, parent_name: &str, result: &mut String) {
+    match node.borrow().kind() {
+        NodeKind::Document | NodeKind::DocumentFragment => {
+            result.push_str(&serialize_children(node))
+        }
+        NodeKind::DocumentType(doctype) => {
+            result.push_str("<!DOCTYPE ");
+            result.push_str(&doctype.name());
+            result.push('>');
+        }
+        NodeKind::Element(e) => {
+            // the qualified name is the local name for elements in the HTML, SVG and MathML
+            // namespaces
+            let tag_name = e.local_name();
+            result.push('<');
+            result.push_str(&tag_name);
+            for attr in e.attributes() {
+                result.push(' ');
+                result.push_str(&attr.name());
+                result.push_str("=\"");
+                result.push_str(&escape_string(&attr.value(), true));
+                result.push('"');
+            }
+            result.push('>');
+
+            if e.namespace() == Namespace::Html && is_void_element(&tag_name) {
+                return;
+            }
+
+            result.push_str(&serialize_children(node));
+            result.push_str("</");
+            result.push_str(&tag_name);
+            result.push('>');
+        }
+        NodeKind::Text(text) => {
+            if is_raw_text_parent(parent_name) {
+                result.push_str(&text);
+            } else {
+                result.push_str(&escape_string(&text, false));
+            }
+        }
+        NodeKind::Comment(data) => {
+            result.push_str("<!--");
+            result.push_str(&data);
+            result.push_str("-->");
+        }
+    }
+}
+
+fn parent_local_name(node: &Rc<RefCell<Node>>) -> String {
+    match node.borrow().get_element() {
+        Some(e) if e.namespace() == Namespace::Html => e.local_name(),
+        _ => String::new(),
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/parsing.html#escapingString
+pub fn escape_string(s: &str, attribute_mode: bool) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '\u{00A0}' => result.push_str("&nbsp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' if attribute_mode => result.push_str("&quot;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+// https://html.spec.whatwg.org/multipage/syntax.html#void-elements
+pub fn is_void_element(name: &str) -> bool {
+    matches!(
+        name,
+        "area"
+            | "base"
+            | "basefont"
+            | "bgsound"
+            | "br"
+            | "col"
+            | "embed"
+            | "hr"
+            | "img"
+            | "input"
+            | "keygen"
+            | "link"
+            | "meta"
+            | "param"
+            | "source"
+            | "track"
+            | "wbr"
+    )
+}
+
+// the text of these elements is serialized without escaping
+fn is_raw_text_parent(name: &str) -> bool {
+    matches!(
+        name,
+        "style" | "script" | "xmp" | "iframe" | "noembed" | "noframes" | "plaintext"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::renderer::html::helper::api::get_element_by_id;
+    use crate::renderer::html::helper::test_util::parse;
+
+    #[test]
+    fn test_serialize_document() {
+        let document =
+            parse("<!doctype html><html><head><title>t</title></head><body><p>a<br>b</p><!--c--></body></html>");
+        assert_eq!(
+            "<!DOCTYPE html><html><head><title>t</title></head><body><p>a<br>b</p><!--c--></body></html>",
+            serialize_children(&document)
+        );
+    }
+
+    #[test]
+    fn test_implied_elements() {
+        let document = parse("<!doctype html><p>one<p>two<table><tr><td>x</table>");
+        assert_eq!(
+            "<!DOCTYPE html><html><head></head><body><p>one</p><p>two</p><table><tbody><tr><td>x</td></tr></tbody></table></body></html>",
+            serialize_children(&document)
+        );
+    }
+
+    #[test]
+    fn test_escaping() {
+        let document = parse(
+            "<div id=\"target\" title='a \"b\" &amp; <c>'>1 &lt; 2 &amp;&nbsp;3</div><script>if (a < b && c) {}</script>",
+        );
+        let target =
+            get_element_by_id(Some(document.clone()), &"target".to_string()).expect("no target");
+        assert_eq!(
+            "<div id=\"target\" title=\"a &quot;b&quot; &amp; &lt;c&gt;\">1 &lt; 2 &amp;&nbsp;3</div>",
+            serialize_node(&target)
+        );
+        assert_eq!(
+            "<html><head></head><body><div id=\"target\" title=\"a &quot;b&quot; &amp; &lt;c&gt;\">1 &lt; 2 &amp;&nbsp;3</div><script>if (a < b && c) {}</script></body></html>",
+            serialize_children(&document)
+        );
+    }
+
+    #[test]
+    fn test_inner_and_outer_html() {
+        let document = parse("<div id=\"target\"><img src=\"a.png\"><span>text</span></div>");
+        let target =
+            get_element_by_id(Some(document.clone()), &"target".to_string()).expect("no target");
+        assert_eq!(
+            "<img src=\"a.png\"><span>text</span>",
+            serialize_children(&target)
+        );
+        assert_eq!(
+            "<div id=\"target\"><img src=\"a.png\"><span>text</span></div>",
+            serialize_node(&target)
+        );
+
+        let img = target.borrow().first_child().expect("no img");
+        assert_eq!("", serialize_children(&img));
+    }
+}
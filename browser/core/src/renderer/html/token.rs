@@ -1,5 +1,8 @@
 use crate::renderer::html::helper::attribute::Attribute;
+use crate::renderer::html::helper::entity::find_named_character_reference;
+use alloc::collections::VecDeque;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -12,6 +15,14 @@ pub enum HtmlToken {
     EndTag {
         tag: String,
     },
+    // https://html.spec.whatwg.org/multipage/parsing.html#tokenization
+    Doctype {
+        name: Option<String>,
+        public_id: Option<String>,
+        system_id: Option<String>,
+        force_quirks: bool,
+    },
+    Comment(String),
     Char(char),
     Eof,
 }
@@ -24,6 +35,17 @@ pub struct HtmlTokenizer {
     latest_token: Option<HtmlToken>,
     input: Vec<char>,
     buf: String,
+    // CDATA sections are only allowed in foreign content (SVG and MathML),
+    // elsewhere `<![CDATA[` is tokenized as a bogus comment
+    cdata_allowed: bool,
+    // https://html.spec.whatwg.org/multipage/parsing.html#return-state
+    return_state: State,
+    // https://html.spec.whatwg.org/multipage/parsing.html#character-reference-code
+    character_reference_code: u32,
+    // characters waiting to be emitted, when more than one is decoded at once
+    pending_chars: VecDeque<char>,
+    // https://html.spec.whatwg.org/multipage/parsing.html#appropriate-end-tag-token
+    last_start_tag: Option<String>,
 }
 
 impl HtmlTokenizer {
@@ -35,9 +57,58 @@ impl HtmlTokenizer {
             latest_token: None,
             input: html.chars().collect(),
             buf: String::new(),
+            cdata_allowed: false,
+            return_state: State::Data,
+            character_reference_code: 0,
+            pending_chars: VecDeque::new(),
+            last_start_tag: None,
+        }
+    }
+
+    // the tree builder tells whether the adjusted current node is in foreign content
+    pub fn set_cdata_allowed(&mut self, cdata_allowed: bool) {
+        self.cdata_allowed = cdata_allowed;
+    }
+
+    // the tree builder switches to the RCDATA, RAWTEXT or script data state
+    // for the text of <title>, <textarea>, <style> and <script>
+    pub fn switch_to(&mut self, state: State) {
+        self.state = state;
+    }
+
+    // the state of the text which the less-than sign and end tag states go back to
+    fn text_state(&self) -> State {
+        match self.state {
+            State::Rcdata
+            | State::RcdataLessThanSign
+            | State::RcdataEndTagOpen
+            | State::RcdataEndTagName => State::Rcdata,
+            State::Rawtext
+            | State::RawtextLessThanSign
+            | State::RawtextEndTagOpen
+            | State::RawtextEndTagName => State::Rawtext,
+            _ => State::ScriptData,
         }
     }
 
+    // https://html.spec.whatwg.org/multipage/parsing.html#appropriate-end-tag-token
+    fn is_appropriate_end_tag(&self) -> bool {
+        match (&self.latest_token, &self.last_start_tag) {
+            (Some(HtmlToken::EndTag { tag }), Some(last)) => tag == last,
+            _ => false,
+        }
+    }
+
+    // the "</" and the tag name which turned out not to be an end tag are emitted as text
+    fn emit_end_tag_as_text(&mut self) -> Option<HtmlToken> {
+        self.latest_token = None;
+        self.pending_chars.push_back('/');
+        let buf = core::mem::take(&mut self.buf);
+        self.pending_chars.extend(buf.chars());
+        self.state = self.text_state();
+        Some(HtmlToken::Char('<'))
+    }
+
     // looks at the next character of input at self.pos
     fn consume_next_input(&mut self) -> char {
         let c = self.input[self.pos];
@@ -51,6 +122,31 @@ impl HtmlTokenizer {
         self.input[self.pos - 1]
     }
 
+    // checks if the input from the character just consumed matches the word,
+    // and if so, consumes the whole word
+    fn consume_if_match(&mut self, word: &str, ascii_case_insensitive: bool) -> bool {
+        let start = self.pos - 1;
+        let len = word.chars().count();
+        if start + len > self.input.len() {
+            return false;
+        }
+
+        for (i, w) in word.chars().enumerate() {
+            let c = self.input[start + i];
+            let matched = if ascii_case_insensitive {
+                c.eq_ignore_ascii_case(&w)
+            } else {
+                c == w
+            };
+            if !matched {
+                return false;
+            }
+        }
+
+        self.pos = start + len;
+        true
+    }
+
     // sets a HTML token to self.latest_token
     fn create_tag(&mut self, start_tag_token: bool) {
         if start_tag_token {
@@ -81,6 +177,224 @@ impl HtmlTokenizer {
         }
     }
 
+    fn create_comment(&mut self) {
+        self.latest_token = Some(HtmlToken::Comment(String::new()));
+    }
+
+    fn append_comment(&mut self, s: &str) {
+        assert!(self.latest_token.is_some());
+
+        if let Some(t) = self.latest_token.as_mut() {
+            match t {
+                HtmlToken::Comment(ref mut data) => data.push_str(s),
+                _ => panic!("should be Comment for appending comment"),
+            }
+        }
+    }
+
+    fn create_doctype(&mut self) {
+        self.latest_token = Some(HtmlToken::Doctype {
+            name: None,
+            public_id: None,
+            system_id: None,
+            force_quirks: false,
+        });
+    }
+
+    // appends character to the name, public id or system id of a doctype token,
+    // which is set to empty string first if missing
+    fn append_doctype(&mut self, c: Option<char>, field: DoctypeField) {
+        assert!(self.latest_token.is_some());
+
+        if let Some(t) = self.latest_token.as_mut() {
+            match t {
+                HtmlToken::Doctype {
+                    ref mut name,
+                    ref mut public_id,
+                    ref mut system_id,
+                    force_quirks: _,
+                } => {
+                    let target = match field {
+                        DoctypeField::Name => name,
+                        DoctypeField::PublicId => public_id,
+                        DoctypeField::SystemId => system_id,
+                    };
+                    let value = target.get_or_insert_with(String::new);
+                    if let Some(c) = c {
+                        value.push(c);
+                    }
+                }
+                _ => panic!("should be Doctype for appending doctype"),
+            }
+        }
+    }
+
+    fn set_force_quirks_flag(&mut self) {
+        assert!(self.latest_token.is_some());
+
+        if let Some(t) = self.latest_token.as_mut() {
+            match t {
+                HtmlToken::Doctype {
+                    name: _,
+                    public_id: _,
+                    system_id: _,
+                    ref mut force_quirks,
+                } => *force_quirks = true,
+                _ => panic!("should be Doctype for setting force-quirks flag"),
+            }
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#character-reference-state
+    fn start_character_reference(&mut self) {
+        self.return_state = self.state.clone();
+        self.buf = String::from("&");
+        self.state = State::CharacterReference;
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#charref-in-attribute
+    fn is_consumed_as_part_of_attribute(&self) -> bool {
+        matches!(
+            self.return_state,
+            State::AttributeValueDoubleQuoted
+                | State::AttributeValueSingleQuoted
+                | State::AttributeValueUnquoted
+        )
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#flush-code-points-consumed-as-a-character-reference
+    fn flush_code_points_consumed_as_character_reference(&mut self) {
+        let buf = core::mem::take(&mut self.buf);
+
+        if self.is_consumed_as_part_of_attribute() {
+            for c in buf.chars() {
+                self.append_attribute(c, false);
+            }
+        } else {
+            self.pending_chars.extend(buf.chars());
+        }
+    }
+
+    // flushes the temporary buffer, and goes back to the state before the character reference
+    fn finish_character_reference(&mut self, reconsume: bool) -> Option<HtmlToken> {
+        self.flush_code_points_consumed_as_character_reference();
+        self.reconsume = reconsume;
+        self.state = self.return_state.clone();
+        self.take_pending_char()
+    }
+
+    // https://html.spec.whatwg.org/multipage/parsing.html#numeric-character-reference-end-state
+    fn decode_numeric_character_reference(&mut self) {
+        let code = match self.character_reference_code {
+            // null, out of range and surrogates are parse errors, and are replaced
+            0 | 0x110000.. | 0xD800..=0xDFFF => 0xFFFD,
+            // C1 controls are mapped to windows-1252 for historical reasons
+            0x80 => 0x20AC,
+            0x82 => 0x201A,
+            0x83 => 0x0192,
+            0x84 => 0x201E,
+            0x85 => 0x2026,
+            0x86 => 0x2020,
+            0x87 => 0x2021,
+            0x88 => 0x02C6,
+            0x89 => 0x2030,
+            0x8A => 0x0160,
+            0x8B => 0x2039,
+            0x8C => 0x0152,
+            0x8E => 0x017D,
+            0x91 => 0x2018,
+            0x92 => 0x2019,
+            0x93 => 0x201C,
+            0x94 => 0x201D,
+            0x95 => 0x2022,
+            0x96 => 0x2013,
+            0x97 => 0x2014,
+            0x98 => 0x02DC,
+            0x99 => 0x2122,
+            0x9A => 0x0161,
+            0x9B => 0x203A,
+            0x9C => 0x0153,
+            0x9E => 0x017E,
+            0x9F => 0x0178,
+            // noncharacters and other controls are parse errors, but are kept as is
+            code => code,
+        };
+
+        self.buf = char::from_u32(code).unwrap_or('\u{FFFD}').to_string();
+    }
+
+    fn take_pending_char(&mut self) -> Option<HtmlToken> {
+        self.pending_chars.pop_front().map(HtmlToken::Char)
+    }
+
+    // handles the end of input in the middle of a token
+    fn take_token_at_eof(&mut self) -> Option<HtmlToken> {
+        match self.state {
+            State::CharacterReference
+            | State::NumericCharacterReference
+            | State::HexadecimalCharacterReferenceStart
+            | State::DecimalCharacterReferenceStart => self
+                .finish_character_reference(false)
+                .or(Some(HtmlToken::Eof)),
+            State::HexadecimalCharacterReference | State::DecimalCharacterReference => {
+                self.decode_numeric_character_reference();
+                self.finish_character_reference(false)
+                    .or(Some(HtmlToken::Eof))
+            }
+            State::CommentStart
+            | State::CommentStartDash
+            | State::Comment
+            | State::CommentLessThanSign
+            | State::CommentLessThanSignBang
+            | State::CommentLessThanSignBangDash
+            | State::CommentLessThanSignBangDashDash
+            | State::CommentEndDash
+            | State::CommentEnd
+            | State::CommentEndBang
+            | State::BogusComment
+            | State::BogusDoctype => self.take_latest_token(),
+            State::Doctype | State::BeforeDoctypeName => {
+                self.create_doctype();
+                self.set_force_quirks_flag();
+                self.take_latest_token()
+            }
+            State::DoctypeName
+            | State::AfterDoctypeName
+            | State::AfterDoctypePublicKeyword
+            | State::BeforeDoctypePublicIdentifier
+            | State::DoctypePublicIdentifierDoubleQuoted
+            | State::DoctypePublicIdentifierSingleQuoted
+            | State::AfterDoctypePublicIdentifier
+            | State::BetweenDoctypePublicAndSystemIdentifiers
+            | State::AfterDoctypeSystemKeyword
+            | State::BeforeDoctypeSystemIdentifier
+            | State::DoctypeSystemIdentifierDoubleQuoted
+            | State::DoctypeSystemIdentifierSingleQuoted
+            | State::AfterDoctypeSystemIdentifier => {
+                self.set_force_quirks_flag();
+                self.take_latest_token()
+            }
+            // eof-before-tag-name: the '<' is emitted as text
+            State::TagOpen => {
+                self.state = State::Data;
+                Some(HtmlToken::Char('<'))
+            }
+            State::RcdataLessThanSign
+            | State::RawtextLessThanSign
+            | State::ScriptDataLessThanSign => {
+                self.state = self.text_state();
+                Some(HtmlToken::Char('<'))
+            }
+            State::RcdataEndTagOpen
+            | State::RawtextEndTagOpen
+            | State::ScriptDataEndTagOpen
+            | State::RcdataEndTagName
+            | State::RawtextEndTagName
+            | State::ScriptDataEndTagName => self.emit_end_tag_as_text(),
+            _ => Some(HtmlToken::Eof),
+        }
+    }
+
     fn start_new_attribute(&mut self) {
         assert!(self.latest_token.is_some());
 
@@ -93,6 +407,8 @@ impl HtmlTokenizer {
                 } => {
                     attributes.push(Attribute::new());
                 }
+                // end-tag-with-attributes: the attributes are dropped
+                HtmlToken::EndTag { .. } => {}
                 _ => panic!("should be StartTag for starting attributes"),
             }
         }
@@ -113,6 +429,7 @@ impl HtmlTokenizer {
 
                     attributes[len - 1].add_char(c, is_name);
                 }
+                HtmlToken::EndTag { .. } => {}
                 _ => panic!("should be StartTag for appending attribute"),
             }
         }
@@ -128,6 +445,8 @@ impl HtmlTokenizer {
                     ref mut self_closing,
                     attributes: _,
                 } => *self_closing = true,
+                // end-tag-with-trailing-solidus: the flag is ignored
+                HtmlToken::EndTag { .. } => {}
                 _ => panic!("should be StartTag for setting closing flag"),
             }
         }
@@ -137,6 +456,9 @@ impl HtmlTokenizer {
     fn take_latest_token(&mut self) -> Option<HtmlToken> {
         assert!(self.latest_token.is_some());
         let t = self.latest_token.as_ref().cloned();
+        if let Some(HtmlToken::StartTag { tag, .. }) = &t {
+            self.last_start_tag = Some(tag.clone());
+        }
 
         // resets self.latest_token
         self.latest_token = None;
@@ -151,7 +473,7 @@ impl HtmlTokenizer {
 }
 
 // There are 80 states defined in `HTML Living Standard`,
-// but only 17 will be implemented, with addition to `TemporaryBuffer` state
+// but only 64 will be implemented
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum State {
     // https://html.spec.whatwg.org/multipage/parsing.html#data-state
@@ -180,6 +502,100 @@ pub enum State {
     AfterAttributeValueQuoted,
     // https://html.spec.whatwg.org/multipage/parsing.html#self-closing-start-tag-state
     SelfClosingStartTag,
+    // https://html.spec.whatwg.org/multipage/parsing.html#bogus-comment-state
+    BogusComment,
+    // https://html.spec.whatwg.org/multipage/parsing.html#markup-declaration-open-state
+    MarkupDeclarationOpen,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-start-state
+    CommentStart,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-start-dash-state
+    CommentStartDash,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-state
+    Comment,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-less-than-sign-state
+    CommentLessThanSign,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-less-than-sign-bang-state
+    CommentLessThanSignBang,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-less-than-sign-bang-dash-state
+    CommentLessThanSignBangDash,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-less-than-sign-bang-dash-dash-state
+    CommentLessThanSignBangDashDash,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-end-dash-state
+    CommentEndDash,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-end-state
+    CommentEnd,
+    // https://html.spec.whatwg.org/multipage/parsing.html#comment-end-bang-state
+    CommentEndBang,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-state
+    Doctype,
+    // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-name-state
+    BeforeDoctypeName,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-name-state
+    DoctypeName,
+    // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-name-state
+    AfterDoctypeName,
+    // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-public-keyword-state
+    AfterDoctypePublicKeyword,
+    // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-public-identifier-state
+    BeforeDoctypePublicIdentifier,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-public-identifier-(double-quoted)-state
+    DoctypePublicIdentifierDoubleQuoted,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-public-identifier-(single-quoted)-state
+    DoctypePublicIdentifierSingleQuoted,
+    // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-public-identifier-state
+    AfterDoctypePublicIdentifier,
+    // https://html.spec.whatwg.org/multipage/parsing.html#between-doctype-public-and-system-identifiers-state
+    BetweenDoctypePublicAndSystemIdentifiers,
+    // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-system-keyword-state
+    AfterDoctypeSystemKeyword,
+    // https://html.spec.whatwg.org/multipage/parsing.html#before-doctype-system-identifier-state
+    BeforeDoctypeSystemIdentifier,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-system-identifier-(double-quoted)-state
+    DoctypeSystemIdentifierDoubleQuoted,
+    // https://html.spec.whatwg.org/multipage/parsing.html#doctype-system-identifier-(single-quoted)-state
+    DoctypeSystemIdentifierSingleQuoted,
+    // https://html.spec.whatwg.org/multipage/parsing.html#after-doctype-system-identifier-state
+    AfterDoctypeSystemIdentifier,
+    // https://html.spec.whatwg.org/multipage/parsing.html#bogus-doctype-state
+    BogusDoctype,
+    // https://html.spec.whatwg.org/multipage/parsing.html#cdata-section-state
+    CdataSection,
+    // https://html.spec.whatwg.org/multipage/parsing.html#cdata-section-bracket-state
+    CdataSectionBracket,
+    // https://html.spec.whatwg.org/multipage/parsing.html#cdata-section-end-state
+    CdataSectionEnd,
+    // https://html.spec.whatwg.org/multipage/parsing.html#character-reference-state
+    CharacterReference,
+    // https://html.spec.whatwg.org/multipage/parsing.html#named-character-reference-state
+    NamedCharacterReference,
+    // https://html.spec.whatwg.org/multipage/parsing.html#ambiguous-ampersand-state
+    AmbiguousAmpersand,
+    // https://html.spec.whatwg.org/multipage/parsing.html#numeric-character-reference-state
+    NumericCharacterReference,
+    // https://html.spec.whatwg.org/multipage/parsing.html#hexadecimal-character-reference-start-state
+    HexadecimalCharacterReferenceStart,
+    // https://html.spec.whatwg.org/multipage/parsing.html#decimal-character-reference-start-state
+    DecimalCharacterReferenceStart,
+    // https://html.spec.whatwg.org/multipage/parsing.html#hexadecimal-character-reference-state
+    HexadecimalCharacterReference,
+    // https://html.spec.whatwg.org/multipage/parsing.html#decimal-character-reference-state
+    DecimalCharacterReference,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rcdata-state
+    Rcdata,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rcdata-less-than-sign-state
+    RcdataLessThanSign,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rcdata-end-tag-open-state
+    RcdataEndTagOpen,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rcdata-end-tag-name-state
+    RcdataEndTagName,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rawtext-state
+    Rawtext,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rawtext-less-than-sign-state
+    RawtextLessThanSign,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rawtext-end-tag-open-state
+    RawtextEndTagOpen,
+    // https://html.spec.whatwg.org/multipage/parsing.html#rawtext-end-tag-name-state
+    RawtextEndTagName,
     // https://html.spec.whatwg.org/multipage/parsing.html#script-data-state
     ScriptData,
     // https://html.spec.whatwg.org/multipage/parsing.html#script-data-less-than-sign-state
@@ -188,27 +604,52 @@ pub enum State {
     ScriptDataEndTagOpen,
     // https://html.spec.whatwg.org/multipage/parsing.html#script-data-end-tag-name-state
     ScriptDataEndTagName,
-    // https://html.spec.whatwg.org/multipage/parsing.html#temporary-buffer
-    TemporaryBuffer,
+}
+
+// fields of a doctype token that are filled while tokenizing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum DoctypeField {
+    Name,
+    PublicId,
+    SystemId,
+}
+
+// https://infra.spec.whatwg.org/#ascii-whitespace
+fn is_whitespace(c: char) -> bool {
+    c == '\t' || c == '\n' || c == '\x0C' || c == ' '
 }
 
 impl Iterator for HtmlTokenizer {
     type Item = HtmlToken;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.pos >= self.input.len() {
+        if let Some(t) = self.take_pending_char() {
+            return Some(t);
+        }
+
+        if self.pos >= self.input.len() && !self.reconsume {
             return None;
         }
 
         loop {
             let c = match self.reconsume {
                 true => self.reconsume_input(),
-                false => self.consume_next_input(),
+                false => {
+                    if self.pos >= self.input.len() {
+                        return self.take_token_at_eof();
+                    }
+                    self.consume_next_input()
+                }
             };
 
             match self.state {
                 // Data state, is the initial state
                 State::Data => {
+                    if c == '&' {
+                        self.start_character_reference();
+                        continue;
+                    }
+
                     if c == '<' {
                         self.state = State::TagOpen;
                         continue;
@@ -222,11 +663,23 @@ impl Iterator for HtmlTokenizer {
                 }
 
                 State::TagOpen => {
+                    if c == '!' {
+                        self.state = State::MarkupDeclarationOpen;
+                        continue;
+                    }
+
                     if c == '/' {
                         self.state = State::EndTagOpen;
                         continue;
                     }
 
+                    if c == '?' {
+                        self.reconsume = true;
+                        self.state = State::BogusComment;
+                        self.create_comment();
+                        continue;
+                    }
+
                     if c.is_ascii_alphabetic() {
                         self.reconsume = true;
                         self.state = State::TagName;
@@ -238,8 +691,10 @@ impl Iterator for HtmlTokenizer {
                         return Some(HtmlToken::Eof);
                     }
 
+                    // invalid-first-character-of-tag-name: the '<' is emitted as text
                     self.reconsume = true;
                     self.state = State::Data;
+                    return Some(HtmlToken::Char('<'));
                 }
 
                 State::EndTagOpen => {
@@ -253,6 +708,15 @@ impl Iterator for HtmlTokenizer {
                         self.create_tag(false);
                         continue;
                     }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BogusComment;
+                    self.create_comment();
                 }
 
                 State::TagName => {
@@ -369,6 +833,11 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    if c == '&' {
+                        self.start_character_reference();
+                        continue;
+                    }
+
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
@@ -382,6 +851,11 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    if c == '&' {
+                        self.start_character_reference();
+                        continue;
+                    }
+
                     if self.is_eof() {
                         return Some(HtmlToken::Eof);
                     }
@@ -395,6 +869,11 @@ impl Iterator for HtmlTokenizer {
                         continue;
                     }
 
+                    if c == '&' {
+                        self.start_character_reference();
+                        continue;
+                    }
+
                     if c == '>' {
                         self.state = State::Data;
                         return self.take_latest_token();
@@ -443,160 +922,755 @@ impl Iterator for HtmlTokenizer {
                     }
                 }
 
-                State::ScriptData => {
-                    if c == '<' {
-                        self.state = State::ScriptDataLessThanSign;
-                        continue;
+                State::BogusComment => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
                     }
 
-                    if self.is_eof() {
-                        return Some(HtmlToken::Eof);
+                    if c == '\0' {
+                        self.append_comment("\u{FFFD}");
+                        continue;
                     }
 
-                    return Some(HtmlToken::Char(c));
+                    self.append_comment(c.encode_utf8(&mut [0; 4]));
                 }
 
-                State::ScriptDataLessThanSign => {
-                    if c == '/' {
-                        self.buf = String::new();
-                        self.state = State::ScriptDataEndTagOpen;
+                State::MarkupDeclarationOpen => {
+                    if self.consume_if_match("--", false) {
+                        self.state = State::CommentStart;
+                        self.create_comment();
+                        continue;
+                    }
+
+                    if self.consume_if_match("DOCTYPE", true) {
+                        self.state = State::Doctype;
+                        continue;
+                    }
+
+                    if self.consume_if_match("[CDATA[", false) {
+                        if self.cdata_allowed {
+                            self.state = State::CdataSection;
+                        } else {
+                            self.state = State::BogusComment;
+                            self.create_comment();
+                            self.append_comment("[CDATA[");
+                        }
                         continue;
                     }
 
                     self.reconsume = true;
-                    self.state = State::ScriptData;
-                    return Some(HtmlToken::Char('<'));
+                    self.state = State::BogusComment;
+                    self.create_comment();
                 }
 
-                State::ScriptDataEndTagOpen => {
-                    if c.is_ascii_alphabetic() {
-                        self.reconsume = true;
-                        self.state = State::ScriptDataEndTagName;
-                        self.create_tag(false);
+                State::CommentStart => {
+                    if c == '-' {
+                        self.state = State::CommentStartDash;
                         continue;
                     }
 
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
                     self.reconsume = true;
-                    self.state = State::ScriptData;
-                    return Some(HtmlToken::Char('<'));
+                    self.state = State::Comment;
                 }
 
-                State::ScriptDataEndTagName => {
+                State::CommentStartDash => {
+                    if c == '-' {
+                        self.state = State::CommentEnd;
+                        continue;
+                    }
+
                     if c == '>' {
                         self.state = State::Data;
                         return self.take_latest_token();
                     }
 
-                    if c.is_ascii_alphabetic() {
-                        self.buf.push(c);
-                        self.append_tag_name(c.to_ascii_lowercase());
+                    self.append_comment("-");
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+
+                State::Comment => {
+                    if c == '<' {
+                        self.append_comment("<");
+                        self.state = State::CommentLessThanSign;
+                        continue;
+                    }
+
+                    if c == '-' {
+                        self.state = State::CommentEndDash;
+                        continue;
+                    }
+
+                    if c == '\0' {
+                        self.append_comment("\u{FFFD}");
                         continue;
                     }
 
-                    self.state = State::TemporaryBuffer;
-                    self.buf = String::from("</") + &self.buf;
-                    self.buf.push(c);
-                    continue;
+                    self.append_comment(c.encode_utf8(&mut [0; 4]));
                 }
 
-                State::TemporaryBuffer => {
+                State::CommentLessThanSign => {
+                    if c == '!' {
+                        self.append_comment("!");
+                        self.state = State::CommentLessThanSignBang;
+                        continue;
+                    }
+
+                    if c == '<' {
+                        self.append_comment("<");
+                        continue;
+                    }
+
                     self.reconsume = true;
+                    self.state = State::Comment;
+                }
 
-                    if self.buf.chars().count() == 0 {
-                        self.state = State::ScriptData;
+                State::CommentLessThanSignBang => {
+                    if c == '-' {
+                        self.state = State::CommentLessThanSignBangDash;
                         continue;
                     }
 
-                    let c = self
-                        .buf
-                        .chars()
-                        .nth(0)
-                        .expect("self.buf should have at least 1 char");
-                    self.buf.remove(0);
-                    return Some(HtmlToken::Char(c));
+                    self.reconsume = true;
+                    self.state = State::Comment;
                 }
-            }
-        }
-    }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::alloc::string::ToString;
-    use alloc::vec;
+                State::CommentLessThanSignBangDash => {
+                    if c == '-' {
+                        self.state = State::CommentLessThanSignBangDashDash;
+                        continue;
+                    }
 
-    #[test]
-    fn test_empty() {
-        let html = "".to_string();
-        let mut tokenizer = HtmlTokenizer::new(html);
-        assert!(tokenizer.next().is_none());
-    }
+                    self.reconsume = true;
+                    self.state = State::CommentEndDash;
+                }
 
-    #[test]
-    fn test_start_and_end_tag() {
-        let html = "<body></body>".to_string();
-        let mut tokenizer = HtmlTokenizer::new(html);
-        let expected = [
-            HtmlToken::StartTag {
-                tag: "body".to_string(),
-                self_closing: false,
-                attributes: Vec::new(),
-            },
-            HtmlToken::EndTag {
-                tag: "body".to_string(),
-            },
-        ];
-        for e in expected {
-            assert_eq!(Some(e), tokenizer.next());
-        }
-    }
+                State::CommentLessThanSignBangDashDash => {
+                    // nested comment is a parse error, but is handled the same
+                    self.reconsume = true;
+                    self.state = State::CommentEnd;
+                }
 
-    #[test]
-    fn test_attributes() {
-        let html = "<p class=\"A\" id='B' foo=bar></p>".to_string();
-        let mut tokenizer = HtmlTokenizer::new(html);
-        let mut attr1 = Attribute::new();
-        attr1.add_char('c', true);
-        attr1.add_char('l', true);
-        attr1.add_char('a', true);
-        attr1.add_char('s', true);
-        attr1.add_char('s', true);
-        attr1.add_char('A', false);
+                State::CommentEndDash => {
+                    if c == '-' {
+                        self.state = State::CommentEnd;
+                        continue;
+                    }
 
-        let mut attr2 = Attribute::new();
-        attr2.add_char('i', true);
-        attr2.add_char('d', true);
-        attr2.add_char('B', false);
+                    self.append_comment("-");
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
 
-        let mut attr3 = Attribute::new();
-        attr3.add_char('f', true);
-        attr3.add_char('o', true);
-        attr3.add_char('o', true);
-        attr3.add_char('b', false);
-        attr3.add_char('a', false);
-        attr3.add_char('r', false);
+                State::CommentEnd => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
 
-        let expected = [
-            HtmlToken::StartTag {
-                tag: "p".to_string(),
-                self_closing: false,
-                attributes: vec![attr1, attr2, attr3],
-            },
-            HtmlToken::EndTag {
-                tag: "p".to_string(),
-            },
-        ];
-        for e in expected {
-            assert_eq!(Some(e), tokenizer.next());
-        }
-    }
+                    if c == '!' {
+                        self.state = State::CommentEndBang;
+                        continue;
+                    }
 
-    #[test]
-    fn test_self_closing_tag() {
-        let html = "<img />".to_string();
-        let mut tokenizer = HtmlTokenizer::new(html);
-        let expected = [HtmlToken::StartTag {
+                    if c == '-' {
+                        self.append_comment("-");
+                        continue;
+                    }
+
+                    self.append_comment("--");
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+
+                State::CommentEndBang => {
+                    if c == '-' {
+                        self.append_comment("--!");
+                        self.state = State::CommentEndDash;
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    self.append_comment("--!");
+                    self.reconsume = true;
+                    self.state = State::Comment;
+                }
+
+                State::Doctype => {
+                    if is_whitespace(c) {
+                        self.state = State::BeforeDoctypeName;
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BeforeDoctypeName;
+                }
+
+                State::BeforeDoctypeName => {
+                    if is_whitespace(c) {
+                        continue;
+                    }
+
+                    self.create_doctype();
+
+                    if c == '>' {
+                        self.set_force_quirks_flag();
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    let c = if c == '\0' {
+                        '\u{FFFD}'
+                    } else {
+                        c.to_ascii_lowercase()
+                    };
+                    self.append_doctype(Some(c), DoctypeField::Name);
+                    self.state = State::DoctypeName;
+                }
+
+                State::DoctypeName => {
+                    if is_whitespace(c) {
+                        self.state = State::AfterDoctypeName;
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    let c = if c == '\0' {
+                        '\u{FFFD}'
+                    } else {
+                        c.to_ascii_lowercase()
+                    };
+                    self.append_doctype(Some(c), DoctypeField::Name);
+                }
+
+                State::AfterDoctypeName => {
+                    if is_whitespace(c) {
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    if self.consume_if_match("PUBLIC", true) {
+                        self.state = State::AfterDoctypePublicKeyword;
+                        continue;
+                    }
+
+                    if self.consume_if_match("SYSTEM", true) {
+                        self.state = State::AfterDoctypeSystemKeyword;
+                        continue;
+                    }
+
+                    self.set_force_quirks_flag();
+                    self.reconsume = true;
+                    self.state = State::BogusDoctype;
+                }
+
+                State::AfterDoctypePublicKeyword | State::BeforeDoctypePublicIdentifier => {
+                    if is_whitespace(c) {
+                        self.state = State::BeforeDoctypePublicIdentifier;
+                        continue;
+                    }
+
+                    if c == '"' {
+                        self.append_doctype(None, DoctypeField::PublicId);
+                        self.state = State::DoctypePublicIdentifierDoubleQuoted;
+                        continue;
+                    }
+
+                    if c == '\'' {
+                        self.append_doctype(None, DoctypeField::PublicId);
+                        self.state = State::DoctypePublicIdentifierSingleQuoted;
+                        continue;
+                    }
+
+                    self.set_force_quirks_flag();
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BogusDoctype;
+                }
+
+                State::DoctypePublicIdentifierDoubleQuoted
+                | State::DoctypePublicIdentifierSingleQuoted => {
+                    let quote = if self.state == State::DoctypePublicIdentifierDoubleQuoted {
+                        '"'
+                    } else {
+                        '\''
+                    };
+
+                    if c == quote {
+                        self.state = State::AfterDoctypePublicIdentifier;
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.set_force_quirks_flag();
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    let c = if c == '\0' { '\u{FFFD}' } else { c };
+                    self.append_doctype(Some(c), DoctypeField::PublicId);
+                }
+
+                State::AfterDoctypePublicIdentifier
+                | State::BetweenDoctypePublicAndSystemIdentifiers => {
+                    if is_whitespace(c) {
+                        self.state = State::BetweenDoctypePublicAndSystemIdentifiers;
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    if c == '"' {
+                        self.append_doctype(None, DoctypeField::SystemId);
+                        self.state = State::DoctypeSystemIdentifierDoubleQuoted;
+                        continue;
+                    }
+
+                    if c == '\'' {
+                        self.append_doctype(None, DoctypeField::SystemId);
+                        self.state = State::DoctypeSystemIdentifierSingleQuoted;
+                        continue;
+                    }
+
+                    self.set_force_quirks_flag();
+                    self.reconsume = true;
+                    self.state = State::BogusDoctype;
+                }
+
+                State::AfterDoctypeSystemKeyword | State::BeforeDoctypeSystemIdentifier => {
+                    if is_whitespace(c) {
+                        self.state = State::BeforeDoctypeSystemIdentifier;
+                        continue;
+                    }
+
+                    if c == '"' {
+                        self.append_doctype(None, DoctypeField::SystemId);
+                        self.state = State::DoctypeSystemIdentifierDoubleQuoted;
+                        continue;
+                    }
+
+                    if c == '\'' {
+                        self.append_doctype(None, DoctypeField::SystemId);
+                        self.state = State::DoctypeSystemIdentifierSingleQuoted;
+                        continue;
+                    }
+
+                    self.set_force_quirks_flag();
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::BogusDoctype;
+                }
+
+                State::DoctypeSystemIdentifierDoubleQuoted
+                | State::DoctypeSystemIdentifierSingleQuoted => {
+                    let quote = if self.state == State::DoctypeSystemIdentifierDoubleQuoted {
+                        '"'
+                    } else {
+                        '\''
+                    };
+
+                    if c == quote {
+                        self.state = State::AfterDoctypeSystemIdentifier;
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.set_force_quirks_flag();
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    let c = if c == '\0' { '\u{FFFD}' } else { c };
+                    self.append_doctype(Some(c), DoctypeField::SystemId);
+                }
+
+                State::AfterDoctypeSystemIdentifier => {
+                    if is_whitespace(c) {
+                        continue;
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+
+                    // unlike other doctype states, this does not set the force-quirks flag
+                    self.reconsume = true;
+                    self.state = State::BogusDoctype;
+                }
+
+                State::BogusDoctype => {
+                    if c == '>' {
+                        self.state = State::Data;
+                        return self.take_latest_token();
+                    }
+                }
+
+                State::CdataSection => {
+                    if c == ']' {
+                        self.state = State::CdataSectionBracket;
+                        continue;
+                    }
+
+                    return Some(HtmlToken::Char(c));
+                }
+
+                State::CdataSectionBracket => {
+                    if c == ']' {
+                        self.state = State::CdataSectionEnd;
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::CdataSection;
+                    return Some(HtmlToken::Char(']'));
+                }
+
+                State::CdataSectionEnd => {
+                    if c == ']' {
+                        return Some(HtmlToken::Char(']'));
+                    }
+
+                    if c == '>' {
+                        self.state = State::Data;
+                        continue;
+                    }
+
+                    // emits the two brackets, the second one through the bracket state
+                    self.reconsume = true;
+                    self.state = State::CdataSectionBracket;
+                    return Some(HtmlToken::Char(']'));
+                }
+
+                State::CharacterReference => {
+                    if c.is_ascii_alphanumeric() {
+                        self.reconsume = true;
+                        self.state = State::NamedCharacterReference;
+                        continue;
+                    }
+
+                    if c == '#' {
+                        self.buf.push(c);
+                        self.state = State::NumericCharacterReference;
+                        continue;
+                    }
+
+                    if let Some(t) = self.finish_character_reference(true) {
+                        return Some(t);
+                    }
+                }
+
+                State::NamedCharacterReference => {
+                    let start = self.pos - 1;
+                    let (name, value) = match find_named_character_reference(&self.input[start..]) {
+                        Some(reference) => reference,
+                        None => {
+                            self.flush_code_points_consumed_as_character_reference();
+                            self.reconsume = true;
+                            self.state = State::AmbiguousAmpersand;
+                            if let Some(t) = self.take_pending_char() {
+                                return Some(t);
+                            }
+                            continue;
+                        }
+                    };
+
+                    self.pos = start + name.len();
+                    self.buf.push_str(name);
+
+                    // for historical reasons, `&amp=` or `&ampx` in attribute value is kept as is
+                    let next = self.input.get(self.pos).copied();
+                    let kept_as_is = self.is_consumed_as_part_of_attribute()
+                        && !name.ends_with(';')
+                        && matches!(next, Some(n) if n == '=' || n.is_ascii_alphanumeric());
+                    if !kept_as_is {
+                        self.buf = value.to_string();
+                    }
+
+                    if let Some(t) = self.finish_character_reference(false) {
+                        return Some(t);
+                    }
+                }
+
+                State::AmbiguousAmpersand => {
+                    if c.is_ascii_alphanumeric() {
+                        if self.is_consumed_as_part_of_attribute() {
+                            self.append_attribute(c, false);
+                            continue;
+                        }
+                        return Some(HtmlToken::Char(c));
+                    }
+
+                    self.reconsume = true;
+                    self.state = self.return_state.clone();
+                }
+
+                State::NumericCharacterReference => {
+                    self.character_reference_code = 0;
+
+                    if c == 'x' || c == 'X' {
+                        self.buf.push(c);
+                        self.state = State::HexadecimalCharacterReferenceStart;
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = State::DecimalCharacterReferenceStart;
+                }
+
+                State::HexadecimalCharacterReferenceStart => {
+                    if c.is_ascii_hexdigit() {
+                        self.reconsume = true;
+                        self.state = State::HexadecimalCharacterReference;
+                        continue;
+                    }
+
+                    if let Some(t) = self.finish_character_reference(true) {
+                        return Some(t);
+                    }
+                }
+
+                State::DecimalCharacterReferenceStart => {
+                    if c.is_ascii_digit() {
+                        self.reconsume = true;
+                        self.state = State::DecimalCharacterReference;
+                        continue;
+                    }
+
+                    if let Some(t) = self.finish_character_reference(true) {
+                        return Some(t);
+                    }
+                }
+
+                State::HexadecimalCharacterReference | State::DecimalCharacterReference => {
+                    let radix = if self.state == State::HexadecimalCharacterReference {
+                        16
+                    } else {
+                        10
+                    };
+
+                    if let Some(d) = c.to_digit(radix) {
+                        self.character_reference_code = self
+                            .character_reference_code
+                            .saturating_mul(radix)
+                            .saturating_add(d);
+                        continue;
+                    }
+
+                    // missing semicolon is a parse error, but the reference is still decoded
+                    self.decode_numeric_character_reference();
+                    if let Some(t) = self.finish_character_reference(c != ';') {
+                        return Some(t);
+                    }
+                }
+
+                State::Rcdata | State::Rawtext | State::ScriptData => {
+                    if c == '&' && self.state == State::Rcdata {
+                        self.start_character_reference();
+                        continue;
+                    }
+
+                    if c == '<' {
+                        self.state = match self.state {
+                            State::Rcdata => State::RcdataLessThanSign,
+                            State::Rawtext => State::RawtextLessThanSign,
+                            _ => State::ScriptDataLessThanSign,
+                        };
+                        continue;
+                    }
+
+                    if self.is_eof() {
+                        return Some(HtmlToken::Eof);
+                    }
+
+                    // unexpected-null-character
+                    if c == '\0' {
+                        return Some(HtmlToken::Char('\u{FFFD}'));
+                    }
+
+                    return Some(HtmlToken::Char(c));
+                }
+
+                // the escaped script data states for "<!--" in <script> are not implemented
+                State::RcdataLessThanSign
+                | State::RawtextLessThanSign
+                | State::ScriptDataLessThanSign => {
+                    if c == '/' {
+                        self.buf = String::new();
+                        self.state = match self.text_state() {
+                            State::Rcdata => State::RcdataEndTagOpen,
+                            State::Rawtext => State::RawtextEndTagOpen,
+                            _ => State::ScriptDataEndTagOpen,
+                        };
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    self.state = self.text_state();
+                    return Some(HtmlToken::Char('<'));
+                }
+
+                State::RcdataEndTagOpen
+                | State::RawtextEndTagOpen
+                | State::ScriptDataEndTagOpen => {
+                    if c.is_ascii_alphabetic() {
+                        self.reconsume = true;
+                        self.state = match self.text_state() {
+                            State::Rcdata => State::RcdataEndTagName,
+                            State::Rawtext => State::RawtextEndTagName,
+                            _ => State::ScriptDataEndTagName,
+                        };
+                        self.create_tag(false);
+                        continue;
+                    }
+
+                    self.reconsume = true;
+                    return self.emit_end_tag_as_text();
+                }
+
+                State::RcdataEndTagName
+                | State::RawtextEndTagName
+                | State::ScriptDataEndTagName => {
+                    if c.is_ascii_alphabetic() {
+                        self.buf.push(c);
+                        self.append_tag_name(c.to_ascii_lowercase());
+                        continue;
+                    }
+
+                    // only the end tag of the element which the text is in closes it
+                    if self.is_appropriate_end_tag() {
+                        if matches!(c, '\t' | '\n' | '\x0C' | ' ') {
+                            self.state = State::BeforeAttributeName;
+                            continue;
+                        }
+
+                        if c == '/' {
+                            self.state = State::SelfClosingStartTag;
+                            continue;
+                        }
+
+                        if c == '>' {
+                            self.state = State::Data;
+                            return self.take_latest_token();
+                        }
+                    }
+
+                    self.reconsume = true;
+                    return self.emit_end_tag_as_text();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use alloc::vec;
+
+    #[test]
+    fn test_empty() {
+        let html = "".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert!(tokenizer.next().is_none());
+    }
+
+    #[test]
+    fn test_start_and_end_tag() {
+        let html = "<body></body>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::StartTag {
+                tag: "body".to_string(),
+                self_closing: false,
+                attributes: Vec::new(),
+            },
+            HtmlToken::EndTag {
+                tag: "body".to_string(),
+            },
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let html = "<p class=\"A\" id='B' foo=bar></p>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let mut attr1 = Attribute::new();
+        attr1.add_char('c', true);
+        attr1.add_char('l', true);
+        attr1.add_char('a', true);
+        attr1.add_char('s', true);
+        attr1.add_char('s', true);
+        attr1.add_char('A', false);
+
+        let mut attr2 = Attribute::new();
+        attr2.add_char('i', true);
+        attr2.add_char('d', true);
+        attr2.add_char('B', false);
+
+        let mut attr3 = Attribute::new();
+        attr3.add_char('f', true);
+        attr3.add_char('o', true);
+        attr3.add_char('o', true);
+        attr3.add_char('b', false);
+        attr3.add_char('a', false);
+        attr3.add_char('r', false);
+
+        let expected = [
+            HtmlToken::StartTag {
+                tag: "p".to_string(),
+                self_closing: false,
+                attributes: vec![attr1, attr2, attr3],
+            },
+            HtmlToken::EndTag {
+                tag: "p".to_string(),
+            },
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_self_closing_tag() {
+        let html = "<img />".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [HtmlToken::StartTag {
             tag: "img".to_string(),
             self_closing: true,
             attributes: Vec::new(),
@@ -632,4 +1706,245 @@ mod tests {
             assert_eq!(Some(e), tokenizer.next());
         }
     }
+
+    #[test]
+    fn test_comment() {
+        let html = "<p><!-- a <b> -- c --></p><!---->".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::StartTag {
+                tag: "p".to_string(),
+                self_closing: false,
+                attributes: Vec::new(),
+            },
+            HtmlToken::Comment(" a <b> -- c ".to_string()),
+            HtmlToken::EndTag {
+                tag: "p".to_string(),
+            },
+            HtmlToken::Comment("".to_string()),
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+        assert!(tokenizer.next().is_none());
+    }
+
+    #[test]
+    fn test_unclosed_comment() {
+        let html = "<!-- comment".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(
+            Some(HtmlToken::Comment(" comment".to_string())),
+            tokenizer.next()
+        );
+        assert!(tokenizer.next().is_none());
+    }
+
+    #[test]
+    fn test_bogus_comment() {
+        let html = "<?xml version=\"1.0\"?></>x</ y>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::Comment("?xml version=\"1.0\"?".to_string()),
+            HtmlToken::Char('x'),
+            HtmlToken::Comment(" y".to_string()),
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_doctype() {
+        let html = "<!DOCTYPE html>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = HtmlToken::Doctype {
+            name: Some("html".to_string()),
+            public_id: None,
+            system_id: None,
+            force_quirks: false,
+        };
+        assert_eq!(Some(expected), tokenizer.next());
+        assert!(tokenizer.next().is_none());
+    }
+
+    #[test]
+    fn test_doctype_with_identifiers() {
+        let html = r#"<!doctype HTML PUBLIC "-//W3C//DTD HTML 4.01//EN" 'http://www.w3.org/TR/html4/strict.dtd'>"#
+            .to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = HtmlToken::Doctype {
+            name: Some("html".to_string()),
+            public_id: Some("-//W3C//DTD HTML 4.01//EN".to_string()),
+            system_id: Some("http://www.w3.org/TR/html4/strict.dtd".to_string()),
+            force_quirks: false,
+        };
+        assert_eq!(Some(expected), tokenizer.next());
+    }
+
+    #[test]
+    fn test_bogus_doctype() {
+        let html = "<!DOCTYPE html FOO><!DOCTYPE>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        let expected = [
+            HtmlToken::Doctype {
+                name: Some("html".to_string()),
+                public_id: None,
+                system_id: None,
+                force_quirks: true,
+            },
+            HtmlToken::Doctype {
+                name: None,
+                public_id: None,
+                system_id: None,
+                force_quirks: true,
+            },
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_cdata() {
+        let html = "<![CDATA[a]]b]]>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html.clone());
+        assert_eq!(
+            Some(HtmlToken::Comment("[CDATA[a]]b]]".to_string())),
+            tokenizer.next()
+        );
+
+        let mut tokenizer = HtmlTokenizer::new(html);
+        tokenizer.set_cdata_allowed(true);
+        let expected = [
+            HtmlToken::Char('a'),
+            HtmlToken::Char(']'),
+            HtmlToken::Char(']'),
+            HtmlToken::Char('b'),
+            HtmlToken::Eof,
+        ];
+        for e in expected {
+            assert_eq!(Some(e), tokenizer.next());
+        }
+    }
+
+    #[test]
+    fn test_named_character_reference() {
+        let html = "a&lt;b&amp;c&notit;&copy".to_string();
+        let tokenizer = HtmlTokenizer::new(html);
+        let text: String = tokenizer
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!("a<b&c\u{00AC}it;\u{00A9}", text);
+    }
+
+    #[test]
+    fn test_numeric_character_reference() {
+        let html = "&#65;&#x42;&#X43&#0;&#x110000;&#xD800;&#x80;&#;&#xg".to_string();
+        let tokenizer = HtmlTokenizer::new(html);
+        let text: String = tokenizer
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!("ABC\u{FFFD}\u{FFFD}\u{FFFD}\u{20AC}&#;&#xg", text);
+    }
+
+    #[test]
+    fn test_ambiguous_ampersand() {
+        let html = "&foo; & &".to_string();
+        let tokenizer = HtmlTokenizer::new(html);
+        let text: String = tokenizer
+            .filter_map(|t| match t {
+                HtmlToken::Char(c) => Some(c),
+                _ => None,
+            })
+            .collect();
+        assert_eq!("&foo; & &", text);
+    }
+
+    #[test]
+    fn test_character_reference_in_attribute() {
+        let html = r#"<a title="&lt;&#x41;" href='?a=1&amp;b=2' alt=&copy=x>"#.to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        match tokenizer.next() {
+            Some(HtmlToken::StartTag { attributes, .. }) => {
+                assert_eq!("<A", attributes[0].value());
+                assert_eq!("?a=1&b=2", attributes[1].value());
+                // kept as is for historical reasons
+                assert_eq!("&copy=x", attributes[2].value());
+            }
+            t => panic!("unexpected token {:?}", t),
+        }
+    }
+
+    #[test]
+    fn test_less_than_sign_in_text() {
+        for (html, expected) in [
+            ("1 < 2", "1 < 2"),
+            ("a <", "a <"),
+            ("<1>", "<1>"),
+            ("<<b>", "<"),
+        ] {
+            let tokenizer = HtmlTokenizer::new(html.to_string());
+            let text: String = tokenizer
+                .filter_map(|t| match t {
+                    HtmlToken::Char(c) => Some(c),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(expected, text);
+        }
+    }
+
+    #[test]
+    fn test_text_states() {
+        for (html, state, expected) in [
+            ("<title>a<b>&amp;</b></title>", State::Rcdata, "a<b>&</b>"),
+            ("<style>a<b>&amp;</style >", State::Rawtext, "a<b>&amp;"),
+            (
+                "<script>if (a<b) {}</scrip</script/>",
+                State::ScriptData,
+                "if (a<b) {}</scrip",
+            ),
+            ("<script>a</", State::ScriptData, "a</"),
+            ("<script>a</scr", State::ScriptData, "a</scr"),
+        ] {
+            let mut tokenizer = HtmlTokenizer::new(html.to_string());
+            assert!(matches!(tokenizer.next(), Some(HtmlToken::StartTag { .. })));
+            tokenizer.switch_to(state);
+
+            let mut text = String::new();
+            let mut end_tag = None;
+            for t in tokenizer.by_ref() {
+                match t {
+                    HtmlToken::Char(c) => text.push(c),
+                    HtmlToken::EndTag { tag } => end_tag = Some(tag),
+                    t => panic!("unexpected token {:?}", t),
+                }
+            }
+            assert_eq!(expected, text);
+            if html.ends_with('>') {
+                assert!(end_tag.is_some());
+            } else {
+                assert_eq!(None, end_tag);
+            }
+        }
+    }
+
+    #[test]
+    fn test_end_tag_with_attributes() {
+        let html = "</a b=c/>".to_string();
+        let mut tokenizer = HtmlTokenizer::new(html);
+        assert_eq!(
+            Some(HtmlToken::EndTag {
+                tag: "a".to_string()
+            }),
+            tokenizer.next()
+        );
+    }
 }
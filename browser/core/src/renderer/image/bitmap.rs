@@ -0,0 +1,82 @@
+use crate::error::Error;
+use crate::renderer::image::gif;
+use crate::renderer::image::jpeg;
+use crate::renderer::image::png;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+// images larger than this are rejected, so that a broken header doesn't exhaust the memory
+static MAX_PIXELS: usize = 1 << 24;
+
+// a decoded bitmap, whose pixels are stored as RGBA bytes in row-major order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, pixels: Vec<u8>) -> Self {
+        assert_eq!(width * height * 4, pixels.len());
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    // the RGBA color of the pixel, which is transparent outside of the image
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        if x >= self.width || y >= self.height {
+            return [0, 0, 0, 0];
+        }
+        let i = (y * self.width + x) * 4;
+        [
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        ]
+    }
+}
+
+// https://mimesniff.spec.whatwg.org/#matching-an-image-type-pattern
+// decodes the image, whose format is determined by its signature
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png::decode(data)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        gif::decode(data)
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        jpeg::decode(data)
+    } else {
+        Err(Error::UnexpectedInput(
+            "unsupported image format".to_string(),
+        ))
+    }
+}
+
+// checks the dimensions of the image before allocating its pixels
+pub fn check_size(width: usize, height: usize) -> Result<(), Error> {
+    if width == 0 || height == 0 || width.saturating_mul(height) > MAX_PIXELS {
+        return Err(Error::UnexpectedInput(format!(
+            "unsupported image size {}x{}",
+            width, height
+        )));
+    }
+    Ok(())
+}
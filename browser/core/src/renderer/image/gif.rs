@@ -0,0 +1,339 @@
+use crate::error::Error;
+use crate::renderer::image::bitmap::check_size;
+use crate::renderer::image::bitmap::Image;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+// reads the bytes of the GIF data stream
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        match self.data.get(self.pos..self.pos + count) {
+            Some(b) => {
+                self.pos += count;
+                Ok(b)
+            }
+            None => Err(Error::UnexpectedInput("unexpected end of GIF".to_string())),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    // https://www.w3.org/Graphics/GIF/spec-gif89a.txt (15. Data Sub-blocks)
+    fn sub_blocks(&mut self) -> Result<Vec<u8>, Error> {
+        let mut data = Vec::new();
+        loop {
+            let size = self.u8()? as usize;
+            if size == 0 {
+                return Ok(data);
+            }
+            data.extend_from_slice(self.bytes(size)?);
+        }
+    }
+
+    // the color table of 2^(size+1) entries
+    fn color_table(&mut self, packed: u8) -> Result<Vec<u8>, Error> {
+        let entries = 2usize << (packed & 0x07);
+        Ok(self.bytes(entries * 3)?.to_vec())
+    }
+}
+
+// https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+// decodes the first image of the GIF, which is drawn on the logical screen
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    let mut reader = Reader::new(data);
+    let signature = reader.bytes(6)?;
+    if signature != b"GIF87a" && signature != b"GIF89a" {
+        return Err(Error::UnexpectedInput("invalid GIF signature".to_string()));
+    }
+
+    // https://www.w3.org/Graphics/GIF/spec-gif89a.txt (18. Logical Screen Descriptor)
+    let width = reader.u16()? as usize;
+    let height = reader.u16()? as usize;
+    let packed = reader.u8()?;
+    // the background color and the pixel aspect ratio
+    reader.bytes(2)?;
+    check_size(width, height)?;
+    let global_color_table = if packed & 0x80 != 0 {
+        reader.color_table(packed)?
+    } else {
+        Vec::new()
+    };
+
+    let mut transparent_index = None;
+    loop {
+        match reader.u8()? {
+            // https://www.w3.org/Graphics/GIF/spec-gif89a.txt (23. Graphic Control Extension)
+            0x21 => {
+                let label = reader.u8()?;
+                let block = reader.sub_blocks()?;
+                if label == 0xf9 && block.len() >= 4 && block[0] & 0x01 != 0 {
+                    transparent_index = Some(block[3]);
+                }
+            }
+            // https://www.w3.org/Graphics/GIF/spec-gif89a.txt (20. Image Descriptor)
+            0x2c => {
+                return decode_image(
+                    &mut reader,
+                    width,
+                    height,
+                    &global_color_table,
+                    transparent_index,
+                )
+            }
+            0x3b => return Err(Error::UnexpectedInput("no image in GIF".to_string())),
+            _ => return Err(Error::UnexpectedInput("invalid GIF block".to_string())),
+        }
+    }
+}
+
+fn decode_image(
+    reader: &mut Reader,
+    width: usize,
+    height: usize,
+    global_color_table: &[u8],
+    transparent_index: Option<u8>,
+) -> Result<Image, Error> {
+    let left = reader.u16()? as usize;
+    let top = reader.u16()? as usize;
+    let frame_width = reader.u16()? as usize;
+    let frame_height = reader.u16()? as usize;
+    let packed = reader.u8()?;
+    let local_color_table = if packed & 0x80 != 0 {
+        reader.color_table(packed)?
+    } else {
+        Vec::new()
+    };
+    let color_table = if local_color_table.is_empty() {
+        global_color_table
+    } else {
+        &local_color_table
+    };
+    let is_interlaced = packed & 0x40 != 0;
+
+    let min_code_size = reader.u8()?;
+    let indices = decode_lzw(
+        &reader.sub_blocks()?,
+        min_code_size,
+        frame_width * frame_height,
+    )?;
+
+    // https://www.w3.org/Graphics/GIF/spec-gif89a.txt (Appendix E. Interlaced Images)
+    let rows: Vec<usize> = if is_interlaced {
+        [(0, 8), (4, 8), (2, 4), (1, 2)]
+            .iter()
+            .flat_map(|(start, step)| (*start..frame_height).step_by(*step))
+            .collect()
+    } else {
+        (0..frame_height).collect()
+    };
+
+    // pixels outside of the frame are transparent
+    let mut pixels = vec![0; width * height * 4];
+    for (i, row) in rows.iter().enumerate() {
+        for column in 0..frame_width {
+            let index = match indices.get(i * frame_width + column) {
+                Some(index) => *index,
+                None => continue,
+            };
+            let (x, y) = (left + column, top + row);
+            if x >= width || y >= height || Some(index) == transparent_index {
+                continue;
+            }
+            if let Some(rgb) = color_table.get(index as usize * 3..index as usize * 3 + 3) {
+                let p = (y * width + x) * 4;
+                pixels[p..p + 4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+            }
+        }
+    }
+
+    Ok(Image::new(width, height, pixels))
+}
+
+// https://www.w3.org/Graphics/GIF/spec-gif89a.txt (Appendix F. Variable-Length-Code LZW Compression)
+// decodes the color indices, where codes are packed from the least significant bit
+fn decode_lzw(data: &[u8], min_code_size: u8, count: usize) -> Result<Vec<u8>, Error> {
+    if !(2..=8).contains(&min_code_size) {
+        return Err(Error::UnexpectedInput(
+            "invalid LZW minimum code size".to_string(),
+        ));
+    }
+    let clear_code = 1u16 << min_code_size;
+    let end_code = clear_code + 1;
+
+    // each entry is the previous entry and the last index, and the first index of the string
+    let mut prefixes: Vec<u16> = vec![0; 4096];
+    let mut suffixes: Vec<u8> = vec![0; 4096];
+    let mut firsts: Vec<u8> = vec![0; 4096];
+    for code in 0..clear_code {
+        suffixes[code as usize] = code as u8;
+        firsts[code as usize] = code as u8;
+    }
+
+    let mut code_size = min_code_size + 1;
+    let mut next_code = end_code + 1;
+    let mut previous: Option<u16> = None;
+    let mut output = Vec::with_capacity(count);
+    let mut string = Vec::new();
+
+    let (mut bits, mut bit_count, mut pos) = (0u32, 0u8, 0);
+    while output.len() < count {
+        while bit_count < code_size {
+            let byte = match data.get(pos) {
+                Some(b) => *b,
+                // a truncated image keeps the decoded pixels
+                None => return Ok(output),
+            };
+            bits |= (byte as u32) << bit_count;
+            bit_count += 8;
+            pos += 1;
+        }
+        let code = (bits & ((1 << code_size) - 1)) as u16;
+        bits >>= code_size;
+        bit_count -= code_size;
+
+        if code == clear_code {
+            code_size = min_code_size + 1;
+            next_code = end_code + 1;
+            previous = None;
+            continue;
+        }
+        if code == end_code {
+            break;
+        }
+
+        let previous_code = match previous {
+            Some(p) => p,
+            None => {
+                if code >= clear_code {
+                    return Err(Error::UnexpectedInput("invalid LZW code".to_string()));
+                }
+                output.push(code as u8);
+                previous = Some(code);
+                continue;
+            }
+        };
+
+        // the code which is not in the table yet is the previous string and its first index
+        let first = match code.cmp(&next_code) {
+            Ordering::Less => firsts[code as usize],
+            Ordering::Equal => firsts[previous_code as usize],
+            Ordering::Greater => {
+                return Err(Error::UnexpectedInput("invalid LZW code".to_string()))
+            }
+        };
+        if next_code < 4096 {
+            prefixes[next_code as usize] = previous_code;
+            suffixes[next_code as usize] = first;
+            firsts[next_code as usize] = firsts[previous_code as usize];
+            next_code += 1;
+            if next_code == 1 << code_size && code_size < 12 {
+                code_size += 1;
+            }
+        }
+
+        // the string is built from its last index
+        string.clear();
+        let mut current = code;
+        while current > end_code {
+            string.push(suffixes[current as usize]);
+            current = prefixes[current as usize];
+        }
+        string.push(current as u8);
+        output.extend(string.iter().rev());
+
+        previous = Some(code);
+    }
+
+    output.truncate(count);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transparent_gif() {
+        let data = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x04, 0x00, 0x03, 0x00, 0x81, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x21, 0xf9, 0x04,
+            0x01, 0x00, 0x00, 0x00, 0x00, 0x21, 0xfe, 0x03, 0x68, 0x69, 0x21, 0x00, 0x2c, 0x00,
+            0x00, 0x00, 0x00, 0x04, 0x00, 0x03, 0x00, 0x00, 0x02, 0x06, 0x44, 0x34, 0xa1, 0x31,
+            0x12, 0x50, 0x00, 0x3b,
+        ];
+        let image = decode(&data).expect("failed to decode");
+        assert_eq!((4, 3), (image.width(), image.height()));
+        // the index 0 is transparent
+        assert_eq!([0, 0, 0, 0], image.pixel(0, 0));
+        assert_eq!([255, 0, 0, 255], image.pixel(1, 0));
+        assert_eq!([0, 0, 255, 255], image.pixel(3, 0));
+        assert_eq!([255, 0, 0, 255], image.pixel(3, 1));
+        assert_eq!([0, 255, 0, 255], image.pixel(1, 2));
+    }
+
+    #[test]
+    fn test_interlaced_gif() {
+        let data = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x05, 0x00, 0x81, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x21, 0xfe, 0x03,
+            0x68, 0x69, 0x21, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x05, 0x00, 0x40,
+            0x02, 0x03, 0x04, 0x14, 0x53, 0x00, 0x3b,
+        ];
+        let image = decode(&data).expect("failed to decode");
+        // the row y has the index y % 4
+        let colors = [
+            [0, 0, 0, 255],
+            [255, 0, 0, 255],
+            [0, 255, 0, 255],
+            [0, 0, 255, 255],
+            [0, 0, 0, 255],
+        ];
+        for (y, color) in colors.iter().enumerate() {
+            assert_eq!(*color, image.pixel(0, y));
+        }
+    }
+
+    #[test]
+    fn test_code_size_growth() {
+        let data = [
+            0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x14, 0x00, 0x0a, 0x00, 0x81, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x00, 0x00, 0x00, 0xff, 0x21, 0xfe, 0x03,
+            0x68, 0x69, 0x21, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00, 0x14, 0x00, 0x0a, 0x00, 0x00,
+            0x02, 0x2c, 0x44, 0x64, 0x93, 0x20, 0xda, 0x37, 0x8e, 0x41, 0x41, 0x55, 0xe6, 0xe2,
+            0xa3, 0xd4, 0x66, 0x0c, 0x49, 0xa2, 0x87, 0x81, 0xdb, 0xb4, 0x5c, 0x8d, 0xa9, 0xa1,
+            0x97, 0x9a, 0x85, 0x5c, 0xfa, 0x6d, 0xed, 0xb8, 0xac, 0xb6, 0xd8, 0xe5, 0xf1, 0x89,
+            0x83, 0xb1, 0x0e, 0x05, 0x00, 0x3b,
+        ];
+        let image = decode(&data).expect("failed to decode");
+        let palette = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+        for i in 0..200 {
+            let [r, g, b] = palette[(i * 7 / 5) % 4];
+            assert_eq!([r, g, b, 255], image.pixel(i % 20, i / 20));
+        }
+    }
+
+    #[test]
+    fn test_invalid_gif() {
+        assert!(decode(b"GIF89a").is_err());
+        assert!(decode(b"GIF89a\x01\x00\x01\x00\x00\x00\x00\x3b").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use crate::error::Error;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.5
+static LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+static LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+static DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+static DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+// the order of the code lengths of the code length alphabet
+static CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+// reads bits from the least significant bit of each byte
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bit: 0,
+        }
+    }
+
+    fn read_bit(&mut self) -> Result<u32, Error> {
+        let byte = match self.data.get(self.pos) {
+            Some(b) => *b,
+            None => return Err(unexpected_end()),
+        };
+        let bit = (byte >> self.bit) & 1;
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+        Ok(bit as u32)
+    }
+
+    fn read_bits(&mut self, count: u8) -> Result<u32, Error> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.read_bit()? << i;
+        }
+        Ok(value)
+    }
+
+    // skips the remaining bits of the current byte
+    fn align_to_byte(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.2
+// a canonical Huffman code, which is decoded from the number of codes of each length
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for length in lengths {
+            counts[*length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0; 16];
+        for i in 1..15 {
+            offsets[i + 1] = offsets[i] + counts[i];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in lengths.iter().enumerate() {
+            if *length != 0 {
+                symbols[offsets[*length as usize] as usize] = symbol as u16;
+                offsets[*length as usize] += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    // codes are read from the most significant bit, one bit at a time
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, Error> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for length in 1..16 {
+            code |= reader.read_bit()? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(Error::UnexpectedInput("invalid Huffman code".to_string()))
+    }
+}
+
+fn unexpected_end() -> Error {
+    Error::UnexpectedInput("unexpected end of compressed data".to_string())
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951
+// decompresses raw DEFLATE data
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut reader = BitReader::new(data);
+    let mut output = Vec::new();
+
+    loop {
+        let is_final = reader.read_bit()? == 1;
+        match reader.read_bits(2)? {
+            0 => inflate_stored(&mut reader, &mut output)?,
+            1 => {
+                // https://www.rfc-editor.org/rfc/rfc1951#section-3.2.6
+                let mut lengths = [0u8; 288];
+                lengths[..144].fill(8);
+                lengths[144..256].fill(9);
+                lengths[256..280].fill(7);
+                lengths[280..].fill(8);
+                let literals = Huffman::new(&lengths);
+                let distances = Huffman::new(&[5; 30]);
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            2 => {
+                let (literals, distances) = read_dynamic_codes(&mut reader)?;
+                inflate_block(&mut reader, &mut output, &literals, &distances)?;
+            }
+            _ => {
+                return Err(Error::UnexpectedInput(
+                    "invalid DEFLATE block type".to_string(),
+                ))
+            }
+        }
+        if is_final {
+            return Ok(output);
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.4
+fn inflate_stored(reader: &mut BitReader, output: &mut Vec<u8>) -> Result<(), Error> {
+    reader.align_to_byte();
+    let header = match reader.data.get(reader.pos..reader.pos + 4) {
+        Some(h) => h,
+        None => return Err(unexpected_end()),
+    };
+    let length = u16::from_le_bytes([header[0], header[1]]);
+    let complement = u16::from_le_bytes([header[2], header[3]]);
+    if length != !complement {
+        return Err(Error::UnexpectedInput(
+            "invalid length of a stored block".to_string(),
+        ));
+    }
+    let start = reader.pos + 4;
+    match reader.data.get(start..start + length as usize) {
+        Some(bytes) => output.extend_from_slice(bytes),
+        None => return Err(unexpected_end()),
+    }
+    reader.pos = start + length as usize;
+    Ok(())
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.7
+fn read_dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), Error> {
+    let literal_count = reader.read_bits(5)? as usize + 257;
+    let distance_count = reader.read_bits(5)? as usize + 1;
+    let code_length_count = reader.read_bits(4)? as usize + 4;
+
+    let mut code_length_lengths = [0u8; 19];
+    for i in CODE_LENGTH_ORDER.iter().take(code_length_count) {
+        code_length_lengths[*i] = reader.read_bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&code_length_lengths);
+
+    let mut lengths = Vec::new();
+    while lengths.len() < literal_count + distance_count {
+        let symbol = code_lengths.decode(reader)?;
+        let (length, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => match lengths.last() {
+                Some(previous) => (*previous, 3 + reader.read_bits(2)?),
+                None => {
+                    return Err(Error::UnexpectedInput(
+                        "no code length to repeat".to_string(),
+                    ))
+                }
+            },
+            17 => (0, 3 + reader.read_bits(3)?),
+            _ => (0, 11 + reader.read_bits(7)?),
+        };
+        for _ in 0..repeat {
+            lengths.push(length);
+        }
+    }
+    if lengths.len() > literal_count + distance_count {
+        return Err(Error::UnexpectedInput("too many code lengths".to_string()));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literal_count]),
+        Huffman::new(&lengths[literal_count..]),
+    ))
+}
+
+// https://www.rfc-editor.org/rfc/rfc1951#section-3.2.5
+fn inflate_block(
+    reader: &mut BitReader,
+    output: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+) -> Result<(), Error> {
+    loop {
+        let symbol = literals.decode(reader)? as usize;
+        match symbol {
+            0..=255 => output.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let index = symbol - 257;
+                if index >= LENGTH_BASES.len() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid length symbol {}",
+                        symbol
+                    )));
+                }
+                let length = LENGTH_BASES[index] as usize
+                    + reader.read_bits(LENGTH_EXTRA_BITS[index])? as usize;
+
+                let index = distances.decode(reader)? as usize;
+                if index >= DISTANCE_BASES.len() {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid distance symbol {}",
+                        index
+                    )));
+                }
+                let distance = DISTANCE_BASES[index] as usize
+                    + reader.read_bits(DISTANCE_EXTRA_BITS[index])? as usize;
+                if distance > output.len() {
+                    return Err(Error::UnexpectedInput(
+                        "distance is too far back".to_string(),
+                    ));
+                }
+
+                // the copy can overlap the bytes which are being written
+                let start = output.len() - distance;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            }
+        }
+    }
+}
+
+// https://www.rfc-editor.org/rfc/rfc1950
+// decompresses zlib data, whose checksum is verified
+pub fn zlib_decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    if data.len() < 6 {
+        return Err(unexpected_end());
+    }
+    let (cmf, flg) = (data[0], data[1]);
+    if cmf & 0x0f != 8 || ((cmf as u16) << 8 | flg as u16) % 31 != 0 {
+        return Err(Error::UnexpectedInput("invalid zlib header".to_string()));
+    }
+    if flg & 0x20 != 0 {
+        return Err(Error::UnexpectedInput(
+            "preset dictionaries are not supported".to_string(),
+        ));
+    }
+
+    let output = inflate(&data[2..])?;
+
+    let checksum = &data[data.len() - 4..];
+    if adler32(&output).to_be_bytes() != checksum {
+        return Err(Error::UnexpectedInput(
+            "invalid Adler-32 checksum".to_string(),
+        ));
+    }
+    Ok(output)
+}
+
+// https://www.rfc-editor.org/rfc/rfc1950#section-8.2
+pub fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+
+    #[test]
+    fn test_fixed_huffman() {
+        let data = [
+            0x78, 0xda, 0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0x57, 0xc8, 0x40, 0x22, 0xcb, 0xf3, 0x8b,
+            0x72, 0x52, 0x00, 0x68, 0x7d, 0x08, 0xc5,
+        ];
+        assert_eq!(
+            Ok(b"hello hello hello world".to_vec()),
+            zlib_decompress(&data)
+        );
+    }
+
+    #[test]
+    fn test_dynamic_huffman() {
+        let data = [
+            0x78, 0xda, 0xed, 0xd0, 0xb7, 0x15, 0x80, 0x30, 0x0c, 0x05, 0xc0, 0x9e, 0x29, 0xfe,
+            0x08, 0xe4, 0x7d, 0x08, 0xc6, 0x64, 0x81, 0x91, 0x49, 0xd3, 0xc3, 0x00, 0x34, 0x6a,
+            0x79, 0xea, 0xaf, 0x3a, 0x6e, 0x0d, 0x56, 0xdf, 0x55, 0x03, 0x4a, 0x47, 0xc7, 0x8c,
+            0x86, 0x4e, 0x84, 0xe8, 0xfd, 0xb4, 0x6c, 0xa0, 0xdd, 0x38, 0xf0, 0x0b, 0xc6, 0xe2,
+            0xbe, 0x50, 0x93, 0x0d, 0xf8, 0x43, 0x47, 0x22, 0x1d, 0x8b, 0x74, 0x22, 0xd2, 0xa9,
+            0x48, 0x67, 0x22, 0x9d, 0x8b, 0xb4, 0x0e, 0xea, 0xa0, 0x0e, 0xea, 0xa0, 0x0e, 0xfe,
+            0x6d, 0xf0, 0x01, 0x48, 0x07, 0x8d, 0x8a,
+        ];
+        let mut expected = String::new();
+        for i in 0..40 {
+            expected.push_str(&format!(
+                "the quick brown fox {} jumps over the lazy dog\n",
+                i % 7
+            ));
+        }
+        assert_eq!(Ok(expected.into_bytes()), zlib_decompress(&data));
+    }
+
+    #[test]
+    fn test_stored_and_invalid() {
+        // a stored block of "abc"
+        let data = [0x01, 0x03, 0x00, 0xfc, 0xff, b'a', b'b', b'c'];
+        assert_eq!(Ok(b"abc".to_vec()), inflate(&data));
+
+        assert!(inflate(&[0x01, 0x03, 0x00, 0x00, 0x00]).is_err());
+        assert!(zlib_decompress(&[0x78, 0xda, 0x03, 0x00, 0x00, 0x00, 0x00, 0x02]).is_err());
+    }
+}
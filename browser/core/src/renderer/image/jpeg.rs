@@ -0,0 +1,714 @@
+use crate::error::Error;
+use crate::renderer::image::bitmap::check_size;
+use crate::renderer::image::bitmap::Image;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (Figure A.6)
+// the natural order of the coefficients in the zig-zag sequence
+static ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+// cos(k * pi / 16) for k in 0..=8
+static COS: [f32; 9] = [
+    1.0,
+    0.980_785_3,
+    0.923_879_5,
+    0.831_469_6,
+    0.707_106_77,
+    0.555_570_2,
+    0.382_683_43,
+    0.195_090_32,
+    0.0,
+];
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (Annex C)
+// a Huffman table, which is decoded in the way of the figure F.16
+#[derive(Debug, Clone)]
+struct HuffmanTable {
+    // the smallest and the largest code, and the index of the first value of each length
+    min_codes: [i32; 17],
+    max_codes: [i32; 17],
+    value_pointers: [usize; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    fn new(counts: &[u8], values: Vec<u8>) -> Self {
+        let mut min_codes = [0; 17];
+        let mut max_codes = [-1; 17];
+        let mut value_pointers = [0; 17];
+        let (mut code, mut k) = (0i32, 0usize);
+        for length in 1..=16 {
+            let count = counts[length - 1] as usize;
+            if count > 0 {
+                value_pointers[length] = k;
+                min_codes[length] = code;
+                code += count as i32;
+                k += count;
+                max_codes[length] = code - 1;
+            }
+            code <<= 1;
+        }
+        Self {
+            min_codes,
+            max_codes,
+            value_pointers,
+            values,
+        }
+    }
+
+    fn decode(&self, reader: &mut EntropyReader) -> Result<u8, Error> {
+        let mut code = reader.bit() as i32;
+        for length in 1..=16 {
+            if code <= self.max_codes[length] {
+                let index = self.value_pointers[length] + (code - self.min_codes[length]) as usize;
+                return match self.values.get(index) {
+                    Some(value) => Ok(*value),
+                    None => Err(Error::UnexpectedInput("invalid Huffman table".to_string())),
+                };
+            }
+            code = (code << 1) | reader.bit() as i32;
+        }
+        Err(Error::UnexpectedInput("invalid Huffman code".to_string()))
+    }
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (F.1.2.3)
+// reads the entropy-coded data, where 0xFF is followed by a stuffed 0x00
+struct EntropyReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    bits: u32,
+    count: u8,
+}
+
+impl<'a> EntropyReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            pos: 0,
+            bits: 0,
+            count: 0,
+        }
+    }
+
+    // the missing data after a marker is read as zeros
+    fn bit(&mut self) -> u32 {
+        if self.count == 0 {
+            let mut byte = 0;
+            if let Some(b) = self.data.get(self.pos) {
+                if *b != 0xff {
+                    byte = *b;
+                    self.pos += 1;
+                } else if self.data.get(self.pos + 1) == Some(&0x00) {
+                    byte = 0xff;
+                    self.pos += 2;
+                }
+            }
+            self.bits = byte as u32;
+            self.count = 8;
+        }
+        self.count -= 1;
+        (self.bits >> self.count) & 1
+    }
+
+    fn bits(&mut self, count: u8) -> u32 {
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.bit();
+        }
+        value
+    }
+
+    // https://www.w3.org/Graphics/JPEG/itu-t81.pdf (F.2.2.1)
+    fn receive_extend(&mut self, size: u8) -> i32 {
+        if size == 0 {
+            return 0;
+        }
+        let value = self.bits(size) as i32;
+        if value < 1 << (size - 1) {
+            value - (1 << size) + 1
+        } else {
+            value
+        }
+    }
+
+    // https://www.w3.org/Graphics/JPEG/itu-t81.pdf (F.2.2.5)
+    // skips the remaining bits and the next restart marker
+    fn restart(&mut self) {
+        self.count = 0;
+        while self.pos + 1 < self.data.len() {
+            let is_restart_marker =
+                self.data[self.pos] == 0xff && (0xd0..=0xd7).contains(&self.data[self.pos + 1]);
+            self.pos += 1;
+            if is_restart_marker {
+                self.pos += 1;
+                return;
+            }
+        }
+    }
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.2)
+#[derive(Debug, Clone)]
+struct Component {
+    id: u8,
+    horizontal_sampling: usize,
+    vertical_sampling: usize,
+    quantization_table: usize,
+    // the decoded samples, whose size is a multiple of the MCU
+    plane: Vec<u8>,
+    plane_width: usize,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    max_horizontal_sampling: usize,
+    max_vertical_sampling: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.3)
+struct ScanComponent {
+    index: usize,
+    dc_table: usize,
+    ac_table: usize,
+}
+
+fn segment_error(name: &str) -> Error {
+    Error::UnexpectedInput(format!("invalid {} segment", name))
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf
+// decodes a baseline JPEG, which is JFIF with greyscale or YCbCr components
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    if !data.starts_with(&[0xff, 0xd8]) {
+        return Err(Error::UnexpectedInput("invalid JPEG signature".to_string()));
+    }
+
+    let mut quantization_tables = [[1u16; 64]; 4];
+    let mut dc_tables: [Option<HuffmanTable>; 4] = [None, None, None, None];
+    let mut ac_tables: [Option<HuffmanTable>; 4] = [None, None, None, None];
+    let mut restart_interval = 0;
+    let mut frame: Option<Frame> = None;
+
+    let mut pos = 2;
+    // https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.1.1.2)
+    while pos < data.len() {
+        if data[pos] != 0xff {
+            return Err(Error::UnexpectedInput("invalid JPEG marker".to_string()));
+        }
+        // markers can be preceded by fill bytes
+        while data.get(pos) == Some(&0xff) {
+            pos += 1;
+        }
+        let marker = match data.get(pos) {
+            Some(m) => *m,
+            None => break,
+        };
+        pos += 1;
+
+        match marker {
+            0xd9 => break,
+            0x01 | 0xd0..=0xd8 => continue,
+            _ => {}
+        }
+
+        let length = match data.get(pos..pos + 2) {
+            Some(b) => u16::from_be_bytes([b[0], b[1]]) as usize,
+            None => break,
+        };
+        let segment = match data.get(pos + 2..pos + length) {
+            Some(s) if length >= 2 => s,
+            _ => return Err(Error::UnexpectedInput("unexpected end of JPEG".to_string())),
+        };
+        pos += length;
+
+        match marker {
+            0xdb => read_quantization_tables(segment, &mut quantization_tables)?,
+            0xc4 => read_huffman_tables(segment, &mut dc_tables, &mut ac_tables)?,
+            0xc0 | 0xc1 => frame = Some(read_frame(segment)?),
+            0xc2 | 0xc3 | 0xc5..=0xc7 | 0xc9..=0xcb | 0xcd..=0xcf => {
+                return Err(Error::UnexpectedInput(
+                    "only baseline JPEG is supported".to_string(),
+                ))
+            }
+            0xdd => {
+                if segment.len() < 2 {
+                    return Err(segment_error("DRI"));
+                }
+                restart_interval = u16::from_be_bytes([segment[0], segment[1]]) as usize;
+            }
+            0xda => {
+                let frame = match &mut frame {
+                    Some(f) => f,
+                    None => return Err(Error::UnexpectedInput("no frame header".to_string())),
+                };
+                let scan = read_scan(segment, frame)?;
+
+                // the entropy-coded data ends at the first marker except restart markers
+                let mut end = pos;
+                while end + 1 < data.len() {
+                    if data[end] == 0xff
+                        && data[end + 1] != 0
+                        && !(0xd0..=0xd7).contains(&data[end + 1])
+                    {
+                        break;
+                    }
+                    end += 1;
+                }
+                let mut reader = EntropyReader::new(&data[pos..end.max(pos)]);
+                decode_scan(
+                    &mut reader,
+                    frame,
+                    &scan,
+                    &quantization_tables,
+                    &dc_tables,
+                    &ac_tables,
+                    restart_interval,
+                )?;
+                pos = end;
+            }
+            // application data and comments are ignored
+            _ => {}
+        }
+    }
+
+    match frame {
+        Some(frame) => Ok(convert_colors(&frame)),
+        None => Err(Error::UnexpectedInput("no frame header".to_string())),
+    }
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.4.1)
+fn read_quantization_tables(segment: &[u8], tables: &mut [[u16; 64]; 4]) -> Result<(), Error> {
+    let mut pos = 0;
+    while pos < segment.len() {
+        let precision = segment[pos] >> 4;
+        let id = (segment[pos] & 0x0f) as usize;
+        pos += 1;
+        if id > 3 {
+            return Err(segment_error("DQT"));
+        }
+        for value in tables[id].iter_mut() {
+            *value = if precision == 0 {
+                let v = segment.get(pos).copied().ok_or(segment_error("DQT"))?;
+                pos += 1;
+                v as u16
+            } else {
+                let v = segment.get(pos..pos + 2).ok_or(segment_error("DQT"))?;
+                pos += 2;
+                u16::from_be_bytes([v[0], v[1]])
+            };
+        }
+    }
+    Ok(())
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.4.2)
+fn read_huffman_tables(
+    segment: &[u8],
+    dc_tables: &mut [Option<HuffmanTable>; 4],
+    ac_tables: &mut [Option<HuffmanTable>; 4],
+) -> Result<(), Error> {
+    let mut pos = 0;
+    while pos < segment.len() {
+        let class = segment[pos] >> 4;
+        let id = (segment[pos] & 0x0f) as usize;
+        let counts = segment.get(pos + 1..pos + 17).ok_or(segment_error("DHT"))?;
+        let total: usize = counts.iter().map(|c| *c as usize).sum();
+        let values = segment
+            .get(pos + 17..pos + 17 + total)
+            .ok_or(segment_error("DHT"))?;
+        pos += 17 + total;
+        if id > 3 {
+            return Err(segment_error("DHT"));
+        }
+
+        let table = Some(HuffmanTable::new(counts, values.to_vec()));
+        if class == 0 {
+            dc_tables[id] = table;
+        } else {
+            ac_tables[id] = table;
+        }
+    }
+    Ok(())
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.2)
+fn read_frame(segment: &[u8]) -> Result<Frame, Error> {
+    if segment.len() < 6 || segment[0] != 8 {
+        return Err(segment_error("SOF"));
+    }
+    let height = u16::from_be_bytes([segment[1], segment[2]]) as usize;
+    let width = u16::from_be_bytes([segment[3], segment[4]]) as usize;
+    check_size(width, height)?;
+    let count = segment[5] as usize;
+    if count != 1 && count != 3 {
+        return Err(Error::UnexpectedInput(format!(
+            "unsupported number of JPEG components {}",
+            count
+        )));
+    }
+
+    let mut components = Vec::new();
+    for i in 0..count {
+        let c = segment
+            .get(6 + i * 3..9 + i * 3)
+            .ok_or(segment_error("SOF"))?;
+        let (h, v) = ((c[1] >> 4) as usize, (c[1] & 0x0f) as usize);
+        if !(1..=4).contains(&h) || !(1..=4).contains(&v) || c[2] > 3 {
+            return Err(segment_error("SOF"));
+        }
+        components.push(Component {
+            id: c[0],
+            horizontal_sampling: h,
+            vertical_sampling: v,
+            quantization_table: c[2] as usize,
+            plane: Vec::new(),
+            plane_width: 0,
+        });
+    }
+
+    let max_h = components
+        .iter()
+        .map(|c| c.horizontal_sampling)
+        .max()
+        .unwrap_or(1);
+    let max_v = components
+        .iter()
+        .map(|c| c.vertical_sampling)
+        .max()
+        .unwrap_or(1);
+    // https://www.w3.org/Graphics/JPEG/itu-t81.pdf (A.2.4)
+    let mcus_x = (width + 8 * max_h - 1) / (8 * max_h);
+    let mcus_y = (height + 8 * max_v - 1) / (8 * max_v);
+    for c in components.iter_mut() {
+        c.plane_width = mcus_x * c.horizontal_sampling * 8;
+        c.plane = vec![0; c.plane_width * mcus_y * c.vertical_sampling * 8];
+    }
+
+    Ok(Frame {
+        width,
+        height,
+        components,
+        max_horizontal_sampling: max_h,
+        max_vertical_sampling: max_v,
+        mcus_x,
+        mcus_y,
+    })
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (B.2.3)
+fn read_scan(segment: &[u8], frame: &Frame) -> Result<Vec<ScanComponent>, Error> {
+    let count = segment.first().copied().ok_or(segment_error("SOS"))? as usize;
+    let mut scan = Vec::new();
+    for i in 0..count {
+        let c = segment
+            .get(1 + i * 2..3 + i * 2)
+            .ok_or(segment_error("SOS"))?;
+        let index = frame
+            .components
+            .iter()
+            .position(|component| component.id == c[0])
+            .ok_or(segment_error("SOS"))?;
+        scan.push(ScanComponent {
+            index,
+            dc_table: (c[1] >> 4) as usize & 3,
+            ac_table: (c[1] & 0x0f) as usize & 3,
+        });
+    }
+    if scan.is_empty() {
+        return Err(segment_error("SOS"));
+    }
+    Ok(scan)
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (Annex F)
+fn decode_scan(
+    reader: &mut EntropyReader,
+    frame: &mut Frame,
+    scan: &[ScanComponent],
+    quantization_tables: &[[u16; 64]; 4],
+    dc_tables: &[Option<HuffmanTable>; 4],
+    ac_tables: &[Option<HuffmanTable>; 4],
+    restart_interval: usize,
+) -> Result<(), Error> {
+    // the blocks of each MCU, which is a single block for a non-interleaved scan
+    let mut mcus: Vec<Vec<(usize, usize, usize)>> = Vec::new();
+    if scan.len() == 1 {
+        let c = &frame.components[scan[0].index];
+        let width = (frame.width * c.horizontal_sampling + frame.max_horizontal_sampling - 1)
+            / frame.max_horizontal_sampling;
+        let height = (frame.height * c.vertical_sampling + frame.max_vertical_sampling - 1)
+            / frame.max_vertical_sampling;
+        for y in 0..(height + 7) / 8 {
+            for x in 0..(width + 7) / 8 {
+                mcus.push(vec![(0, x, y)]);
+            }
+        }
+    } else {
+        for mcu_y in 0..frame.mcus_y {
+            for mcu_x in 0..frame.mcus_x {
+                let mut blocks = Vec::new();
+                for (i, s) in scan.iter().enumerate() {
+                    let c = &frame.components[s.index];
+                    for v in 0..c.vertical_sampling {
+                        for h in 0..c.horizontal_sampling {
+                            blocks.push((
+                                i,
+                                mcu_x * c.horizontal_sampling + h,
+                                mcu_y * c.vertical_sampling + v,
+                            ));
+                        }
+                    }
+                }
+                mcus.push(blocks);
+            }
+        }
+    }
+
+    let mut predictions = vec![0i32; scan.len()];
+    for (n, blocks) in mcus.iter().enumerate() {
+        if restart_interval > 0 && n > 0 && n % restart_interval == 0 {
+            reader.restart();
+            predictions.fill(0);
+        }
+
+        for (i, block_x, block_y) in blocks {
+            let s = &scan[*i];
+            let (dc_table, ac_table) = match (&dc_tables[s.dc_table], &ac_tables[s.ac_table]) {
+                (Some(dc), Some(ac)) => (dc, ac),
+                _ => return Err(Error::UnexpectedInput("no Huffman table".to_string())),
+            };
+            let component = &mut frame.components[s.index];
+            let quantization_table = &quantization_tables[component.quantization_table];
+
+            // https://www.w3.org/Graphics/JPEG/itu-t81.pdf (F.2.2)
+            let mut coefficients = [0i32; 64];
+            let size = dc_table.decode(reader)?;
+            // the sizes come from the table as they are, and larger ones can't be read
+            if size > 16 {
+                return Err(Error::UnexpectedInput(
+                    "invalid DC difference size".to_string(),
+                ));
+            }
+            predictions[*i] = predictions[*i].saturating_add(reader.receive_extend(size));
+            coefficients[0] = predictions[*i].saturating_mul(quantization_table[0] as i32);
+            let mut k = 1;
+            while k < 64 {
+                let rs = ac_table.decode(reader)?;
+                let (run, size) = ((rs >> 4) as usize, rs & 0x0f);
+                if size == 0 {
+                    if run != 15 {
+                        break;
+                    }
+                    k += 16;
+                    continue;
+                }
+                k += run;
+                if k > 63 {
+                    break;
+                }
+                coefficients[ZIGZAG[k]] = reader
+                    .receive_extend(size)
+                    .saturating_mul(quantization_table[k] as i32);
+                k += 1;
+            }
+
+            let samples = inverse_dct(&coefficients);
+            for y in 0..8 {
+                let row = (block_y * 8 + y) * component.plane_width + block_x * 8;
+                if let Some(plane_row) = component.plane.get_mut(row..row + 8) {
+                    plane_row.copy_from_slice(&samples[y * 8..y * 8 + 8]);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// https://www.w3.org/Graphics/JPEG/itu-t81.pdf (A.3.3)
+// converts the coefficients to the samples, which are shifted to unsigned values
+fn inverse_dct(coefficients: &[i32; 64]) -> [u8; 64] {
+    // cos((2x + 1) * u * pi / 16), where the coefficient of u = 0 is 1 / sqrt(2)
+    let mut table = [[0f32; 8]; 8];
+    for (x, row) in table.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            let k = ((2 * x + 1) * u) % 32;
+            let cos = match k {
+                0..=8 => COS[k],
+                9..=16 => -COS[16 - k],
+                17..=24 => -COS[k - 16],
+                _ => COS[32 - k],
+            };
+            *value = if u == 0 { cos * COS[4] } else { cos } / 2.0;
+        }
+    }
+
+    // the rows, and then the columns
+    let mut temp = [0f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            temp[v * 8 + x] = (0..8)
+                .map(|u| table[x][u] * coefficients[v * 8 + u] as f32)
+                .sum();
+        }
+    }
+    let mut samples = [0u8; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| table[y][v] * temp[v * 8 + x]).sum();
+            samples[y * 8 + x] = clamp(value + 128.0);
+        }
+    }
+    samples
+}
+
+fn clamp(value: f32) -> u8 {
+    if value <= 0.0 {
+        0
+    } else if value >= 255.0 {
+        255
+    } else {
+        (value + 0.5) as u8
+    }
+}
+
+// https://www.w3.org/Graphics/JPEG/jfif3.pdf (Conversion to and from RGB)
+// upsamples the components, and converts YCbCr to RGB
+fn convert_colors(frame: &Frame) -> Image {
+    let mut pixels = Vec::with_capacity(frame.width * frame.height * 4);
+    for y in 0..frame.height {
+        for x in 0..frame.width {
+            let samples: Vec<f32> = frame
+                .components
+                .iter()
+                .map(|c| {
+                    let sx = x * c.horizontal_sampling / frame.max_horizontal_sampling;
+                    let sy = y * c.vertical_sampling / frame.max_vertical_sampling;
+                    c.plane[sy * c.plane_width + sx] as f32
+                })
+                .collect();
+
+            if samples.len() == 1 {
+                let gray = samples[0] as u8;
+                pixels.extend_from_slice(&[gray, gray, gray, 255]);
+                continue;
+            }
+            let (luma, cb, cr) = (samples[0], samples[1] - 128.0, samples[2] - 128.0);
+            pixels.extend_from_slice(&[
+                clamp(luma + 1.402 * cr),
+                clamp(luma - 0.344_136 * cb - 0.714_136 * cr),
+                clamp(luma + 1.772 * cb),
+                255,
+            ]);
+        }
+    }
+    Image::new(frame.width, frame.height, pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(marker: u8, payload: &[u8]) -> Vec<u8> {
+        let mut segment = vec![0xff, marker];
+        segment.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+        segment.extend_from_slice(payload);
+        segment
+    }
+
+    // builds a JPEG whose blocks have only DC coefficients, where the quantization table is
+    // all ones, the DC table has 4-bit codes for the sizes 0 to 11, and the AC table has only
+    // the end-of-block code "0"
+    fn jpeg(frame: &[u8], scan: &[u8], restart_interval: u16, data: &[u8]) -> Vec<u8> {
+        let mut jpeg = vec![0xff, 0xd8];
+        let mut dqt = vec![0];
+        dqt.extend_from_slice(&[1; 64]);
+        jpeg.extend(segment(0xdb, &dqt));
+        jpeg.extend(segment(0xc0, frame));
+        let mut dc = vec![0x00, 0, 0, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        dc.extend(0..12);
+        jpeg.extend(segment(0xc4, &dc));
+        let ac = [0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        jpeg.extend(segment(0xc4, &ac));
+        if restart_interval > 0 {
+            jpeg.extend(segment(0xdd, &restart_interval.to_be_bytes()));
+        }
+        jpeg.extend(segment(0xda, scan));
+        jpeg.extend_from_slice(data);
+        jpeg.extend_from_slice(&[0xff, 0xd9]);
+        jpeg
+    }
+
+    #[test]
+    fn test_grayscale_with_restart() {
+        // 16x8, the left block is 64 and the right block is 192, separated by RST0
+        let image = decode(&jpeg(
+            &[8, 0, 8, 0, 16, 1, 1, 0x11, 0],
+            &[1, 1, 0x00, 0, 63, 0],
+            1,
+            &[0xa7, 0xfd, 0xff, 0xd0, 0xa8, 0x01],
+        ))
+        .expect("failed to decode");
+        assert_eq!((16, 8), (image.width(), image.height()));
+        assert_eq!([64, 64, 64, 255], image.pixel(0, 0));
+        assert_eq!([64, 64, 64, 255], image.pixel(7, 7));
+        assert_eq!([192, 192, 192, 255], image.pixel(8, 0));
+        assert_eq!([192, 192, 192, 255], image.pixel(15, 7));
+    }
+
+    #[test]
+    fn test_subsampled_color() {
+        // 16x8, Y is 100 on the left and 200 on the right, Cb is 128 and Cr is 160
+        let image = decode(&jpeg(
+            &[8, 0, 8, 0, 16, 3, 1, 0x21, 0, 2, 0x11, 0, 3, 0x11, 0],
+            &[3, 1, 0x00, 2, 0x00, 3, 0x00, 0, 63, 0],
+            0,
+            &[0x81, 0xf5, 0x64, 0x00, 0x4c, 0x01],
+        ))
+        .expect("failed to decode");
+        let close = |a: [u8; 4], b: [u8; 4]| (0..4).all(|i| a[i].abs_diff(b[i]) <= 1);
+        assert!(close([145, 77, 100, 255], image.pixel(0, 0)));
+        assert!(close([245, 177, 200, 255], image.pixel(15, 7)));
+    }
+
+    #[test]
+    fn test_unsupported_jpeg() {
+        // progressive JPEG
+        let mut data = vec![0xff, 0xd8];
+        data.extend(segment(0xc2, &[8, 0, 8, 0, 8, 1, 1, 0x11, 0]));
+        assert!(decode(&data).is_err());
+        // no frame
+        assert!(decode(&[0xff, 0xd8, 0xff, 0xd9]).is_err());
+    }
+
+    #[test]
+    fn test_invalid_dc_size() {
+        let mut data = jpeg(
+            &[8, 0, 8, 0, 8, 1, 1, 0x11, 0],
+            &[1, 1, 0x00, 0, 63, 0],
+            0,
+            &[0x00],
+        );
+        // every DC code decodes to the size 200
+        let values: Vec<u8> = (0..12).collect();
+        let start = data
+            .windows(12)
+            .position(|w| w == values)
+            .expect("no DC table");
+        data[start..start + 12].fill(200);
+        assert!(decode(&data).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+pub mod bitmap;
+pub mod gif;
+pub mod inflate;
+pub mod jpeg;
+pub mod png;
@@ -0,0 +1,406 @@
+use crate::error::Error;
+use crate::renderer::image::bitmap::check_size;
+use crate::renderer::image::bitmap::Image;
+use crate::renderer::image::inflate::zlib_decompress;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// https://www.w3.org/TR/png/#5PNG-file-signature
+static SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// https://www.w3.org/TR/png/#8Interlace
+// the origin and the interval of the pixels of each Adam7 pass
+static ADAM7_PASSES: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+// https://www.w3.org/TR/png/#11IHDR
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: u8,
+    color_type: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color_type {
+            2 => 3,
+            4 => 2,
+            6 => 4,
+            // greyscale and indexed-colour
+            _ => 1,
+        }
+    }
+
+    fn bits_per_pixel(&self) -> usize {
+        self.channels() * self.bit_depth as usize
+    }
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32, Error> {
+    match data.get(pos..pos + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => Err(Error::UnexpectedInput("unexpected end of PNG".to_string())),
+    }
+}
+
+// https://www.w3.org/TR/png/
+pub fn decode(data: &[u8]) -> Result<Image, Error> {
+    if !data.starts_with(&SIGNATURE) {
+        return Err(Error::UnexpectedInput("invalid PNG signature".to_string()));
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut transparency = Vec::new();
+    let mut compressed = Vec::new();
+
+    // https://www.w3.org/TR/png/#5Chunk-layout
+    let mut pos = SIGNATURE.len();
+    loop {
+        let length = read_u32(data, pos)? as usize;
+        let chunk_type = match data.get(pos + 4..pos + 8) {
+            Some(t) => t,
+            None => return Err(Error::UnexpectedInput("unexpected end of PNG".to_string())),
+        };
+        let chunk = match data.get(pos + 8..pos + 8 + length) {
+            Some(c) => c,
+            None => return Err(Error::UnexpectedInput("unexpected end of PNG".to_string())),
+        };
+        // the length, the type, the data and the CRC
+        pos += 12 + length;
+
+        match chunk_type {
+            b"IHDR" => {
+                if length != 13 {
+                    return Err(Error::UnexpectedInput("invalid IHDR chunk".to_string()));
+                }
+                header = Some(Header {
+                    width: read_u32(chunk, 0)? as usize,
+                    height: read_u32(chunk, 4)? as usize,
+                    bit_depth: chunk[8],
+                    color_type: chunk[9],
+                    interlaced: chunk[12] == 1,
+                });
+            }
+            b"PLTE" => palette = chunk.to_vec(),
+            b"tRNS" => transparency = chunk.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(chunk),
+            b"IEND" => break,
+            // ancillary chunks are ignored
+            _ => {}
+        }
+    }
+
+    let header = match header {
+        Some(h) => h,
+        None => return Err(Error::UnexpectedInput("no IHDR chunk".to_string())),
+    };
+    let is_valid_depth = match header.color_type {
+        0 => matches!(header.bit_depth, 1 | 2 | 4 | 8 | 16),
+        3 => matches!(header.bit_depth, 1 | 2 | 4 | 8),
+        2 | 4 | 6 => matches!(header.bit_depth, 8 | 16),
+        _ => false,
+    };
+    check_size(header.width, header.height)?;
+    if !is_valid_depth {
+        return Err(Error::UnexpectedInput(format!(
+            "unsupported PNG: color type {}, bit depth {}",
+            header.color_type, header.bit_depth
+        )));
+    }
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(Error::UnexpectedInput("no PLTE chunk".to_string()));
+    }
+
+    let raw = zlib_decompress(&compressed)?;
+    let mut pixels = vec![0; header.width * header.height * 4];
+
+    let passes = if header.interlaced {
+        ADAM7_PASSES.to_vec()
+    } else {
+        vec![(0, 0, 1, 1)]
+    };
+    let mut offset = 0;
+    for (x0, y0, dx, dy) in passes {
+        if x0 >= header.width || y0 >= header.height {
+            continue;
+        }
+        let pass_width = (header.width - x0 + dx - 1) / dx;
+        let pass_height = (header.height - y0 + dy - 1) / dy;
+        let stride = (pass_width * header.bits_per_pixel() + 7) / 8;
+
+        let rows = unfilter(&raw, &mut offset, &header, stride, pass_height)?;
+        for (row_index, row) in rows.iter().enumerate() {
+            for i in 0..pass_width {
+                let color = pixel_color(&header, row, i, &palette, &transparency);
+                let (x, y) = (x0 + i * dx, y0 + row_index * dy);
+                let p = (y * header.width + x) * 4;
+                pixels[p..p + 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    Ok(Image::new(header.width, header.height, pixels))
+}
+
+// https://www.w3.org/TR/png/#9Filters
+// reverses the filter of each scanline, which starts with the filter type
+fn unfilter(
+    raw: &[u8],
+    offset: &mut usize,
+    header: &Header,
+    stride: usize,
+    height: usize,
+) -> Result<Vec<Vec<u8>>, Error> {
+    // the distance to the corresponding byte of the previous pixel
+    let bpp = (header.bits_per_pixel() / 8).max(1);
+    let mut rows: Vec<Vec<u8>> = Vec::new();
+    let mut previous = vec![0; stride];
+
+    for _ in 0..height {
+        let filter_type = match raw.get(*offset) {
+            Some(t) => *t,
+            None => return Err(Error::UnexpectedInput("PNG data is too short".to_string())),
+        };
+        let mut row = match raw.get(*offset + 1..*offset + 1 + stride) {
+            Some(r) => r.to_vec(),
+            None => return Err(Error::UnexpectedInput("PNG data is too short".to_string())),
+        };
+        *offset += 1 + stride;
+
+        for i in 0..stride {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = previous[i];
+            let c = if i >= bpp { previous[i - bpp] } else { 0 };
+            let predictor = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => {
+                    return Err(Error::UnexpectedInput(format!(
+                        "invalid PNG filter type {}",
+                        filter_type
+                    )))
+                }
+            };
+            row[i] = row[i].wrapping_add(predictor);
+        }
+
+        previous = row.clone();
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+// https://www.w3.org/TR/png/#9Filter-type-4-Paeth
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// the samples of the pixel in the scanline, where 16-bit samples keep all bits
+fn samples(header: &Header, row: &[u8], index: usize) -> Vec<u16> {
+    let depth = header.bit_depth as usize;
+    let channels = header.channels();
+    (0..channels)
+        .map(|channel| {
+            let bit = (index * channels + channel) * depth;
+            match depth {
+                16 => u16::from_be_bytes([row[bit / 8], row[bit / 8 + 1]]),
+                8 => row[bit / 8] as u16,
+                // samples of less than 8 bits are packed from the most significant bit
+                _ => ((row[bit / 8] >> (8 - depth - bit % 8)) & ((1 << depth) - 1)) as u16,
+            }
+        })
+        .collect()
+}
+
+fn pixel_color(
+    header: &Header,
+    row: &[u8],
+    index: usize,
+    palette: &[u8],
+    transparency: &[u8],
+) -> [u8; 4] {
+    let samples = samples(header, row, index);
+    let max = (1u32 << header.bit_depth) - 1;
+    // scales the sample to 8 bits
+    let scale = |sample: u16| (sample as u32 * 255 / max) as u8;
+    // https://www.w3.org/TR/png/#11tRNS
+    let transparent_sample = |i: usize| {
+        transparency
+            .get(i * 2..i * 2 + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+    };
+
+    match header.color_type {
+        0 => {
+            let gray = scale(samples[0]);
+            let alpha = if transparent_sample(0) == Some(samples[0]) {
+                0
+            } else {
+                255
+            };
+            [gray, gray, gray, alpha]
+        }
+        2 => {
+            let is_transparent = transparency.len() >= 6
+                && (0..3).all(|i| transparent_sample(i) == Some(samples[i]));
+            let alpha = if is_transparent { 0 } else { 255 };
+            [
+                scale(samples[0]),
+                scale(samples[1]),
+                scale(samples[2]),
+                alpha,
+            ]
+        }
+        3 => {
+            let i = samples[0] as usize;
+            match palette.get(i * 3..i * 3 + 3) {
+                Some(rgb) => [
+                    rgb[0],
+                    rgb[1],
+                    rgb[2],
+                    transparency.get(i).copied().unwrap_or(255),
+                ],
+                // an index out of the palette is an error, which is shown as transparent
+                None => [0, 0, 0, 0],
+            }
+        }
+        4 => {
+            let gray = scale(samples[0]);
+            [gray, gray, gray, scale(samples[1])]
+        }
+        _ => [
+            scale(samples[0]),
+            scale(samples[1]),
+            scale(samples[2]),
+            scale(samples[3]),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::image::inflate::adler32;
+
+    // wraps the data in a zlib stream of a stored block
+    fn zlib_stored(data: &[u8]) -> Vec<u8> {
+        let length = data.len() as u16;
+        let mut zlib = vec![0x78, 0x01, 0x01];
+        zlib.extend_from_slice(&length.to_le_bytes());
+        zlib.extend_from_slice(&(!length).to_le_bytes());
+        zlib.extend_from_slice(data);
+        zlib.extend_from_slice(&adler32(data).to_be_bytes());
+        zlib
+    }
+
+    // builds a PNG whose image data is stored without compression
+    fn png(
+        width: u32,
+        height: u32,
+        depth: u8,
+        color_type: u8,
+        chunks: &[(&[u8; 4], &[u8])],
+        data: &[u8],
+        interlaced: bool,
+    ) -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+        let mut ihdr = Vec::new();
+        ihdr.extend_from_slice(&width.to_be_bytes());
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[depth, color_type, 0, 0, interlaced as u8]);
+
+        let idat = zlib_stored(data);
+        let mut all_chunks: Vec<(&[u8; 4], &[u8])> = vec![(b"IHDR", &ihdr)];
+        all_chunks.extend_from_slice(chunks);
+        all_chunks.push((b"IDAT", &idat));
+        all_chunks.push((b"IEND", &[]));
+        for (chunk_type, chunk) in all_chunks {
+            png.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            png.extend_from_slice(chunk_type);
+            png.extend_from_slice(chunk);
+            // the CRC is not verified
+            png.extend_from_slice(&[0, 0, 0, 0]);
+        }
+        png
+    }
+
+    #[test]
+    fn test_rgba_with_filters() {
+        // 2x2 RGBA: the first row uses Sub, and the second row uses Paeth
+        let data = [
+            1, 10, 20, 30, 255, 5, 5, 5, 0, //
+            4, 1, 1, 1, 0, 0, 0, 0, 0,
+        ];
+        let image = decode(&png(2, 2, 8, 6, &[], &data, false)).expect("failed to decode");
+        assert_eq!(2, image.width());
+        assert_eq!([10, 20, 30, 255], image.pixel(0, 0));
+        assert_eq!([15, 25, 35, 255], image.pixel(1, 0));
+        assert_eq!([11, 21, 31, 255], image.pixel(0, 1));
+        assert_eq!([15, 25, 35, 255], image.pixel(1, 1));
+    }
+
+    #[test]
+    fn test_indexed_color() {
+        // 1-bit palette indices 0, 1, 1 with a transparent first entry
+        let palette = [255, 0, 0, 0, 0, 255];
+        let image = decode(&png(
+            3,
+            1,
+            1,
+            3,
+            &[(b"PLTE", &palette), (b"tRNS", &[0])],
+            &[0, 0b0110_0000],
+            false,
+        ))
+        .expect("failed to decode");
+        assert_eq!([255, 0, 0, 0], image.pixel(0, 0));
+        assert_eq!([0, 0, 255, 255], image.pixel(1, 0));
+        assert_eq!([0, 0, 255, 255], image.pixel(2, 0));
+    }
+
+    #[test]
+    fn test_interlaced_grayscale() {
+        // 2x2 greyscale, where pass 1 has (0,0), pass 6 has (1,0) and pass 7 has (0,1) and (1,1)
+        let data = [0, 10, 0, 20, 0, 30, 40];
+        let image = decode(&png(2, 2, 8, 0, &[], &data, true)).expect("failed to decode");
+        assert_eq!([10, 10, 10, 255], image.pixel(0, 0));
+        assert_eq!([20, 20, 20, 255], image.pixel(1, 0));
+        assert_eq!([30, 30, 30, 255], image.pixel(0, 1));
+        assert_eq!([40, 40, 40, 255], image.pixel(1, 1));
+    }
+
+    #[test]
+    fn test_invalid_png() {
+        assert!(decode(b"\x89PNG\r\n\x1a\n").is_err());
+        assert!(decode(&png(1, 1, 3, 2, &[], &[0, 0], false)).is_err());
+        // an indexed-colour image without a palette
+        assert!(decode(&png(1, 1, 8, 3, &[], &[0, 0], false)).is_err());
+    }
+}
@@ -275,24 +275,15 @@ impl JsParser {
 
     // MemberExpression ::= PrimaryExpression ( "." Identifier )*
     fn member_expression(&mut self) -> Option<Rc<Node>> {
-        let expr = self.primary_expression();
+        let mut expr = self.primary_expression();
 
-        let t = match self.t.peek() {
-            Some(token) => token,
-            None => return expr,
-        };
-
-        match t {
-            Token::Punctuator(c) => {
-                if c == &'.' {
-                    assert!(self.t.next().is_some());
-                    return Node::new_member_expression(expr, self.identifier());
-                }
-
-                expr
-            }
-            _ => expr,
+        // a chain such as a.b.c is nested from the left
+        while let Some(Token::Punctuator('.')) = self.t.peek() {
+            assert!(self.t.next().is_some());
+            expr = Node::new_member_expression(expr, self.identifier());
         }
+
+        expr
     }
 
     // PrimaryExpression ::= Identifier | Literal
@@ -1,12 +1,31 @@
 use crate::alloc::string::ToString;
+use crate::renderer::html::dom::replace_all;
+use crate::renderer::html::dom::set_attribute;
+use crate::renderer::html::dom::template_contents;
 use crate::renderer::html::dom::Node as DomNode;
 use crate::renderer::html::dom::NodeKind as DomNodeKind;
+use crate::renderer::html::dom::Window;
+use crate::renderer::html::event::add_event_listener;
+use crate::renderer::html::event::remove_event_listener;
+use crate::renderer::html::event::Event;
+use crate::renderer::html::event::EventCallback;
 use crate::renderer::html::helper::api::get_element_by_id;
+use crate::renderer::html::helper::api::get_elements_by_class_name;
+use crate::renderer::html::helper::api::get_elements_by_tag_name;
+use crate::renderer::html::helper::api::insert_adjacent_html;
+use crate::renderer::html::helper::api::query_selector;
+use crate::renderer::html::helper::api::query_selector_all;
+use crate::renderer::html::helper::api::set_inner_html;
+use crate::renderer::html::serializer::serialize_children;
+use crate::renderer::html::serializer::serialize_node;
+use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::ast::Node;
 use crate::renderer::js::ast::Program;
+use crate::renderer::js::token::JsLexer;
 use alloc::format;
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec;
 use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::cell::RefCell;
@@ -20,15 +39,19 @@ pub struct JsRuntime {
     env: Rc<RefCell<Environment>>,
     functions: Vec<Function>,
     dom_root: Rc<RefCell<DomNode>>,
+    // the global object, which is None for a document without a window
+    window: Option<Rc<RefCell<Window>>>,
 }
 
 impl JsRuntime {
     pub fn new(dom_root: Rc<RefCell<DomNode>>) -> Self {
+        let window = (*dom_root).borrow().default_view();
         Self {
             env: Rc::new(RefCell::new(Environment::new(None))),
             // all fuctions are able to be called anywhere (as global function)
             functions: Vec::new(),
             dom_root,
+            window,
         }
     }
 
@@ -38,6 +61,58 @@ impl JsRuntime {
         }
     }
 
+    // https://dom.spec.whatwg.org/#concept-event-listener-inner-invoke
+    // calls the function which a script added as the listener, with the event as the argument
+    pub fn call_event_listener(&mut self, name: &str, event: &mut Event) {
+        let function = match self.functions.iter().rev().find(|f| f.id == name) {
+            Some(f) => f.clone(),
+            None => return,
+        };
+        let object = Rc::new(RefCell::new(event.clone()));
+        let env = Rc::new(RefCell::new(Environment::new(Some(self.env.clone()))));
+        if let Some(param) = function.params.first() {
+            if let Some(RuntimeValue::StringLiteral(param)) = self.eval(param, env.clone()) {
+                env.borrow_mut().add_variable(
+                    param,
+                    Some(RuntimeValue::Event {
+                        object: object.clone(),
+                        property: None,
+                    }),
+                );
+            }
+        }
+        self.eval(&function.body, env);
+
+        // the listener may cancel the event or stop its propagation
+        *event = (*object).borrow().clone();
+    }
+
+    // https://html.spec.whatwg.org/multipage/webappapis.html#the-event-handler-processing-algorithm
+    // runs the value of the event handler attribute such as onsubmit="return false", where the
+    // event is `event`. the event is canceled when the handler returns false
+    pub fn run_event_handler(&mut self, source: &str, event: &mut Event) {
+        let object = Rc::new(RefCell::new(event.clone()));
+        let env = Rc::new(RefCell::new(Environment::new(Some(self.env.clone()))));
+        env.borrow_mut().add_variable(
+            "event".to_string(),
+            Some(RuntimeValue::Event {
+                object: object.clone(),
+                property: None,
+            }),
+        );
+
+        let program = JsParser::new(JsLexer::new(source.to_string())).parse_ast();
+        let mut result = None;
+        for node in program.body() {
+            result = self.eval(&Some(node.clone()), env.clone());
+        }
+
+        *event = (*object).borrow().clone();
+        if result == Some(RuntimeValue::StringLiteral("false".to_string())) {
+            event.prevent_default();
+        }
+    }
+
     fn eval(
         &mut self,
         node: &Option<Rc<Node>>,
@@ -103,22 +178,48 @@ impl JsRuntime {
                     }
                 }
 
-                if let Some(RuntimeValue::HtmlElement { object, property }) =
-                    self.eval(left, env.clone())
-                {
+                // the property of an element is resolved without calling its getter
+                let target = match left {
+                    Some(node) => match node.borrow() {
+                        Node::MemberExpression { object, property } => {
+                            match (
+                                self.eval(object, env.clone()),
+                                self.eval(property, env.clone()),
+                            ) {
+                                (
+                                    Some(RuntimeValue::HtmlElement {
+                                        object,
+                                        property: None,
+                                    }),
+                                    Some(property),
+                                ) => Some((object, property.to_string())),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    },
+                    None => None,
+                };
+
+                if let Some((object, property)) = target {
                     let right_value = match self.eval(right, env.clone()) {
                         Some(value) => value,
                         None => return None,
                     };
 
-                    if let Some(p) = property {
-                        if p == "textContent" {
-                            object
-                                .borrow_mut()
-                                .set_first_child(Some(Rc::new(RefCell::new(DomNode::new(
-                                    DomNodeKind::Text(right_value.to_string()),
-                                )))));
-                        }
+                    // https://dom.spec.whatwg.org/#dom-node-textcontent
+                    if property == "textContent" {
+                        let text = right_value.to_string();
+                        let nodes = if text.is_empty() {
+                            Vec::new()
+                        } else {
+                            vec![Rc::new(RefCell::new(DomNode::new(DomNodeKind::Text(text))))]
+                        };
+                        replace_all(&object, nodes);
+                    }
+                    // https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-innerhtml
+                    if property == "innerHTML" {
+                        set_inner_html(&object, &right_value.to_string());
                     }
                 }
 
@@ -204,15 +305,87 @@ impl JsRuntime {
 
                 if let RuntimeValue::HtmlElement { object, property } = object_value {
                     assert!(property.is_none());
+
+                    // https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-innerhtml
+                    if property_value.to_string() == "innerHTML" {
+                        return Some(RuntimeValue::StringLiteral(serialize_children(&object)));
+                    }
+                    // https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-outerhtml
+                    if property_value.to_string() == "outerHTML" {
+                        return Some(RuntimeValue::StringLiteral(serialize_node(&object)));
+                    }
+                    // https://html.spec.whatwg.org/multipage/scripting.html#dom-template-content
+                    if property_value.to_string() == "content" {
+                        if let Some(contents) = template_contents(&object) {
+                            return Some(RuntimeValue::HtmlElement {
+                                object: contents,
+                                property: None,
+                            });
+                        }
+                    }
+
                     return Some(RuntimeValue::HtmlElement {
                         object,
                         property: Some(property_value.to_string()),
                     });
                 }
 
-                return Some(
-                    object_value + RuntimeValue::StringLiteral(".".to_string()) + property_value,
-                );
+                // https://dom.spec.whatwg.org/#interface-event
+                if let RuntimeValue::Event { object, property } = object_value {
+                    // the unknown properties of the event are undefined
+                    if property.is_some() {
+                        return None;
+                    }
+
+                    let event = (*object).borrow().clone();
+                    let node = |node: Option<Rc<RefCell<DomNode>>>| {
+                        node.map(|object| RuntimeValue::HtmlElement {
+                            object,
+                            property: None,
+                        })
+                    };
+                    return match property_value.to_string().as_str() {
+                        "type" => Some(RuntimeValue::StringLiteral(event.event_type())),
+                        "target" => node(event.target()),
+                        "currentTarget" => node(event.current_target()),
+                        "eventPhase" => Some(RuntimeValue::Number(event.event_phase() as u64)),
+                        "bubbles" => Some(RuntimeValue::StringLiteral(event.bubbles().to_string())),
+                        "cancelable" => {
+                            Some(RuntimeValue::StringLiteral(event.cancelable().to_string()))
+                        }
+                        "defaultPrevented" => Some(RuntimeValue::StringLiteral(
+                            event.default_prevented().to_string(),
+                        )),
+                        _ => Some(RuntimeValue::Event {
+                            object,
+                            property: Some(property_value.to_string()),
+                        }),
+                    };
+                }
+
+                // https://dom.spec.whatwg.org/#interface-htmlcollection
+                if let RuntimeValue::HtmlCollection { objects, property } = object_value {
+                    // the unknown properties of the collection are undefined
+                    if property.is_some() {
+                        return None;
+                    }
+
+                    if property_value.to_string() == "length" {
+                        return Some(RuntimeValue::Number(objects.len() as u64));
+                    }
+
+                    return Some(RuntimeValue::HtmlCollection {
+                        objects,
+                        property: Some(property_value.to_string()),
+                    });
+                }
+
+                let name =
+                    object_value + RuntimeValue::StringLiteral(".".to_string()) + property_value;
+                if let Some(value) = self.window_property(&name.to_string()) {
+                    return Some(value);
+                }
+                return Some(name);
             }
             Node::Identifier(name) => {
                 match env.borrow_mut().get_variable(name.to_string()) {
@@ -226,6 +399,52 @@ impl JsRuntime {
         }
     }
 
+    // reads a property of the location, navigator or screen of the window, such as
+    // "window.location.href" and "document.defaultView.screen.width"
+    fn window_property(&self, name: &str) -> Option<RuntimeValue> {
+        let window = match &self.window {
+            Some(window) => (**window).borrow(),
+            None => return None,
+        };
+
+        // window.window and document.defaultView are the window itself
+        let mut name = name;
+        while let Some(rest) = name
+            .strip_prefix("window.")
+            .or_else(|| name.strip_prefix("document.defaultView."))
+        {
+            name = rest;
+        }
+        // https://html.spec.whatwg.org/multipage/nav-history-apis.html#dom-document-location
+        let name = match name.strip_prefix("document.") {
+            Some(rest) if rest.starts_with("location.") => rest,
+            _ => name,
+        };
+
+        let location = window.location();
+        let navigator = window.navigator();
+        let screen = window.screen();
+        let value = match name {
+            "location.href" => RuntimeValue::StringLiteral(location.href()),
+            "location.protocol" => RuntimeValue::StringLiteral(location.protocol()),
+            "location.host" => RuntimeValue::StringLiteral(location.host()),
+            "location.hostname" => RuntimeValue::StringLiteral(location.hostname()),
+            "location.port" => RuntimeValue::StringLiteral(location.port()),
+            "location.pathname" => RuntimeValue::StringLiteral(location.pathname()),
+            "location.search" => RuntimeValue::StringLiteral(location.search()),
+            "location.hash" => RuntimeValue::StringLiteral(location.hash()),
+            "location.origin" => RuntimeValue::StringLiteral(location.origin()),
+            "navigator.userAgent" => RuntimeValue::StringLiteral(navigator.user_agent()),
+            "navigator.language" => RuntimeValue::StringLiteral(navigator.language()),
+            "navigator.appName" => RuntimeValue::StringLiteral(navigator.app_name()),
+            "screen.width" => RuntimeValue::Number(screen.width() as u64),
+            "screen.height" => RuntimeValue::Number(screen.height() as u64),
+            "screen.colorDepth" => RuntimeValue::Number(screen.color_depth() as u64),
+            _ => return None,
+        };
+        Some(value)
+    }
+
     // calls browser supported api
     fn call_browser_api(
         &mut self,
@@ -251,6 +470,206 @@ impl JsRuntime {
             );
         }
 
+        // https://dom.spec.whatwg.org/#dom-document-getelementsbyclassname
+        // https://dom.spec.whatwg.org/#dom-document-getelementsbytagname
+        if func == &RuntimeValue::StringLiteral("document.getElementsByClassName".to_string())
+            || func == &RuntimeValue::StringLiteral("document.getElementsByTagName".to_string())
+        {
+            let arg = match self.eval(&arguments[0], env.clone()) {
+                Some(a) => a.to_string(),
+                None => return (true, None),
+            };
+            let objects = if func.to_string() == "document.getElementsByClassName" {
+                get_elements_by_class_name(&self.dom_root, &arg)
+            } else {
+                get_elements_by_tag_name(&self.dom_root, &arg)
+            };
+            return (
+                true,
+                Some(RuntimeValue::HtmlCollection {
+                    objects,
+                    property: None,
+                }),
+            );
+        }
+
+        // https://dom.spec.whatwg.org/#dom-parentnode-queryselector
+        // https://dom.spec.whatwg.org/#dom-parentnode-queryselectorall
+        let query_root = match func {
+            RuntimeValue::StringLiteral(name) => match name.as_str() {
+                "document.querySelector" => Some((self.dom_root.clone(), false)),
+                "document.querySelectorAll" => Some((self.dom_root.clone(), true)),
+                _ => None,
+            },
+            RuntimeValue::HtmlElement {
+                object,
+                property: Some(property),
+            } => match property.as_str() {
+                "querySelector" => Some((object.clone(), false)),
+                "querySelectorAll" => Some((object.clone(), true)),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some((root, all)) = query_root {
+            let selectors = match self.eval(&arguments[0], env.clone()) {
+                Some(s) => s.to_string(),
+                None => return (true, None),
+            };
+            // errors are thrown as exceptions, which are not supported yet
+            if all {
+                return (
+                    true,
+                    query_selector_all(&root, &selectors).ok().map(|objects| {
+                        RuntimeValue::HtmlCollection {
+                            objects,
+                            property: None,
+                        }
+                    }),
+                );
+            }
+            return (
+                true,
+                query_selector(&root, &selectors)
+                    .ok()
+                    .flatten()
+                    .map(|object| RuntimeValue::HtmlElement {
+                        object,
+                        property: None,
+                    }),
+            );
+        }
+
+        // https://dom.spec.whatwg.org/#dom-htmlcollection-item
+        if let RuntimeValue::HtmlCollection {
+            objects,
+            property: Some(property),
+        } = func
+        {
+            if property == "item" && arguments.len() == 1 {
+                let index = match self.eval(&arguments[0], env.clone()) {
+                    Some(RuntimeValue::Number(index)) => index as usize,
+                    _ => return (true, None),
+                };
+                return (
+                    true,
+                    objects.get(index).map(|object| RuntimeValue::HtmlElement {
+                        object: object.clone(),
+                        property: None,
+                    }),
+                );
+            }
+        }
+
+        // https://dom.spec.whatwg.org/#dom-element-getattribute
+        // https://dom.spec.whatwg.org/#dom-element-setattribute
+        if let RuntimeValue::HtmlElement {
+            object,
+            property: Some(property),
+        } = func
+        {
+            if property == "getAttribute" && arguments.len() == 1 {
+                let name = match self.eval(&arguments[0], env.clone()) {
+                    Some(n) => n.to_string(),
+                    None => return (true, None),
+                };
+                let value = (**object)
+                    .borrow()
+                    .get_element()
+                    .and_then(|e| e.get_attribute(&name));
+                return (true, value.map(RuntimeValue::StringLiteral));
+            }
+            if property == "setAttribute" && arguments.len() == 2 {
+                let name = match self.eval(&arguments[0], env.clone()) {
+                    Some(n) => n.to_string(),
+                    None => return (true, None),
+                };
+                let value = match self.eval(&arguments[1], env.clone()) {
+                    Some(v) => v.to_string(),
+                    None => return (true, None),
+                };
+                set_attribute(object, &name, &value);
+                return (true, None);
+            }
+        }
+
+        // https://dom.spec.whatwg.org/#dom-event-preventdefault
+        // https://dom.spec.whatwg.org/#dom-event-stoppropagation
+        // https://dom.spec.whatwg.org/#dom-event-stopimmediatepropagation
+        if let RuntimeValue::Event {
+            object,
+            property: Some(property),
+        } = func
+        {
+            let mut event = (**object).borrow_mut();
+            match property.as_str() {
+                "preventDefault" => event.prevent_default(),
+                "stopPropagation" => event.stop_propagation(),
+                "stopImmediatePropagation" => event.stop_immediate_propagation(),
+                _ => return (false, None),
+            }
+            return (true, None);
+        }
+
+        // https://dom.spec.whatwg.org/#dom-eventtarget-addeventlistener
+        // https://dom.spec.whatwg.org/#dom-eventtarget-removeeventlistener
+        // the listener is the name of a declared function, and the third argument is the capture
+        // flag, as object literals are not supported yet
+        let event_target = match func {
+            RuntimeValue::StringLiteral(name) => name
+                .strip_prefix("document.")
+                .map(|method| (self.dom_root.clone(), method.to_string())),
+            RuntimeValue::HtmlElement {
+                object,
+                property: Some(property),
+            } => Some((object.clone(), property.clone())),
+            _ => None,
+        };
+        if let Some((target, method)) = event_target {
+            if (method == "addEventListener" || method == "removeEventListener")
+                && (arguments.len() == 2 || arguments.len() == 3)
+            {
+                let event_type = match self.eval(&arguments[0], env.clone()) {
+                    Some(t) => t.to_string(),
+                    None => return (true, None),
+                };
+                let callback = match self.eval(&arguments[1], env.clone()) {
+                    Some(RuntimeValue::StringLiteral(name)) => EventCallback::Script(name),
+                    _ => return (true, None),
+                };
+                let capture = arguments.len() == 3
+                    && self.eval(&arguments[2], env.clone())
+                        == Some(RuntimeValue::StringLiteral("true".to_string()));
+                if method == "addEventListener" {
+                    add_event_listener(&target, &event_type, callback, capture, false);
+                } else {
+                    remove_event_listener(&target, &event_type, &callback, capture);
+                }
+                return (true, None);
+            }
+        }
+
+        // https://html.spec.whatwg.org/multipage/dynamic-markup-insertion.html#dom-element-insertadjacenthtml
+        if let RuntimeValue::HtmlElement {
+            object,
+            property: Some(property),
+        } = func
+        {
+            if property == "insertAdjacentHTML" && arguments.len() == 2 {
+                let position = match self.eval(&arguments[0], env.clone()) {
+                    Some(p) => p.to_string(),
+                    None => return (true, None),
+                };
+                let html = match self.eval(&arguments[1], env.clone()) {
+                    Some(h) => h.to_string(),
+                    None => return (true, None),
+                };
+                // errors are thrown as exceptions, which are not supported yet
+                let _ = insert_adjacent_html(object, &position, &html);
+                return (true, None);
+            }
+        }
+
         (false, None)
     }
 }
@@ -325,6 +744,18 @@ pub enum RuntimeValue {
         object: Rc<RefCell<DomNode>>,
         property: Option<String>,
     },
+    // https://dom.spec.whatwg.org/#interface-htmlcollection
+    // a snapshot of the elements at the time of the call, which is not live
+    HtmlCollection {
+        objects: Vec<Rc<RefCell<DomNode>>>,
+        property: Option<String>,
+    },
+    // https://dom.spec.whatwg.org/#interface-event
+    // the event which is being dispatched to the listener
+    Event {
+        object: Rc<RefCell<Event>>,
+        property: Option<String>,
+    },
     // https://tc39.es/ecma262/#sec-numeric-types
     Number(u64),
     // https://tc39.es/ecma262/#sec-ecmascript-language-types-string-type
@@ -340,6 +771,18 @@ impl Display for RuntimeValue {
             } => {
                 format!("HtmlElement: {:#?}", object)
             }
+            RuntimeValue::HtmlCollection {
+                objects,
+                property: _,
+            } => {
+                format!("HtmlCollection: {} elements", objects.len())
+            }
+            RuntimeValue::Event {
+                object,
+                property: _,
+            } => {
+                format!("Event: {}", (**object).borrow().event_type())
+            }
             RuntimeValue::Number(value) => format!("{}", value),
             RuntimeValue::StringLiteral(value) => value.to_string(),
         };
@@ -375,6 +818,10 @@ impl Sub<RuntimeValue> for RuntimeValue {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::WINDOW_WIDTH;
+    use crate::renderer::html::dom::Location;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
     use crate::renderer::js::ast::JsParser;
     use crate::renderer::js::token::JsLexer;
 
@@ -522,6 +969,184 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_inner_and_outer_html() {
+        let html = "<html><body><div id=\"target\"><p>hello</p></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = (*window).borrow().document();
+        let input =
+            "var target=document.getElementById(\"target\"); target.innerHTML; target.outerHTML"
+                .to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("<p>hello</p>".to_string())),
+            Some(RuntimeValue::StringLiteral(
+                "<div id=\"target\"><p>hello</p></div>".to_string(),
+            )),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_set_inner_html() {
+        let html = "<html><body><div id=\"target\">old</div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = (*window).borrow().document();
+        let input = "var target=document.getElementById(\"target\"); target.innerHTML=\"<p>new</p>\"; target.insertAdjacentHTML(\"beforeend\", \"<b>!</b>\"); target.innerHTML".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral(
+                "<p>new</p><b>!</b>".to_string(),
+            )),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_template_content() {
+        let html = "<html><body><template id=\"t\"><p>hi</p></template><div id=\"out\"></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = (*window).borrow().document();
+        let input = "var t=document.getElementById(\"t\"); var before=document.querySelectorAll(\"p\"); before.length; t.content.innerHTML; var out=document.getElementById(\"out\"); out.innerHTML=t.innerHTML; var after=document.querySelectorAll(\"p\"); after.length".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        // the contents are inert until they are copied into the document
+        let expected = [
+            None,
+            None,
+            Some(RuntimeValue::Number(0)),
+            Some(RuntimeValue::StringLiteral("<p>hi</p>".to_string())),
+            None,
+            None,
+            None,
+            Some(RuntimeValue::Number(1)),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_get_elements() {
+        let html = "<html><body><p class=\"a b\">1</p><div><p class=\"b\" id=\"x\">2</p></div></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = (*window).borrow().document();
+        let input = "var b=document.getElementsByClassName(\"b\"); b.length; var p=document.getElementsByTagName(\"P\"); var first=p.item(0); first.innerHTML; var x=document.getElementById(\"x\"); x.setAttribute(\"class\", \"a\"); x.getAttribute(\"class\"); var a=document.getElementsByClassName(\"a\"); a.length; a.foo.bar".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::Number(2)),
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("1".to_string())),
+            None,
+            None,
+            Some(RuntimeValue::StringLiteral("a".to_string())),
+            None,
+            Some(RuntimeValue::Number(2)),
+            None,
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_query_selector() {
+        let html = "<html><body><ul id=\"list\"><li>1</li><li class=\"x\">2</li><li class=\"x\">3</li></ul></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        let dom = (*window).borrow().document();
+        let input = "var item=document.querySelector(\"ul > li:nth-child(2)\"); item.innerHTML; var list=document.getElementById(\"list\"); var x=list.querySelectorAll(\".x\"); x.length; var all=document.querySelectorAll(\"li\"); all.length".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            None,
+            Some(RuntimeValue::StringLiteral("2".to_string())),
+            None,
+            None,
+            Some(RuntimeValue::Number(2)),
+            None,
+            Some(RuntimeValue::Number(3)),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
+    #[test]
+    fn test_window_properties() {
+        let html = "<html><body></body></html>".to_string();
+        let window = HtmlParser::new(HtmlTokenizer::new(html)).construct_tree();
+        window
+            .borrow_mut()
+            .set_location(Location::new("http://example.com/a?b#c".to_string()));
+        let dom = (*window).borrow().document();
+        let input = "window.location.href; location.pathname; document.location.search; var view=document.defaultView; view.location.hash; navigator.appName; window.screen.width".to_string();
+        let lexer = JsLexer::new(input);
+        let mut parser = JsParser::new(lexer);
+        let ast = parser.parse_ast();
+        let mut runtime = JsRuntime::new(dom);
+        let expected = [
+            Some(RuntimeValue::StringLiteral(
+                "http://example.com/a?b#c".to_string(),
+            )),
+            Some(RuntimeValue::StringLiteral("/a".to_string())),
+            Some(RuntimeValue::StringLiteral("?b".to_string())),
+            None,
+            Some(RuntimeValue::StringLiteral("#c".to_string())),
+            Some(RuntimeValue::StringLiteral("Netscape".to_string())),
+            Some(RuntimeValue::Number(WINDOW_WIDTH as u64)),
+        ];
+        let mut i = 0;
+
+        for node in ast.body() {
+            let result = runtime.eval(&Some(node.clone()), runtime.env.clone());
+            assert_eq!(expected[i], result);
+            i += 1;
+        }
+    }
+
     #[test]
     fn test_local_variable() {
         let dom = Rc::new(RefCell::new(DomNode::new(DomNodeKind::Document)));
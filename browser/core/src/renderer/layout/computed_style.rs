@@ -1,4 +1,6 @@
 use crate::error::Error;
+use crate::renderer::form::control::control_type;
+use crate::renderer::form::control::ControlType;
 use crate::renderer::html::dom::ElementKind;
 use crate::renderer::html::dom::Node;
 use crate::renderer::html::dom::NodeKind;
@@ -7,6 +9,7 @@ use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
 use core::cell::RefCell;
+use core::str::FromStr;
 
 // CSS properties are calculated as values in the following order:
 // 1. declared values
@@ -24,6 +27,7 @@ pub struct ComputedStyle {
     display: Option<DisplayType>,
     font_size: Option<FontSize>,
     text_decoration: Option<TextDecoration>,
+    white_space: Option<WhiteSpace>,
     height: Option<f64>,
     width: Option<f64>,
 }
@@ -36,6 +40,7 @@ impl ComputedStyle {
             display: None,
             font_size: None,
             text_decoration: None,
+            white_space: None,
             height: None,
             width: None,
         }
@@ -80,6 +85,15 @@ impl ComputedStyle {
             .expect("failed to access CSS property: text_decoration")
     }
 
+    pub fn set_white_space(&mut self, white_space: WhiteSpace) {
+        self.white_space = Some(white_space);
+    }
+
+    pub fn white_space(&self) -> WhiteSpace {
+        self.white_space
+            .expect("failed to access CSS property: white_space")
+    }
+
     pub fn set_height(&mut self, height: f64) {
         self.height = Some(height);
     }
@@ -114,6 +128,9 @@ impl ComputedStyle {
             {
                 self.text_decoration = Some(parent_style.text_decoration());
             }
+            if self.white_space.is_none() && parent_style.white_space() != WhiteSpace::Normal {
+                self.white_space = Some(parent_style.white_space());
+            }
         }
 
         // initial values for each properties
@@ -132,6 +149,9 @@ impl ComputedStyle {
         if self.text_decoration.is_none() {
             self.text_decoration = Some(TextDecoration::default(node));
         }
+        if self.white_space.is_none() {
+            self.white_space = Some(WhiteSpace::default(node));
+        }
         if self.height.is_none() {
             self.height = Some(0.0);
         }
@@ -153,10 +173,24 @@ pub enum DisplayType {
 
 impl DisplayType {
     fn default(node: &Rc<RefCell<Node>>) -> Self {
+        // https://html.spec.whatwg.org/multipage/input.html#hidden-state-(type=hidden)
+        if control_type(node) == Some(ControlType::Hidden) {
+            return DisplayType::DisplayNone;
+        }
+
         match &node.borrow().kind() {
             NodeKind::Document => DisplayType::Block,
             NodeKind::Element(e) => {
-                if e.kind() == ElementKind::Script || e.kind() == ElementKind::Style {
+                if matches!(
+                    e.kind(),
+                    Some(ElementKind::Script)
+                        | Some(ElementKind::Style)
+                        | Some(ElementKind::Meta)
+                        | Some(ElementKind::Link)
+                        | Some(ElementKind::Colgroup)
+                        | Some(ElementKind::Col)
+                        | Some(ElementKind::Template)
+                ) {
                     return DisplayType::DisplayNone;
                 }
 
@@ -167,6 +201,10 @@ impl DisplayType {
                 }
             }
             NodeKind::Text(_) => DisplayType::Inline,
+            // comments and doctypes are never rendered
+            NodeKind::DocumentFragment | NodeKind::DocumentType(_) | NodeKind::Comment(_) => {
+                DisplayType::DisplayNone
+            }
         }
     }
 
@@ -194,7 +232,7 @@ impl TextDecoration {
     fn default(node: &Rc<RefCell<Node>>) -> Self {
         match &node.borrow().kind() {
             NodeKind::Element(element) => match element.kind() {
-                ElementKind::A => TextDecoration::Underline,
+                Some(ElementKind::A) => TextDecoration::Underline,
                 _ => TextDecoration::None,
             },
             _ => TextDecoration::None,
@@ -202,6 +240,66 @@ impl TextDecoration {
     }
 }
 
+// https://www.w3.org/TR/css-text-3/#white-space-property
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    PreLine,
+}
+
+impl WhiteSpace {
+    fn default(node: &Rc<RefCell<Node>>) -> Self {
+        // https://html.spec.whatwg.org/multipage/rendering.html#flow-content-3
+        match node.borrow().element_kind() {
+            Some(ElementKind::Pre) => WhiteSpace::Pre,
+            _ => WhiteSpace::Normal,
+        }
+    }
+
+    // https://www.w3.org/TR/css-text-3/#white-space-phase-1
+    // whether spaces and tabs are kept as they are
+    pub fn preserves_spaces(&self) -> bool {
+        matches!(self, WhiteSpace::Pre | WhiteSpace::PreWrap)
+    }
+
+    // whether newlines are forced line breaks
+    pub fn preserves_newlines(&self) -> bool {
+        matches!(
+            self,
+            WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::PreLine
+        )
+    }
+
+    // whether lines are wrapped at the edge of the content area
+    pub fn wraps(&self) -> bool {
+        matches!(
+            self,
+            WhiteSpace::Normal | WhiteSpace::PreWrap | WhiteSpace::PreLine
+        )
+    }
+}
+
+impl FromStr for WhiteSpace {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "normal" => Ok(Self::Normal),
+            "pre" => Ok(Self::Pre),
+            "nowrap" => Ok(Self::Nowrap),
+            "pre-wrap" => Ok(Self::PreWrap),
+            "pre-line" => Ok(Self::PreLine),
+            _ => Err(Error::UnexpectedInput(format!(
+                "white-space {:?} is not supported yet",
+                s
+            ))),
+        }
+    }
+}
+
 // https://www.w3.org/TR/css-fonts-4/#absolute-size-mapping
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum FontSize {
@@ -214,8 +312,8 @@ impl FontSize {
     fn default(node: &Rc<RefCell<Node>>) -> Self {
         match &node.borrow().kind() {
             NodeKind::Element(element) => match element.kind() {
-                ElementKind::H1 => FontSize::XXLarge,
-                ElementKind::H2 => FontSize::XLarge,
+                Some(ElementKind::H1) => FontSize::XXLarge,
+                Some(ElementKind::H2) => FontSize::XLarge,
                 _ => FontSize::Medium,
             },
             _ => FontSize::Medium,
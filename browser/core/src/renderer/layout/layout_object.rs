@@ -3,20 +3,25 @@ use crate::constants::*;
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::ComponentValue;
 use crate::renderer::css::cssom::Declaration;
-use crate::renderer::css::cssom::Selector;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::form::control::control_label;
+use crate::renderer::form::control::control_type;
+use crate::renderer::html::dom::ElementKind;
 use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeId;
 use crate::renderer::html::dom::NodeKind;
 use crate::renderer::layout::computed_style::Color;
 use crate::renderer::layout::computed_style::ComputedStyle;
 use crate::renderer::layout::computed_style::DisplayType;
 use crate::renderer::layout::computed_style::FontSize;
+use crate::renderer::layout::computed_style::WhiteSpace;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
+use core::str::FromStr;
 
 #[derive(Debug, Clone)]
 pub struct LayoutObject {
@@ -59,6 +64,11 @@ impl LayoutObject {
         self.node.clone()
     }
 
+    // the id of the node is shared with the DOM, so that the layout object can be found by it
+    pub fn node_id(&self) -> Option<NodeId> {
+        self.node.borrow().id()
+    }
+
     pub fn node_kind(&self) -> NodeKind {
         self.node.borrow().kind().clone()
     }
@@ -95,37 +105,6 @@ impl LayoutObject {
         self.size
     }
 
-    pub fn is_node_selected(&self, selector: &Selector) -> bool {
-        match &self.node_kind() {
-            NodeKind::Element(e) => match selector {
-                Selector::TypeSelector(type_name) => {
-                    if e.kind().to_string() == *type_name {
-                        return true;
-                    }
-                    false
-                }
-                Selector::ClassSelector(class_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "class" && attr.value() == *class_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::IdSelector(id_name) => {
-                    for attr in &e.attributes() {
-                        if attr.name() == "id" && attr.value() == *id_name {
-                            return true;
-                        }
-                    }
-                    false
-                }
-                Selector::UnknownSelector => false,
-            },
-            _ => false,
-        }
-    }
-
     // https://www.w3.org/TR/css-cascade-4/#cascading
     pub fn cascading_style(&mut self, declarations: Vec<Declaration>) {
         for declaration in declarations {
@@ -175,6 +154,14 @@ impl LayoutObject {
                         self.style.set_display(display_type)
                     }
                 }
+                "white-space" => {
+                    if let ComponentValue::Ident(value) = declaration.value {
+                        // an invalid value is ignored
+                        if let Ok(white_space) = WhiteSpace::from_str(&value) {
+                            self.style.set_white_space(white_space);
+                        }
+                    }
+                }
                 _ => {}
             }
         }
@@ -193,6 +180,11 @@ impl LayoutObject {
     pub fn update_kind(&mut self) {
         match self.node_kind() {
             NodeKind::Document => panic!("should not create a layout object for a Document node"),
+            // https://html.spec.whatwg.org/multipage/rendering.html#phrasing-content-3
+            // <br> is a forced line break, which ends the current line like a block
+            NodeKind::Element(e) if e.kind() == Some(ElementKind::Br) => {
+                self.kind = LayoutObjectKind::Block;
+            }
             NodeKind::Element(_) => {
                 let display = self.style.display();
                 match display {
@@ -204,14 +196,48 @@ impl LayoutObject {
                 }
             }
             NodeKind::Text(_) => self.kind = LayoutObjectKind::Text,
+            NodeKind::DocumentFragment | NodeKind::DocumentType(_) | NodeKind::Comment(_) => {
+                panic!("should not create a layout object for a non-rendered node")
+            }
         }
     }
 
+    fn font_ratio(&self) -> i64 {
+        match self.style.font_size() {
+            FontSize::Medium => 1,
+            FontSize::XLarge => 2,
+            FontSize::XXLarge => 3,
+        }
+    }
+
+    // the lines of the text node after the white space processing and the line breaking
+    fn text_lines(&self) -> Vec<String> {
+        let text = match self.node_kind() {
+            NodeKind::Text(t) => t,
+            _ => return Vec::new(),
+        };
+        let white_space = self.style.white_space();
+        split_text(
+            process_white_space(&text, white_space),
+            CHAR_WIDTH * self.font_ratio(),
+            white_space.wraps(),
+        )
+    }
+
     pub fn compute_size(&mut self, parent_size: LayoutSize) {
         let mut size = LayoutSize::new(0, 0);
 
         match self.kind() {
             LayoutObjectKind::Block => {
+                // a line break takes the height of a line only when the line is empty
+                if self.node.borrow().element_kind() == Some(ElementKind::Br) {
+                    if starts_line(&self.node) {
+                        size.set_height(CHAR_HEIGHT_WITH_PADDING);
+                    }
+                    self.size = size;
+                    return;
+                }
+
                 size.set_width(parent_size.width());
 
                 let mut height = 0;
@@ -235,6 +261,24 @@ impl LayoutObject {
                 size.set_height(height);
             }
             LayoutObjectKind::Inline => {
+                if let Some(image_size) = image_size(&self.node) {
+                    self.size = image_size;
+                    return;
+                }
+
+                if let Some(frame_size) = frame_size(&self.node) {
+                    self.size = frame_size;
+                    return;
+                }
+
+                // form controls are rendered as a line of text
+                if let Some(label) = control_label(&self.node) {
+                    size.set_width(CHAR_WIDTH * label.chars().count() as i64);
+                    size.set_height(CHAR_HEIGHT_WITH_PADDING);
+                    self.size = size;
+                    return;
+                }
+
                 let mut width = 0;
                 let mut height = 0;
                 let mut child = self.first_child();
@@ -254,28 +298,18 @@ impl LayoutObject {
                 size.set_height(height);
             }
             LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let width = CHAR_WIDTH * ratio * t.len() as i64;
-
-                    // if text is multilined
-                    if width > CONTENT_AREA_WIDTH {
-                        size.set_width(CONTENT_AREA_WIDTH);
-                        let line_num = if width.wrapping_rem(CONTENT_AREA_WIDTH) == 0 {
-                            width.wrapping_div(CONTENT_AREA_WIDTH)
-                        } else {
-                            width.wrapping_div(CONTENT_AREA_WIDTH) + 1
-                        };
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * line_num);
-                    } else {
-                        size.set_width(width);
-                        size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio);
-                    }
+                let ratio = self.font_ratio();
+                let lines = self.text_lines();
+                let max_chars = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+                let width = CHAR_WIDTH * ratio * max_chars as i64;
+
+                // wrapped lines fill the content area, and the other lines overflow it
+                if self.style.white_space().wraps() {
+                    size.set_width(width.min(CONTENT_AREA_WIDTH));
+                } else {
+                    size.set_width(width);
                 }
+                size.set_height(CHAR_HEIGHT_WITH_PADDING * ratio * lines.len() as i64);
             }
         }
 
@@ -327,6 +361,20 @@ impl LayoutObject {
 
         match self.kind {
             LayoutObjectKind::Block => {
+                // a line break which ends an empty line is painted as the empty line
+                if self.node.borrow().element_kind() == Some(ElementKind::Br) {
+                    if self.size.height() == 0 {
+                        return vec![];
+                    }
+                    return vec![DisplayItem::Text {
+                        text: String::new(),
+                        style: self.style(),
+                        layout_point: self.point(),
+                        href: None,
+                        node_id: None,
+                    }];
+                }
+
                 if let NodeKind::Element(_e) = self.node_kind() {
                     return vec![DisplayItem::Rect {
                         style: self.style(),
@@ -335,40 +383,59 @@ impl LayoutObject {
                     }];
                 }
             }
-            LayoutObjectKind::Inline => {}
-            LayoutObjectKind::Text => {
-                if let NodeKind::Text(t) = self.node_kind() {
-                    let mut v = vec![];
-
-                    let ratio = match self.style.font_size() {
-                        FontSize::Medium => 1,
-                        FontSize::XLarge => 2,
-                        FontSize::XXLarge => 3,
-                    };
-                    let plain_text = t
-                        .replace("\n", " ")
-                        .split(' ')
-                        .filter(|s| !s.is_empty())
-                        .collect::<Vec<_>>()
-                        .join(" ");
-                    let lines = split_text(plain_text, CHAR_WIDTH * ratio);
-                    let mut i = 0;
-                    for line in lines {
-                        let item = DisplayItem::Text {
-                            text: line,
+            LayoutObjectKind::Inline => {
+                if let Some(e) = self.node.borrow().get_element() {
+                    if e.kind() == Some(ElementKind::Img) {
+                        return vec![DisplayItem::Image {
+                            image: e.image(),
+                            alt: e.get_attribute("alt").unwrap_or_default(),
                             style: self.style(),
-                            layout_point: LayoutPoint::new(
-                                self.point().x(),
-                                self.point().y() + CHAR_HEIGHT_WITH_PADDING * i,
-                            ),
-                        };
-                        v.push(item);
-                        i += 1;
+                            layout_point: self.point(),
+                            layout_size: self.size(),
+                        }];
+                    }
+                    if let (Some(ElementKind::Iframe), Some(node_id)) = (e.kind(), self.node_id()) {
+                        return vec![DisplayItem::Frame {
+                            node_id,
+                            style: self.style(),
+                            layout_point: self.point(),
+                            layout_size: self.size(),
+                        }];
                     }
+                }
 
-                    return v;
+                if let (Some(node_id), Some(control_type), Some(text)) = (
+                    self.node_id(),
+                    control_type(&self.node),
+                    control_label(&self.node),
+                ) {
+                    return vec![DisplayItem::FormControl {
+                        node_id,
+                        control_type,
+                        text,
+                        style: self.style(),
+                        layout_point: self.point(),
+                    }];
                 }
             }
+            LayoutObjectKind::Text => {
+                let href = link_destination(&self.node);
+                return self
+                    .text_lines()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, line)| DisplayItem::Text {
+                        text: line,
+                        style: self.style(),
+                        layout_point: LayoutPoint::new(
+                            self.point().x(),
+                            self.point().y() + CHAR_HEIGHT_WITH_PADDING * i as i64,
+                        ),
+                        href: href.clone(),
+                        node_id: self.node_id(),
+                    })
+                    .collect();
+            }
         }
 
         vec![]
@@ -454,13 +521,23 @@ pub fn create_layout_object(
     if let Some(n) = node {
         let layout_object = Rc::new(RefCell::new(LayoutObject::new(n.clone(), parent_obj)));
 
-        // apply CSS rules to LayoutObject
-        for rule in &cssom.rules {
-            if layout_object.borrow().is_node_selected(&rule.selector) {
-                layout_object
-                    .borrow_mut()
-                    .cascading_style(rule.declarations.clone());
-            }
+        // https://www.w3.org/TR/css-cascade-4/#cascade-sort
+        // apply CSS rules to LayoutObject, where the rules of higher specificity win, and the
+        // later ones win among the same specificity
+        let mut rules = cssom
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                rule.selectors
+                    .matching_specificity(n)
+                    .map(|specificity| (specificity, rule))
+            })
+            .collect::<Vec<_>>();
+        rules.sort_by_key(|(specificity, _)| *specificity);
+        for (_, rule) in rules {
+            layout_object
+                .borrow_mut()
+                .cascading_style(rule.declarations.clone());
         }
 
         // defaulting a parent CSS style
@@ -481,8 +558,192 @@ pub fn create_layout_object(
     None
 }
 
+// https://html.spec.whatwg.org/multipage/links.html#hyperlink
+// gets the href of the nearest <a> element which contains the node
+fn link_destination(node: &Rc<RefCell<Node>>) -> Option<String> {
+    let mut current = Some(node.clone());
+    while let Some(n) = current {
+        if n.borrow().element_kind() == Some(ElementKind::A) {
+            if let Some(href) = n
+                .borrow()
+                .get_element()
+                .and_then(|e| e.get_attribute("href"))
+            {
+                return Some(href);
+            }
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    None
+}
+
+// https://html.spec.whatwg.org/multipage/embedded-content-other.html#dimension-attributes
+// gets the size of <img> from the width and height attributes, the image or the alt text,
+// where the missing dimension keeps the aspect ratio of the image
+fn image_size(node: &Rc<RefCell<Node>>) -> Option<LayoutSize> {
+    let element = node.borrow().get_element()?;
+    if element.kind() != Some(ElementKind::Img) {
+        return None;
+    }
+
+    let width = element
+        .get_attribute("width")
+        .and_then(|w| parse_dimension(&w));
+    let height = element
+        .get_attribute("height")
+        .and_then(|h| parse_dimension(&h));
+    let intrinsic = element.image().map(|image| {
+        (
+            clamp_dimension(image.width() as i64).max(1),
+            clamp_dimension(image.height() as i64).max(1),
+        )
+    });
+
+    let (width, height) = match (width, height, intrinsic) {
+        (Some(w), Some(h), _) => (w, h),
+        (Some(w), None, Some((iw, ih))) => (w, clamp_dimension(w * ih / iw)),
+        (None, Some(h), Some((iw, ih))) => (clamp_dimension(h * iw / ih), h),
+        (None, None, Some((iw, ih))) => (iw, ih),
+        (w, h, None) => {
+            let alt = element.get_attribute("alt").unwrap_or_default();
+            (
+                w.unwrap_or(clamp_dimension(
+                    CHAR_WIDTH.saturating_mul(alt.chars().count() as i64),
+                )),
+                h.unwrap_or(CHAR_HEIGHT_WITH_PADDING),
+            )
+        }
+    };
+
+    // images wider than the content area are scaled down
+    if width > CONTENT_AREA_WIDTH {
+        return Some(LayoutSize::new(
+            CONTENT_AREA_WIDTH,
+            height * CONTENT_AREA_WIDTH / width,
+        ));
+    }
+    Some(LayoutSize::new(width, height))
+}
+
+// https://html.spec.whatwg.org/multipage/rendering.html#embedded-content-rendering-rules
+// gets the size of <iframe> from the width and height attributes, which default to 300x150
+fn frame_size(node: &Rc<RefCell<Node>>) -> Option<LayoutSize> {
+    let element = node.borrow().get_element()?;
+    if element.kind() != Some(ElementKind::Iframe) {
+        return None;
+    }
+
+    let width = element
+        .get_attribute("width")
+        .and_then(|w| parse_dimension(&w))
+        .unwrap_or(300);
+    let height = element
+        .get_attribute("height")
+        .and_then(|h| parse_dimension(&h))
+        .unwrap_or(150);
+    Some(LayoutSize::new(width.min(CONTENT_AREA_WIDTH), height))
+}
+
+// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-non-negative-integers
+// the value is clamped, so that the scaling of the other dimension doesn't overflow
+fn parse_dimension(value: &str) -> Option<i64> {
+    let digits: String = value
+        .trim_start()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    if digits.is_empty() {
+        return None;
+    }
+    Some(clamp_dimension(digits.parse().unwrap_or(MAX_DIMENSION)))
+}
+
+fn clamp_dimension(value: i64) -> i64 {
+    value.clamp(0, MAX_DIMENSION)
+}
+
+// https://html.spec.whatwg.org/multipage/rendering.html#phrasing-content-3
+// whether the node is at the start of a line, i.e. a line break there ends an empty line
+fn starts_line(node: &Rc<RefCell<Node>>) -> bool {
+    let mut previous = node.borrow().previous_sibling().upgrade();
+    while let Some(p) = previous {
+        match p.borrow().kind() {
+            NodeKind::Element(e) => {
+                return e.kind() == Some(ElementKind::Br) || e.is_block_element();
+            }
+            NodeKind::Text(t) if !t.trim().is_empty() => return false,
+            _ => {}
+        }
+        previous = p.borrow().previous_sibling().upgrade();
+    }
+    true
+}
+
+// https://www.w3.org/TR/css-text-3/#white-space-phase-1
+// collapses spaces and newlines unless the white-space property preserves them, where the
+// preserved newlines are kept as the forced line breaks
+fn process_white_space(text: &str, white_space: WhiteSpace) -> String {
+    let text = if white_space.preserves_newlines() {
+        text.replace("\r\n", "\n")
+    } else {
+        text.replace(['\r', '\n'], " ")
+    };
+
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            if white_space.preserves_spaces() {
+                expand_tabs(line)
+            } else {
+                line.split([' ', '\t'])
+                    .filter(|s| !s.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+        })
+        .collect();
+    lines.join("\n")
+}
+
+// https://www.w3.org/TR/css-text-3/#tab-size-property
+// replaces tabs with spaces up to the next tab stop, which is every 8 characters
+fn expand_tabs(line: &str) -> String {
+    let mut result = String::new();
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = 8 - column % 8;
+            result.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            result.push(c);
+            column += 1;
+        }
+    }
+    result
+}
+
 // https://drafts.csswg.org/css-text/#word-break-property
-fn split_text(line: String, char_width: i64) -> Vec<String> {
+// splits the text into lines at the preserved newlines, and wraps the lines if it's allowed
+fn split_text(text: String, char_width: i64, wrap: bool) -> Vec<String> {
+    let mut segments: Vec<&str> = text.split('\n').collect();
+    // a newline at the end of the text doesn't start another line
+    if segments.len() > 1 && segments.last() == Some(&"") {
+        segments.pop();
+    }
+
+    let mut result: Vec<String> = vec![];
+    for segment in segments {
+        if wrap {
+            result.extend(wrap_line(segment.to_string(), char_width));
+        } else {
+            result.push(segment.to_string());
+        }
+    }
+    result
+}
+
+fn wrap_line(line: String, char_width: i64) -> Vec<String> {
     let mut result: Vec<String> = vec![];
     if line.len() as i64 * char_width > (WINDOW_WIDTH + WINDOW_PADDING) {
         let s = line.split_at(find_index_for_line_break(
@@ -490,7 +751,7 @@ fn split_text(line: String, char_width: i64) -> Vec<String> {
             ((WINDOW_WIDTH + WINDOW_PADDING) / char_width) as usize,
         ));
         result.push(s.0.to_string());
-        result.extend(split_text(s.1.trim().to_string(), char_width))
+        result.extend(wrap_line(s.1.trim().to_string(), char_width))
     } else {
         result.push(line);
     }
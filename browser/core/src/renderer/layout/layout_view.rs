@@ -1,6 +1,7 @@
 use crate::constants::CONTENT_AREA_WIDTH;
 use crate::display_item::DisplayItem;
 use crate::renderer::css::cssom::StyleSheet;
+use crate::renderer::form::control::control_type;
 use crate::renderer::html::dom::ElementKind;
 use crate::renderer::html::dom::Node;
 use crate::renderer::html::helper::api::get_target_element_node;
@@ -146,10 +147,18 @@ fn build_layout_tree(
     }
 
     if let Some(n) = target_node {
-        let original_first_child = n.borrow().first_child();
+        // the contents of form controls are rendered as the label of the control, and the
+        // contents of <iframe> are replaced by the nested document
+        let is_frame = n.borrow().element_kind() == Some(ElementKind::Iframe);
+        let original_first_child = if control_type(&n).is_some() || is_frame {
+            None
+        } else {
+            n.borrow().first_child()
+        };
         let original_next_sibling = n.borrow().next_sibling();
         let mut first_child = build_layout_tree(&original_first_child, &layout_object, cssom);
-        let mut next_sibling = build_layout_tree(&original_next_sibling, &None, cssom);
+        // siblings share the parent, whose style they inherit
+        let mut next_sibling = build_layout_tree(&original_next_sibling, parent_obj, cssom);
 
         if first_child.is_none() && original_first_child.is_some() {
             let mut original_dom_node = original_first_child
@@ -180,7 +189,7 @@ fn build_layout_tree(
                 .next_sibling();
 
             loop {
-                next_sibling = build_layout_tree(&original_dom_node, &None, cssom);
+                next_sibling = build_layout_tree(&original_dom_node, parent_obj, cssom);
 
                 if next_sibling.is_none() && original_dom_node.is_some() {
                     original_dom_node = original_dom_node
@@ -209,20 +218,28 @@ fn build_layout_tree(
 mod tests {
     use super::*;
     use crate::alloc::string::ToString;
+    use crate::constants::CHAR_HEIGHT_WITH_PADDING;
+    use crate::constants::CHAR_WIDTH;
     use crate::renderer::css::parser::CssParser;
     use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::html::dom::set_image;
     use crate::renderer::html::dom::Element;
     use crate::renderer::html::dom::NodeKind;
-    use crate::renderer::html::helper::api::get_style_content;
+    use crate::renderer::html::helper::api::get_element_nodes;
+    use crate::renderer::html::helper::api::get_style_contents;
     use crate::renderer::html::parser::HtmlParser;
     use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::image::bitmap::Image;
+    use crate::renderer::layout::computed_style::Color;
+    use alloc::format;
     use alloc::string::String;
+    use alloc::vec;
 
     fn create_layout_view(html: String) -> LayoutView {
         let t = HtmlTokenizer::new(html);
         let window = HtmlParser::new(t).construct_tree();
         let dom = window.borrow().document();
-        let style = get_style_content(dom.clone());
+        let style = get_style_contents(dom.clone()).concat();
         let css_tokenizer = CssTokenizer::new(style);
         let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
         LayoutView::new(dom, &cssom)
@@ -231,7 +248,14 @@ mod tests {
     #[test]
     fn test_empty() {
         let layout_view = create_layout_view("".to_string());
-        assert_eq!(None, layout_view.root());
+
+        // the parser inserts an empty <body>
+        let root = layout_view.root().expect("root should exist");
+        assert_eq!(
+            NodeKind::Element(Element::new("body", Vec::new())),
+            root.borrow().node_kind()
+        );
+        assert!(root.borrow().first_child().is_none());
     }
 
     #[test]
@@ -356,4 +380,170 @@ mod tests {
             .next_sibling()
             .is_none());
     }
+
+    #[test]
+    fn test_selector_specificity() {
+        // the rule of higher specificity wins regardless of the order
+        let html = "<html><head><style>#x .note { color: red; } div > .note, p:not(.note) { color: green; } p { color: blue; } div p[title] { color: gray; }</style></head><body><div id=x><p class=note>a</p><p>b</p><p title=c>c</p></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let div = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("div should exist");
+        let mut colors = Vec::new();
+        let mut p = div.borrow().first_child();
+        while let Some(node) = p {
+            colors.push(node.borrow().style().color());
+            p = node.borrow().next_sibling();
+        }
+        let color = |name| Color::from_name(name).expect("failed to parse the color");
+        assert_eq!(vec![color("red"), color("green"), color("gray")], colors);
+    }
+
+    #[test]
+    fn test_cascade_source_order() {
+        // among the rules of the same specificity, the later one wins as before
+        let html = "<html><head><style>p { color: red; } .b { color: gray; } .a { color: green; } p { color: blue; }</style></head><body><div><p>a</p><p class=\"a b\">b</p><p class=\"b a\">c</p></div></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        let div = layout_view
+            .root()
+            .and_then(|body| body.borrow().first_child())
+            .expect("div should exist");
+        let mut colors = Vec::new();
+        let mut p = div.borrow().first_child();
+        while let Some(node) = p {
+            colors.push(node.borrow().style().color());
+            p = node.borrow().next_sibling();
+        }
+        let color = |name| Color::from_name(name).expect("failed to parse the color");
+        assert_eq!(vec![color("blue"), color("green"), color("green")], colors);
+    }
+
+    #[test]
+    fn test_image_size() {
+        let html = r#"<html><body><img id=a src=a.gif width=100><img id=b src=b.gif height="20px"><img id=c alt="no image" height=30><img id=d src=d.gif></body></html>"#;
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let dom = window.borrow().document();
+        // images of 4x2 pixels, as if they were fetched and decoded
+        for img in get_element_nodes(Some(dom.clone()), &[ElementKind::Img]) {
+            let id = img
+                .borrow()
+                .get_element()
+                .and_then(|e| e.get_attribute("id"));
+            if id != Some("c".to_string()) {
+                set_image(&img, Some(Rc::new(Image::new(4, 2, vec![0; 32]))));
+            }
+        }
+        let layout_view = LayoutView::new(dom, &StyleSheet::new());
+
+        let sizes: Vec<(i64, i64)> = layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Image { layout_size, .. } => {
+                    Some((layout_size.width(), layout_size.height()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![(100, 50), (40, 20), (CHAR_WIDTH * 8, 30), (4, 2)],
+            sizes
+        );
+    }
+
+    #[test]
+    fn test_huge_image_size() {
+        let html = "<html><body><img alt=x width=100000000000000000 height=100000000000000000><img id=b src=b.gif height=100000000000></body></html>";
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let dom = window.borrow().document();
+        let img = get_element_nodes(Some(dom.clone()), &[ElementKind::Img])
+            .pop()
+            .expect("no img");
+        set_image(&img, Some(Rc::new(Image::new(4, 2, vec![0; 32]))));
+        let layout_view = LayoutView::new(dom, &StyleSheet::new());
+
+        // the dimensions are clamped, and then scaled down to the content area
+        let sizes: Vec<(i64, i64)> = layout_view
+            .paint()
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Image { layout_size, .. } => {
+                    Some((layout_size.width(), layout_size.height()))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                (CONTENT_AREA_WIDTH, CONTENT_AREA_WIDTH),
+                (CONTENT_AREA_WIDTH, CONTENT_AREA_WIDTH)
+            ],
+            sizes
+        );
+    }
+
+    fn texts(layout_view: &LayoutView) -> Vec<String> {
+        layout_view
+            .paint()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { text, .. } => Some(text),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_white_space() {
+        let html = "<html><head><style>.nowrap{white-space:nowrap;} .line{white-space:pre-line;} .bogus{white-space:wrap-anywhere;}</style></head><body><p>a  b\n c</p><pre>  x\ty\n\n  <b>z</b>  w\n</pre><p class=line>  one  \n two </p><p class=bogus>  bogus </p></body></html>".to_string();
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                "a b c".to_string(),
+                "  x     y".to_string(),
+                "".to_string(),
+                "  ".to_string(),
+                "z".to_string(),
+                "  w".to_string(),
+                "one".to_string(),
+                "two".to_string(),
+                "bogus".to_string(),
+            ],
+            texts(&layout_view)
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        let long = "word ".repeat(40);
+        let html = format!(
+            "<html><head><style>.nowrap{{white-space:nowrap;}}</style></head><body><p>x<br>y<br><br>z</p><p class=nowrap>{}</p></body></html>",
+            long
+        );
+        let layout_view = create_layout_view(html);
+
+        assert_eq!(
+            vec![
+                "x".to_string(),
+                "y".to_string(),
+                "".to_string(),
+                "z".to_string(),
+                long.trim().to_string(),
+            ],
+            texts(&layout_view)
+        );
+
+        // the empty line between the line breaks takes its height
+        let p = layout_view
+            .root()
+            .expect("root should exist")
+            .borrow()
+            .first_child()
+            .expect("p should exist");
+        assert_eq!(CHAR_HEIGHT_WITH_PADDING * 4, p.borrow().size().height());
+    }
 }
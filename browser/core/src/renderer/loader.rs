@@ -0,0 +1,325 @@
+use crate::error::Error;
+use crate::http::HttpResponse;
+use crate::renderer::html::dom::set_image;
+use crate::renderer::html::dom::ElementKind;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::helper::api::get_element_nodes;
+use crate::renderer::html::helper::api::get_text_content;
+use crate::renderer::html::helper::api::is_classic_script;
+use crate::renderer::image::bitmap::decode;
+use crate::url::resolve_url;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// fetches the resource of the URL through the network layer
+pub type Fetcher = fn(String) -> Result<HttpResponse, Error>;
+
+// loads the stylesheets and scripts which a document refers to
+#[derive(Debug, Clone)]
+pub struct SubresourceLoader {
+    base_url: String,
+    fetcher: Option<Fetcher>,
+    errors: Vec<Error>,
+}
+
+impl SubresourceLoader {
+    pub fn new(base_url: String, fetcher: Option<Fetcher>) -> Self {
+        Self {
+            base_url,
+            fetcher,
+            errors: Vec::new(),
+        }
+    }
+
+    // errors of the failed fetches, which don't abort loading the page
+    pub fn errors(&self) -> Vec<Error> {
+        self.errors.clone()
+    }
+
+    fn fetch(&mut self, href: &str) -> Option<String> {
+        self.fetch_response(href).map(|response| response.body())
+    }
+
+    // fetches the resource of the URL, which is resolved against the base URL. failures are
+    // recorded in the errors
+    pub fn fetch_response(&mut self, href: &str) -> Option<HttpResponse> {
+        let url = resolve_url(&self.base_url, href);
+
+        let fetcher = match self.fetcher {
+            Some(fetcher) => fetcher,
+            None => {
+                self.errors.push(Error::Network(format!(
+                    "no network is available to fetch {}",
+                    url
+                )));
+                return None;
+            }
+        };
+
+        match fetcher(url.clone()) {
+            Ok(response) => {
+                if (200..300).contains(&response.status_code()) {
+                    return Some(response);
+                }
+                self.errors.push(Error::Network(format!(
+                    "failed to fetch {}: {} {}",
+                    url,
+                    response.status_code(),
+                    response.reason()
+                )));
+                None
+            }
+            Err(e) => {
+                self.errors
+                    .push(Error::Network(format!("failed to fetch {}: {:?}", url, e)));
+                None
+            }
+        }
+    }
+
+    // gets the contents of <style> and <link rel=stylesheet> in document order
+    pub fn load_stylesheets(&mut self, root: Rc<RefCell<Node>>) -> Vec<String> {
+        let mut stylesheets = Vec::new();
+
+        for node in get_element_nodes(Some(root), &[ElementKind::Style, ElementKind::Link]) {
+            if node.borrow().element_kind() == Some(ElementKind::Style) {
+                stylesheets.push(get_text_content(&node));
+                continue;
+            }
+
+            let element = match node.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+            if !is_stylesheet_link(&element.get_attribute("rel")) {
+                continue;
+            }
+            if let Some(href) = element.get_attribute("href") {
+                if let Some(content) = self.fetch(&href) {
+                    stylesheets.push(content);
+                }
+            }
+        }
+
+        stylesheets
+    }
+
+    // https://html.spec.whatwg.org/multipage/scripting.html#script-processing-model
+    // gets the contents of the scripts in the order they are executed
+    pub fn load_scripts(&mut self, root: Rc<RefCell<Node>>) -> Vec<String> {
+        let mut scripts = Vec::new();
+        let mut deferred_scripts = Vec::new();
+
+        for node in get_element_nodes(Some(root), &[ElementKind::Script]) {
+            if !is_classic_script(&node) {
+                continue;
+            }
+
+            let element = match node.borrow().get_element() {
+                Some(e) => e,
+                None => continue,
+            };
+
+            // defer and async have no effect on inline scripts
+            let src = match element.get_attribute("src") {
+                Some(src) => src,
+                None => {
+                    scripts.push(get_text_content(&node));
+                    continue;
+                }
+            };
+
+            let content = match self.fetch(&src) {
+                Some(content) => content,
+                None => continue,
+            };
+
+            // async scripts run as soon as they are fetched, which is immediately here,
+            // and deferred scripts run after the document has been parsed
+            let is_async = element.get_attribute("async").is_some();
+            if !is_async && element.get_attribute("defer").is_some() {
+                deferred_scripts.push(content);
+            } else {
+                scripts.push(content);
+            }
+        }
+
+        scripts.extend(deferred_scripts);
+        scripts
+    }
+
+    // https://html.spec.whatwg.org/multipage/images.html#update-the-image-data
+    // fetches and decodes the images of <img>, and keeps the alt text for the broken ones
+    pub fn load_images(&mut self, root: Rc<RefCell<Node>>) {
+        for node in get_element_nodes(Some(root), &[ElementKind::Img]) {
+            let src = match node.borrow().get_element() {
+                Some(e) => e.get_attribute("src"),
+                None => continue,
+            };
+            let src = match src {
+                Some(src) if !src.trim().is_empty() => src,
+                _ => {
+                    set_image(&node, None);
+                    continue;
+                }
+            };
+
+            let image = match self.fetch_response(&src) {
+                Some(response) => match decode(&response.body_bytes()) {
+                    Ok(image) => Some(Rc::new(image)),
+                    Err(e) => {
+                        self.errors.push(Error::UnexpectedInput(format!(
+                            "failed to decode {}: {:?}",
+                            resolve_url(&self.base_url, &src),
+                            e
+                        )));
+                        None
+                    }
+                },
+                None => None,
+            };
+            set_image(&node, image);
+        }
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/links.html#link-type-stylesheet
+// alternative style sheets are not applied unless the user selects them, which isn't supported
+fn is_stylesheet_link(rel: &Option<String>) -> bool {
+    let has_token = |rel: &str, token: &str| {
+        rel.split_ascii_whitespace()
+            .any(|t| t.eq_ignore_ascii_case(token))
+    };
+    match rel {
+        Some(rel) => has_token(rel, "stylesheet") && !has_token(rel, "alternate"),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::renderer::css::parser::CssParser;
+    use crate::renderer::css::token::CssTokenizer;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+    use crate::renderer::image::bitmap::Image;
+    use alloc::vec;
+
+    // a 1x1 GIF whose only pixel is red
+    const RED_GIF: &[u8] = b"GIF89a\x01\x00\x01\x00\x80\x00\x00\xff\x00\x00\x00\x00\x00,\x00\x00\x00\x00\x01\x00\x01\x00\x00\x02\x02\x44\x01\x00;";
+
+    fn fetch(url: String) -> Result<HttpResponse, Error> {
+        if url == "http://example.com/img/red.gif" {
+            let mut raw = b"HTTP/1.1 200 OK\nContent-Type: image/gif\n\n".to_vec();
+            raw.extend_from_slice(RED_GIF);
+            return HttpResponse::from_bytes(raw);
+        }
+
+        let raw = match url.as_str() {
+            "http://example.com/img/broken.png" => {
+                "HTTP/1.1 200 OK\nDate: xx xx xx\n\nnot an image"
+            }
+            "http://example.com/css/a.css" => "HTTP/1.1 200 OK\nDate: xx xx xx\n\na{color:red;}",
+            "http://example.com/css/b.css" => "HTTP/1.1 200 OK\nDate: xx xx xx\n\nb{color:red;}",
+            "http://example.com/css/c.css" => {
+                "HTTP/1.1 200 OK\nDate: xx xx xx\n\np{width:50% !important;}\nq{content:'x\n}\nr{content:\"y"
+            }
+            "http://example.com/js/a.js" => "HTTP/1.1 200 OK\nDate: xx xx xx\n\nvar a=1;",
+            "http://example.com/js/b.js" => "HTTP/1.1 200 OK\nDate: xx xx xx\n\nvar b=2;",
+            "http://example.com/js/c.js" => "HTTP/1.1 200 OK\nDate: xx xx xx\n\nvar c=3;",
+            "http://example.com/missing.js" => "HTTP/1.1 404 NotFound\nDate: xx xx xx\n\n",
+            _ => return Err(Error::Network("connection refused".to_string())),
+        };
+        HttpResponse::new(raw.to_string())
+    }
+
+    fn load(html: &str) -> (Vec<String>, Vec<String>, Vec<Error>) {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let dom = window.borrow().document();
+        let mut loader =
+            SubresourceLoader::new("http://example.com/index.html".to_string(), Some(fetch));
+        let stylesheets = loader.load_stylesheets(dom.clone());
+        let scripts = loader.load_scripts(dom);
+        (stylesheets, scripts, loader.errors())
+    }
+
+    #[test]
+    fn test_load_stylesheets() {
+        let (stylesheets, _, errors) = load(
+            r#"<html><head><style>p{color:blue;}</style><link rel=" StyleSheet " href="css/a.css"><link rel="Alternate StyleSheet" href="css/b.css"><link rel=icon href=favicon.ico></head></html>"#,
+        );
+        assert_eq!(
+            vec!["p{color:blue;}".to_string(), "a{color:red;}".to_string()],
+            stylesheets
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_load_malformed_stylesheet() {
+        let (stylesheets, _, errors) =
+            load(r#"<html><head><link rel=stylesheet href="css/c.css"></head></html>"#);
+        assert!(errors.is_empty());
+        assert_eq!(1, stylesheets.len());
+
+        // the percentage, !important and the bad and unterminated strings don't stop parsing
+        let sheet = CssParser::new(CssTokenizer::new(stylesheets[0].clone())).parse_stylesheet();
+        assert_eq!(3, sheet.rules.len());
+    }
+
+    #[test]
+    fn test_load_scripts_in_order() {
+        let (_, scripts, errors) = load(
+            r#"<html><head><script src="js/a.js" defer></script><script>var x=0;</script><script src="/js/b.js" async></script></head><body><script src="js/c.js"></script></body></html>"#,
+        );
+        assert_eq!(
+            vec![
+                "var x=0;".to_string(),
+                "var b=2;".to_string(),
+                "var c=3;".to_string(),
+                "var a=1;".to_string()
+            ],
+            scripts
+        );
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn test_load_failures() {
+        let (stylesheets, scripts, errors) = load(
+            r#"<html><head><link rel=stylesheet href="http://down.example.com/a.css"><script src="missing.js"></script><script>var x=0;</script></head></html>"#,
+        );
+        assert!(stylesheets.is_empty());
+        assert_eq!(vec!["var x=0;".to_string()], scripts);
+        assert_eq!(2, errors.len());
+    }
+
+    #[test]
+    fn test_load_images() {
+        let window = HtmlParser::new(HtmlTokenizer::new(
+            r#"<html><body><img src="img/red.gif"><img src="/img/broken.png" alt="broken"><img alt="none"></body></html>"#.to_string(),
+        ))
+        .construct_tree();
+        let dom = window.borrow().document();
+        let mut loader =
+            SubresourceLoader::new("http://example.com/index.html".to_string(), Some(fetch));
+        loader.load_images(dom.clone());
+
+        let images: Vec<Option<Rc<Image>>> = get_element_nodes(Some(dom), &[ElementKind::Img])
+            .iter()
+            .map(|node| node.borrow().get_element().and_then(|e| e.image()))
+            .collect();
+        assert_eq!(3, images.len());
+        let red = images[0].clone().expect("failed to decode the image");
+        assert_eq!((1, 1), (red.width(), red.height()));
+        assert_eq!([0xff, 0, 0, 0xff], red.pixel(0, 0));
+        assert!(images[1].is_none());
+        assert!(images[2].is_none());
+        assert_eq!(1, loader.errors().len());
+    }
+}
@@ -0,0 +1,339 @@
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::helper::api::attribute;
+use crate::renderer::html::helper::api::get_elements_by_tag_name;
+use crate::renderer::html::helper::api::get_text_content;
+use crate::renderer::html::helper::api::strip_and_collapse_whitespace;
+use crate::url::resolve_url;
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// a link of the document to another resource, such as <link rel=icon href=...>
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkRelation {
+    // a single link type of the rel attribute, in lowercase
+    rel: String,
+    // the absolute URL of the href attribute
+    href: String,
+    // the type, hreflang and title attributes, which distinguish alternates
+    media_type: Option<String>,
+    hreflang: Option<String>,
+    title: Option<String>,
+}
+
+impl LinkRelation {
+    pub fn rel(&self) -> String {
+        self.rel.clone()
+    }
+
+    pub fn href(&self) -> String {
+        self.href.clone()
+    }
+
+    pub fn media_type(&self) -> Option<String> {
+        self.media_type.clone()
+    }
+
+    pub fn hreflang(&self) -> Option<String> {
+        self.hreflang.clone()
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/semantics.html#document-metadata
+// metadata of the document, which is extracted from <title>, <meta> and <link> elements
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    title: Option<String>,
+    language: Option<String>,
+    charset: Option<String>,
+    // https://html.spec.whatwg.org/multipage/semantics.html#attr-meta-name
+    // names are in lowercase, and the first one wins for duplicates
+    meta: BTreeMap<String, String>,
+    links: Vec<LinkRelation>,
+    // https://ogp.me/
+    // properties without the "og:" prefix, e.g. "title" and "image:width"
+    open_graph: BTreeMap<String, String>,
+}
+
+// link types which are collected, the others such as stylesheet are used for loading
+const LINK_TYPES: [&str; 5] = ["icon", "canonical", "alternate", "next", "prev"];
+
+impl PageMetadata {
+    pub fn new() -> Self {
+        Self {
+            title: None,
+            language: None,
+            charset: None,
+            meta: BTreeMap::new(),
+            links: Vec::new(),
+            open_graph: BTreeMap::new(),
+        }
+    }
+
+    // extracts the metadata from the document, where relative URLs are resolved against the
+    // base URL
+    pub fn from_document(document: &Rc<RefCell<Node>>, base_url: &str) -> Self {
+        let mut metadata = Self::new();
+
+        // https://html.spec.whatwg.org/multipage/dom.html#document.title
+        if let Some(title) = get_elements_by_tag_name(document, "title").first() {
+            let title = strip_and_collapse_whitespace(&get_text_content(title));
+            if !title.is_empty() {
+                metadata.title = Some(title);
+            }
+        }
+
+        // https://html.spec.whatwg.org/multipage/dom.html#the-lang-and-xml:lang-attributes
+        if let Some(html) = get_elements_by_tag_name(document, "html").first() {
+            metadata.language = attribute(html, "lang").filter(|lang| !lang.is_empty());
+        }
+
+        for meta in get_elements_by_tag_name(document, "meta") {
+            metadata.add_meta(&meta);
+        }
+
+        for link in get_elements_by_tag_name(document, "link") {
+            metadata.add_links(&link, base_url);
+        }
+
+        metadata
+    }
+
+    fn add_meta(&mut self, meta: &Rc<RefCell<Node>>) {
+        // https://html.spec.whatwg.org/multipage/semantics.html#attr-meta-charset
+        if let Some(charset) = attribute(meta, "charset") {
+            if self.charset.is_none() {
+                self.charset = Some(charset.trim().to_ascii_lowercase());
+            }
+        }
+
+        let content = match attribute(meta, "content") {
+            Some(content) => content,
+            None => return,
+        };
+
+        // https://html.spec.whatwg.org/multipage/semantics.html#attr-meta-http-equiv-content-type
+        if let Some(http_equiv) = attribute(meta, "http-equiv") {
+            if http_equiv.eq_ignore_ascii_case("content-type") && self.charset.is_none() {
+                self.charset = charset_from_content_type(&content);
+            }
+        }
+
+        if let Some(name) = attribute(meta, "name") {
+            self.meta
+                .entry(name.to_ascii_lowercase())
+                .or_insert(content.clone());
+        }
+
+        // open graph tags use the property attribute instead of name
+        if let Some(property) = attribute(meta, "property") {
+            if let Some(property) = property.strip_prefix("og:") {
+                self.open_graph
+                    .entry(property.to_string())
+                    .or_insert(content);
+            }
+        }
+    }
+
+    fn add_links(&mut self, link: &Rc<RefCell<Node>>, base_url: &str) {
+        let (rel, href) = match (attribute(link, "rel"), attribute(link, "href")) {
+            (Some(rel), Some(href)) => (rel, href),
+            _ => return,
+        };
+
+        // https://html.spec.whatwg.org/multipage/links.html#linkTypes
+        // rel is a set of space-separated link types
+        for link_type in rel.split_ascii_whitespace() {
+            let link_type = link_type.to_ascii_lowercase();
+            if !LINK_TYPES.contains(&link_type.as_str()) {
+                continue;
+            }
+            self.links.push(LinkRelation {
+                rel: link_type,
+                href: resolve_url(base_url, &href),
+                media_type: attribute(link, "type"),
+                hreflang: attribute(link, "hreflang"),
+                title: attribute(link, "title"),
+            });
+        }
+    }
+
+    pub fn title(&self) -> Option<String> {
+        self.title.clone()
+    }
+
+    pub fn language(&self) -> Option<String> {
+        self.language.clone()
+    }
+
+    pub fn charset(&self) -> Option<String> {
+        self.charset.clone()
+    }
+
+    pub fn meta(&self) -> BTreeMap<String, String> {
+        self.meta.clone()
+    }
+
+    // gets the content of <meta name=...>, where the name is case-insensitive
+    pub fn meta_content(&self, name: &str) -> Option<String> {
+        self.meta.get(&name.to_ascii_lowercase()).cloned()
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.meta_content("description")
+    }
+
+    pub fn links(&self) -> Vec<LinkRelation> {
+        self.links.clone()
+    }
+
+    // gets the links of the link type in document order
+    pub fn links_of(&self, rel: &str) -> Vec<LinkRelation> {
+        self.links
+            .iter()
+            .filter(|link| link.rel == rel)
+            .cloned()
+            .collect()
+    }
+
+    fn first_link(&self, rel: &str) -> Option<String> {
+        self.links_of(rel).first().map(|link| link.href())
+    }
+
+    pub fn icon(&self) -> Option<String> {
+        self.first_link("icon")
+    }
+
+    pub fn canonical(&self) -> Option<String> {
+        self.first_link("canonical")
+    }
+
+    pub fn next(&self) -> Option<String> {
+        self.first_link("next")
+    }
+
+    pub fn prev(&self) -> Option<String> {
+        self.first_link("prev")
+    }
+
+    pub fn alternates(&self) -> Vec<LinkRelation> {
+        self.links_of("alternate")
+    }
+
+    pub fn open_graph(&self) -> BTreeMap<String, String> {
+        self.open_graph.clone()
+    }
+
+    // gets the open graph property without the "og:" prefix, e.g. "title"
+    pub fn open_graph_property(&self, property: &str) -> Option<String> {
+        self.open_graph.get(property).cloned()
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#algorithm-for-extracting-a-character-encoding-from-a-meta-element
+// extracts the charset from a value such as "text/html; charset=utf-8"
+fn charset_from_content_type(content: &str) -> Option<String> {
+    let lowercase = content.to_ascii_lowercase();
+    let start = lowercase.find("charset")? + "charset".len();
+    let value = lowercase[start..]
+        .trim_start()
+        .strip_prefix('=')?
+        .trim_start();
+    let value = match value.strip_prefix(['"', '\'']) {
+        Some(quoted) => quoted.split(['"', '\'']).next()?,
+        None => value.split([';', ' ']).next()?,
+    };
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn metadata(html: &str) -> PageMetadata {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        PageMetadata::from_document(&document, "http://example.com/blog/post.html")
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(PageMetadata::new(), metadata("<p>no metadata</p>"));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata = metadata(
+            "<!doctype html><html lang=en><head><meta charset=UTF-8><title>  A\n  title </title><title>second</title><meta name=Description content=\"about\"><meta name=description content=\"ignored\"><meta property=\"og:title\" content=\"OG\"><meta property=\"og:image:width\" content=\"100\"><link rel=\"shortcut icon\" href=\"/favicon.ico\"><link rel=canonical href=\"post.html\"><link rel=alternate type=\"application/rss+xml\" title=RSS href=\"../feed\"><link rel=stylesheet href=a.css><link rel=next href=\"?page=2\"></head><body></body></html>",
+        );
+
+        assert_eq!(Some("A title".to_string()), metadata.title());
+        assert_eq!(Some("en".to_string()), metadata.language());
+        assert_eq!(Some("utf-8".to_string()), metadata.charset());
+        assert_eq!(Some("about".to_string()), metadata.description());
+        assert_eq!(
+            Some("OG".to_string()),
+            metadata.open_graph_property("title")
+        );
+        assert_eq!(
+            Some("100".to_string()),
+            metadata.open_graph_property("image:width")
+        );
+
+        assert_eq!(
+            Some("http://example.com/favicon.ico".to_string()),
+            metadata.icon()
+        );
+        assert_eq!(
+            Some("http://example.com/blog/post.html".to_string()),
+            metadata.canonical()
+        );
+        assert_eq!(
+            Some("http://example.com/blog/post.html?page=2".to_string()),
+            metadata.next()
+        );
+        assert_eq!(None, metadata.prev());
+
+        let alternates = metadata.alternates();
+        assert_eq!(1, alternates.len());
+        assert_eq!("http://example.com/feed", alternates[0].href());
+        assert_eq!(
+            Some("application/rss+xml".to_string()),
+            alternates[0].media_type()
+        );
+        assert_eq!(Some("RSS".to_string()), alternates[0].title());
+        // stylesheets are not metadata
+        assert_eq!(4, metadata.links().len());
+    }
+
+    #[test]
+    fn test_charset_from_content_type() {
+        assert_eq!(
+            Some("shift_jis".to_string()),
+            charset_from_content_type("text/html; charset=Shift_JIS")
+        );
+        assert_eq!(
+            Some("utf-8".to_string()),
+            charset_from_content_type("text/html;charset = \"utf-8\"")
+        );
+        assert_eq!(None, charset_from_content_type("text/html"));
+        assert_eq!(
+            Some("euc-jp".to_string()),
+            metadata("<meta http-equiv=Content-Type content=\"text/html; charset=euc-jp\">")
+                .charset()
+        );
+    }
+}
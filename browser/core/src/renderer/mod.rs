@@ -1,5 +1,12 @@
+pub mod accessibility;
 pub mod css;
+pub mod form;
 pub mod html;
+pub mod image;
 pub mod js;
 pub mod layout;
+pub mod loader;
+pub mod metadata;
 pub mod page;
+pub mod reader;
+pub mod refresh;
@@ -1,41 +1,116 @@
 use crate::browser::Browser;
+use crate::constants::MAX_FRAMES;
+use crate::constants::MAX_FRAME_DEPTH;
+use crate::constants::MIN_REFRESH_DELAY;
 use crate::display_item::DisplayItem;
+use crate::error::Error;
 use crate::http::HttpResponse;
+use crate::renderer::accessibility::tree::AccessibilityTree;
 use crate::renderer::css::cssom::StyleSheet;
 use crate::renderer::css::parser::CssParser;
 use crate::renderer::css::token::CssTokenizer;
+use crate::renderer::form::control::checkedness;
+use crate::renderer::form::control::control_type;
+use crate::renderer::form::control::form_owner;
+use crate::renderer::form::control::has_attribute;
+use crate::renderer::form::control::is_disabled;
+use crate::renderer::form::control::listed_elements;
+use crate::renderer::form::control::reset_form;
+use crate::renderer::form::control::select_next_option;
+use crate::renderer::form::control::set_checkedness;
+use crate::renderer::form::control::set_value;
+use crate::renderer::form::control::value;
+use crate::renderer::form::control::ControlType;
+use crate::renderer::form::submission::plan_submission;
+use crate::renderer::form::submission::FormSubmission;
+use crate::renderer::form::validation::validation_message;
+use crate::renderer::html::dom::ElementKind;
+use crate::renderer::html::dom::Location;
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeId;
+use crate::renderer::html::dom::NodeKind;
 use crate::renderer::html::dom::Window;
-use crate::renderer::html::helper::api::get_js_content;
-use crate::renderer::html::helper::api::get_style_content;
+use crate::renderer::html::event::dispatch_event;
+use crate::renderer::html::event::Event;
+use crate::renderer::html::helper::api::attribute;
+use crate::renderer::html::helper::api::get_document_base_url;
+use crate::renderer::html::helper::api::get_element_nodes;
+use crate::renderer::html::helper::api::get_elements_by_tag_name;
 use crate::renderer::html::parser::HtmlParser;
+use crate::renderer::html::serializer::serialize_children;
 use crate::renderer::html::token::HtmlTokenizer;
 use crate::renderer::js::ast::JsParser;
 use crate::renderer::js::runtime::JsRuntime;
 use crate::renderer::js::token::JsLexer;
+use crate::renderer::layout::layout_object::LayoutPoint;
+use crate::renderer::layout::layout_object::LayoutSize;
 use crate::renderer::layout::layout_view::LayoutView;
+use crate::renderer::loader::SubresourceLoader;
+use crate::renderer::metadata::PageMetadata;
+use crate::renderer::reader::Article;
+use crate::renderer::refresh::parse_refresh;
+use crate::renderer::refresh::ScheduledNavigation;
+use crate::url::resolve_url;
+use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
 use alloc::string::String;
+use alloc::string::ToString;
 use alloc::vec::Vec;
+use core::cell::Cell;
 use core::cell::RefCell;
 
 #[derive(Debug, Clone)]
 pub struct Page {
     browser: Weak<RefCell<Browser>>,
+    url: Option<String>,
+    // https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    base_url: Option<String>,
     frame: Option<Rc<RefCell<Window>>>,
     style: Option<StyleSheet>,
     layout_view: Option<LayoutView>,
     display_items: Vec<DisplayItem>,
+    metadata: PageMetadata,
+    // https://html.spec.whatwg.org/multipage/document-lifecycle.html#shared-declarative-refresh-steps
+    // the refresh requested by the page, which the UI performs after the delay
+    scheduled_navigation: Option<ScheduledNavigation>,
+    // errors while loading subresources, which are reported without aborting the page
+    load_errors: Vec<Error>,
+    // the runtime which ran the scripts, and runs event handler attributes later
+    js_runtime: Option<JsRuntime>,
+    // https://html.spec.whatwg.org/multipage/document-sequences.html#child-navigable
+    // the pages of the <iframe> elements, which have their own DOM tree, style and scripts
+    child_pages: BTreeMap<NodeId, Rc<RefCell<Page>>>,
+    // the origin and the base URL of the parent page, which about:blank and srcdoc documents
+    // of <iframe> inherit
+    inherited_origin: Option<String>,
+    parent_base_url: Option<String>,
+    // the URLs of the pages which contain this page, which prevent a page nesting itself
+    ancestor_urls: Vec<String>,
+    // the number of the child pages in the whole tree of pages, which is shared with the child
+    // pages and stops pages that fan out
+    frame_count: Rc<Cell<usize>>,
 }
 
 impl Page {
     pub fn new() -> Self {
         Self {
             browser: Weak::new(),
+            url: None,
+            base_url: None,
             frame: None,
             style: None,
             layout_view: None,
             display_items: Vec::new(),
+            metadata: PageMetadata::new(),
+            scheduled_navigation: None,
+            load_errors: Vec::new(),
+            js_runtime: None,
+            child_pages: BTreeMap::new(),
+            inherited_origin: None,
+            parent_base_url: None,
+            ancestor_urls: Vec::new(),
+            frame_count: Rc::new(Cell::new(0)),
         }
     }
 
@@ -51,10 +126,129 @@ impl Page {
         self.display_items = Vec::new();
     }
 
-    pub fn receive_response(&mut self, response: HttpResponse) {
-        self.create_frame(response.body());
+    pub fn url(&self) -> Option<String> {
+        self.url.clone()
+    }
+
+    // the URL which relative URLs are resolved against, which is set by <base href> or the URL
+    // of the document
+    pub fn base_url(&self) -> Option<String> {
+        self.base_url.clone().or_else(|| self.url.clone())
+    }
+
+    // resolves the URL of links, subresources and forms in the page
+    pub fn resolve_url(&self, input: &str) -> String {
+        match self.base_url() {
+            Some(base) => resolve_url(&base, input),
+            None => input.to_string(),
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/browsers.html#concept-origin
+    pub fn origin(&self) -> String {
+        match &self.inherited_origin {
+            Some(origin) => origin.clone(),
+            None => Location::new(self.url.clone().unwrap_or_default()).origin(),
+        }
+    }
+
+    // the pages of the <iframe> elements, keyed by the node id of the element
+    pub fn child_pages(&self) -> BTreeMap<NodeId, Rc<RefCell<Page>>> {
+        self.child_pages.clone()
+    }
+
+    // the window of the current document, which owns the document
+    pub fn window(&self) -> Option<Rc<RefCell<Window>>> {
+        self.frame.clone()
+    }
+
+    // serializes the current DOM tree, which is used to save the page as HTML
+    pub fn serialize(&self) -> Option<String> {
+        self.frame
+            .as_ref()
+            .map(|frame| serialize_children(&frame.borrow().document()))
+    }
+
+    // the accessibility tree of the current document, where the focused node is given by the UI
+    pub fn accessibility_tree(&self, focused: Option<NodeId>) -> Option<AccessibilityTree> {
+        let frame = self.frame.as_ref()?;
+        let layout_view = self.layout_view.as_ref()?;
+        Some(AccessibilityTree::new(
+            frame.borrow().document(),
+            layout_view,
+            focused,
+        ))
+    }
+
+    // the main content of the document, which the reader view shows
+    pub fn article(&self) -> Option<Article> {
+        let frame = self.frame.as_ref()?;
+        Article::from_document(&frame.borrow().document(), &self.base_url()?)
+    }
+
+    // the page of the reader view, which renders the simplified article in the same pipeline
+    pub fn reader_page(&self) -> Option<Page> {
+        let article = self.article()?;
+        let mut page = Page::new();
+        page.set_browser(self.browser.clone());
+        page.load_document(
+            self.url.clone().unwrap_or_default(),
+            article.to_html(),
+            None,
+        );
+        Some(page)
+    }
+
+    // the title, meta and link metadata of the document
+    pub fn metadata(&self) -> PageMetadata {
+        self.metadata.clone()
+    }
 
-        self.execute_js();
+    pub fn scheduled_navigation(&self) -> Option<ScheduledNavigation> {
+        self.scheduled_navigation.clone()
+    }
+
+    // cancels the refresh, e.g. when the user navigates away or stops it
+    pub fn cancel_scheduled_navigation(&mut self) {
+        self.scheduled_navigation = None;
+    }
+
+    pub fn load_errors(&self) -> Vec<Error> {
+        self.load_errors.clone()
+    }
+
+    pub fn receive_response(&mut self, url: String, response: HttpResponse) {
+        self.load_document(url, response.body(), Some(&response));
+    }
+
+    // loads the HTML of the document, which is given by the response or by srcdoc of <iframe>
+    fn load_document(&mut self, url: String, html: String, response: Option<&HttpResponse>) {
+        let fetcher = match self.browser.upgrade() {
+            Some(browser) => browser.borrow().fetcher(),
+            None => None,
+        };
+        self.url = Some(url);
+        // a new document cancels the refresh of the previous one
+        self.scheduled_navigation = None;
+
+        self.create_frame(html);
+        if let Some(response) = response {
+            self.schedule_refresh(response);
+        }
+
+        // subresources are resolved against the base URL, which is known after parsing
+        let mut loader = SubresourceLoader::new(self.base_url().unwrap_or_default(), fetcher);
+        self.load_stylesheets(&mut loader);
+
+        self.execute_js(&mut loader);
+
+        // images are loaded after the scripts, which may have added <img> to the document
+        if let Some(frame) = &self.frame {
+            loader.load_images(frame.borrow().document());
+        }
+        self.load_frames(&mut loader);
+
+        self.load_errors = loader.errors();
 
         self.set_layout_view();
 
@@ -66,14 +260,135 @@ impl Page {
         let frame = HtmlParser::new(html_tokenizer).construct_tree();
 
         let dom = frame.borrow().document();
-        let style = get_style_content(dom);
-        let css_tokenizer = CssTokenizer::new(style);
-        let cssom = CssParser::new(css_tokenizer).parse_stylesheet();
+        let url = self.url.clone().unwrap_or_default();
+        frame.borrow_mut().set_location(Location::new(url.clone()));
+        // https://html.spec.whatwg.org/multipage/urls-and-fetching.html#fallback-base-url
+        let fallback_base_url = match &self.parent_base_url {
+            Some(base) if url.starts_with("about:") => base.clone(),
+            _ => url,
+        };
+        self.base_url = Some(get_document_base_url(&dom, &fallback_base_url));
+        self.metadata = PageMetadata::from_document(&dom, &self.base_url().unwrap_or_default());
 
         self.frame = Some(frame);
+    }
+
+    // the Refresh header is processed first, and then the first <meta http-equiv=refresh>
+    fn schedule_refresh(&mut self, response: &HttpResponse) {
+        let mut values = Vec::new();
+        if let Ok(value) = response.header_value("Refresh") {
+            values.push(value);
+        }
+        if let Some(frame) = &self.frame {
+            let document = frame.borrow().document();
+            for meta in get_elements_by_tag_name(&document, "meta") {
+                let element = match meta.borrow().get_element() {
+                    Some(e) => e,
+                    None => continue,
+                };
+                let is_refresh = element
+                    .get_attribute("http-equiv")
+                    .is_some_and(|v| v.eq_ignore_ascii_case("refresh"));
+                if let (true, Some(content)) = (is_refresh, element.get_attribute("content")) {
+                    values.push(content);
+                }
+            }
+        }
+
+        let (delay, url) = match values.iter().find_map(|v| parse_refresh(v)) {
+            Some(refresh) => refresh,
+            None => return,
+        };
+
+        let min_delay = match self.browser.upgrade() {
+            Some(browser) => browser.borrow().min_refresh_delay(),
+            None => MIN_REFRESH_DELAY,
+        };
+        let url = match url {
+            Some(url) => self.resolve_url(&url),
+            None => self.url.clone().unwrap_or_default(),
+        };
+        self.scheduled_navigation = Some(ScheduledNavigation::new(url, delay.max(min_delay)));
+    }
+
+    fn load_stylesheets(&mut self, loader: &mut SubresourceLoader) {
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+
+        // all stylesheets are merged in document order
+        let mut cssom = StyleSheet::new();
+        for style in loader.load_stylesheets(dom) {
+            let css_tokenizer = CssTokenizer::new(style);
+            let stylesheet = CssParser::new(css_tokenizer).parse_stylesheet();
+            cssom.append_rules(stylesheet.rules);
+        }
+
         self.style = Some(cssom);
     }
 
+    // https://html.spec.whatwg.org/multipage/iframe-embed-object.html#process-the-iframe-attributes
+    // loads srcdoc or src of each <iframe> into a child page. about:blank is loaded when neither
+    // is given, or when the page would nest itself
+    fn load_frames(&mut self, loader: &mut SubresourceLoader) {
+        self.child_pages = BTreeMap::new();
+        let dom = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return,
+        };
+
+        let mut ancestor_urls = self.ancestor_urls.clone();
+        ancestor_urls.push(self.url.clone().unwrap_or_default());
+        if ancestor_urls.len() > MAX_FRAME_DEPTH {
+            return;
+        }
+        if self.ancestor_urls.is_empty() {
+            self.frame_count.set(0);
+        }
+
+        for iframe in get_element_nodes(Some(dom), &[ElementKind::Iframe]) {
+            let (id, element) = match (iframe.borrow().id(), iframe.borrow().get_element()) {
+                (Some(id), Some(element)) => (id, element),
+                _ => continue,
+            };
+            if self.frame_count.get() >= MAX_FRAMES {
+                break;
+            }
+            self.frame_count.set(self.frame_count.get() + 1);
+
+            let mut child = Page::new();
+            child.set_browser(self.browser.clone());
+            child.ancestor_urls = ancestor_urls.clone();
+            child.frame_count = self.frame_count.clone();
+            child.inherited_origin = Some(self.origin());
+            child.parent_base_url = self.base_url();
+
+            let src = element
+                .get_attribute("src")
+                .filter(|src| !src.trim().is_empty())
+                .map(|src| self.resolve_url(&src));
+            let response = match &src {
+                Some(src) if !ancestor_urls.contains(src) => loader.fetch_response(src),
+                _ => None,
+            };
+
+            match (element.get_attribute("srcdoc"), src, response) {
+                (Some(srcdoc), _, _) => {
+                    child.load_document("about:srcdoc".to_string(), srcdoc, None)
+                }
+                (None, Some(src), Some(response)) => {
+                    // the document of another URL has its own origin
+                    child.inherited_origin = None;
+                    child.receive_response(src, response);
+                }
+                _ => child.load_document("about:blank".to_string(), String::new(), None),
+            }
+
+            self.child_pages.insert(id, Rc::new(RefCell::new(child)));
+        }
+    }
+
     fn set_layout_view(&mut self) {
         let dom = match &self.frame {
             Some(frame) => frame.borrow().document(),
@@ -91,24 +406,722 @@ impl Page {
     }
 
     fn paint_tree(&mut self) {
-        if let Some(layout_view) = &self.layout_view {
-            self.display_items = layout_view.paint();
+        let display_items = match &self.layout_view {
+            Some(layout_view) => layout_view.paint(),
+            None => return,
+        };
+
+        // links are resolved here, so that the UI can navigate to them as they are
+        let mut items = Vec::new();
+        for item in display_items {
+            match item {
+                DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                    href,
+                    node_id,
+                } => items.push(DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                    href: href.map(|h| self.resolve_url(&h)),
+                    node_id,
+                }),
+                DisplayItem::Frame {
+                    node_id,
+                    layout_point,
+                    layout_size,
+                    ..
+                } => {
+                    items.push(item.clone());
+                    items.extend(self.frame_display_items(node_id, layout_point, layout_size));
+                }
+                item => items.push(item),
+            }
         }
+        self.display_items = items;
+    }
+
+    // the display items of the child page, which are moved into the box of <iframe> and
+    // clipped by it. the ids of the child's nodes are dropped, since they would refer to other
+    // nodes of this page, so form controls are painted as text and the UI can't click them
+    fn frame_display_items(
+        &self,
+        id: NodeId,
+        point: LayoutPoint,
+        size: LayoutSize,
+    ) -> Vec<DisplayItem> {
+        let child = match self.child_pages.get(&id) {
+            Some(child) => child.borrow().display_items(),
+            None => return Vec::new(),
+        };
+        let inside = |p: &LayoutPoint| {
+            p.x() >= 0 && p.y() >= 0 && p.x() < size.width() && p.y() < size.height()
+        };
+        let translate = |p: LayoutPoint| LayoutPoint::new(point.x() + p.x(), point.y() + p.y());
+
+        child
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Rect {
+                    style,
+                    layout_point,
+                    layout_size,
+                } if inside(&layout_point) => Some(DisplayItem::Rect {
+                    style,
+                    layout_point: translate(layout_point),
+                    layout_size,
+                }),
+                DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point,
+                    href,
+                    ..
+                } if inside(&layout_point) => Some(DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point: translate(layout_point),
+                    href,
+                    node_id: None,
+                }),
+                DisplayItem::FormControl {
+                    text,
+                    style,
+                    layout_point,
+                    ..
+                } if inside(&layout_point) => Some(DisplayItem::Text {
+                    text,
+                    style,
+                    layout_point: translate(layout_point),
+                    href: None,
+                    node_id: None,
+                }),
+                DisplayItem::Image {
+                    image,
+                    alt,
+                    style,
+                    layout_point,
+                    layout_size,
+                } if inside(&layout_point) => Some(DisplayItem::Image {
+                    image,
+                    alt,
+                    style,
+                    layout_point: translate(layout_point),
+                    layout_size,
+                }),
+                // the nested frame is painted as its box, since its id belongs to the child
+                DisplayItem::Frame {
+                    style,
+                    layout_point,
+                    layout_size,
+                    ..
+                } if inside(&layout_point) => Some(DisplayItem::Rect {
+                    style,
+                    layout_point: translate(layout_point),
+                    layout_size,
+                }),
+                _ => None,
+            })
+            .collect()
     }
 
-    fn execute_js(&mut self) {
+    fn execute_js(&mut self, loader: &mut SubresourceLoader) {
         let dom = match &self.frame {
             Some(frame) => frame.borrow().document(),
             None => return,
         };
 
-        let js = get_js_content(dom.clone());
-        let lexer = JsLexer::new(js);
+        // scripts run in document order, sharing the same global environment
+        let mut runtime = JsRuntime::new(dom.clone());
+        for js in loader.load_scripts(dom) {
+            let lexer = JsLexer::new(js);
+
+            let mut parser = JsParser::new(lexer);
+            let ast = parser.parse_ast();
+
+            runtime.execute(&ast);
+        }
+        self.js_runtime = Some(runtime);
+    }
+
+    // updates the layout and the display items after the DOM tree is changed
+    fn rerender(&mut self) {
+        self.set_layout_view();
+        self.paint_tree();
+    }
+
+    // the node of the current document with the id, which display items refer to
+    fn node(&self, id: NodeId) -> Option<Rc<RefCell<Node>>> {
+        let frame = self.frame.as_ref()?;
+        let arena = frame.borrow().arena();
+        let node = arena.borrow().get(id);
+        node
+    }
+
+    // the current value of the form control, which the UI edits
+    pub fn control_value(&self, id: NodeId) -> Option<String> {
+        let node = self.node(id)?;
+        control_type(&node).map(|_| value(&node))
+    }
+
+    // sets the value which the user typed into the form control
+    pub fn set_control_value(&mut self, id: NodeId, new_value: &str) {
+        let node = match self.node(id) {
+            Some(node) => node,
+            None => return,
+        };
+        if is_disabled(&node) || has_attribute(&node, "readonly") {
+            return;
+        }
+        set_value(&node, new_value);
+        self.rerender();
+    }
+
+    // dispatches the event which the UI created to the node, e.g. a key press on the focused
+    // control. returns false if a listener cancels the event
+    pub fn dispatch_event(&mut self, id: NodeId, event: &mut Event) -> bool {
+        match self.node(id) {
+            Some(node) => self.fire_event(&node, event),
+            None => true,
+        }
+    }
+
+    // https://dom.spec.whatwg.org/#concept-event-fire
+    // dispatches the event with the runtime of the document, which calls the listeners of
+    // scripts and the event handler attributes
+    fn fire_event(&mut self, node: &Rc<RefCell<Node>>, event: &mut Event) -> bool {
+        let document = match &self.frame {
+            Some(frame) => frame.borrow().document(),
+            None => return true,
+        };
+        let runtime = self
+            .js_runtime
+            .get_or_insert_with(|| JsRuntime::new(document));
+        let not_canceled = dispatch_event(node, event, Some(runtime));
+        // the listeners may change the DOM tree
+        self.rerender();
+        not_canceled
+    }
+
+    // https://html.spec.whatwg.org/multipage/webappapis.html#fire-a-click-event
+    // text nodes are never hit by the pointer, so the click is fired at their parent element
+    fn fire_click(&mut self, node: &Rc<RefCell<Node>>) -> bool {
+        let target = match node.borrow().kind() {
+            NodeKind::Text(_) => node.borrow().parent().upgrade(),
+            _ => Some(node.clone()),
+        };
+        match target {
+            Some(target) => self.fire_event(&target, &mut Event::new("click", true, true)),
+            None => true,
+        }
+    }
+
+    // https://html.spec.whatwg.org/multipage/links.html#following-hyperlinks-2
+    // clicks the link which contains the node, and returns the destination to navigate to
+    // unless a listener cancels the click
+    pub fn follow_link(&mut self, id: NodeId) -> Option<String> {
+        let node = self.node(id)?;
+        let mut link = Some(node.clone());
+        while let Some(n) = link.clone() {
+            let is_link = n.borrow().get_element().is_some_and(|e| {
+                matches!(e.local_name().as_str(), "a" | "area") && e.get_attribute("href").is_some()
+            });
+            if is_link {
+                break;
+            }
+            link = n.borrow().parent().upgrade();
+        }
+        let link = link?;
+
+        if !self.fire_click(&node) {
+            return None;
+        }
+        // the listeners may change the destination
+        let href = attribute(&link, "href")?;
+        Some(self.resolve_url(&href))
+    }
+
+    // https://html.spec.whatwg.org/multipage/interaction.html#activation-behaviour
+    // toggles checkboxes, checks radio buttons, selects the next option, and resets or submits
+    // the form. text fields are edited by the UI instead
+    pub fn activate_control(&mut self, id: NodeId) -> Result<Option<FormSubmission>, Error> {
+        let node = match self.node(id) {
+            Some(node) => node,
+            None => return Ok(None),
+        };
+        let control_type = match control_type(&node) {
+            Some(t) if !is_disabled(&node) => t,
+            _ => return Ok(None),
+        };
+
+        // https://html.spec.whatwg.org/multipage/input.html#the-input-element:legacy-pre-activation-behavior
+        // the listeners of the click see the new checkedness, which is restored if they cancel it
+        let was_checked = checkedness(&node);
+        match control_type {
+            ControlType::Checkbox => set_checkedness(&node, !was_checked),
+            ControlType::Radio => set_checkedness(&node, true),
+            _ => {}
+        }
+        if !self.fire_click(&node) {
+            if matches!(control_type, ControlType::Checkbox | ControlType::Radio) {
+                set_checkedness(&node, was_checked);
+                self.rerender();
+            }
+            return Ok(None);
+        }
+
+        let submission = match control_type {
+            ControlType::Checkbox | ControlType::Radio => None,
+            ControlType::Select => {
+                select_next_option(&node);
+                None
+            }
+            ControlType::Reset => {
+                if let Some(form) = form_owner(&node) {
+                    reset_form(&form);
+                }
+                None
+            }
+            t if t.is_submit_button() => match form_owner(&node) {
+                Some(form) => self.submit_form(&form, Some(&node))?,
+                None => None,
+            },
+            _ => None,
+        };
+
+        self.rerender();
+        Ok(submission)
+    }
 
-        let mut parser = JsParser::new(lexer);
-        let ast = parser.parse_ast();
+    // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#implicit-submission
+    // submits the form when the user presses Enter in a text field. the first submit button is
+    // the submitter, and a form without one is submitted only if it has a single text field
+    pub fn submit_implicitly(&mut self, id: NodeId) -> Result<Option<FormSubmission>, Error> {
+        let form = match self.node(id).and_then(|node| form_owner(&node)) {
+            Some(form) => form,
+            None => return Ok(None),
+        };
+        let controls = listed_elements(&form);
+
+        let default_button = controls
+            .iter()
+            .find(|c| control_type(c).is_some_and(|t| t.is_submit_button()));
+        if let Some(button) = default_button {
+            if is_disabled(button) {
+                return Ok(None);
+            }
+            return self.submit_form(&form, Some(button));
+        }
+
+        let blocking_fields = controls
+            .iter()
+            .filter(|c| {
+                control_type(c).is_some_and(|t| t.is_text_like() && t != ControlType::Textarea)
+            })
+            .count();
+        if blocking_fields > 1 {
+            return Ok(None);
+        }
+        self.submit_form(&form, None)
+    }
+
+    // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-submission-algorithm
+    // validates the controls and runs the onsubmit attribute, and then returns the request which
+    // the UI sends. invalid controls are reported as an error
+    fn submit_form(
+        &mut self,
+        form: &Rc<RefCell<Node>>,
+        submitter: Option<&Rc<RefCell<Node>>>,
+    ) -> Result<Option<FormSubmission>, Error> {
+        // https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#interactively-validate-the-constraints
+        let no_validate = has_attribute(form, "novalidate")
+            || submitter.is_some_and(|s| has_attribute(s, "formnovalidate"));
+        if !no_validate {
+            let messages = listed_elements(form)
+                .iter()
+                .filter_map(validation_message)
+                .collect::<Vec<String>>();
+            if !messages.is_empty() {
+                return Err(Error::UnexpectedInput(messages.join(" ")));
+            }
+        }
+
+        // https://html.spec.whatwg.org/multipage/indices.html#event-submit
+        // the submission is cancelled when a listener or the onsubmit attribute cancels it
+        if !self.fire_event(form, &mut Event::new("submit", true, true)) {
+            return Ok(None);
+        }
+
+        let document_url = self.url.clone().unwrap_or_default();
+        let base_url = self.base_url().unwrap_or_default();
+        Ok(Some(plan_submission(
+            form,
+            submitter,
+            &document_url,
+            &base_url,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alloc::string::ToString;
+    use crate::renderer::html::helper::api::get_element_by_id;
+    use alloc::format;
+    use alloc::vec;
+
+    fn load(url: &str, html: &str) -> Page {
+        let mut page = Page::new();
+        let response = HttpResponse::new(format!("HTTP/1.1 200 OK\nDate: xx xx xx\n\n{}", html))
+            .expect("failed to parse the response");
+        page.receive_response(url.to_string(), response);
+        page
+    }
+
+    fn hrefs(page: &Page) -> Vec<Option<String>> {
+        page.display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { href, .. } => Some(href),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_resolve_links_against_document_url() {
+        let page = load(
+            "http://example.com/dir/index.html",
+            "<html><body><a href=\"next.html\">next</a><p>text</p></body></html>",
+        );
+        assert_eq!(
+            Some("http://example.com/dir/index.html".to_string()),
+            page.base_url()
+        );
+        let window = page.window().expect("no window");
+        assert_eq!(
+            "http://example.com/dir/index.html",
+            window.borrow().location().href()
+        );
+        assert_eq!(
+            vec![Some("http://example.com/dir/next.html".to_string()), None],
+            hrefs(&page)
+        );
+    }
+
+    #[test]
+    fn test_resolve_links_against_base_element() {
+        let page = load(
+            "http://example.com/dir/index.html",
+            "<html><head><base href=\"/other/\"></head><body><a href=\"next.html\"><b>next</b></a></body></html>",
+        );
+        assert_eq!(
+            Some("http://example.com/other/".to_string()),
+            page.base_url()
+        );
+        assert_eq!("http://example.com/other/x.css", page.resolve_url("x.css"));
+        assert_eq!(
+            vec![Some("http://example.com/other/next.html".to_string())],
+            hrefs(&page)
+        );
+    }
+
+    #[test]
+    fn test_schedule_meta_refresh() {
+        let mut page = load(
+            "http://example.com/dir/index.html",
+            "<html><head><meta http-equiv=Refresh content=\"0; url=next.html\"></head></html>",
+        );
+        // the minimum delay clamps the immediate refresh
+        assert_eq!(
+            Some(ScheduledNavigation::new(
+                "http://example.com/dir/next.html".to_string(),
+                MIN_REFRESH_DELAY
+            )),
+            page.scheduled_navigation()
+        );
+
+        page.cancel_scheduled_navigation();
+        assert_eq!(None, page.scheduled_navigation());
+    }
+
+    #[test]
+    fn test_schedule_refresh_header() {
+        let mut page = Page::new();
+        let response = HttpResponse::new(
+            "HTTP/1.1 200 OK\nrefresh: 5\n\n<meta http-equiv=refresh content=\"3; url=/meta\">"
+                .to_string(),
+        )
+        .expect("failed to parse the response");
+        page.receive_response("http://example.com/".to_string(), response);
+        // the header takes priority, and reloads the page itself without a URL
+        assert_eq!(
+            Some(ScheduledNavigation::new(
+                "http://example.com/".to_string(),
+                5
+            )),
+            page.scheduled_navigation()
+        );
+
+        // a new response without a refresh resets the schedule
+        let response = HttpResponse::new("HTTP/1.1 200 OK\nDate: xx xx xx\n\n<p>a</p>".to_string())
+            .expect("failed to parse the response");
+        page.receive_response("http://example.com/a".to_string(), response);
+        assert_eq!(None, page.scheduled_navigation());
+    }
+
+    fn node_id(page: &Page, id: &str) -> NodeId {
+        let document = page.window().expect("no window").borrow().document();
+        get_element_by_id(Some(document), &id.to_string())
+            .and_then(|n| n.borrow().id())
+            .expect("no element")
+    }
+
+    #[test]
+    fn test_submit_form() {
+        let mut page = load(
+            "http://example.com/dir/index.html",
+            "<html><body><form action=search><input id=q name=q required><input type=checkbox id=c name=c><button id=go>Go</button></form><form id=post method=post onsubmit=\"return false\"><input id=only name=x></form></body></html>",
+        );
+        let q = node_id(&page, "q");
+
+        // the required field blocks the submission
+        assert!(page.activate_control(node_id(&page, "go")).is_err());
+
+        page.set_control_value(q, "rust lang");
+        assert_eq!(Some("rust lang".to_string()), page.control_value(q));
+        page.activate_control(node_id(&page, "c"))
+            .expect("failed to activate the checkbox");
+        let submission = page
+            .submit_implicitly(q)
+            .expect("failed to submit the form")
+            .expect("no submission");
+        assert_eq!(
+            "http://example.com/dir/search?q=rust+lang&c=on",
+            submission.url()
+        );
+
+        // the text field is rendered with its value
+        assert!(page.display_items().iter().any(|item| matches!(
+            item,
+            DisplayItem::FormControl { text, .. } if text.starts_with("[rust lang ")
+        )));
+
+        // onsubmit cancels the submission
+        assert_eq!(
+            None,
+            page.submit_implicitly(node_id(&page, "only"))
+                .expect("failed to submit the form")
+        );
+    }
+
+    #[test]
+    fn test_events() {
+        let mut page = load(
+            "http://example.com/dir/index.html",
+            "<html><body><a href=next.html>next</a><a href=stay.html onclick=\"return false\">stay</a><input type=checkbox id=c><p id=log>none</p><script>function cancel(e) { e.preventDefault(); } function log(e) { var l=document.getElementById(\"log\"); l.textContent=e.type; } var c=document.getElementById(\"c\"); c.addEventListener(\"click\", cancel); document.addEventListener(\"click\", log, true); document.addEventListener(\"input\", log);</script></body></html>",
+        );
+        let links = page
+            .display_items()
+            .into_iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text {
+                    href: Some(_),
+                    node_id,
+                    ..
+                } => node_id,
+                _ => None,
+            })
+            .collect::<Vec<NodeId>>();
+        assert_eq!(2, links.len());
+        let log = |page: &Page| {
+            let document = page.window().expect("no window").borrow().document();
+            let log = get_element_by_id(Some(document), &"log".to_string()).expect("no log");
+            serialize_children(&log)
+        };
+
+        assert_eq!(
+            Some("http://example.com/dir/next.html".to_string()),
+            page.follow_link(links[0])
+        );
+        // the capture listener of the document sees the click
+        assert_eq!("click", log(&page));
+
+        // the onclick attribute cancels the navigation
+        assert_eq!(None, page.follow_link(links[1]));
+
+        // the listener cancels the click, which restores the checkedness
+        let c = node_id(&page, "c");
+        page.activate_control(c)
+            .expect("failed to activate the checkbox");
+        let checkbox = page.node(c).expect("no checkbox");
+        assert!(!checkedness(&checkbox));
+
+        // the UI dispatches its own events
+        let mut event = Event::new("input", true, false);
+        assert!(page.dispatch_event(c, &mut event));
+        assert_eq!("input", log(&page));
+    }
+
+    #[test]
+    fn test_iframe() {
+        let page = load(
+            "http://example.com/dir/index.html",
+            "<html><body><p>top</p><iframe id=doc width=100 height=20 srcdoc=\"<p id=inner>inner</p><a href=next.html>next</a>\"></iframe><iframe id=blank src=\"\"></iframe></body></html>",
+        );
+        let children = page.child_pages();
+        assert_eq!(2, children.len());
+
+        // srcdoc has its own DOM tree, and inherits the origin and the base URL of the parent
+        let child = children.get(&node_id(&page, "doc")).expect("no child page");
+        assert_eq!(Some("about:srcdoc".to_string()), child.borrow().url());
+        assert_eq!("http://example.com", child.borrow().origin());
+        assert_eq!(
+            Some("http://example.com/dir/index.html".to_string()),
+            child.borrow().base_url()
+        );
+        let document = page.window().expect("no window").borrow().document();
+        assert!(get_element_by_id(Some(document), &"inner".to_string()).is_none());
+        node_id(&child.borrow(), "inner");
+
+        let blank = children
+            .get(&node_id(&page, "blank"))
+            .expect("no child page");
+        assert_eq!(Some("about:blank".to_string()), blank.borrow().url());
+        assert_eq!("http://example.com", blank.borrow().origin());
+
+        // the items of the child page follow the frame, and are moved into its box
+        let items = page.display_items();
+        let (frame_point, frame_size) = items
+            .iter()
+            .find_map(|item| match item {
+                DisplayItem::Frame {
+                    layout_point,
+                    layout_size,
+                    ..
+                } => Some((*layout_point, *layout_size)),
+                _ => None,
+            })
+            .expect("no frame");
+        assert_eq!(LayoutSize::new(100, 20), frame_size);
+        let inner = items
+            .iter()
+            .find_map(|item| match item {
+                DisplayItem::Text {
+                    text, layout_point, ..
+                } if text == "inner" => Some(*layout_point),
+                _ => None,
+            })
+            .expect("no text of the child page");
+        assert!(inner.x() >= frame_point.x() && inner.y() >= frame_point.y());
+        // the link below the box of the frame is clipped
+        assert_eq!(vec![None, None], hrefs(&page));
+    }
+
+    #[test]
+    fn test_nested_frame_click() {
+        // the child documents have the same structure, so their nodes have the same ids as
+        // the nodes of the parent
+        let mut page = load(
+            "http://example.com/dir/index.html",
+            "<html><body><a href=top.html>top</a><iframe width=300 height=100 srcdoc=\"<a href=inner.html onclick='return false'>inner</a><iframe srcdoc='<p>deep</p>'></iframe>\"></iframe></body></html>",
+        );
+        let items = page.display_items();
+
+        // the link of the child is followed by its URL, since its node isn't in this document
+        let inner = items
+            .iter()
+            .find_map(|item| match item {
+                DisplayItem::Text {
+                    text,
+                    href,
+                    node_id,
+                    ..
+                } if text == "inner" => Some((href.clone(), *node_id)),
+                _ => None,
+            })
+            .expect("no link of the child page");
+        assert_eq!(
+            (Some("http://example.com/dir/inner.html".to_string()), None),
+            inner
+        );
+
+        // the frames of this page are the only frames, and the nested one is a box
+        let frames = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Frame { node_id, .. } => Some(*node_id),
+                _ => None,
+            })
+            .collect::<Vec<NodeId>>();
+        assert_eq!(
+            page.child_pages().keys().copied().collect::<Vec<NodeId>>(),
+            frames
+        );
+        assert!(items.iter().any(|item| matches!(
+            item,
+            DisplayItem::Text { text, node_id: None, .. } if text == "deep"
+        )));
+
+        // clicking any of the nodes reaches the link of this page only
+        let ids = items
+            .iter()
+            .filter_map(|item| match item {
+                DisplayItem::Text { node_id, .. } => *node_id,
+                DisplayItem::FormControl { node_id, .. } | DisplayItem::Frame { node_id, .. } => {
+                    Some(*node_id)
+                }
+                _ => None,
+            })
+            .collect::<Vec<NodeId>>();
+        let destinations = ids
+            .into_iter()
+            .filter_map(|id| page.follow_link(id))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            vec!["http://example.com/dir/top.html".to_string()],
+            destinations
+        );
+    }
+
+    #[test]
+    fn test_frame_count() {
+        // each of the frames has frames, and the total number of the child pages is capped
+        let inner = "<iframe></iframe>".repeat(8);
+        let html = format!("<iframe srcdoc=\"{}\"></iframe>", inner).repeat(8);
+        let page = load("http://example.com/", &html);
+
+        fn count(page: &Page) -> usize {
+            page.child_pages()
+                .values()
+                .map(|child| 1 + count(&child.borrow()))
+                .sum()
+        }
+        // the frames are loaded in tree order, so the fourth one takes the last 4 of 32
+        assert_eq!(4, page.child_pages().len());
+        assert_eq!(MAX_FRAMES, count(&page));
+    }
 
-        let mut runtime = JsRuntime::new(dom);
-        runtime.execute(&ast);
+    #[test]
+    fn test_reader_page() {
+        let page = load(
+            "http://example.com/news/story.html",
+            "<html><head><title>Release notes | Example</title></head><body><nav><a href=/>Home</a></nav><div class=post><h1>Release notes</h1><p>The new release makes builds faster, errors clearer, and the <a href=docs.html>docs</a> better.</p></div></body></html>",
+        );
+        let reader = page.reader_page().expect("no reader page");
+        assert_eq!(Some("Release notes".to_string()), reader.metadata().title());
+        // the menu is dropped, and the links keep the destinations of the page
+        assert_eq!(
+            vec![
+                None,
+                None,
+                Some("http://example.com/news/docs.html".to_string()),
+                None
+            ],
+            hrefs(&reader)
+        );
     }
 }
@@ -0,0 +1,805 @@
+use crate::renderer::html::dom::Node;
+use crate::renderer::html::dom::NodeId;
+use crate::renderer::html::dom::NodeKind;
+use crate::renderer::html::helper::api::get_elements_by_tag_name;
+use crate::renderer::html::helper::api::get_text_content;
+use crate::renderer::html::helper::api::heading_level;
+use crate::renderer::html::helper::api::strip_and_collapse_whitespace;
+use crate::renderer::html::serializer::escape_string;
+use crate::url::resolve_url;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// a run of text in a paragraph, which is a link when it has the destination
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextRun {
+    text: String,
+    href: Option<String>,
+}
+
+impl TextRun {
+    pub fn new(text: String, href: Option<String>) -> Self {
+        Self { text, href }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.clone()
+    }
+
+    pub fn href(&self) -> Option<String> {
+        self.href.clone()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArticleBlock {
+    Heading { level: usize, text: String },
+    Paragraph(Vec<TextRun>),
+}
+
+// https://github.com/mozilla/readability
+// the main content of a page, which is found by scoring the blocks by the density of their text,
+// and is simplified to the title, the byline, the headings, the paragraphs and the links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Article {
+    title: String,
+    byline: Option<String>,
+    blocks: Vec<ArticleBlock>,
+}
+
+// the elements which are never a part of the article
+const SKIPPED_ELEMENTS: [&str; 13] = [
+    "aside", "button", "footer", "form", "iframe", "input", "nav", "noscript", "script", "select",
+    "style", "template", "textarea",
+];
+
+// the elements which separate paragraphs
+const BLOCK_ELEMENTS: [&str; 38] = [
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+// the words of class and id, which make a block more or less likely a part of the article
+const POSITIVE_WORDS: [&str; 10] = [
+    "article", "blog", "body", "content", "entry", "main", "page", "post", "story", "text",
+];
+const NEGATIVE_WORDS: [&str; 13] = [
+    "ad-", "banner", "comment", "footer", "footnote", "masthead", "menu", "nav", "related",
+    "share", "sidebar", "sponsor", "widget",
+];
+const BYLINE_WORDS: [&str; 4] = ["author", "byline", "dateline", "writtenby"];
+
+// separators between the title of the article and the name of the site
+const TITLE_SEPARATORS: [&str; 5] = [" | ", " - ", " \u{2013} ", " \u{2014} ", " :: "];
+
+impl Article {
+    // extracts the article from the document, where the links are resolved against the base
+    // URL. it's None when the document has no text
+    pub fn from_document(document: &Rc<RefCell<Node>>, base_url: &str) -> Option<Self> {
+        let body = get_elements_by_tag_name(document, "body")
+            .into_iter()
+            .next()?;
+        let title = article_title(document);
+        let (byline, byline_node) = byline(document, &body);
+
+        let mut extractor = BlockExtractor {
+            base_url: base_url.to_string(),
+            byline: byline_node,
+            blocks: Vec::new(),
+        };
+        let (top, nodes) = top_candidates(&body);
+        for node in nodes {
+            // the siblings of the top candidate are added as blocks of their own
+            if Rc::ptr_eq(&node, &top) {
+                extractor.collect_children(&node);
+            } else {
+                extractor.collect_block(&node);
+            }
+        }
+
+        // the heading which repeats the title is shown as the title
+        let blocks = extractor
+            .blocks
+            .into_iter()
+            .filter(|block| !matches!(block, ArticleBlock::Heading { text, .. } if *text == title))
+            .collect::<Vec<ArticleBlock>>();
+        if blocks.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            title,
+            byline,
+            blocks,
+        })
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone()
+    }
+
+    pub fn byline(&self) -> Option<String> {
+        self.byline.clone()
+    }
+
+    pub fn blocks(&self) -> Vec<ArticleBlock> {
+        self.blocks.clone()
+    }
+
+    // the simplified document, which is rendered by the same pipeline as the page
+    pub fn to_html(&self) -> String {
+        let title = escape_string(&self.title, false);
+        let mut html = format!(
+            "<html><head><title>{}</title></head><body><h1>{}</h1>",
+            title, title
+        );
+        if let Some(byline) = &self.byline {
+            html.push_str(&format!("<p><i>{}</i></p>", escape_string(byline, false)));
+        }
+        for block in &self.blocks {
+            match block {
+                ArticleBlock::Heading { level, text } => {
+                    let level = (*level).clamp(1, 6);
+                    html.push_str(&format!(
+                        "<h{}>{}</h{}>",
+                        level,
+                        escape_string(text, false),
+                        level
+                    ));
+                }
+                ArticleBlock::Paragraph(runs) => {
+                    html.push_str("<p>");
+                    for run in runs {
+                        let text = escape_string(&run.text, false);
+                        match &run.href {
+                            Some(href) => html.push_str(&format!(
+                                "<a href=\"{}\">{}</a>",
+                                escape_string(href, true),
+                                text
+                            )),
+                            None => html.push_str(&text),
+                        }
+                    }
+                    html.push_str("</p>");
+                }
+            }
+        }
+        html.push_str("</body></html>");
+        html
+    }
+
+    // the text of the article, where the blocks are separated by empty lines
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![self.title.clone()];
+        if let Some(byline) = &self.byline {
+            lines.push(byline.clone());
+        }
+        for block in &self.blocks {
+            match block {
+                ArticleBlock::Heading { text, .. } => lines.push(text.clone()),
+                ArticleBlock::Paragraph(runs) => {
+                    lines.push(runs.iter().map(|run| run.text.as_str()).collect())
+                }
+            }
+        }
+        lines.join("\n\n") + "\n"
+    }
+
+    // https://spec.commonmark.org/
+    // the article in Markdown, where the links are inline links
+    pub fn to_markdown(&self) -> String {
+        let mut lines = vec![format!("# {}", escape_markdown(&self.title))];
+        if let Some(byline) = &self.byline {
+            lines.push(format!("*{}*", escape_markdown(byline)));
+        }
+        for block in &self.blocks {
+            match block {
+                ArticleBlock::Heading { level, text } => lines.push(format!(
+                    "{} {}",
+                    "#".repeat((*level).clamp(1, 6)),
+                    escape_markdown(text)
+                )),
+                ArticleBlock::Paragraph(runs) => lines.push(
+                    runs.iter()
+                        .map(|run| match &run.href {
+                            Some(href) => format!(
+                                "[{}](<{}>)",
+                                escape_markdown(&run.text),
+                                escape_link_destination(href)
+                            ),
+                            None => escape_markdown(&run.text),
+                        })
+                        .collect(),
+                ),
+            }
+        }
+        lines.join("\n\n") + "\n"
+    }
+}
+
+// https://spec.commonmark.org/0.31.2/#backslash-escapes
+fn escape_markdown(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        if matches!(c, '\\' | '*' | '_' | '[' | ']' | '`' | '<' | '#') {
+            result.push('\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+// https://spec.commonmark.org/0.31.2/#link-destination
+// a destination in '<' and '>' can't contain line endings or unescaped '<' and '>',
+// and a trailing backslash would escape the closing '>'
+fn escape_link_destination(href: &str) -> String {
+    let mut result = String::new();
+    for c in href.chars() {
+        match c {
+            '<' => result.push_str("%3C"),
+            '>' => result.push_str("%3E"),
+            '\\' => result.push_str("%5C"),
+            '\n' => result.push_str("%0A"),
+            '\r' => result.push_str("%0D"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+// builds the blocks of the article from the DOM tree
+struct BlockExtractor {
+    base_url: String,
+    // the element of the byline, which is shown apart from the blocks
+    byline: Option<NodeId>,
+    blocks: Vec<ArticleBlock>,
+}
+
+impl BlockExtractor {
+    // the blocks of the children, where the inline children between blocks make a paragraph
+    fn collect_children(&mut self, node: &Rc<RefCell<Node>>) {
+        let mut runs = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            if is_block(&c) {
+                self.push_paragraph(core::mem::take(&mut runs));
+                self.collect_block(&c);
+            } else {
+                runs.extend(self.runs(&c, None));
+            }
+            child = c.borrow().next_sibling();
+        }
+        self.push_paragraph(runs);
+    }
+
+    fn collect_block(&mut self, node: &Rc<RefCell<Node>>) {
+        if self.is_skipped(node) {
+            return;
+        }
+
+        if let Some(level) = heading_level(node) {
+            let text = strip_and_collapse_whitespace(&inner_text(node));
+            if !text.is_empty() {
+                self.blocks.push(ArticleBlock::Heading { level, text });
+            }
+            return;
+        }
+
+        if has_block_children(node) {
+            self.collect_children(node);
+        } else {
+            let runs = self.runs(node, None);
+            self.push_paragraph(runs);
+        }
+    }
+
+    // the runs of the inline contents, which inherit the destination of the enclosing link
+    fn runs(&self, node: &Rc<RefCell<Node>>, href: Option<String>) -> Vec<TextRun> {
+        let element = match node.borrow().kind() {
+            NodeKind::Text(text) => return vec![TextRun::new(text, href)],
+            NodeKind::Element(e) => e,
+            _ => return Vec::new(),
+        };
+        if self.is_skipped(node) {
+            return Vec::new();
+        }
+        if element.local_name() == "br" {
+            return vec![TextRun::new(" ".to_string(), href)];
+        }
+
+        let href = match element.get_attribute("href") {
+            Some(h) if element.local_name() == "a" => Some(resolve_url(&self.base_url, &h)),
+            _ => href,
+        };
+        let mut runs = Vec::new();
+        let mut child = node.borrow().first_child();
+        while let Some(c) = child {
+            runs.extend(self.runs(&c, href.clone()));
+            child = c.borrow().next_sibling();
+        }
+        runs
+    }
+
+    fn push_paragraph(&mut self, runs: Vec<TextRun>) {
+        let runs = normalize_runs(runs);
+        if !runs.is_empty() {
+            self.blocks.push(ArticleBlock::Paragraph(runs));
+        }
+    }
+
+    // the elements which are not shown, and the link lists such as the menus
+    fn is_skipped(&self, node: &Rc<RefCell<Node>>) -> bool {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => return false,
+        };
+        SKIPPED_ELEMENTS.contains(&element.local_name().as_str())
+            || element.get_attribute("hidden").is_some()
+            || element
+                .get_attribute("aria-hidden")
+                .is_some_and(|v| v.eq_ignore_ascii_case("true"))
+            || (node.borrow().id().is_some() && node.borrow().id() == self.byline)
+            || (class_weight(node) < 0.0 && link_density(node) > 0.5)
+    }
+}
+
+// collapses the white space across the runs, and merges the adjacent runs of the same link
+fn normalize_runs(runs: Vec<TextRun>) -> Vec<TextRun> {
+    let mut result: Vec<TextRun> = Vec::new();
+    // the leading white space of the paragraph is dropped
+    let mut ends_with_space = true;
+    for run in runs {
+        let mut text = String::new();
+        for c in run.text.chars() {
+            if c.is_ascii_whitespace() {
+                if !ends_with_space {
+                    text.push(' ');
+                    ends_with_space = true;
+                }
+            } else {
+                text.push(c);
+                ends_with_space = false;
+            }
+        }
+        if text.is_empty() {
+            continue;
+        }
+        match result.last_mut() {
+            Some(last) if last.href == run.href => last.text.push_str(&text),
+            _ => result.push(TextRun::new(text, run.href)),
+        }
+    }
+
+    if let Some(last) = result.last_mut() {
+        last.text = last.text.trim_end().to_string();
+    }
+    result.retain(|run| !run.text.is_empty());
+    result
+}
+
+// https://github.com/mozilla/readability/blob/main/Readability.js
+// scores the ancestors of the paragraphs by their text, and returns the best one with itself and
+// its siblings which look like a part of the article in the document order. the body is used
+// when nothing is scored
+fn top_candidates(body: &Rc<RefCell<Node>>) -> (Rc<RefCell<Node>>, Vec<Rc<RefCell<Node>>>) {
+    let mut scores: BTreeMap<NodeId, (Rc<RefCell<Node>>, f64)> = BTreeMap::new();
+    for node in descendants_of(body) {
+        if !is_scored_paragraph(&node) || has_skipped_ancestor(&node) {
+            continue;
+        }
+        let text = strip_and_collapse_whitespace(&inner_text(&node));
+        let length = text.chars().count();
+        if length < 25 {
+            continue;
+        }
+
+        // a point, a point per comma, and a point per 100 characters up to 3
+        let score = 1.0 + text.matches(',').count() as f64 + (length / 100).min(3) as f64;
+
+        let mut ancestor = node.borrow().parent().upgrade();
+        for level in 0..3 {
+            let a = match ancestor {
+                Some(a) => a,
+                None => break,
+            };
+            let id = match (a.borrow().id(), a.borrow().get_element()) {
+                (Some(id), Some(_)) => id,
+                _ => break,
+            };
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f64 * 3.0,
+            };
+            let entry = scores
+                .entry(id)
+                .or_insert_with(|| (a.clone(), initial_score(&a)));
+            entry.1 += score / divider;
+
+            ancestor = a.borrow().parent().upgrade();
+        }
+    }
+
+    // links lower the score, since lists of links are menus rather than articles
+    let final_score = |node: &Rc<RefCell<Node>>, score: f64| score * (1.0 - link_density(node));
+    let top = scores
+        .values()
+        .map(|(node, score)| (node.clone(), final_score(node, *score)))
+        .fold(
+            None,
+            |best: Option<(Rc<RefCell<Node>>, f64)>, candidate| match best {
+                Some(b) if b.1 >= candidate.1 => Some(b),
+                _ => Some(candidate),
+            },
+        );
+    let (top, top_score) = match top {
+        Some(top) => top,
+        None => return (body.clone(), vec![body.clone()]),
+    };
+
+    let parent = match top.borrow().parent().upgrade() {
+        Some(parent) if !Rc::ptr_eq(&top, body) => parent,
+        _ => return (top.clone(), vec![top.clone()]),
+    };
+    let threshold = (top_score * 0.2).max(10.0);
+    let mut nodes = Vec::new();
+    let mut sibling = parent.borrow().first_child();
+    while let Some(s) = sibling {
+        let score = s
+            .borrow()
+            .id()
+            .and_then(|id| scores.get(&id))
+            .map(|(node, score)| final_score(node, *score))
+            .unwrap_or(0.0);
+        let is_element = s.borrow().get_element().is_some();
+        if Rc::ptr_eq(&s, &top) || (is_element && (score >= threshold || is_article_paragraph(&s)))
+        {
+            nodes.push(s.clone());
+        }
+        sibling = s.borrow().next_sibling();
+    }
+    (top, nodes)
+}
+
+// a sibling paragraph which has long text and few links
+fn is_article_paragraph(node: &Rc<RefCell<Node>>) -> bool {
+    if local_name(node).as_deref() != Some("p") {
+        return false;
+    }
+    let text = strip_and_collapse_whitespace(&inner_text(node));
+    let length = text.chars().count();
+    let link_density = link_density(node);
+    (length > 80 && link_density < 0.25)
+        || (length > 0 && link_density == 0.0 && text.contains(". "))
+}
+
+// the paragraphs whose text is scored, including <div> used as a paragraph
+fn is_scored_paragraph(node: &Rc<RefCell<Node>>) -> bool {
+    match local_name(node).as_deref() {
+        Some("p") | Some("pre") | Some("td") | Some("blockquote") => true,
+        Some("div") => !has_block_children(node),
+        _ => false,
+    }
+}
+
+fn has_skipped_ancestor(node: &Rc<RefCell<Node>>) -> bool {
+    let mut current = node.borrow().parent().upgrade();
+    while let Some(n) = current {
+        if local_name(&n).is_some_and(|name| SKIPPED_ELEMENTS.contains(&name.as_str())) {
+            return true;
+        }
+        current = n.borrow().parent().upgrade();
+    }
+    false
+}
+
+fn initial_score(node: &Rc<RefCell<Node>>) -> f64 {
+    let score = match local_name(node).as_deref() {
+        Some("div") => 5.0,
+        Some("pre") | Some("td") | Some("blockquote") => 3.0,
+        Some("address") | Some("ol") | Some("ul") | Some("dl") | Some("dd") | Some("dt")
+        | Some("li") | Some("form") => -3.0,
+        Some("h1") | Some("h2") | Some("h3") | Some("h4") | Some("h5") | Some("h6")
+        | Some("th") => -5.0,
+        _ => 0.0,
+    };
+    score + class_weight(node)
+}
+
+// 25 points for each of class and id which look like the article, and -25 points for those
+// which look like the other parts of the page
+fn class_weight(node: &Rc<RefCell<Node>>) -> f64 {
+    let element = match node.borrow().get_element() {
+        Some(e) => e,
+        None => return 0.0,
+    };
+    let mut weight = 0.0;
+    for name in ["class", "id"] {
+        let value = match element.get_attribute(name) {
+            Some(value) => value.to_ascii_lowercase(),
+            None => continue,
+        };
+        if NEGATIVE_WORDS.iter().any(|word| value.contains(word)) {
+            weight -= 25.0;
+        }
+        if POSITIVE_WORDS.iter().any(|word| value.contains(word)) {
+            weight += 25.0;
+        }
+    }
+    weight
+}
+
+// the ratio of the text in links to all the text of the node
+fn link_density(node: &Rc<RefCell<Node>>) -> f64 {
+    let length = inner_text(node).chars().count();
+    if length == 0 {
+        return 0.0;
+    }
+    let link_length: usize = descendants_of(node)
+        .iter()
+        .filter(|n| local_name(n).as_deref() == Some("a"))
+        .map(|a| inner_text(a).chars().count())
+        .sum();
+    link_length as f64 / length as f64
+}
+
+fn is_block(node: &Rc<RefCell<Node>>) -> bool {
+    local_name(node).is_some_and(|name| BLOCK_ELEMENTS.contains(&name.as_str()))
+}
+
+fn has_block_children(node: &Rc<RefCell<Node>>) -> bool {
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        if is_block(&c) {
+            return true;
+        }
+        child = c.borrow().next_sibling();
+    }
+    false
+}
+
+fn local_name(node: &Rc<RefCell<Node>>) -> Option<String> {
+    node.borrow().get_element().map(|e| e.local_name())
+}
+
+// the descendant elements and texts of the node in tree order
+fn descendants_of(node: &Rc<RefCell<Node>>) -> Vec<Rc<RefCell<Node>>> {
+    let mut nodes = Vec::new();
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        nodes.push(c.clone());
+        nodes.extend(descendants_of(&c));
+        child = c.borrow().next_sibling();
+    }
+    nodes
+}
+
+// the text of the node, without the contents of scripts and styles
+fn inner_text(node: &Rc<RefCell<Node>>) -> String {
+    match node.borrow().kind() {
+        NodeKind::Text(text) => return text,
+        NodeKind::Element(e)
+            if matches!(
+                e.local_name().as_str(),
+                "noscript" | "script" | "style" | "template"
+            ) =>
+        {
+            return String::new()
+        }
+        _ => {}
+    }
+    let mut text = String::new();
+    let mut child = node.borrow().first_child();
+    while let Some(c) = child {
+        text.push_str(&inner_text(&c));
+        child = c.borrow().next_sibling();
+    }
+    text
+}
+
+// the title without the name of the site, or the only <h1> which the title contains
+fn article_title(document: &Rc<RefCell<Node>>) -> String {
+    let title = get_elements_by_tag_name(document, "title")
+        .first()
+        .map(|t| strip_and_collapse_whitespace(&get_text_content(t)))
+        .unwrap_or_default();
+
+    if let [h1] = get_elements_by_tag_name(document, "h1").as_slice() {
+        let heading = strip_and_collapse_whitespace(&inner_text(h1));
+        if !heading.is_empty() && (title.is_empty() || title.contains(&heading)) {
+            return heading;
+        }
+    }
+
+    for separator in TITLE_SEPARATORS {
+        if let Some((head, _)) = title.rsplit_once(separator) {
+            if head.split_ascii_whitespace().count() >= 3 {
+                return head.to_string();
+            }
+        }
+    }
+    title
+}
+
+// the author which the byline element or <meta name=author> shows, and the byline element
+fn byline(
+    document: &Rc<RefCell<Node>>,
+    body: &Rc<RefCell<Node>>,
+) -> (Option<String>, Option<NodeId>) {
+    let meta_author = get_elements_by_tag_name(document, "meta")
+        .iter()
+        .find(|meta| {
+            meta.borrow().get_element().is_some_and(|e| {
+                e.get_attribute("name")
+                    .is_some_and(|n| n.eq_ignore_ascii_case("author"))
+            })
+        })
+        .and_then(|meta| meta.borrow().get_element()?.get_attribute("content"))
+        .map(|author| strip_and_collapse_whitespace(&author))
+        .filter(|author| !author.is_empty());
+
+    for node in descendants_of(body) {
+        let element = match node.borrow().get_element() {
+            Some(e) => e,
+            None => continue,
+        };
+        let matches_word = |name: &str| {
+            element.get_attribute(name).is_some_and(|value| {
+                let value = value.to_ascii_lowercase();
+                BYLINE_WORDS.iter().any(|word| value.contains(word))
+            })
+        };
+        let is_byline = element
+            .get_attribute("rel")
+            .is_some_and(|rel| rel.eq_ignore_ascii_case("author"))
+            || matches_word("itemprop")
+            || matches_word("class")
+            || matches_word("id");
+        if !is_byline {
+            continue;
+        }
+
+        let text = strip_and_collapse_whitespace(&inner_text(&node));
+        if !text.is_empty() && text.chars().count() < 100 {
+            return (meta_author.or(Some(text)), node.borrow().id());
+        }
+    }
+    (meta_author, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::html::parser::HtmlParser;
+    use crate::renderer::html::token::HtmlTokenizer;
+
+    fn extract(html: &str) -> Option<Article> {
+        let window = HtmlParser::new(HtmlTokenizer::new(html.to_string())).construct_tree();
+        let document = window.borrow().document();
+        Article::from_document(&document, "http://example.com/news/story.html")
+    }
+
+    const STORY: &str = "<html><head><title>Rust ships a new release | Example News</title></head><body><nav class=menu><a href=/>Home</a> <a href=/news>News</a> <a href=/about>About</a></nav><div id=content><h1>Rust ships a new release</h1><p class=byline>By Jane Doe</p><p>The Rust team has announced a new release today, with faster builds, better errors, and a number of library additions.</p><h2>What changed</h2><p>The compiler is faster, the <a href=notes.html>release notes</a>, which list every change, and the tooling is improved as well.</p><script>track()</script></div><div class=sidebar><a href=/a>Related story one</a><a href=/b>Related story two</a></div><footer>Copyright Example News, all rights reserved.</footer></body></html>";
+
+    #[test]
+    fn test_extract_article() {
+        let article = extract(STORY).expect("no article");
+        assert_eq!("Rust ships a new release", article.title());
+        assert_eq!(Some("By Jane Doe".to_string()), article.byline());
+        assert_eq!(
+            vec![
+                ArticleBlock::Paragraph(vec![TextRun::new(
+                    "The Rust team has announced a new release today, with faster builds, better errors, and a number of library additions.".to_string(),
+                    None
+                )]),
+                ArticleBlock::Heading {
+                    level: 2,
+                    text: "What changed".to_string()
+                },
+                ArticleBlock::Paragraph(vec![
+                    TextRun::new("The compiler is faster, the ".to_string(), None),
+                    TextRun::new(
+                        "release notes".to_string(),
+                        Some("http://example.com/news/notes.html".to_string())
+                    ),
+                    TextRun::new(
+                        ", which list every change, and the tooling is improved as well."
+                            .to_string(),
+                        None
+                    ),
+                ]),
+            ],
+            article.blocks()
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let article = extract(STORY).expect("no article");
+        assert_eq!(
+            "# Rust ships a new release
+
+*By Jane Doe*
+
+The Rust team has announced a new release today, with faster builds, better errors, and a number of library additions.
+
+## What changed
+
+The compiler is faster, the [release notes](<http://example.com/news/notes.html>), which list every change, and the tooling is improved as well.
+",
+            article.to_markdown()
+        );
+        assert!(article
+            .to_text()
+            .starts_with("Rust ships a new release\n\nBy Jane Doe\n\nThe Rust team"));
+        assert!(article.to_html().contains(
+            "<h2>What changed</h2><p>The compiler is faster, the <a href=\"http://example.com/news/notes.html\">release notes</a>, which"
+        ));
+    }
+
+    #[test]
+    fn test_export_link_destination() {
+        let article = Article {
+            title: "Links".to_string(),
+            byline: None,
+            blocks: vec![ArticleBlock::Paragraph(vec![TextRun::new(
+                "link".to_string(),
+                Some("http://example.com/a<b>\nc\\".to_string()),
+            )])],
+        };
+        assert_eq!(
+            "# Links\n\n[link](<http://example.com/a%3Cb%3E%0Ac%5C>)\n",
+            article.to_markdown()
+        );
+    }
+
+    #[test]
+    fn test_no_article() {
+        assert_eq!(
+            None,
+            extract("<html><body><nav><a href=/>Home</a></nav></body></html>")
+        );
+    }
+}
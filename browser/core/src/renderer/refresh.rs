@@ -0,0 +1,142 @@
+use crate::constants::MAX_REFRESH_DELAY;
+use alloc::string::String;
+use alloc::string::ToString;
+
+// a navigation which the page requests after the delay, by <meta http-equiv=refresh> or the
+// Refresh header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledNavigation {
+    // the absolute URL to navigate to, which is the page itself for a plain reload
+    url: String,
+    delay_seconds: u64,
+}
+
+impl ScheduledNavigation {
+    pub fn new(url: String, delay_seconds: u64) -> Self {
+        Self { url, delay_seconds }
+    }
+
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    pub fn delay_seconds(&self) -> u64 {
+        self.delay_seconds
+    }
+}
+
+// https://html.spec.whatwg.org/multipage/document-lifecycle.html#shared-declarative-refresh-steps
+// parses a value such as "5; url=/next", and returns the delay and the URL which is not
+// resolved yet. the URL is None to reload the page itself
+pub fn parse_refresh(input: &str) -> Option<(u64, Option<String>)> {
+    let input = input.trim_start_matches(is_ascii_whitespace);
+
+    // the integer part is the delay, and the fractional part is ignored
+    let digits_end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (digits, rest) = input.split_at(digits_end);
+    if digits.is_empty() && !rest.starts_with('.') {
+        return None;
+    }
+    let delay = if digits.is_empty() {
+        0
+    } else {
+        digits
+            .parse::<u64>()
+            .unwrap_or(MAX_REFRESH_DELAY)
+            .min(MAX_REFRESH_DELAY)
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+
+    if rest.is_empty() {
+        return Some((delay, None));
+    }
+    if !rest.starts_with([';', ',']) && !rest.starts_with(is_ascii_whitespace) {
+        return None;
+    }
+
+    let rest = rest.trim_start_matches(is_ascii_whitespace);
+    let rest = rest.strip_prefix([';', ',']).unwrap_or(rest);
+    let rest = rest.trim_start_matches(is_ascii_whitespace);
+    if rest.is_empty() {
+        return Some((delay, None));
+    }
+
+    // "url=" is optional
+    let url = match rest.get(..3) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("url") => {
+            let after = rest[3..].trim_start_matches(is_ascii_whitespace);
+            match after.strip_prefix('=') {
+                Some(after) => after.trim_start_matches(is_ascii_whitespace),
+                None => rest,
+            }
+        }
+        _ => rest,
+    };
+
+    // a quoted URL ends at the matching quote
+    let url = match url.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let unquoted = &url[1..];
+            match unquoted.find(quote) {
+                Some(end) => &unquoted[..end],
+                None => unquoted,
+            }
+        }
+        _ => url,
+    };
+
+    Some((delay, Some(url.to_string())))
+}
+
+fn is_ascii_whitespace(c: char) -> bool {
+    c.is_ascii_whitespace()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_refresh() {
+        assert_eq!(Some((5, None)), parse_refresh("5"));
+        assert_eq!(Some((0, None)), parse_refresh(" .5 "));
+        assert_eq!(Some((3, None)), parse_refresh("3.9;"));
+        assert_eq!(
+            Some((5, Some("/next".to_string()))),
+            parse_refresh("5; url=/next")
+        );
+        assert_eq!(
+            Some((0, Some("/next".to_string()))),
+            parse_refresh("0,URL = '/next'junk")
+        );
+        assert_eq!(
+            Some((1, Some("other.html".to_string()))),
+            parse_refresh("1 other.html")
+        );
+        assert_eq!(
+            Some((2, Some("urlish.html".to_string()))),
+            parse_refresh("2;urlish.html")
+        );
+    }
+
+    #[test]
+    fn test_invalid_refresh() {
+        assert_eq!(None, parse_refresh(""));
+        assert_eq!(None, parse_refresh("url=/next"));
+        assert_eq!(None, parse_refresh("5x; url=/next"));
+    }
+
+    #[test]
+    fn test_huge_refresh_delay() {
+        assert_eq!(
+            Some((MAX_REFRESH_DELAY, Some("/x".to_string()))),
+            parse_refresh("99999999999999999999; url=/x")
+        );
+        assert_eq!(
+            Some((MAX_REFRESH_DELAY, None)),
+            parse_refresh("18446744073709551615")
+        );
+    }
+}
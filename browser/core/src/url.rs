@@ -1,3 +1,4 @@
+use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
@@ -127,6 +128,107 @@ impl Url {
     }
 }
 
+// https://url.spec.whatwg.org/#concept-basic-url-parser
+// resolves the input, which can be a relative URL, against the base URL
+pub fn resolve_url(base: &str, input: &str) -> String {
+    let input = input.trim();
+
+    // the input is already an absolute URL
+    if has_scheme(input) {
+        return input.to_string();
+    }
+
+    let (scheme, rest) = match base.split_once("://") {
+        Some((scheme, rest)) => (scheme, rest),
+        None => return input.to_string(),
+    };
+    let authority_end = rest.find(['/', '?', '#']).unwrap_or(rest.len());
+    let (authority, path_and_rest) = rest.split_at(authority_end);
+    let path_end = path_and_rest
+        .find(['?', '#'])
+        .unwrap_or(path_and_rest.len());
+    let base_path = &path_and_rest[..path_end];
+    let base_without_fragment = match base.split_once('#') {
+        Some((b, _)) => b,
+        None => base,
+    };
+
+    if input.is_empty() {
+        return base_without_fragment.to_string();
+    }
+    if input.starts_with("//") {
+        return format!("{}:{}", scheme, input);
+    }
+    if input.starts_with('#') {
+        return format!("{}{}", base_without_fragment, input);
+    }
+    if input.starts_with('?') {
+        return format!("{}://{}{}{}", scheme, authority, base_path, input);
+    }
+
+    // merges the relative path with the directory of the base path
+    let merged = if input.starts_with('/') {
+        input.to_string()
+    } else {
+        match base_path.rfind('/') {
+            Some(i) => format!("{}{}", &base_path[..=i], input),
+            None => format!("/{}", input),
+        }
+    };
+
+    let query_start = merged.find(['?', '#']).unwrap_or(merged.len());
+    let (path, query) = merged.split_at(query_start);
+    format!(
+        "{}://{}{}{}",
+        scheme,
+        authority,
+        remove_dot_segments(path),
+        query
+    )
+}
+
+// https://url.spec.whatwg.org/#url-scheme-string
+fn has_scheme(input: &str) -> bool {
+    match input.split_once(':') {
+        Some((scheme, _)) => {
+            let mut chars = scheme.chars();
+            match chars.next() {
+                Some(c) if c.is_ascii_alphabetic() => {
+                    chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+                }
+                _ => false,
+            }
+        }
+        None => false,
+    }
+}
+
+// https://url.spec.whatwg.org/#shorten-a-urls-path
+fn remove_dot_segments(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').skip(1).collect();
+    let mut output: Vec<&str> = Vec::new();
+
+    for (i, segment) in segments.iter().enumerate() {
+        let last = i == segments.len() - 1;
+        match *segment {
+            "." => {}
+            ".." => {
+                output.pop();
+            }
+            s => {
+                output.push(s);
+                continue;
+            }
+        }
+        // a trailing dot segment keeps the trailing slash
+        if last {
+            output.push("");
+        }
+    }
+
+    format!("/{}", output.join("/"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +311,42 @@ mod tests {
         let expected = Err("only HTTP scheme is supported".to_string());
         assert_eq!(expected, Url::new(url).parse());
     }
+
+    #[test]
+    fn test_resolve_url() {
+        let base = "http://example.com:8000/a/b/c.html?q=1#top";
+        assert_eq!(
+            "http://example.com:8000/a/b/d.css",
+            resolve_url(base, "d.css")
+        );
+        assert_eq!(
+            "http://example.com:8000/a/d.js",
+            resolve_url(base, "../d.js")
+        );
+        assert_eq!("http://example.com:8000/a/b/", resolve_url(base, "./"));
+        assert_eq!(
+            "http://example.com:8000/x/y?z=1",
+            resolve_url(base, "/x/./y?z=1")
+        );
+        assert_eq!(
+            "http://example.com:8000/a/b/c.html?r=2",
+            resolve_url(base, "?r=2")
+        );
+        assert_eq!(
+            "http://example.com:8000/a/b/c.html?q=1#bottom",
+            resolve_url(base, "#bottom")
+        );
+        assert_eq!(
+            "http://other.com/index.html",
+            resolve_url(base, "//other.com/index.html")
+        );
+        assert_eq!(
+            "https://other.com/",
+            resolve_url(base, " https://other.com/ ")
+        );
+        assert_eq!(
+            "http://example.com/index.html",
+            resolve_url("http://example.com", "index.html")
+        );
+    }
 }
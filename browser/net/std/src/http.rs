@@ -33,10 +33,54 @@ impl HttpClient {
 
         stream.write(request.as_bytes())?;
 
-        let mut buf = String::new();
-        stream.read_to_string(&mut buf)?;
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf)?;
 
-        match HttpResponse::new(buf.to_string()) {
+        match HttpResponse::from_bytes(buf) {
+            Ok(res) => Ok(res),
+            Err(e) => Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{:?}", e),
+            )),
+        }
+    }
+
+    // sends a form submission, whose body is already encoded with the content type
+    pub fn post(
+        &self,
+        host: String,
+        port: u16,
+        path: String,
+        content_type: String,
+        body: String,
+    ) -> std::io::Result<HttpResponse> {
+        let ips = lookup_host(&host)?.into_iter();
+        let ipv4s: Vec<std::net::IpAddr> = ips.filter(|ip| ip.is_ipv4()).collect();
+
+        let mut stream = TcpStream::connect((ipv4s[0], port))?;
+
+        let mut request = String::from("POST /");
+        request.push_str(&path);
+        request.push_str(" HTTP/1.1\n");
+
+        request.push_str("Host: ");
+        request.push_str(&host);
+        request.push('\n');
+        request.push_str("Accept: */*\n");
+        request.push_str("Content-Type: ");
+        request.push_str(&content_type);
+        request.push('\n');
+        request.push_str(&format!("Content-Length: {}\n", body.len()));
+        request.push_str("Connection: close\n");
+        request.push('\n');
+        request.push_str(&body);
+
+        stream.write_all(request.as_bytes())?;
+
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf)?;
+
+        match HttpResponse::from_bytes(buf) {
             Ok(res) => Ok(res),
             Err(e) => Err(std::io::Error::new(
                 ErrorKind::InvalidData,
@@ -1,6 +1,9 @@
 use alloc::string::String;
 use browser_core::error::Error;
 use browser_core::http::HttpResponse;
+use browser_core::renderer::form::submission::FormMethod;
+use browser_core::renderer::form::submission::FormSubmission;
+use browser_core::url::resolve_url;
 use browser_core::url::Url;
 use net_std::http::HttpClient;
 
@@ -23,7 +26,7 @@ pub fn handle_url(url: String) -> Result<HttpResponse, Error> {
             .port()
             .parse::<u16>()
             .unwrap_or_else(|_| panic!("port number should be u16 but got {}", parsed_url.port())),
-        parsed_url.path(),
+        request_path(&parsed_url),
     ) {
         Ok(res) => {
             // redirect to Location
@@ -43,7 +46,7 @@ pub fn handle_url(url: String) -> Result<HttpResponse, Error> {
                         .unwrap_or_else(|_| {
                             panic!("port number should be u16 but got {}", parsed_url.port())
                         }),
-                    redirect_parsed_url.path(),
+                    request_path(&redirect_parsed_url),
                 ) {
                     Ok(res) => res,
                     Err(e) => return Err(Error::Network(format!("{:?}", e))),
@@ -62,3 +65,61 @@ pub fn handle_url(url: String) -> Result<HttpResponse, Error> {
 
     Ok(response)
 }
+
+// the path and the query of the request, e.g. "search?q=rust"
+fn request_path(url: &Url) -> String {
+    let searchpart = url.searchpart();
+    let query = searchpart.split('#').next().unwrap_or_default();
+    if query.is_empty() {
+        url.path()
+    } else {
+        format!("{}?{}", url.path(), query)
+    }
+}
+
+// sends a form submission, and follows the redirect after a POST with a GET request
+pub fn handle_form(submission: FormSubmission) -> Result<HttpResponse, Error> {
+    if submission.method() == FormMethod::Get {
+        return handle_url(submission.url());
+    }
+
+    let parsed_url = match Url::new(submission.url()).parse() {
+        Ok(url) => url,
+        Err(e) => {
+            return Err(Error::UnexpectedInput(format!(
+                "form action is not supported: {:?}",
+                e
+            )));
+        }
+    };
+
+    let client = HttpClient::new();
+    let response = match client.post(
+        parsed_url.host(),
+        parsed_url
+            .port()
+            .parse::<u16>()
+            .unwrap_or_else(|_| panic!("port number should be u16 but got {}", parsed_url.port())),
+        request_path(&parsed_url),
+        submission.content_type().unwrap_or_default(),
+        submission.body().unwrap_or_default(),
+    ) {
+        Ok(res) => res,
+        Err(e) => {
+            return Err(Error::Network(format!(
+                "failed to submit the form: {:?}",
+                e
+            )))
+        }
+    };
+
+    // https://fetch.spec.whatwg.org/#http-redirect-fetch
+    // a POST request is changed to GET on 301, 302 and 303 redirects
+    if matches!(response.status_code(), 301 | 302 | 303) {
+        if let Ok(location) = response.header_value("Location") {
+            return handle_url(resolve_url(&submission.url(), &location));
+        }
+    }
+
+    Ok(response)
+}
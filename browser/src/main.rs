@@ -18,6 +18,7 @@ fn create_ui(browser: Rc<RefCell<Browser>>) -> Rc<RefCell<ui_gui::app::Gui>> {
 
 fn main() {
     let browser = Browser::new();
+    browser.borrow_mut().set_form_sender(handler::handle_form);
 
     let ui = create_ui(browser);
 
@@ -1,10 +1,17 @@
 use alloc::rc::Rc;
 use browser_core::{
     browser::Browser,
+    constants::CHAR_WIDTH,
     display_item::DisplayItem,
     error::Error,
     http::HttpResponse,
+    renderer::form::control::ControlType,
+    renderer::form::submission::{FormMethod, FormSubmission},
+    renderer::html::dom::NodeId,
+    renderer::image::bitmap::Image,
     renderer::layout::computed_style::{FontSize, TextDecoration},
+    renderer::metadata::PageMetadata,
+    renderer::page::Page,
 };
 use core::cell::RefCell;
 use crossterm::{
@@ -12,7 +19,7 @@ use crossterm::{
     execute,
     terminal::{
         disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
-        LeaveAlternateScreen,
+        LeaveAlternateScreen, SetTitle,
     },
 };
 use ratatui::{
@@ -23,33 +30,67 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Wrap},
     Terminal,
 };
+use std::fs::OpenOptions;
 use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthStr;
 
 #[derive(Clone, Copy, Debug)]
 enum InputMode {
     Normal,
     Editing,
+    // typing into the focused text field of a form
+    EditingControl,
+    // reading the accessibility tree of the page heading by heading
+    Reading,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 struct Link {
     text: String,
     destination: String,
+    // the text node of the link, which the click event is dispatched to
+    node_id: Option<NodeId>,
 }
 
 impl Link {
-    fn new(text: String, destination: String) -> Self {
-        Self { text, destination }
+    fn new(text: String, destination: String, node_id: Option<NodeId>) -> Self {
+        Self {
+            text,
+            destination,
+            node_id,
+        }
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+enum Focus {
+    Link(Link),
+    // form controls are identified by their nodes, which are kept across re-renders
+    Control {
+        node_id: NodeId,
+        control_type: ControlType,
+    },
+}
+
 #[derive(Clone, Debug)]
 pub struct Tui {
     browser: Rc<RefCell<Browser>>,
     input_url: String,
     input_mode: InputMode,
-    focus: Option<Link>,
+    focus: Option<Focus>,
+    // the value of the text field which is being edited
+    input_control: String,
+    status: Option<String>,
+    // the deadline and the destination of the refresh requested by the page
+    refresh: Option<(Instant, String)>,
+    // the section of the accessibility tree which is read in the reading mode
+    reading_section: usize,
+    // the reader view of the current page, which is shown instead of the page
+    reader: Option<Rc<RefCell<Page>>>,
 }
 
 impl Tui {
@@ -59,6 +100,11 @@ impl Tui {
             input_url: String::new(),
             input_mode: InputMode::Normal,
             focus: None,
+            input_control: String::new(),
+            status: None,
+            refresh: None,
+            reading_section: 0,
+            reader: None,
         }
     }
 
@@ -66,6 +112,9 @@ impl Tui {
         &mut self,
         handle_url: fn(String) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
+        // subresources such as stylesheets and scripts are fetched in the same way
+        self.browser.borrow_mut().set_fetcher(handle_url);
+
         match enable_raw_mode() {
             Ok(_) => {}
             Err(e) => return Err(Error::Other(format!("{:?}", e))),
@@ -119,102 +168,110 @@ impl Tui {
         self.browser.clone()
     }
 
-    fn move_focus_up(&mut self) {
-        let display_items = self
+    // the page which is shown, which is the reader view when it's open
+    fn displayed_page(&self) -> Rc<RefCell<Page>> {
+        match &self.reader {
+            Some(reader) => reader.clone(),
+            None => self.browser.borrow().current_page(),
+        }
+    }
+
+    // links and form controls in the order of the display items
+    fn focusable_items(&self) -> Vec<Focus> {
+        let display_items = self.displayed_page().borrow().display_items();
+
+        let mut focusable_items = Vec::new();
+        for item in display_items {
+            match item {
+                DisplayItem::Text {
+                    text,
+                    style: _,
+                    layout_point: _,
+                    href: Some(destination),
+                    node_id,
+                } => focusable_items.push(Focus::Link(Link::new(text, destination, node_id))),
+                DisplayItem::FormControl {
+                    node_id,
+                    control_type,
+                    ..
+                } => focusable_items.push(Focus::Control {
+                    node_id,
+                    control_type,
+                }),
+                _ => {}
+            }
+        }
+        focusable_items
+    }
+
+    // the accessibility tree split before each heading, where the focused form control is marked
+    fn reading_sections(&self) -> Vec<Vec<String>> {
+        let focused = match &self.focus {
+            Some(Focus::Control { node_id, .. }) => Some(*node_id),
+            _ => None,
+        };
+        match self
             .browser
             .borrow()
             .current_page()
             .borrow()
-            .display_items();
-
-        // store all links
-        let mut focusable_links = Vec::new();
-        for item in display_items {
-            if let DisplayItem::Text {
-                text,
-                style,
-                layout_point: _,
-            } = item
-            {
-                if style.text_decoration() != TextDecoration::Underline {
-                    continue;
-                }
-                // TODO: get correct destination link from Node.
-                let destination = "http://example.com".to_string();
-                focusable_links.push(Link::new(text, destination));
-            }
+            .accessibility_tree(focused)
+        {
+            Some(tree) => tree.sections(),
+            None => Vec::new(),
         }
+    }
+
+    fn move_focus_up(&mut self) {
+        let focusable_items = self.focusable_items();
 
-        // if focus is not set, on down arrow, focus on the first link
+        // if focus is not set, on up arrow, focus on the first item
         if self.focus.is_none() {
-            if let Some(first_link) = focusable_links.first() {
-                self.focus = Some(first_link.clone());
+            if let Some(first_item) = focusable_items.first() {
+                self.focus = Some(first_item.clone());
             }
             return;
         }
 
-        // if focus is already set, move to the previous link
+        // if focus is already set, move to the previous item
         if let Some(current_focus) = &self.focus {
-            let current_index = focusable_links
+            let current_index = focusable_items
                 .iter()
-                .position(|link| link == current_focus);
+                .position(|item| item == current_focus);
 
-            // loop through the links, in reverse order
+            // loop through the items, in reverse order
             if let Some(index) = current_index {
                 let prev_index = if index == 0 {
-                    focusable_links.len() - 1
+                    focusable_items.len() - 1
                 } else {
                     index - 1
                 };
-                self.focus = Some(focusable_links[prev_index].clone());
+                self.focus = Some(focusable_items[prev_index].clone());
             }
         }
     }
 
     fn move_focus_down(&mut self) {
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
-
-        // store all links in a vector
-        let mut focusable_links = Vec::new();
-        for item in display_items {
-            if let DisplayItem::Text {
-                text,
-                style,
-                layout_point: _,
-            } = item
-            {
-                if style.text_decoration() != TextDecoration::Underline {
-                    continue;
-                }
-                // TODO: get correct destination link from Node.
-                let destination = "http://example.com".to_string();
-                focusable_links.push(Link::new(text, destination));
-            }
-        }
+        let focusable_items = self.focusable_items();
 
-        // if focus is not set, on down arrow, focus on the first link
+        // if focus is not set, on down arrow, focus on the first item
         if self.focus.is_none() {
-            if let Some(first_link) = focusable_links.first() {
-                self.focus = Some(first_link.clone());
+            if let Some(first_item) = focusable_items.first() {
+                self.focus = Some(first_item.clone());
             }
             return;
         }
 
-        // if focus is already set, move to the next link
+        // if focus is already set, move to the next item
         if let Some(current_focus) = &self.focus {
-            let current_index = focusable_links
+            let current_index = focusable_items
                 .iter()
-                .position(|link| link == current_focus);
+                .position(|item| item == current_focus);
 
-            // loop through the links
+            // loop through the items
             if let Some(index) = current_index {
-                let next_index = (index + 1) % focusable_links.len();
-                self.focus = Some(focusable_links[next_index].clone());
+                let next_index = (index + 1) % focusable_items.len();
+                self.focus = Some(focusable_items[next_index].clone());
             }
         }
     }
@@ -224,12 +281,11 @@ impl Tui {
         handle_url: fn(String) -> Result<HttpResponse, Error>,
         destination: String,
     ) -> Result<(), Error> {
-        match handle_url(destination) {
-            Ok(response) => {
-                let page = self.browser.borrow().current_page();
-                page.borrow_mut().clear_display_items();
-                page.borrow_mut().receive_response(response);
-            }
+        // navigating away cancels the refresh of the current page
+        self.refresh = None;
+
+        match handle_url(destination.clone()) {
+            Ok(response) => self.load_response(destination, response),
             Err(e) => {
                 return Err(e);
             }
@@ -237,6 +293,199 @@ impl Tui {
         Ok(())
     }
 
+    // clicks the focused link, and navigates to its destination unless the page cancels the click
+    fn follow_link(
+        &mut self,
+        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        link: Link,
+    ) -> Result<(), Error> {
+        let destination = match link.node_id {
+            Some(node_id) => self.displayed_page().borrow_mut().follow_link(node_id),
+            // the links of child pages are followed without events
+            None => Some(link.destination),
+        };
+        match destination {
+            Some(destination) => self.start_navigation(handle_url, destination),
+            None => Ok(()),
+        }
+    }
+
+    fn load_response(&mut self, url: String, response: HttpResponse) {
+        let page = self.browser.borrow().current_page();
+        page.borrow_mut().clear_display_items();
+        page.borrow_mut().receive_response(url.clone(), response);
+        self.status = None;
+        // nodes of the previous document can't be focused
+        self.focus = None;
+        self.reader = None;
+
+        // the terminal title shows the page title, or the URL without a title
+        let title = page.borrow().metadata().title().unwrap_or(url);
+        let _ = execute!(io::stdout(), SetTitle(title));
+
+        let navigation = page.borrow().scheduled_navigation();
+        // a deadline which is out of the range of the clock is never reached
+        if let Some(navigation) = navigation {
+            self.refresh = Instant::now()
+                .checked_add(Duration::from_secs(navigation.delay_seconds()))
+                .map(|deadline| (deadline, navigation.url()));
+        }
+    }
+
+    // activates the focused form control, or starts editing it if it's a text field
+    fn activate_control(
+        &mut self,
+        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        node_id: NodeId,
+        control_type: ControlType,
+    ) {
+        let page = self.browser.borrow().current_page();
+        if control_type.is_text_like() {
+            self.input_control = page.borrow().control_value(node_id).unwrap_or_default();
+            self.input_mode = InputMode::EditingControl;
+            return;
+        }
+
+        let result = page.borrow_mut().activate_control(node_id);
+        self.submit(handle_url, result);
+    }
+
+    // sets the edited value to the focused text field, and submits the form if requested
+    fn finish_editing_control(
+        &mut self,
+        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        submit: bool,
+    ) {
+        self.input_mode = InputMode::Normal;
+        let value: String = self.input_control.drain(..).collect();
+        let node_id = match &self.focus {
+            Some(Focus::Control { node_id, .. }) => *node_id,
+            _ => return,
+        };
+
+        let page = self.browser.borrow().current_page();
+        page.borrow_mut().set_control_value(node_id, &value);
+        if submit {
+            let result = page.borrow_mut().submit_implicitly(node_id);
+            self.submit(handle_url, result);
+        }
+    }
+
+    // navigates to the result of the form submission, and reports invalid controls and network
+    // errors in the status bar
+    fn submit(
+        &mut self,
+        handle_url: fn(String) -> Result<HttpResponse, Error>,
+        result: Result<Option<FormSubmission>, Error>,
+    ) {
+        let submission = match result {
+            Ok(Some(submission)) => submission,
+            Ok(None) => return,
+            Err(Error::UnexpectedInput(msg)) => {
+                self.status = Some(msg);
+                return;
+            }
+            Err(e) => {
+                self.status = Some(format!("{:?}", e));
+                return;
+            }
+        };
+
+        let url = submission.url();
+        let response = match submission.method() {
+            FormMethod::Get => handle_url(url.clone()),
+            FormMethod::Post => match self.browser.borrow().form_sender() {
+                Some(form_sender) => form_sender(submission),
+                None => Err(Error::Other("POST forms are not supported".to_string())),
+            },
+        };
+        match response {
+            Ok(response) => {
+                self.refresh = None;
+                self.load_response(url, response);
+            }
+            Err(e) => self.status = Some(format!("failed to submit the form to {}: {:?}", url, e)),
+        }
+    }
+
+    fn cancel_refresh(&mut self) {
+        if self.refresh.take().is_some() {
+            let page = self.browser.borrow().current_page();
+            page.borrow_mut().cancel_scheduled_navigation();
+            self.status = Some("cancelled the refresh".to_string());
+        }
+    }
+
+    // navigates to the destination of the refresh, and reports errors in the status bar
+    // instead of exiting
+    fn perform_refresh(&mut self, handle_url: fn(String) -> Result<HttpResponse, Error>) {
+        if let Some((_, destination)) = self.refresh.take() {
+            if let Err(e) = self.start_navigation(handle_url, destination.clone()) {
+                self.status = Some(format!("failed to refresh to {}: {:?}", destination, e));
+            }
+        }
+    }
+
+    // saves the current DOM tree as an HTML file in the working directory
+    fn save_page(&mut self) {
+        let page = self.browser.borrow().current_page();
+        let html = match page.borrow().serialize() {
+            Some(html) => html,
+            None => {
+                self.status = Some("no page to save".to_string());
+                return;
+            }
+        };
+
+        let file_name = file_name_from_url(&page.borrow().url().unwrap_or_default());
+        self.status = Some(match write_new_file(Path::new("."), &file_name, &html) {
+            Ok(path) => format!("saved the page to {}", path.display()),
+            Err(e) => format!("failed to save the page to {}: {}", file_name, e),
+        });
+    }
+
+    // opens the reader view of the current page, or closes it
+    fn toggle_reader(&mut self) {
+        self.focus = None;
+        if self.reader.take().is_some() {
+            return;
+        }
+        let reader = self.browser.borrow().current_page().borrow().reader_page();
+        match reader {
+            Some(reader) => self.reader = Some(Rc::new(RefCell::new(reader))),
+            None => self.status = Some("no article is found in the page".to_string()),
+        }
+    }
+
+    // saves the article of the current page as Markdown or plain text in the working directory
+    fn export_article(&mut self, markdown: bool) {
+        let page = self.browser.borrow().current_page();
+        let article = match page.borrow().article() {
+            Some(article) => article,
+            None => {
+                self.status = Some("no article is found in the page".to_string());
+                return;
+            }
+        };
+
+        let (contents, extension) = if markdown {
+            (article.to_markdown(), "md")
+        } else {
+            (article.to_text(), "txt")
+        };
+        let file_name = file_name_from_url(&page.borrow().url().unwrap_or_default());
+        let file_name = match file_name.rsplit_once('.') {
+            Some((stem, _)) => format!("{}.{}", stem, extension),
+            None => format!("{}.{}", file_name, extension),
+        };
+        self.status = Some(
+            match write_new_file(Path::new("."), &file_name, &contents) {
+                Ok(path) => format!("exported the article to {}", path.display()),
+                Err(e) => format!("failed to export the article to {}: {}", file_name, e),
+            },
+        );
+    }
+
     fn run_app<B: Backend>(
         &mut self,
         handle_url: fn(String) -> Result<HttpResponse, Error>,
@@ -248,6 +497,21 @@ impl Tui {
                 Err(e) => return Err(Error::Other(format!("{:?}", e))),
             }
 
+            // waits for events until the refresh, and redraws every second for the countdown
+            if let Some((deadline, _)) = &self.refresh {
+                let now = Instant::now();
+                if now >= *deadline {
+                    self.perform_refresh(handle_url);
+                    continue;
+                }
+                let timeout = (*deadline - now).min(Duration::from_secs(1));
+                match event::poll(timeout) {
+                    Ok(true) => {}
+                    Ok(false) => continue,
+                    Err(e) => return Err(Error::Other(format!("{:?}", e))),
+                }
+            }
+
             let event = match event::read() {
                 Ok(event) => event,
                 Err(e) => return Err(Error::Other(format!("{:?}", e))),
@@ -267,13 +531,39 @@ impl Tui {
                                 continue;
                             }
 
-                            if let Some(focus_item) = &self.focus {
-                                self.start_navigation(handle_url, focus_item.destination.clone())?;
+                            match self.focus.clone() {
+                                Some(Focus::Link(link)) => {
+                                    self.follow_link(handle_url, link)?;
+                                }
+                                Some(Focus::Control {
+                                    node_id,
+                                    control_type,
+                                }) => self.activate_control(handle_url, node_id, control_type),
+                                None => {}
                             }
                         }
                         KeyCode::Char('e') => {
                             self.input_mode = InputMode::Editing;
                         }
+                        KeyCode::Char('s') => {
+                            self.save_page();
+                        }
+                        KeyCode::Char('c') => {
+                            self.cancel_refresh();
+                        }
+                        KeyCode::Char('r') => {
+                            self.reading_section = 0;
+                            self.input_mode = InputMode::Reading;
+                        }
+                        KeyCode::Char('v') => {
+                            self.toggle_reader();
+                        }
+                        KeyCode::Char('m') => {
+                            self.export_article(true);
+                        }
+                        KeyCode::Char('t') => {
+                            self.export_article(false);
+                        }
                         KeyCode::Char('q') => {
                             return Ok(());
                         }
@@ -299,6 +589,48 @@ impl Tui {
                         }
                         _ => {}
                     },
+                    InputMode::EditingControl => match key.code {
+                        KeyCode::Enter => {
+                            // line breaks are typed into textareas, which are submitted by a
+                            // button instead
+                            if matches!(
+                                self.focus,
+                                Some(Focus::Control {
+                                    control_type: ControlType::Textarea,
+                                    ..
+                                })
+                            ) {
+                                self.input_control.push('\n');
+                            } else {
+                                self.finish_editing_control(handle_url, true);
+                            }
+                        }
+                        KeyCode::Char(c) => {
+                            self.input_control.push(c);
+                        }
+                        KeyCode::Backspace => {
+                            self.input_control.pop();
+                        }
+                        KeyCode::Esc => {
+                            self.finish_editing_control(handle_url, false);
+                        }
+                        _ => {}
+                    },
+                    InputMode::Reading => match key.code {
+                        KeyCode::Up | KeyCode::Char('p') => {
+                            self.reading_section = self.reading_section.saturating_sub(1);
+                        }
+                        KeyCode::Down | KeyCode::Char('n') => {
+                            let sections = self.reading_sections().len();
+                            if self.reading_section + 1 < sections {
+                                self.reading_section += 1;
+                            }
+                        }
+                        KeyCode::Esc => {
+                            self.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    },
                 },
                 Event::Mouse(_) => {
                     // no support for mouse event in Tui browser
@@ -337,7 +669,19 @@ impl Tui {
                         "↓ (down arrow)",
                         Style::default().add_modifier(Modifier::BOLD),
                     ),
-                    Span::raw(" to move between focused links, "),
+                    Span::raw(" to move between links and form controls, "),
+                    Span::styled("s", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to save the page, "),
+                    Span::styled("c", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to cancel the refresh, "),
+                    Span::styled("r", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to read the page by headings, "),
+                    Span::styled("v", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to toggle the reader view, "),
+                    Span::styled("m", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" and "),
+                    Span::styled("t", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to export the article as Markdown and text, "),
                     Span::styled("q", Style::default().add_modifier(Modifier::BOLD)),
                     Span::raw(" to exit."),
                 ],
@@ -353,6 +697,28 @@ impl Tui {
                 ],
                 Style::default(),
             ),
+            InputMode::EditingControl => (
+                vec![
+                    Span::raw("Press "),
+                    Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to stop editing the field, "),
+                    Span::styled("Enter", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to submit the form."),
+                ],
+                Style::default(),
+            ),
+            InputMode::Reading => (
+                vec![
+                    Span::raw("Press "),
+                    Span::styled("n", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" and "),
+                    Span::styled("p", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to move to the next and the previous heading, "),
+                    Span::styled("Esc", Style::default().add_modifier(Modifier::BOLD)),
+                    Span::raw(" to stop reading."),
+                ],
+                Style::default(),
+            ),
         };
         let text = Text::from(Line::from(msg)).style(style);
         let help_message = Paragraph::new(text);
@@ -360,26 +726,23 @@ impl Tui {
 
         let input = Paragraph::new(self.input_url.as_str())
             .style(match self.input_mode {
-                InputMode::Normal => Style::default(),
+                InputMode::Normal | InputMode::EditingControl | InputMode::Reading => {
+                    Style::default()
+                }
                 InputMode::Editing => Style::default().fg(Color::Yellow),
             })
             .block(Block::default().borders(Borders::ALL).title("URL"));
         frame.render_widget(input, chunks[1]);
 
         match self.input_mode {
-            InputMode::Normal => {}
+            InputMode::Normal | InputMode::EditingControl | InputMode::Reading => {}
             InputMode::Editing => frame.set_cursor_position((
                 chunks[1].x + self.input_url.width() as u16 + 1,
                 chunks[1].y + 1,
             )),
         }
 
-        let display_items = self
-            .browser
-            .borrow()
-            .current_page()
-            .borrow()
-            .display_items();
+        let display_items = self.displayed_page().borrow().display_items();
 
         let mut lines: Vec<Line> = Vec::new();
 
@@ -389,10 +752,12 @@ impl Tui {
                     text,
                     style,
                     layout_point: _,
+                    href: _,
+                    node_id: _,
                 } => {
                     if style.text_decoration() == TextDecoration::Underline {
-                        if let Some(focus_item) = &self.focus {
-                            if focus_item.text == text {
+                        if let Some(Focus::Link(link)) = &self.focus {
+                            if link.text == text {
                                 lines.push(Line::from(Span::styled(
                                     text,
                                     Style::default()
@@ -414,13 +779,299 @@ impl Tui {
                         }));
                     }
                 }
-                DisplayItem::Rect { .. } => {}
+                DisplayItem::FormControl { node_id, text, .. } => {
+                    let is_focused = matches!(
+                        &self.focus,
+                        Some(Focus::Control { node_id: id, .. }) if *id == node_id
+                    );
+                    if !is_focused {
+                        lines.push(Line::from(Span::styled(
+                            text,
+                            Style::default().fg(Color::Cyan),
+                        )));
+                        continue;
+                    }
+
+                    // the field shows what the user is typing
+                    let text = match self.input_mode {
+                        InputMode::EditingControl => {
+                            format!("[{}_]", self.input_control.replace('\n', "\u{21b5}"))
+                        }
+                        _ => text,
+                    };
+                    lines.push(Line::from(Span::styled(
+                        text,
+                        Style::default().fg(Color::Black).bg(Color::Cyan),
+                    )));
+                }
+                DisplayItem::Image {
+                    image,
+                    alt,
+                    layout_size,
+                    ..
+                } => match image {
+                    Some(image) => lines.extend(image_lines(
+                        &image,
+                        layout_size.width(),
+                        layout_size.height(),
+                    )),
+                    // https://html.spec.whatwg.org/multipage/images.html#alt
+                    // an image with the empty alt text is decorative, and is not rendered
+                    None if alt.is_empty() => {}
+                    None => lines.push(Line::from(Span::styled(
+                        format!("[{}]", alt),
+                        Style::default()
+                            .fg(Color::DarkGray)
+                            .add_modifier(Modifier::ITALIC),
+                    ))),
+                },
+                // the nested document of <iframe> follows as display items of its own
+                DisplayItem::Rect { .. } | DisplayItem::Frame { .. } => {}
             }
         }
 
+        let metadata = self.browser.borrow().current_page().borrow().metadata();
+        let mut title = metadata.title().unwrap_or_else(|| "Content".to_string());
+        if self.reader.is_some() {
+            title = format!("Reader view: {}", title);
+        }
+        // the reading mode shows the section of the accessibility tree instead of the page
+        if let InputMode::Reading = self.input_mode {
+            let sections = self.reading_sections();
+            lines = sections
+                .get(self.reading_section)
+                .map(|section| section.iter().map(|l| Line::from(l.clone())).collect())
+                .unwrap_or_default();
+            title = format!(
+                "Reading {}/{}",
+                (self.reading_section + 1).min(sections.len()),
+                sections.len()
+            );
+        }
         let contents = Paragraph::new(Text::from(lines))
-            .block(Block::default().title("Content").borders(Borders::ALL))
-            .wrap(Wrap { trim: true });
+            .block(Block::default().title(title).borders(Borders::ALL))
+            // the white space of the lines is already processed by the layout
+            .wrap(Wrap { trim: false });
         frame.render_widget(contents, chunks[2]);
+
+        let load_errors = self.browser.borrow().current_page().borrow().load_errors();
+        let mut status_lines: Vec<Line> = Vec::new();
+        if let Some(status) = &self.status {
+            status_lines.push(Line::from(Span::raw(status.clone())));
+        }
+        if let Some((deadline, destination)) = &self.refresh {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            status_lines.push(Line::from(Span::styled(
+                format!(
+                    "refreshing to {} in {}s",
+                    destination,
+                    remaining.as_secs_f64().ceil()
+                ),
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        let summary = metadata_summary(&metadata);
+        if !summary.is_empty() {
+            status_lines.push(Line::from(Span::styled(
+                summary,
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        if let Some(description) = metadata.description() {
+            status_lines.push(Line::from(Span::raw(description)));
+        }
+        for e in load_errors {
+            let msg = match e {
+                Error::Network(msg) => msg,
+                _ => format!("{:?}", e),
+            };
+            status_lines.push(Line::from(Span::styled(
+                msg,
+                Style::default().fg(Color::Red),
+            )));
+        }
+
+        let status = Paragraph::new(Text::from(status_lines))
+            .block(Block::default().title("Status").borders(Borders::ALL))
+            .wrap(Wrap { trim: true });
+        frame.render_widget(status, chunks[3]);
+    }
+}
+
+// the maximum number of the columns and the rows of pixels which an image is rendered with
+const MAX_IMAGE_CELLS: i64 = 200;
+
+// renders the image with half blocks, whose upper and lower halves are a pixel each, where a
+// cell is as wide as a character and the image is scaled to the width and height in pixels
+fn image_lines(image: &Image, width: i64, height: i64) -> Vec<Line<'static>> {
+    // the size is limited, as the layout size comes from the attributes of the page
+    let columns = (width.clamp(1, MAX_IMAGE_CELLS * CHAR_WIDTH) + CHAR_WIDTH - 1) / CHAR_WIDTH;
+    let rows = (height.clamp(1, MAX_IMAGE_CELLS * CHAR_WIDTH) + CHAR_WIDTH - 1) / CHAR_WIDTH;
+    let (columns, rows) = (columns as usize, rows as usize);
+
+    // nearest neighbor sampling, where transparent pixels and the rows out of the image are None
+    let color = |column: usize, row: usize| -> Option<Color> {
+        if row >= rows {
+            return None;
+        }
+        let [r, g, b, a] = image.pixel(
+            column * image.width() / columns,
+            row * image.height() / rows,
+        );
+        if a < 128 {
+            None
+        } else {
+            Some(Color::Rgb(r, g, b))
+        }
+    };
+
+    (0..rows.div_ceil(2))
+        .map(|line| {
+            let spans: Vec<Span> = (0..columns)
+                .map(
+                    |column| match (color(column, line * 2), color(column, line * 2 + 1)) {
+                        (Some(upper), Some(lower)) => {
+                            Span::styled("\u{2580}", Style::default().fg(upper).bg(lower))
+                        }
+                        (Some(upper), None) => Span::styled("\u{2580}", Style::default().fg(upper)),
+                        (None, Some(lower)) => Span::styled("\u{2584}", Style::default().fg(lower)),
+                        (None, None) => Span::raw(" "),
+                    },
+                )
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+// summarizes the metadata in a line, e.g. "lang: en | charset: utf-8 | canonical: ..."
+fn metadata_summary(metadata: &PageMetadata) -> String {
+    let mut parts = Vec::new();
+    if let Some(language) = metadata.language() {
+        parts.push(format!("lang: {}", language));
+    }
+    if let Some(charset) = metadata.charset() {
+        parts.push(format!("charset: {}", charset));
+    }
+    if let Some(canonical) = metadata.canonical() {
+        parts.push(format!("canonical: {}", canonical));
+    }
+    if let Some(icon) = metadata.icon() {
+        parts.push(format!("icon: {}", icon));
+    }
+    if let Some(prev) = metadata.prev() {
+        parts.push(format!("prev: {}", prev));
+    }
+    if let Some(next) = metadata.next() {
+        parts.push(format!("next: {}", next));
+    }
+    parts.join(" | ")
+}
+
+// uses the last path segment of the URL as the file name. the name is chosen by the server, so
+// leading dots are stripped and the characters other than ASCII alphanumerics, '-', '_' and '.'
+// are replaced, which keeps the file visible and in the directory
+fn file_name_from_url(url: &str) -> String {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    let path = match path.split_once("://") {
+        Some((_, rest)) => rest.split_once('/').map(|(_, p)| p).unwrap_or_default(),
+        None => path,
+    };
+
+    let name: String = path
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .trim_start_matches('.')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if name.is_empty() {
+        "index.html".to_string()
+    } else if name.contains('.') {
+        name
+    } else {
+        format!("{}.html", name)
+    }
+}
+
+// writes the contents to a new file in the directory, which never overwrites an existing file.
+// a number is added to the name while the file exists, e.g. "index-1.html"
+fn write_new_file(directory: &Path, file_name: &str, contents: &str) -> io::Result<PathBuf> {
+    let (stem, extension) = match file_name.rsplit_once('.') {
+        Some((stem, extension)) => (stem, format!(".{}", extension)),
+        None => (file_name, String::new()),
+    };
+
+    for i in 0..100 {
+        let path = if i == 0 {
+            directory.join(file_name)
+        } else {
+            directory.join(format!("{}-{}{}", stem, i, extension))
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many files with the same name",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_name_from_url() {
+        assert_eq!(
+            "page.html",
+            file_name_from_url("http://example.com/a/page.html?x#y")
+        );
+        assert_eq!("docs.html", file_name_from_url("http://example.com/docs"));
+        assert_eq!("index.html", file_name_from_url("http://example.com/"));
+        // dotfiles are not created
+        assert_eq!(
+            "bashrc.html",
+            file_name_from_url("http://example.com/.bashrc")
+        );
+        assert_eq!("index.html", file_name_from_url("http://example.com/.."));
+        assert_eq!(
+            "a_20b.html",
+            file_name_from_url("http://example.com/a%20b.html")
+        );
+    }
+
+    #[test]
+    fn test_write_new_file() {
+        let directory = std::env::temp_dir().join(format!("cui-save-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).expect("failed to create the directory");
+        std::fs::write(directory.join("Cargo.toml"), "original").expect("failed to write");
+
+        // the existing file is kept, and the contents are written next to it
+        let path = write_new_file(&directory, "Cargo.toml", "page").expect("failed to save");
+        assert_eq!(directory.join("Cargo-1.toml"), path);
+        assert_eq!(
+            "original",
+            std::fs::read_to_string(directory.join("Cargo.toml")).expect("failed to read")
+        );
+        assert_eq!(
+            "page",
+            std::fs::read_to_string(&path).expect("failed to read")
+        );
+
+        std::fs::remove_dir_all(&directory).expect("failed to clean up");
     }
 }
@@ -7,21 +7,23 @@ use std::cell::RefCell;
 use std::rc::Rc;
 
 pub struct Gui {
-    _browser: Rc<RefCell<Browser>>,
+    browser: Rc<RefCell<Browser>>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {}
 
 impl Gui {
-    pub fn new(_browser: Rc<RefCell<Browser>>) -> Self {
-        Self { _browser }
+    pub fn new(browser: Rc<RefCell<Browser>>) -> Self {
+        Self { browser }
     }
 
     pub fn start(
         &mut self,
-        _handle_url: fn(String) -> Result<HttpResponse, Error>,
+        handle_url: fn(String) -> Result<HttpResponse, Error>,
     ) -> Result<(), Error> {
+        self.browser.borrow_mut().set_fetcher(handle_url);
+
         self.setup()?;
 
         IcedApp::run(Settings::default()).expect("Failed to run the app");